@@ -1,6 +1,8 @@
+use std::error::Error;
 use std::future::Future;
-use std::io;
 use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{fmt, io};
 
 use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
 pub use open_ssl::ssl::{Error as SslError, SslConnector, SslMethod};
@@ -32,6 +34,14 @@ impl<T> OpensslConnector<T> {
             openssl: connector,
         }
     }
+
+    /// Race connection attempts to resolved addresses.
+    ///
+    /// See `Connector::happy_eyeballs()` for details.
+    pub fn happy_eyeballs(mut self, delay: Duration) -> Self {
+        self.connector = self.connector.happy_eyeballs(delay);
+        self
+    }
 }
 
 impl<T: Address + 'static> OpensslConnector<T> {
@@ -61,8 +71,7 @@ impl<T: Address + 'static> OpensslConnector<T> {
                     }
                     Err(e) => {
                         trace!("SSL Handshake error: {:?}", e);
-                        Err(io::Error::new(io::ErrorKind::Other, format!("{}", e))
-                            .into())
+                        Err(handshake_error(e).into())
                     }
                 },
             }
@@ -70,6 +79,19 @@ impl<T: Address + 'static> OpensslConnector<T> {
     }
 }
 
+/// Convert handshake error to io error, openssl error stack is kept
+/// as error source, so received tls alerts could be inspected
+pub(crate) fn handshake_error<S: fmt::Debug>(err: HandshakeError<S>) -> io::Error {
+    match err
+        .source()
+        .and_then(|e| e.downcast_ref::<SslError>())
+        .and_then(|e| e.ssl_error())
+    {
+        Some(stack) => io::Error::new(io::ErrorKind::Other, stack.clone()),
+        None => io::Error::new(io::ErrorKind::Other, format!("{}", err)),
+    }
+}
+
 impl<T> Clone for OpensslConnector<T> {
     fn clone(&self) -> Self {
         OpensslConnector {
@@ -1,6 +1,7 @@
 use std::io;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 pub use rust_tls::Session;
 pub use tokio_rustls::{client::TlsStream, rustls::ClientConfig};
@@ -35,6 +36,14 @@ impl<T> RustlsConnector<T> {
             connector: Connector::new(resolver),
         }
     }
+
+    /// Race connection attempts to resolved addresses.
+    ///
+    /// See `Connector::happy_eyeballs()` for details.
+    pub fn happy_eyeballs(mut self, delay: Duration) -> Self {
+        self.connector = self.connector.happy_eyeballs(delay);
+        self
+    }
 }
 
 impl<T: Address + 'static> RustlsConnector<T> {
@@ -65,7 +74,7 @@ impl<T: Address + 'static> RustlsConnector<T> {
                 }
                 Err(e) => {
                     trace!("SSL Handshake error: {:?}", e);
-                    Err(io::Error::new(io::ErrorKind::Other, format!("{}", e)).into())
+                    Err(e.into())
                 }
             }
         }
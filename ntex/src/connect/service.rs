@@ -4,17 +4,20 @@ use std::io;
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use either::Either;
 use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
 
 use crate::rt::net::TcpStream;
+use crate::rt::time::{delay_for, Delay};
 use crate::service::{Service, ServiceFactory};
 
 use super::{Address, AsyncResolver, Connect, ConnectError, Resolver};
 
 pub struct Connector<T> {
     resolver: Resolver<T>,
+    happy_eyeballs: Option<Duration>,
 }
 
 impl<T> Connector<T> {
@@ -22,8 +25,20 @@ impl<T> Connector<T> {
     pub fn new(resolver: AsyncResolver) -> Self {
         Connector {
             resolver: Resolver::new(resolver),
+            happy_eyeballs: None,
         }
     }
+
+    /// Race connection attempts to resolved addresses (RFC 8305).
+    ///
+    /// Addresses are interleaved by address family. If connection attempt
+    /// does not succeed within `delay`, next attempt is started in parallel,
+    /// first established connection is used and other attempts are cancelled.
+    /// By default addresses are tried one by one.
+    pub fn happy_eyeballs(mut self, delay: Duration) -> Self {
+        self.happy_eyeballs = Some(delay);
+        self
+    }
 }
 
 impl<T: Address> Connector<T> {
@@ -36,6 +51,7 @@ impl<T: Address> Connector<T> {
         Connect<T>: From<U>,
     {
         ConnectServiceResponse::new(self.resolver.lookup(message.into()))
+            .happy_eyeballs(self.happy_eyeballs)
     }
 }
 
@@ -43,6 +59,7 @@ impl<T> Default for Connector<T> {
     fn default() -> Self {
         Connector {
             resolver: Resolver::default(),
+            happy_eyeballs: None,
         }
     }
 }
@@ -51,6 +68,7 @@ impl<T> Clone for Connector<T> {
     fn clone(&self) -> Self {
         Connector {
             resolver: self.resolver.clone(),
+            happy_eyeballs: self.happy_eyeballs,
         }
     }
 }
@@ -84,6 +102,7 @@ impl<T: Address> Service for Connector<T> {
     #[inline]
     fn call(&self, req: Connect<T>) -> Self::Future {
         ConnectServiceResponse::new(self.resolver.lookup(req))
+            .happy_eyeballs(self.happy_eyeballs)
     }
 }
 
@@ -95,14 +114,21 @@ enum ConnectState<T: Address> {
 #[doc(hidden)]
 pub struct ConnectServiceResponse<T: Address> {
     state: ConnectState<T>,
+    happy_eyeballs: Option<Duration>,
 }
 
 impl<T: Address> ConnectServiceResponse<T> {
     pub(super) fn new(fut: <Resolver<T> as Service>::Future) -> Self {
         ConnectServiceResponse {
             state: ConnectState::Resolve(fut),
+            happy_eyeballs: None,
         }
     }
+
+    fn happy_eyeballs(mut self, delay: Option<Duration>) -> Self {
+        self.happy_eyeballs = delay;
+        self
+    }
 }
 
 impl<T: Address> Future for ConnectServiceResponse<T> {
@@ -117,9 +143,10 @@ impl<T: Address> Future for ConnectServiceResponse<T> {
                     let Connect { req, addr, .. } = address;
 
                     if let Some(addr) = addr {
-                        self.state = ConnectState::Connect(TcpConnectorResponse::new(
-                            req, port, addr,
-                        ));
+                        self.state = ConnectState::Connect(
+                            TcpConnectorResponse::new(req, port, addr)
+                                .happy_eyeballs(self.happy_eyeballs),
+                        );
                         self.poll(cx)
                     } else if let Some(addr) = req.addr() {
                         self.state = ConnectState::Connect(TcpConnectorResponse::new(
@@ -145,6 +172,15 @@ struct TcpConnectorResponse<T> {
     port: u16,
     addrs: Option<VecDeque<SocketAddr>>,
     stream: Option<LocalBoxFuture<'static, Result<TcpStream, io::Error>>>,
+    race: Option<Race>,
+}
+
+/// Parallel connection attempts with staggered start
+struct Race {
+    delay: Duration,
+    timer: Option<Delay>,
+    streams: Vec<LocalBoxFuture<'static, Result<TcpStream, io::Error>>>,
+    error: Option<io::Error>,
 }
 
 impl<T: Address> TcpConnectorResponse<T> {
@@ -165,15 +201,119 @@ impl<T: Address> TcpConnectorResponse<T> {
                 port,
                 addrs: None,
                 stream: Some(TcpStream::connect(addr).boxed_local()),
+                race: None,
             },
             Either::Right(addrs) => TcpConnectorResponse {
                 req: Some(req),
                 port,
                 addrs: Some(addrs),
                 stream: None,
+                race: None,
             },
         }
     }
+
+    /// Race connection attempts, if there are more than one address
+    fn happy_eyeballs(mut self, delay: Option<Duration>) -> Self {
+        match (delay, self.addrs.take()) {
+            (Some(delay), Some(addrs)) if addrs.len() > 1 => {
+                self.addrs = Some(interleave(addrs));
+                self.race = Some(Race {
+                    delay,
+                    timer: None,
+                    streams: Vec::new(),
+                    error: None,
+                });
+            }
+            (_, addrs) => self.addrs = addrs,
+        }
+        self
+    }
+
+    fn poll_race(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<TcpStream, ConnectError>> {
+        let race = self.race.as_mut().unwrap();
+        let addrs = self.addrs.as_mut().unwrap();
+
+        if race.streams.is_empty() && race.error.is_none() {
+            let addr = addrs.pop_front().unwrap();
+            race.streams.push(TcpStream::connect(addr).boxed_local());
+        }
+
+        loop {
+            let mut idx = 0;
+            while idx < race.streams.len() {
+                match race.streams[idx].as_mut().poll(cx) {
+                    Poll::Ready(Ok(sock)) => {
+                        let req = self.req.take().unwrap();
+                        trace!(
+                            "TCP connector - successfully connected to {:?} - {:?}",
+                            req.host(),
+                            sock.peer_addr()
+                        );
+                        // pending attempts get cancelled on drop
+                        race.streams.clear();
+                        return Poll::Ready(Ok(sock));
+                    }
+                    Poll::Ready(Err(err)) => {
+                        trace!(
+                            "TCP connector - failed to connect to {:?} port: {}",
+                            self.req.as_ref().unwrap().host(),
+                            self.port,
+                        );
+                        drop(race.streams.swap_remove(idx));
+                        race.error = Some(err);
+
+                        // start next attempt without waiting for delay
+                        if let Some(addr) = addrs.pop_front() {
+                            race.streams.push(TcpStream::connect(addr).boxed_local());
+                            race.timer = None;
+                        }
+                    }
+                    Poll::Pending => idx += 1,
+                }
+            }
+
+            if addrs.is_empty() {
+                return if race.streams.is_empty() {
+                    Poll::Ready(Err(race.error.take().unwrap().into()))
+                } else {
+                    Poll::Pending
+                };
+            }
+
+            let delay = race.delay;
+            let timer = race.timer.get_or_insert_with(|| delay_for(delay));
+            match Pin::new(timer).poll(cx) {
+                Poll::Ready(_) => {
+                    let addr = addrs.pop_front().unwrap();
+                    race.streams.push(TcpStream::connect(addr).boxed_local());
+                    race.timer = None;
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Interleave addresses by address family, first address family is preferred
+fn interleave(addrs: VecDeque<SocketAddr>) -> VecDeque<SocketAddr> {
+    let first_v6 = addrs.front().map(|a| a.is_ipv6()).unwrap_or(false);
+    let (mut preferred, mut other): (VecDeque<_>, VecDeque<_>) =
+        addrs.into_iter().partition(|a| a.is_ipv6() == first_v6);
+
+    let mut result = VecDeque::with_capacity(preferred.len() + other.len());
+    loop {
+        match (preferred.pop_front(), other.pop_front()) {
+            (None, None) => return result,
+            (a, b) => {
+                result.extend(a);
+                result.extend(b);
+            }
+        }
+    }
 }
 
 impl<T: Address> Future for TcpConnectorResponse<T> {
@@ -182,6 +322,10 @@ impl<T: Address> Future for TcpConnectorResponse<T> {
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
+        if this.race.is_some() {
+            return this.poll_race(cx);
+        }
+
         // connect
         loop {
             if let Some(new) = this.stream.as_mut() {
@@ -250,4 +394,56 @@ mod tests {
         let result = crate::connect::connect(msg).await;
         assert!(result.is_ok());
     }
+
+    #[ntex_rt::test]
+    async fn test_happy_eyeballs() {
+        let server = crate::server::test_server(|| {
+            crate::fn_service(|_| async { Ok::<_, ()>(()) })
+        });
+
+        // first address does not respond
+        let srv = Connector::default().happy_eyeballs(Duration::from_millis(50));
+        let msg = Connect::new(format!("{}", server.addr()))
+            .set_addrs(vec!["10.255.255.1:80".parse().unwrap(), server.addr()]);
+        let result = srv.connect(msg).await;
+        assert!(result.is_ok());
+
+        let msg = Connect::new(format!("{}", server.addr())).set_addrs(vec![
+            format!("127.0.0.1:{}", server.addr().port() - 1)
+                .parse()
+                .unwrap(),
+            format!("127.0.0.1:{}", server.addr().port() - 2)
+                .parse()
+                .unwrap(),
+        ]);
+        let result = srv.connect(msg).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_interleave() {
+        let addrs: VecDeque<SocketAddr> = vec![
+            "[::1]:80".parse().unwrap(),
+            "[::2]:80".parse().unwrap(),
+            "127.0.0.1:80".parse().unwrap(),
+            "127.0.0.2:80".parse().unwrap(),
+            "127.0.0.3:80".parse().unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        let result: Vec<_> = interleave(addrs)
+            .into_iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(
+            result,
+            vec![
+                "[::1]:80",
+                "127.0.0.1:80",
+                "[::2]:80",
+                "127.0.0.2:80",
+                "127.0.0.3:80"
+            ]
+        );
+    }
 }
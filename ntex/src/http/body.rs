@@ -7,6 +7,8 @@ use std::{fmt, mem};
 use bytes::{Bytes, BytesMut};
 use futures::{ready, Stream};
 
+use crate::http::header::HeaderMap;
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 /// Body size hint
 pub enum BodySize {
@@ -33,6 +35,13 @@ pub trait MessageBody {
         &mut self,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>>;
+
+    /// Trailer fields, sent after the last chunk of chunked body.
+    ///
+    /// Called once body stream is complete.
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        None
+    }
 }
 
 impl MessageBody for () {
@@ -59,6 +68,10 @@ impl<T: MessageBody> MessageBody for Box<T> {
     ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
         self.as_mut().poll_next_chunk(cx)
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        self.as_mut().trailers()
+    }
 }
 
 pub enum ResponseBody<B> {
@@ -118,6 +131,13 @@ impl<B: MessageBody> MessageBody for ResponseBody<B> {
             ResponseBody::Other(ref mut body) => body.poll_next_chunk(cx),
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            ResponseBody::Body(ref mut body) => body.trailers(),
+            ResponseBody::Other(ref mut body) => body.trailers(),
+        }
+    }
 }
 
 impl<B: MessageBody + Unpin> Stream for ResponseBody<B> {
@@ -186,6 +206,13 @@ impl MessageBody for Body {
             Body::Message(ref mut body) => body.poll_next_chunk(cx),
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            Body::Message(ref mut body) => body.trailers(),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for Body {
@@ -528,6 +555,70 @@ where
     }
 }
 
+/// Streaming body with explicit chunk boundaries.
+///
+/// Body is sent with chunked transfer encoding, each item of the stream
+/// is sent as exactly one chunk, chunks are never coalesced or split.
+/// Optional trailer fields are sent after the last chunk. For http/2
+/// connections each item is sent as data frames and trailers are sent
+/// as trailing headers.
+pub struct ChunkedStream<S, E> {
+    stream: S,
+    trailers: Option<HeaderMap>,
+    _t: PhantomData<E>,
+}
+
+impl<S, E> ChunkedStream<S, E>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Error,
+{
+    pub fn new(stream: S) -> Self {
+        ChunkedStream {
+            stream,
+            trailers: None,
+            _t: PhantomData,
+        }
+    }
+
+    /// Set trailer fields, sent after the last chunk
+    pub fn set_trailers(mut self, trailers: HeaderMap) -> Self {
+        self.trailers = Some(trailers);
+        self
+    }
+}
+
+impl<S, E> MessageBody for ChunkedStream<S, E>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Error + 'static,
+{
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    /// Attempts to pull out the next value of the underlying [`Stream`].
+    ///
+    /// Empty values are skipped, zero-length chunk terminates chunked body.
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        loop {
+            return Poll::Ready(
+                match ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+                    Some(Ok(ref bytes)) if bytes.is_empty() => continue,
+                    opt => opt.map(|res| res.map_err(Into::into)),
+                },
+            );
+        }
+    }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io;
@@ -0,0 +1,172 @@
+//! Backoff delays calculator
+use std::cmp;
+use std::time::Duration;
+
+use rand::Rng;
+
+/// Sequence of delays between attempts.
+///
+/// Delay of attempt `n` is `base * factor^(n - 1)`, capped to `max`.
+/// With jitter enabled actual delay is chosen randomly between half
+/// of computed delay and computed delay, without jitter sequence
+/// is deterministic.
+///
+/// Backoff is an infinite iterator of delays, `delay()` calculates
+/// delay for specific attempt without changing iterator state.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use ntex::http::client::{Backoff, Client};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::new();
+///     let backoff =
+///         Backoff::exponential(Duration::from_millis(50), Duration::from_secs(2));
+///
+///     for delay in backoff.take(3) {
+///         match client.get("http://www.rust-lang.org").send().await {
+///             Ok(res) if res.status().is_success() => break,
+///             _ => ntex::rt::time::delay_for(delay).await,
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    base: Duration,
+    factor: f64,
+    max: Duration,
+    jitter: bool,
+    attempt: usize,
+}
+
+impl Backoff {
+    /// Wait fixed period of time between attempts.
+    pub fn fixed(delay: Duration) -> Self {
+        Backoff {
+            base: delay,
+            factor: 1.0,
+            max: delay,
+            jitter: false,
+            attempt: 0,
+        }
+    }
+
+    /// Double delay after each attempt, starting from `base`
+    /// and never exceeding `max`.
+    pub fn exponential(base: Duration, max: Duration) -> Self {
+        Backoff {
+            base,
+            factor: 2.0,
+            max,
+            jitter: false,
+            attempt: 0,
+        }
+    }
+
+    /// Set multiplier of delay after each attempt.
+    ///
+    /// Factors below 1 are treated as 1. By default factor is 2
+    /// for exponential backoff.
+    pub fn factor(mut self, factor: f64) -> Self {
+        self.factor = if factor >= 1.0 { factor } else { 1.0 };
+        self
+    }
+
+    /// Set max delay.
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Randomize delays.
+    ///
+    /// By default jitter is disabled.
+    pub fn jitter(mut self, enabled: bool) -> Self {
+        self.jitter = enabled;
+        self
+    }
+
+    /// Check if jitter is enabled
+    pub(super) fn has_jitter(&self) -> bool {
+        self.jitter
+    }
+
+    /// Calculate delay of `attempt`, attempts start from 1.
+    pub fn delay(&self, attempt: usize) -> Duration {
+        #[allow(clippy::legacy_numeric_constants)]
+        let exp = cmp::min(attempt.saturating_sub(1), std::i32::MAX as usize) as i32;
+        let nanos = self.base.as_nanos() as f64 * self.factor.powi(exp);
+        let delay = if nanos.is_finite() && nanos < self.max.as_nanos() as f64 {
+            Duration::from_nanos(nanos.round() as u64)
+        } else {
+            self.max
+        };
+
+        if self.jitter && delay > Duration::from_millis(1) {
+            let millis = delay.as_millis() as u64;
+            Duration::from_millis(rand::thread_rng().gen_range(millis / 2, millis + 1))
+        } else {
+            delay
+        }
+    }
+
+    /// Start sequence from the first attempt.
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        self.attempt = self.attempt.saturating_add(1);
+        Some(self.delay(self.attempt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let backoff = Backoff::fixed(Duration::from_millis(10));
+        assert_eq!(backoff.delay(1), Duration::from_millis(10));
+        assert_eq!(backoff.delay(4), Duration::from_millis(10));
+
+        let mut backoff =
+            Backoff::exponential(Duration::from_millis(10), Duration::from_millis(50));
+        assert_eq!(
+            backoff.by_ref().take(5).collect::<Vec<_>>(),
+            [10, 20, 40, 50, 50]
+                .iter()
+                .map(|ms| Duration::from_millis(*ms))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(backoff.delay(std::usize::MAX), Duration::from_millis(50));
+        backoff.reset();
+        assert_eq!(backoff.next(), Some(Duration::from_millis(10)));
+
+        let backoff =
+            Backoff::exponential(Duration::from_millis(100), Duration::from_secs(1))
+                .factor(1.5);
+        assert_eq!(backoff.delay(2), Duration::from_millis(150));
+        assert_eq!(
+            backoff.clone().factor(0.5).delay(3),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            backoff.max(Duration::from_millis(200)).delay(3),
+            Duration::from_millis(200)
+        );
+
+        let backoff = Backoff::fixed(Duration::from_millis(100)).jitter(true);
+        for delay in backoff.take(100) {
+            assert!(delay >= Duration::from_millis(50));
+            assert!(delay <= Duration::from_millis(100));
+        }
+    }
+}
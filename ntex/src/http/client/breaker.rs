@@ -0,0 +1,227 @@
+//! Http client per-host circuit breaker
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::http::Uri;
+
+/// Max number of tracked hosts, expired entries get dropped after that
+const MAX_HOSTS: usize = 1024;
+
+/// Per-host circuit breaker.
+///
+/// Breaker opens after `failures` consecutive failed requests to the host
+/// within `window`. Requests to the host with open breaker fail immediately
+/// with `SendRequestError::CircuitOpen`. Once `cooldown` has passed, one
+/// trial request is allowed, breaker is closed if it succeeds and opened
+/// again otherwise.
+///
+/// Request fails if connection could not be established or a response
+/// could not be received, any response closes the breaker. Requests dropped
+/// before response is received, for example on timeout, are counted
+/// as failures.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use ntex::http::client::{Client, CircuitBreaker};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .circuit_breaker(CircuitBreaker::new(5).cooldown(Duration::from_secs(10)))
+///         .finish();
+/// }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct CircuitBreaker {
+    failures: u32,
+    window: Duration,
+    cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    /// Create new circuit breaker that opens after `failures` consecutive
+    /// failed requests.
+    ///
+    /// By default failures are counted within 60 seconds window and
+    /// breaker stays open for 30 seconds.
+    pub fn new(failures: u32) -> Self {
+        CircuitBreaker {
+            failures: cmp::max(failures, 1),
+            window: Duration::from_secs(60),
+            cooldown: Duration::from_secs(30),
+        }
+    }
+
+    /// Set time window for counting consecutive failures.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set time breaker stays open before trial request is allowed.
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+}
+
+/// Breaker states of hosts, shared by clones of the client
+pub(super) struct CircuitBreakers {
+    breaker: CircuitBreaker,
+    hosts: Rc<RefCell<HashMap<String, State>>>,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum State {
+    Closed { failures: u32, since: Instant },
+    Open { until: Instant },
+    HalfOpen,
+}
+
+impl CircuitBreakers {
+    pub(super) fn new(breaker: CircuitBreaker) -> Self {
+        CircuitBreakers {
+            breaker,
+            hosts: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Check breaker of the host of `uri`.
+    ///
+    /// Returns guard that records request outcome, or `None` if
+    /// breaker is open.
+    pub(super) fn acquire(&self, uri: &Uri) -> Option<BreakerGuard> {
+        let key = if let Some(authority) = uri.authority() {
+            authority.as_str().to_ascii_lowercase()
+        } else {
+            return Some(BreakerGuard(None));
+        };
+
+        let mut hosts = self.hosts.borrow_mut();
+        match hosts.get(&key).copied() {
+            Some(State::Open { until }) => {
+                if Instant::now() < until {
+                    return None;
+                }
+                // allow trial request
+                hosts.insert(key.clone(), State::HalfOpen);
+            }
+            Some(State::HalfOpen) => return None,
+            _ => (),
+        }
+        Some(BreakerGuard(Some((key, self.breaker, self.hosts.clone()))))
+    }
+}
+
+/// Records request outcome, request is failed if guard is dropped
+pub(super) struct BreakerGuard(
+    Option<(String, CircuitBreaker, Rc<RefCell<HashMap<String, State>>>)>,
+);
+
+impl BreakerGuard {
+    pub(super) fn success(mut self) {
+        if let Some((key, _, hosts)) = self.0.take() {
+            hosts.borrow_mut().remove(&key);
+        }
+    }
+}
+
+impl Drop for BreakerGuard {
+    fn drop(&mut self) {
+        if let Some((key, breaker, hosts)) = self.0.take() {
+            let now = Instant::now();
+            let mut hosts = hosts.borrow_mut();
+            if hosts.len() >= MAX_HOSTS && !hosts.contains_key(&key) {
+                hosts.retain(|_, state| match state {
+                    State::Closed { since, .. } => now - *since <= breaker.window,
+                    State::Open { until } => now < *until,
+                    State::HalfOpen => true,
+                });
+            }
+
+            let state = match hosts.get(&key).copied() {
+                Some(State::Closed { failures, since })
+                    if now - since <= breaker.window =>
+                {
+                    State::Closed {
+                        failures: failures + 1,
+                        since,
+                    }
+                }
+                Some(State::Closed { .. }) | None => State::Closed {
+                    failures: 1,
+                    since: now,
+                },
+                // trial request failed
+                Some(State::HalfOpen) => State::Closed {
+                    failures: breaker.failures,
+                    since: now,
+                },
+                Some(state @ State::Open { .. }) => state,
+            };
+            let state = match state {
+                State::Closed { failures, .. } if failures >= breaker.failures => {
+                    State::Open {
+                        until: now + breaker.cooldown,
+                    }
+                }
+                state => state,
+            };
+            hosts.insert(key, state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_breaker() {
+        let breakers = CircuitBreakers::new(
+            CircuitBreaker::new(2).cooldown(Duration::from_millis(50)),
+        );
+        let uri = Uri::try_from("http://rust-lang.org/test").unwrap();
+        let other = Uri::try_from("http://RUST-lang.org:8080/").unwrap();
+
+        // success resets failures
+        drop(breakers.acquire(&uri).unwrap());
+        breakers.acquire(&uri).unwrap().success();
+        drop(breakers.acquire(&uri).unwrap());
+        assert_eq!(
+            breakers
+                .hosts
+                .borrow()
+                .get("rust-lang.org")
+                .map(|s| match s {
+                    State::Closed { failures, .. } => *failures,
+                    _ => 0,
+                }),
+            Some(1)
+        );
+
+        // opens after consecutive failures
+        drop(breakers.acquire(&uri).unwrap());
+        assert!(breakers.acquire(&uri).is_none());
+        breakers.acquire(&other).unwrap().success();
+        breakers
+            .acquire(&Uri::try_from("/test").unwrap())
+            .unwrap()
+            .success();
+
+        // half-open allows one trial request
+        std::thread::sleep(Duration::from_millis(60));
+        let trial = breakers.acquire(&uri).unwrap();
+        assert!(breakers.acquire(&uri).is_none());
+        drop(trial);
+        assert!(breakers.acquire(&uri).is_none());
+
+        std::thread::sleep(Duration::from_millis(60));
+        breakers.acquire(&uri).unwrap().success();
+        assert!(breakers.acquire(&uri).is_some());
+    }
+}
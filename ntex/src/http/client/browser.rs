@@ -0,0 +1,115 @@
+//! Browser-like request header presets
+use crate::http::header::HeaderName;
+
+/// Browser profile for `ClientRequest::browser_headers()`.
+///
+/// Each profile is a consistent set of headers sent by the browser for
+/// top-level navigation request: `User-Agent`, `Accept`, `Accept-Language`,
+/// `Sec-Fetch-*` and, for Chrome, client hint headers of the same version.
+/// `Accept-Encoding` is not part of the profile, it is controlled by
+/// response decompression settings.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BrowserProfile {
+    /// Chrome on Windows
+    Chrome,
+    /// Firefox on Windows
+    Firefox,
+}
+
+const CHROME: &[(&str, &str)] = &[
+    (
+        "user-agent",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+         (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+    ),
+    (
+        "accept",
+        "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,\
+         image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7",
+    ),
+    ("accept-language", "en-US,en;q=0.9"),
+    (
+        "sec-ch-ua",
+        "\"Not_A Brand\";v=\"8\", \"Chromium\";v=\"120\", \"Google Chrome\";v=\"120\"",
+    ),
+    ("sec-ch-ua-mobile", "?0"),
+    ("sec-ch-ua-platform", "\"Windows\""),
+    ("sec-fetch-dest", "document"),
+    ("sec-fetch-mode", "navigate"),
+    ("sec-fetch-site", "none"),
+    ("sec-fetch-user", "?1"),
+    ("upgrade-insecure-requests", "1"),
+];
+
+const FIREFOX: &[(&str, &str)] = &[
+    (
+        "user-agent",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:121.0) \
+         Gecko/20100101 Firefox/121.0",
+    ),
+    (
+        "accept",
+        "text/html,application/xhtml+xml,application/xml;q=0.9,\
+         image/avif,image/webp,*/*;q=0.8",
+    ),
+    ("accept-language", "en-US,en;q=0.5"),
+    ("sec-fetch-dest", "document"),
+    ("sec-fetch-mode", "navigate"),
+    ("sec-fetch-site", "none"),
+    ("sec-fetch-user", "?1"),
+    ("upgrade-insecure-requests", "1"),
+];
+
+impl BrowserProfile {
+    /// Headers of the profile
+    pub(super) fn headers(self) -> impl Iterator<Item = (HeaderName, &'static str)> {
+        let headers = match self {
+            BrowserProfile::Chrome => CHROME,
+            BrowserProfile::Firefox => FIREFOX,
+        };
+        headers
+            .iter()
+            .map(|(name, value)| (HeaderName::from_static(name), *value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::HeaderValue;
+
+    fn get(profile: BrowserProfile, name: &str) -> Option<&'static str> {
+        profile
+            .headers()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    #[test]
+    fn test_profiles() {
+        for profile in &[BrowserProfile::Chrome, BrowserProfile::Firefox] {
+            for (_, value) in profile.headers() {
+                assert!(HeaderValue::from_str(value).is_ok());
+            }
+            assert_eq!(get(*profile, "sec-fetch-mode"), Some("navigate"));
+            assert!(get(*profile, "accept-encoding").is_none());
+        }
+
+        // client hints match user agent version
+        let ua = get(BrowserProfile::Chrome, "user-agent").unwrap();
+        let hints = get(BrowserProfile::Chrome, "sec-ch-ua").unwrap();
+        assert!(
+            ua.contains("Chrome/120.") && hints.contains("\"Google Chrome\";v=\"120\"")
+        );
+        assert!(ua.contains("Windows"));
+        assert_eq!(
+            get(BrowserProfile::Chrome, "sec-ch-ua-platform"),
+            Some("\"Windows\"")
+        );
+
+        // firefox does not send client hints
+        let ua = get(BrowserProfile::Firefox, "user-agent").unwrap();
+        assert!(ua.contains("rv:121.0") && ua.contains("Firefox/121.0"));
+        assert!(get(BrowserProfile::Firefox, "sec-ch-ua").is_none());
+    }
+}
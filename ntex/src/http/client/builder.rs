@@ -1,15 +1,47 @@
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::rc::Rc;
 use std::time::Duration;
 
+#[cfg(feature = "cookie")]
+use std::sync::{Arc, Mutex};
+
 use crate::http::error::HttpError;
 use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
 use crate::Service;
 
+use super::breaker::CircuitBreakers;
 use super::connect::ConnectorWrapper;
 use super::error::ConnectError;
-use super::{Client, ClientConfig, Connect, Connection, Connector};
+use super::ratelimit::RateLimiter;
+#[cfg(feature = "body-serializer")]
+use super::serialize::Serializers;
+use super::useragent::UserAgentRotation;
+use super::{
+    CircuitBreaker, Client, ClientConfig, ClientIdentity, Connect, Connection,
+    Connector, ProtocolMode, Proxy, RateLimit, RequestInterceptor, RequestMetrics,
+    RequestSigner, Resolve, ResponseInterceptor, RetryPolicy, RootCertificates,
+    TraceContext, UserAgents, DEFAULT_DECOMPRESSION_RATIO,
+    DEFAULT_EXPECT_CONTINUE_TIMEOUT,
+};
+
+#[cfg(feature = "cookie")]
+use super::CookieJar;
+#[cfg(feature = "test-util")]
+use super::Timer;
+
+/// Headers managed by the client, they could not be set as default headers
+const RESTRICTED_HEADERS: [HeaderName; 7] = [
+    header::CONTENT_LENGTH,
+    header::TRANSFER_ENCODING,
+    header::CONNECTION,
+    header::UPGRADE,
+    header::TE,
+    header::TRAILER,
+    header::HOST,
+];
 
 /// An HTTP Client builder
 ///
@@ -17,6 +49,7 @@ use super::{Client, ClientConfig, Connect, Connection, Connector};
 /// builder-like pattern.
 pub struct ClientBuilder {
     config: ClientConfig,
+    custom_connector: bool,
     default_headers: bool,
     allow_redirects: bool,
     max_redirects: usize,
@@ -31,12 +64,53 @@ impl Default for ClientBuilder {
 impl ClientBuilder {
     pub fn new() -> Self {
         ClientBuilder {
+            custom_connector: false,
             default_headers: true,
             allow_redirects: true,
-            max_redirects: 10,
+            max_redirects: 0,
             config: ClientConfig {
                 headers: HeaderMap::new(),
                 timeout: Some(Duration::from_secs(5)),
+                connect_timeout: None,
+                dns_timeout: None,
+                write_timeout: None,
+                expect_continue_timeout: DEFAULT_EXPECT_CONTINUE_TIMEOUT,
+                retry: None,
+                max_redirects: 0,
+                deny_redirects: false,
+                max_response_size: None,
+                max_response_header_size: None,
+                lenient_decompression: false,
+                preserve_raw_headers: false,
+                strict_framing: false,
+                max_decompression_ratio: Some(DEFAULT_DECOMPRESSION_RATIO),
+                resolver: None,
+                proxy: None,
+                rate_limiter: None,
+                circuit_breaker: None,
+                user_agents: None,
+                max_connections: None,
+                max_connections_per_host: None,
+                keep_alive_timeout: None,
+                happy_eyeballs: None,
+                tcp_nodelay: None,
+                tcp_keepalive: None,
+                root_certs: None,
+                identity: None,
+                host_identities: HashMap::new(),
+                protocol: None,
+                trace_context: None,
+                request_interceptors: Vec::new(),
+                response_interceptors: Vec::new(),
+                signer: None,
+                #[cfg(feature = "body-serializer")]
+                serializers: Serializers::default(),
+                closed: Cell::new(false),
+                on_complete: None,
+                #[cfg(feature = "cookie")]
+                cookie_jar: None,
+                #[cfg(feature = "test-util")]
+                timer: None,
                 connector: Box::new(ConnectorWrapper(Connector::default().finish())),
             },
         }
@@ -51,6 +125,7 @@ impl ClientBuilder {
         T::Future: 'static,
     {
         self.config.connector = Box::new(ConnectorWrapper(connector));
+        self.custom_connector = true;
         self
     }
 
@@ -69,22 +144,436 @@ impl ClientBuilder {
         self
     }
 
-    /// Do not follow redirects.
+    /// Set connect timeout
+    ///
+    /// Connect timeout is the max time to wait for a connection to the host,
+    /// including waiting for an available connection in the pool. If it
+    /// expires, request fails with `SendRequestError::ConnectTimeout`.
+    /// Request timeout still applies to the whole request.
+    /// By default connect timeout is not set.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set dns resolution timeout
+    ///
+    /// Dns timeout is the max time to resolve host name of the request
+    /// before connecting, it applies only if socket address of the request
+    /// is not set. If it expires, request fails with
+    /// `SendRequestError::DnsTimeout`. Connect timeout covers resolution
+    /// as well. By default dns timeout is not set.
+    pub fn dns_timeout(mut self, timeout: Duration) -> Self {
+        self.config.dns_timeout = Some(timeout);
+        self
+    }
+
+    /// Set request body write timeout
+    ///
+    /// Write timeout is the max time peer could stay without accepting
+    /// request body data, it is reset on each write progress. If it expires,
+    /// request fails with `SendRequestError::Timeout` in `TimeoutPhase::Write`
+    /// phase. Request timeout still applies to the whole request.
+    /// By default write timeout is not set.
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.config.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Set max time to wait for `100 Continue` interim response
+    ///
+    /// Applies to requests with `Expect: 100-continue` header, if server
+    /// does not respond in time request body is sent anyway.
+    /// By default expect continue timeout is set to 1 second.
+    pub fn expect_continue_timeout(mut self, timeout: Duration) -> Self {
+        self.config.expect_continue_timeout = timeout;
+        self
+    }
+
+    /// Set max size of response payload.
+    ///
+    /// Size of decompressed payload is checked. Request fails with
+    /// `SendRequestError::ResponsePayloadTooLarge` if `Content-Length`
+    /// of the response exceeds the limit, otherwise response payload stream
+    /// fails with `PayloadError::TooLarge` once limit is reached, such error
+    /// converts to `SendRequestError::ResponsePayloadTooLarge` as well.
+    /// By default response size is not limited.
+    pub fn max_response_size(mut self, size: usize) -> Self {
+        self.config.max_response_size = Some(size);
+        self
+    }
+
+    /// Set max size of response headers.
+    ///
+    /// Size of http/1 response head, including status line, headers and
+    /// separators, is checked while response is being parsed, request fails
+    /// with `SendRequestError::ResponseHeadersTooLarge` as soon as buffered
+    /// head exceeds the limit. For http/2 responses header list size is
+    /// checked. By default size of headers is not limited.
+    pub fn max_response_header_size(mut self, size: usize) -> Self {
+        self.config.max_response_header_size = Some(size);
+        self
+    }
+
+    /// Ignore trailing data after the end of compressed response payload.
+    ///
+    /// Some servers append extra bytes after complete gzip stream, by default
+    /// such payloads fail with decompression error. In lenient mode trailing
+    /// bytes are discarded.
+    pub fn lenient_decompression(mut self) -> Self {
+        self.config.lenient_decompression = true;
+        self
+    }
+
+    /// Capture response headers in on-wire order and original case.
+    ///
+    /// Raw headers are available via `ClientResponse::raw_headers()`,
+    /// `HeaderMap` of the response is not affected. Http/2 header names
+    /// are always lowercase. By default raw headers are not captured.
+    pub fn preserve_raw_headers(mut self) -> Self {
+        self.config.preserve_raw_headers = true;
+        self
+    }
+
+    /// Reject http/1 responses with conflicting framing headers.
+    ///
+    /// Response with both `Content-Length` and chunked `Transfer-Encoding`
+    /// headers is decoded as chunked and `Content-Length` header is dropped.
+    /// In strict mode such responses fail with `SendRequestError::Response`
+    /// error. By default strict mode is disabled.
+    pub fn strict_framing(mut self) -> Self {
+        self.config.strict_framing = true;
+        self
+    }
+
+    /// Set max ratio of decompressed response payload size to compressed size.
+    ///
+    /// Protects from decompression bombs, response payload stream fails with
+    /// `PayloadError::DecompressionRatioExceeded` once decompressed payload
+    /// exceeds `ratio` times received payload, such error converts to
+    /// `SendRequestError::DecompressionRatioExceeded`. Set to 0 to disable
+    /// the check. By default ratio is 1000.
+    pub fn max_decompression_ratio(mut self, ratio: usize) -> Self {
+        self.config.max_decompression_ratio =
+            if ratio == 0 { None } else { Some(ratio) };
+        self
+    }
+
+    /// Use custom dns resolver.
+    ///
+    /// Resolver is used by connector for opening new connections,
+    /// if request does not have explicit address. It is supported by
+    /// default connector only.
+    ///
+    /// ```rust
+    /// use std::net::SocketAddr;
+    /// use futures::future::ok;
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    ///     let client = Client::build()
+    ///         .resolver(move |_: &str, _: u16| ok(vec![addr]))
+    ///         .finish();
+    /// }
+    /// ```
+    pub fn resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: Resolve + 'static,
+    {
+        self.config.resolver = Some(Rc::new(resolver));
+        self
+    }
+
+    /// Send requests through proxy server.
+    ///
+    /// Proxy is used by connector for opening new connections, hosts
+    /// excluded with `Proxy::no_proxy()` are connected directly.
+    /// Custom connectors should handle `Connect::proxy` themselves.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.config.proxy = Some(Rc::new(proxy));
+        self
+    }
+
+    /// Set total number of simultaneous connections per type of scheme.
+    ///
+    /// If limit is reached, new requests wait for available connection,
+    /// connect timeout covers wait time. It is supported by default
+    /// connector only. Default limit is 100, 0 disables limit.
+    pub fn max_connections(mut self, limit: usize) -> Self {
+        self.config.max_connections = Some(limit);
+        self
+    }
+
+    /// Set number of simultaneous connections per host.
+    ///
+    /// If limit is reached, new requests wait for available connection,
+    /// connect timeout covers wait time. It is supported by default
+    /// connector only. By default per host limit is not set.
+    pub fn max_connections_per_host(mut self, limit: usize) -> Self {
+        self.config.max_connections_per_host = Some(limit);
+        self
+    }
+
+    /// Set keep-alive period for idle connections.
+    ///
+    /// Idle connection is closed if it is not used during this period.
+    /// It is supported by default connector only. Default keep-alive
+    /// period is 15 seconds.
+    pub fn keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.config.keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Set http protocol selection mode.
+    ///
+    /// Http/2 connection is shared by concurrent requests to the same host.
+    /// It is supported by default connector only, see
+    /// `Connector::protocol()` for details. Default mode is `Auto`.
+    pub fn protocol(mut self, protocol: ProtocolMode) -> Self {
+        self.config.protocol = Some(protocol);
+        self
+    }
+
+    /// Race connection attempts to resolved addresses (happy eyeballs).
+    ///
+    /// If host resolves to several addresses, next attempt is started
+    /// after `delay` or as soon as previous attempt fails, first
+    /// established connection is used. It is supported by default
+    /// connector only. By default addresses are tried sequentially.
+    pub fn happy_eyeballs(mut self, delay: Duration) -> Self {
+        self.config.happy_eyeballs = Some(delay);
+        self
+    }
+
+    /// Set `TCP_NODELAY` option of opened connections.
+    ///
+    /// Nagle's algorithm delays small writes, it is disabled by default
+    /// for lower request latency. It is supported by default connector
+    /// only.
+    pub fn tcp_nodelay(mut self, nodelay: bool) -> Self {
+        self.config.tcp_nodelay = Some(nodelay);
+        self
+    }
+
+    /// Enable tcp keep-alive (`SO_KEEPALIVE`) of opened connections.
+    ///
+    /// Keep-alive probes are sent after connection has been idle for
+    /// `idle` period. It is supported by default connector only.
+    /// By default tcp keep-alive is disabled.
+    pub fn tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.config.tcp_keepalive = Some(idle);
+        self
+    }
+
+    /// Set additional trusted root certificates.
+    ///
+    /// Certificates are used by default secure connector in addition
+    /// to system roots, see `RootCertificates` for details. It is
+    /// supported by default connector only.
+    pub fn root_certificates(mut self, certs: RootCertificates) -> Self {
+        self.config.root_certs = Some(certs);
+        self
+    }
+
+    /// Set default client certificate for mutual TLS.
+    ///
+    /// Identity is presented to servers that request client certificate.
+    /// If server rejects the certificate with tls alert, request fails with
+    /// `ConnectError::ClientCertificate`. With tls 1.3 server could
+    /// close connection before alert is received, in that case request
+    /// fails with io error. It is supported by default connector only.
+    pub fn client_identity(mut self, identity: ClientIdentity) -> Self {
+        self.config.identity = Some(identity);
+        self
+    }
+
+    /// Set client certificate for requests to specific host.
+    ///
+    /// Host identity is used instead of default identity if host name
+    /// of request url matches `host`, host names are case-insensitive.
+    pub fn host_identity<T: Into<String>>(
+        mut self,
+        host: T,
+        identity: ClientIdentity,
+    ) -> Self {
+        self.config.host_identities.insert(host.into(), identity);
+        self
+    }
+
+    /// Propagate W3C trace context with requests.
+    ///
+    /// Provider is called for each request, if it returns context,
+    /// `traceparent` and `tracestate` headers are added to the request.
+    /// `traceparent` header set by caller is not overridden.
+    /// Provider could obtain trace context of the current span, for
+    /// example from `tracing` or `opentelemetry`.
+    pub fn trace_context<F>(mut self, provider: F) -> Self
+    where
+        F: Fn() -> Option<TraceContext> + 'static,
+    {
+        self.config.trace_context = Some(Rc::new(provider));
+        self
+    }
+
+    /// Add request interceptor.
     ///
-    /// Redirects are allowed by default.
+    /// Interceptors are called before request is sent, in order of
+    /// registration. If interceptor returns an error, request fails
+    /// without opening a connection.
+    pub fn request_interceptor<T>(mut self, interceptor: T) -> Self
+    where
+        T: RequestInterceptor + 'static,
+    {
+        self.config.request_interceptors.push(Rc::new(interceptor));
+        self
+    }
+
+    /// Add response interceptor.
+    ///
+    /// Interceptors are called once response head is received, in order
+    /// of registration. Interceptor could replace response or error.
+    pub fn response_interceptor<T>(mut self, interceptor: T) -> Self
+    where
+        T: ResponseInterceptor + 'static,
+    {
+        self.config.response_interceptors.push(Rc::new(interceptor));
+        self
+    }
+
+    /// Sign requests with request signer.
+    ///
+    /// Signer is called after request interceptors, right before request
+    /// is sent. If signer returns an error, request fails with this error.
+    pub fn signer<T>(mut self, signer: T) -> Self
+    where
+        T: RequestSigner + 'static,
+    {
+        self.config.signer = Some(Rc::new(signer));
+        self
+    }
+
+    #[cfg(feature = "body-serializer")]
+    /// Register request body serializer for content type.
+    ///
+    /// Serializer is used by `ClientRequest::send_typed()` for requests
+    /// with matching media type, content type parameters are ignored.
+    /// By default serializers for `application/json` and
+    /// `application/x-www-form-urlencoded` are registered.
+    pub fn body_serializer<T>(mut self, content_type: &str, serializer: T) -> Self
+    where
+        T: super::BodySerializer + 'static,
+    {
+        self.config
+            .serializers
+            .register(content_type, Rc::new(serializer));
+        self
+    }
+
+    /// Set request completion callback.
+    ///
+    /// Callback is called exactly once for each sent request, when
+    /// `SendClientRequest` future resolves to a response, an error or a
+    /// timeout. Redirects and retries are reported as one request.
+    pub fn on_complete<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&RequestMetrics<'_>) + 'static,
+    {
+        self.config.on_complete = Some(Rc::new(f));
+        self
+    }
+
+    #[cfg(feature = "cookie")]
+    /// Use cookie jar to store received cookies and send them back.
+    ///
+    /// Jar is consulted for each request, including redirect hops.
+    pub fn cookie_jar(mut self, jar: Arc<Mutex<CookieJar>>) -> Self {
+        self.config.cookie_jar = Some(jar);
+        self
+    }
+
+    #[cfg(feature = "test-util")]
+    /// Use custom timer for request timeouts.
+    ///
+    /// Allows to trigger request timeouts in tests without waiting,
+    /// see `VirtualClock`. By default runtime timer is used.
+    pub fn timer<T: Timer + 'static>(mut self, timer: T) -> Self {
+        self.config.timer = Some(Rc::new(timer));
+        self
+    }
+
+    /// Retry failed requests according to the policy.
+    ///
+    /// By default requests are not retried.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = Some(policy);
+        self
+    }
+
+    /// Limit outbound request rate per host.
+    ///
+    /// Requests above the limit wait for the rate limiter before connection
+    /// is acquired. If wait exceeds request timeout, request fails with
+    /// `SendRequestError::Timeout`. By default rate is not limited.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.config.rate_limiter = Some(RateLimiter::new(limit));
+        self
+    }
+
+    /// Stop sending requests to failing hosts.
+    ///
+    /// Requests to the host with open circuit breaker fail with
+    /// `SendRequestError::CircuitOpen`. Breaker state is shared by clones
+    /// of the client. By default circuit breaker is not used.
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.config.circuit_breaker = Some(CircuitBreakers::new(breaker));
+        self
+    }
+
+    /// Rotate `User-Agent` header of requests from the pool.
+    ///
+    /// Value is set only if request does not have `User-Agent` header,
+    /// rotation takes precedence over default `User-Agent` header.
+    pub fn user_agents(mut self, agents: UserAgents) -> Self {
+        self.config.user_agents = Some(UserAgentRotation::new(agents));
+        self
+    }
+
+    /// Do not follow redirects, even if max number of redirects is set.
     pub fn disable_redirects(mut self) -> Self {
         self.allow_redirects = false;
         self
     }
 
-    /// Set max number of redirects.
+    /// Follow redirect responses, up to `num` hops.
     ///
-    /// Max redirects is set to 10 by default.
+    /// `303 See Other` responses are followed with `GET` request without body,
+    /// `307` and `308` preserve method and body. Streaming request bodies
+    /// could not be sent again, such requests fail with
+    /// `SendRequestError::BodyNotReplayable`. `Authorization`, `Cookie` and
+    /// `Proxy-Authorization` headers are not sent to other origins.
+    ///
+    /// Exceeding `num` hops fails with `SendRequestError::TooManyRedirects`,
+    /// redirect to already visited url with the same method fails with
+    /// `SendRequestError::RedirectLoop` error. Redirects are not followed
+    /// by default.
     pub fn max_redirects(mut self, num: usize) -> Self {
         self.max_redirects = num;
         self
     }
 
+    /// Fail redirect responses instead of returning them.
+    ///
+    /// Any `3xx` response except `304 Not Modified` fails with
+    /// `SendRequestError::UnexpectedRedirect` error, which contains
+    /// response status and `Location` header. Redirects are not followed,
+    /// even if max number of redirects is set.
+    pub fn deny_redirects(mut self) -> Self {
+        self.config.deny_redirects = true;
+        self
+    }
+
     /// Do not add default request headers.
     /// By default `Date` and `User-Agent` headers are set.
     pub fn no_default_headers(mut self) -> Self {
@@ -94,6 +583,23 @@ impl ClientBuilder {
 
     /// Add default header. Headers added by this method
     /// get added to every request.
+    ///
+    /// Default headers are merged into request before it is sent,
+    /// headers set on request take precedence. Headers managed by
+    /// the client, like `Content-Length`, `Transfer-Encoding` or `Host`,
+    /// are ignored.
+    ///
+    /// ```rust
+    /// use ntex::http::{client::Client, header};
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let client = Client::build()
+    ///         .header(header::USER_AGENT, "my-service/1.0")
+    ///         .header("x-api-version", "2")
+    ///         .finish();
+    /// }
+    /// ```
     pub fn header<K, V>(mut self, key: K, value: V) -> Self
     where
         HeaderName: TryFrom<K>,
@@ -102,6 +608,9 @@ impl ClientBuilder {
         <HeaderValue as TryFrom<V>>::Error: fmt::Debug + Into<HttpError>,
     {
         match HeaderName::try_from(key) {
+            Ok(key) if RESTRICTED_HEADERS.contains(&key) => {
+                log::error!("Header {:?} could not be set as default header", key)
+            }
             Ok(key) => match HeaderValue::try_from(value) {
                 Ok(value) => {
                     self.config.headers.append(key, value);
@@ -137,7 +646,57 @@ impl ClientBuilder {
     }
 
     /// Finish build process and create `Client` instance.
-    pub fn finish(self) -> Client {
+    pub fn finish(mut self) -> Client {
+        if self.allow_redirects {
+            self.config.max_redirects = self.max_redirects;
+        }
+        if !self.custom_connector {
+            let config = &self.config;
+            if config.max_connections.is_some()
+                || config.max_connections_per_host.is_some()
+                || config.keep_alive_timeout.is_some()
+                || config.protocol.is_some()
+                || config.happy_eyeballs.is_some()
+                || config.tcp_nodelay.is_some()
+                || config.tcp_keepalive.is_some()
+                || config.root_certs.is_some()
+                || config.identity.is_some()
+                || !config.host_identities.is_empty()
+            {
+                let mut connector = Connector::default();
+                if let Some(limit) = config.max_connections {
+                    connector = connector.limit(limit);
+                }
+                if let Some(limit) = config.max_connections_per_host {
+                    connector = connector.limit_per_host(limit);
+                }
+                if let Some(timeout) = config.keep_alive_timeout {
+                    connector = connector.keep_alive(timeout);
+                }
+                if let Some(protocol) = config.protocol {
+                    connector = connector.protocol(protocol);
+                }
+                if let Some(delay) = config.happy_eyeballs {
+                    connector = connector.happy_eyeballs(delay);
+                }
+                if let Some(nodelay) = config.tcp_nodelay {
+                    connector = connector.tcp_nodelay(nodelay);
+                }
+                if let Some(idle) = config.tcp_keepalive {
+                    connector = connector.tcp_keepalive(idle);
+                }
+                if let Some(certs) = config.root_certs.clone() {
+                    connector = connector.root_certificates(certs);
+                }
+                if let Some(identity) = config.identity.clone() {
+                    connector = connector.client_identity(identity);
+                }
+                for (host, identity) in &config.host_identities {
+                    connector = connector.host_identity(host.clone(), identity.clone());
+                }
+                self.config.connector = Box::new(ConnectorWrapper(connector.finish()));
+            }
+        }
         Client(Rc::new(self.config))
     }
 }
@@ -158,6 +717,20 @@ mod tests {
         assert_eq!(builder.max_redirects, 10);
     }
 
+    #[ntex_rt::test]
+    async fn restricted_headers() {
+        let builder = ClientBuilder::new()
+            .header(header::CONTENT_LENGTH, "10")
+            .header(header::TRANSFER_ENCODING, "chunked")
+            .header(header::USER_AGENT, "test");
+        assert!(!builder.config.headers.contains_key(header::CONTENT_LENGTH));
+        assert!(!builder
+            .config
+            .headers
+            .contains_key(header::TRANSFER_ENCODING));
+        assert!(builder.config.headers.contains_key(header::USER_AGENT));
+    }
+
     #[ntex_rt::test]
     async fn client_basic_auth() {
         let client = ClientBuilder::new().basic_auth("username", Some("password"));
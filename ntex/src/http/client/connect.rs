@@ -1,17 +1,23 @@
 use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use std::{fmt, io, mem, net};
 
 use crate::codec::{AsyncRead, AsyncWrite, Framed};
 use crate::http::body::Body;
-use crate::http::h1::ClientCodec;
-use crate::http::{RequestHeadType, ResponseHead};
+use crate::http::h1::{ClientCodec, RawHeaders};
+use crate::http::header;
+use crate::http::{RequestHead, RequestHeadType, ResponseHead, Uri};
+use crate::rt::time::{delay_for, timeout};
 use crate::Service;
 
-use super::error::{ConnectError, SendRequestError};
+use super::breaker::BreakerGuard;
+use super::error::{ConnectError, SendRequestError, TimeoutPhase};
 use super::response::ClientResponse;
-use super::{Connect as ClientConnect, Connection};
+use super::sender::{RequestHeaders, RequestOptions, RequestPhase, SendContext};
+use super::{ClientConfig, Connect as ClientConnect, Connection, Proxy};
 
 pub(super) struct ConnectorWrapper<T>(pub(crate) T);
 
@@ -21,6 +27,8 @@ pub(super) trait Connect {
         head: RequestHeadType,
         body: Body,
         addr: Option<net::SocketAddr>,
+        config: &ClientConfig,
+        phase: RequestPhase,
     ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>>;
 
     /// Send request, returns Response and Framed
@@ -28,6 +36,8 @@ pub(super) trait Connect {
         &self,
         head: RequestHeadType,
         addr: Option<net::SocketAddr>,
+        config: &ClientConfig,
+        phase: RequestPhase,
     ) -> Pin<
         Box<
             dyn Future<
@@ -38,6 +48,16 @@ pub(super) trait Connect {
             >,
         >,
     >;
+
+    /// Open connection to the host and release it to the connection pool
+    fn preconnect(
+        &self,
+        uri: Uri,
+        config: &ClientConfig,
+    ) -> Pin<Box<dyn Future<Output = Result<(), SendRequestError>>>>;
+
+    /// Close idle connections, connector does not pool connections anymore
+    fn close(&self);
 }
 
 impl<T> Connect for ConnectorWrapper<T>
@@ -51,31 +71,76 @@ where
 {
     fn send_request(
         &self,
-        head: RequestHeadType,
+        mut head: RequestHeadType,
         body: Body,
         addr: Option<net::SocketAddr>,
+        config: &ClientConfig,
+        phase: RequestPhase,
     ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+        let uri = head.as_ref().uri.clone();
+        let breaker = match circuit_breaker(&uri, config) {
+            Ok(breaker) => breaker,
+            Err(e) => return Box::pin(async move { Err(e) }),
+        };
+        let (proxy, absolute_form) = proxy(&mut head, config);
+        let wait = rate_limit(&uri, config);
+
         // connect to the host
         let fut = self.0.call(ClientConnect {
-            uri: head.as_ref().uri.clone(),
+            uri: uri.clone(),
             addr,
+            fallback_addrs: fallback_addrs(&head, addr),
+            pool_key: pool_key(&head),
+            resolver: config.resolver.clone(),
+            dns_timeout: config.dns_timeout,
+            proxy,
         });
+        let connect_timeout = config.connect_timeout;
+        let mut ctx = SendContext::new(phase.clone(), head.as_ref(), config);
+        ctx.absolute_form = absolute_form;
 
         Box::pin(async move {
-            let connection = fut.await?;
+            phase.set(TimeoutPhase::Connect);
+            if let Some(wait) = wait {
+                delay_for(wait).await;
+            }
+            let start = Instant::now();
+            let connection = connect(fut, connect_timeout).await?;
+            let connected = Instant::now();
+            let peer_addr = connection.peer_addr();
+            let tls_info = connection.tls_info();
+            phase.set(TimeoutPhase::Request);
 
             // send request
-            connection
-                .send_request(head, body)
+            let (head, payload) = connection
+                .send_request_with(head, body, ctx)
                 .await
-                .map(|(head, payload)| ClientResponse::new(head, payload))
+                .map_err(SendRequestError::tls_alert)?;
+            let request_headers = head.extensions_mut().remove::<RequestHeaders>();
+            let raw_headers = head.extensions_mut().remove::<RawHeaders>();
+
+            let mut res = ClientResponse::new(head, payload).set_url(uri);
+            res.peer_addr = peer_addr;
+            res.tls_info = tls_info;
+            res.request_headers = request_headers.map(|h| h.0);
+            res.raw_headers = raw_headers.map(|h| h.0);
+            res.timings.start = start;
+            res.timings.connect = connected - start;
+            res.timings.first_byte = Instant::now();
+            res.timings.end = res.timings.first_byte;
+            if let Some(breaker) = breaker {
+                breaker.success();
+            }
+            Ok(res)
         })
     }
 
     fn open_tunnel(
         &self,
-        head: RequestHeadType,
+        mut head: RequestHeadType,
         addr: Option<net::SocketAddr>,
+        config: &ClientConfig,
+        phase: RequestPhase,
     ) -> Pin<
         Box<
             dyn Future<
@@ -86,22 +151,156 @@ where
             >,
         >,
     > {
+        let (proxy, absolute_form) = proxy(&mut head, config);
+        let wait = rate_limit(&head.as_ref().uri, config);
+
         // connect to the host
         let fut = self.0.call(ClientConnect {
             uri: head.as_ref().uri.clone(),
             addr,
+            fallback_addrs: fallback_addrs(&head, addr),
+            pool_key: pool_key(&head),
+            resolver: config.resolver.clone(),
+            dns_timeout: config.dns_timeout,
+            proxy,
         });
+        let connect_timeout = config.connect_timeout;
+        let mut ctx = SendContext::new(phase.clone(), head.as_ref(), config);
+        ctx.absolute_form = absolute_form;
 
         Box::pin(async move {
-            let connection = fut.await?;
+            phase.set(TimeoutPhase::Connect);
+            if let Some(wait) = wait {
+                delay_for(wait).await;
+            }
+            let connection = connect(fut, connect_timeout).await?;
+            phase.set(TimeoutPhase::Request);
 
             // send request
-            let (head, framed) = connection.open_tunnel(head).await?;
+            let (head, framed) = connection.open_tunnel_with(head, ctx).await?;
 
             let framed = framed.map_io(|io| BoxedSocket(Box::new(Socket(io))));
             Ok((head, framed))
         })
     }
+
+    fn preconnect(
+        &self,
+        uri: Uri,
+        config: &ClientConfig,
+    ) -> Pin<Box<dyn Future<Output = Result<(), SendRequestError>>>> {
+        let mut head = RequestHead::default();
+        head.uri = uri.clone();
+        let mut head = RequestHeadType::Owned(head);
+        let (proxy, _) = proxy(&mut head, config);
+
+        // connect to the host
+        let fut = self.0.call(ClientConnect {
+            uri,
+            addr: None,
+            fallback_addrs: Vec::new(),
+            pool_key: None,
+            resolver: config.resolver.clone(),
+            dns_timeout: config.dns_timeout,
+            proxy,
+        });
+        let connect_timeout = config.connect_timeout;
+
+        Box::pin(async move {
+            let connection = connect(fut, connect_timeout).await?;
+            connection.release();
+            Ok(())
+        })
+    }
+
+    fn close(&self) {
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let _ = self.0.poll_shutdown(&mut cx, false);
+    }
+}
+
+/// Fallback addresses of the request, used only with explicit address,
+/// redirect to other origin drops request address
+fn fallback_addrs(
+    head: &RequestHeadType,
+    addr: Option<net::SocketAddr>,
+) -> Vec<net::SocketAddr> {
+    if addr.is_some() {
+        RequestOptions::get(head.as_ref(), |opts| opts.fallback_addrs.clone())
+    } else {
+        Vec::new()
+    }
+}
+
+/// Connection pool key of the request
+fn pool_key(head: &RequestHeadType) -> Option<String> {
+    RequestOptions::get(head.as_ref(), |opts| opts.pool_key.clone())
+}
+
+/// Proxy of the request and whether request target is written
+/// in absolute-form, it is so if request is sent to http proxy as is
+fn proxy(
+    head: &mut RequestHeadType,
+    config: &ClientConfig,
+) -> (Option<Rc<Proxy>>, bool) {
+    let proxy = config
+        .proxy
+        .as_ref()
+        .filter(|proxy| proxy.is_proxied(&head.as_ref().uri))
+        .cloned();
+
+    match proxy {
+        Some(ref proxy) if proxy.is_forwarding(&head.as_ref().uri) => {
+            if let Some(auth) = proxy.authorization() {
+                if !head.has_header(&header::PROXY_AUTHORIZATION) {
+                    head.insert_header(header::PROXY_AUTHORIZATION, auth);
+                }
+            }
+            (Some(proxy.clone()), true)
+        }
+        _ => (proxy, false),
+    }
+}
+
+/// Circuit breaker guard of the request host
+fn circuit_breaker(
+    uri: &Uri,
+    config: &ClientConfig,
+) -> Result<Option<BreakerGuard>, SendRequestError> {
+    match config.circuit_breaker {
+        Some(ref breakers) => match breakers.acquire(uri) {
+            Some(guard) => Ok(Some(guard)),
+            None => Err(SendRequestError::CircuitOpen),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Time to wait for the rate limiter of the request host
+fn rate_limit(uri: &Uri, config: &ClientConfig) -> Option<Duration> {
+    config
+        .rate_limiter
+        .as_ref()
+        .and_then(|limiter| limiter.acquire(uri))
+}
+
+/// Wait for connection, connect timeout covers pool wait time as well
+async fn connect<F, C>(
+    fut: F,
+    connect_timeout: Option<Duration>,
+) -> Result<C, SendRequestError>
+where
+    F: Future<Output = Result<C, ConnectError>>,
+{
+    if let Some(t) = connect_timeout {
+        match timeout(t, fut).await {
+            Ok(res) => Ok(res?),
+            Err(_) => Err(SendRequestError::ConnectTimeout),
+        }
+    } else {
+        Ok(fut.await?)
+    }
 }
 
 trait AsyncSocket {
@@ -1,4 +1,4 @@
-use std::{fmt, time};
+use std::{fmt, net, time};
 
 use bytes::Bytes;
 use futures::future::{err, Either, Future, FutureExt, LocalBoxFuture, Ready};
@@ -13,6 +13,8 @@ use crate::http::Protocol;
 
 use super::error::SendRequestError;
 use super::pool::Acquired;
+use super::sender::SendContext;
+use super::tls::TlsInfo;
 use super::{h1proto, h2proto};
 
 pub(super) enum ConnectionType<Io> {
@@ -20,12 +22,29 @@ pub(super) enum ConnectionType<Io> {
     H2(SendRequest<Bytes>),
 }
 
+/// Details of established connection reported by the connector
+#[derive(Clone, Debug, Default)]
+pub(super) struct ConnectInfo {
+    pub(super) peer_addr: Option<net::SocketAddr>,
+    pub(super) tls: Option<TlsInfo>,
+}
+
 pub trait Connection {
     type Io: AsyncRead + AsyncWrite + Unpin;
     type Future: Future<Output = Result<(ResponseHead, Payload), SendRequestError>>;
 
     fn protocol(&self) -> Protocol;
 
+    /// Remote address of the connection, if known
+    fn peer_addr(&self) -> Option<net::SocketAddr> {
+        None
+    }
+
+    /// Tls session details of the connection, if connection is secure
+    fn tls_info(&self) -> Option<TlsInfo> {
+        None
+    }
+
     /// Send request and body
     fn send_request<B: MessageBody + 'static, H: Into<RequestHeadType>>(
         self,
@@ -33,12 +52,48 @@ pub trait Connection {
         body: B,
     ) -> Self::Future;
 
+    #[doc(hidden)]
+    /// Send request and body with per-send state of the client
+    fn send_request_with<B: MessageBody + 'static, H: Into<RequestHeadType>>(
+        self,
+        head: H,
+        body: B,
+        ctx: SendContext,
+    ) -> Self::Future
+    where
+        Self: Sized,
+    {
+        let _ = ctx;
+        self.send_request(head, body)
+    }
+
     type TunnelFuture: Future<
         Output = Result<(ResponseHead, Framed<Self::Io, ClientCodec>), SendRequestError>,
     >;
 
     /// Send request, returns Response and Framed
     fn open_tunnel<H: Into<RequestHeadType>>(self, head: H) -> Self::TunnelFuture;
+
+    #[doc(hidden)]
+    /// Send request with per-send state of the client, returns Response and Framed
+    fn open_tunnel_with<H: Into<RequestHeadType>>(
+        self,
+        head: H,
+        ctx: SendContext,
+    ) -> Self::TunnelFuture
+    where
+        Self: Sized,
+    {
+        let _ = ctx;
+        self.open_tunnel(head)
+    }
+
+    /// Release connection to the connection pool without sending a request
+    fn release(self)
+    where
+        Self: Sized,
+    {
+    }
 }
 
 pub(super) trait ConnectionLifetime:
@@ -119,18 +174,40 @@ where
         }
     }
 
+    fn peer_addr(&self) -> Option<net::SocketAddr> {
+        self.pool.as_ref().and_then(|pool| pool.peer_addr())
+    }
+
+    fn tls_info(&self) -> Option<TlsInfo> {
+        self.pool.as_ref().and_then(|pool| pool.tls_info())
+    }
+
+    fn release(self) {
+        IoConnection::release(self)
+    }
+
     fn send_request<B: MessageBody + 'static, H: Into<RequestHeadType>>(
+        self,
+        head: H,
+        body: B,
+    ) -> Self::Future {
+        self.send_request_with(head, body, SendContext::default())
+    }
+
+    fn send_request_with<B: MessageBody + 'static, H: Into<RequestHeadType>>(
         mut self,
         head: H,
         body: B,
+        ctx: SendContext,
     ) -> Self::Future {
+        let head = head.into();
         match self.io.take().unwrap() {
             ConnectionType::H1(io) => {
-                h1proto::send_request(io, head.into(), body, self.created, self.pool)
+                h1proto::send_request(io, head, body, ctx, self.created, self.pool)
                     .boxed_local()
             }
             ConnectionType::H2(io) => {
-                h2proto::send_request(io, head.into(), body, self.created, self.pool)
+                h2proto::send_request(io, head, body, ctx, self.created, self.pool)
                     .boxed_local()
             }
         }
@@ -145,10 +222,18 @@ where
     >;
 
     /// Send request, returns Response and Framed
-    fn open_tunnel<H: Into<RequestHeadType>>(mut self, head: H) -> Self::TunnelFuture {
+    fn open_tunnel<H: Into<RequestHeadType>>(self, head: H) -> Self::TunnelFuture {
+        self.open_tunnel_with(head, SendContext::default())
+    }
+
+    fn open_tunnel_with<H: Into<RequestHeadType>>(
+        mut self,
+        head: H,
+        ctx: SendContext,
+    ) -> Self::TunnelFuture {
         match self.io.take().unwrap() {
             ConnectionType::H1(io) => {
-                Either::Left(h1proto::open_tunnel(io, head.into()).boxed_local())
+                Either::Left(h1proto::open_tunnel(io, head.into(), ctx).boxed_local())
             }
             ConnectionType::H2(io) => {
                 if let Some(mut pool) = self.pool.take() {
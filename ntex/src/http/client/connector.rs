@@ -1,19 +1,26 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 use std::time::Duration;
+use std::{fmt, io};
 
-use futures::future::{err, Either, Ready};
+use futures::future::{err, Either, FutureExt, LocalBoxFuture, Ready, TryFutureExt};
 
 use crate::codec::{AsyncRead, AsyncWrite};
 use crate::connect::{self, Connect as TcpConnect, Connector as TcpConnector};
 use crate::http::{Protocol, Uri};
+use crate::rt::net::TcpStream;
+use crate::rt::time::timeout;
 use crate::service::{apply_fn, boxed, Service};
 use crate::util::timeout::{TimeoutError, TimeoutService};
 
-use super::connection::Connection;
+use super::connection::{ConnectInfo, Connection};
 use super::error::ConnectError;
 use super::pool::ConnectionPool;
-use super::Connect;
+use super::tls::TlsInfo;
+use super::{ClientIdentity, Connect, Proxy, RootCertificates};
 
 #[cfg(feature = "openssl")]
 use crate::connect::openssl::SslConnector as OpensslConnector;
@@ -23,8 +30,75 @@ use crate::connect::rustls::ClientConfig;
 #[cfg(feature = "rustls")]
 use std::sync::Arc;
 
-type BoxedConnector =
-    boxed::BoxService<TcpConnect<Uri>, (Box<dyn Io>, Protocol), ConnectError>;
+type BoxedConnector = boxed::BoxService<
+    TcpConnect<Uri>,
+    (Box<dyn Io>, Protocol, ConnectInfo),
+    ConnectError,
+>;
+
+/// Secure connector and handshake over established connection
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+type TlsConnector = (BoxedConnector, TlsUpgrade);
+
+/// Secure connection handshake over established connection
+type TlsUpgrade = Rc<
+    dyn Fn(
+        Box<dyn Io>,
+        String,
+    ) -> LocalBoxFuture<
+        'static,
+        Result<(Box<dyn Io>, Protocol, TlsInfo), ConnectError>,
+    >,
+>;
+
+/// Http protocol selection mode of the connector
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProtocolMode {
+    /// Use http/2 if it is negotiated via ALPN, http/1.1 otherwise
+    Auto,
+    /// Always use http/1.1
+    Http1Only,
+    /// Always use http/2
+    Http2Only,
+}
+
+impl ProtocolMode {
+    /// Protocol of the established connection
+    fn select(self, proto: Protocol) -> Protocol {
+        match self {
+            ProtocolMode::Auto => proto,
+            ProtocolMode::Http1Only => Protocol::Http1,
+            ProtocolMode::Http2Only => Protocol::Http2,
+        }
+    }
+}
+
+/// Custom dns resolver for http client.
+///
+/// Resolver maps host name and port to a list of socket addresses.
+/// It is implemented for async functions with the same signature.
+pub trait Resolve {
+    /// Lookup socket addresses for the host
+    fn lookup(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> LocalBoxFuture<'static, Result<Vec<SocketAddr>, io::Error>>;
+}
+
+impl<F, R> Resolve for F
+where
+    F: Fn(&str, u16) -> R,
+    R: Future<Output = Result<Vec<SocketAddr>, io::Error>> + 'static,
+{
+    fn lookup(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> LocalBoxFuture<'static, Result<Vec<SocketAddr>, io::Error>> {
+        (self)(host, port).boxed_local()
+    }
+}
 
 /// Manages http client network connectivity.
 ///
@@ -45,15 +119,69 @@ pub struct Connector {
     conn_keep_alive: Duration,
     disconnect_timeout: Duration,
     limit: usize,
+    limit_per_host: usize,
+    protocol: ProtocolMode,
+    happy_eyeballs: Option<Duration>,
+    socket: SocketOptions,
+    #[cfg_attr(not(any(feature = "openssl", feature = "rustls")), allow(dead_code))]
+    root_certs: RootCertificates,
+    #[cfg_attr(not(any(feature = "openssl", feature = "rustls")), allow(dead_code))]
+    identity: Option<ClientIdentity>,
+    #[cfg_attr(not(any(feature = "openssl", feature = "rustls")), allow(dead_code))]
+    host_identities: HashMap<String, ClientIdentity>,
+    default_ssl: bool,
+    custom_connector: bool,
     connector: BoxedConnector,
     ssl_connector: Option<BoxedConnector>,
-    #[allow(dead_code)]
+    ssl_upgrade: Option<TlsUpgrade>,
     resolver: connect::AsyncResolver,
 }
 
+/// Socket options of opened tcp connections
+#[derive(Copy, Clone, Debug)]
+struct SocketOptions {
+    nodelay: bool,
+    keepalive: Option<Duration>,
+}
+
+impl SocketOptions {
+    fn apply(&self, io: &TcpStream) {
+        if let Err(e) = io.set_nodelay(self.nodelay) {
+            error!("Can not set TCP_NODELAY: {:?}", e);
+        }
+        if let Some(keepalive) = self.keepalive {
+            if let Err(e) = io.set_keepalive(Some(keepalive)) {
+                error!("Can not set SO_KEEPALIVE: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Box plain tcp connector
+fn tcp_connector(connector: TcpConnector<Uri>, socket: SocketOptions) -> BoxedConnector {
+    boxed::service(
+        connector
+            .map(move |io| {
+                socket.apply(&io);
+                let info = ConnectInfo {
+                    peer_addr: io.peer_addr().ok(),
+                    tls: None,
+                };
+                (Box::new(io) as Box<dyn Io>, Protocol::Http1, info)
+            })
+            .map_err(ConnectError::from),
+    )
+}
+
 trait Io: AsyncRead + AsyncWrite + Unpin {}
 impl<T: AsyncRead + AsyncWrite + Unpin> Io for T {}
 
+impl fmt::Debug for dyn Io {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Io")
+    }
+}
+
 impl Default for Connector {
     fn default() -> Self {
         Connector::new(connect::default_resolver())
@@ -62,45 +190,158 @@ impl Default for Connector {
 
 impl Connector {
     pub fn new(resolver: connect::AsyncResolver) -> Connector {
+        let socket = SocketOptions {
+            nodelay: true,
+            keepalive: None,
+        };
         let conn = Connector {
-            connector: boxed::service(
-                TcpConnector::new(resolver.clone())
-                    .map(|io| (Box::new(io) as Box<dyn Io>, Protocol::Http1))
-                    .map_err(ConnectError::from),
-            ),
+            connector: tcp_connector(TcpConnector::new(resolver.clone()), socket),
             ssl_connector: None,
+            ssl_upgrade: None,
             timeout: Duration::from_secs(1),
             conn_lifetime: Duration::from_secs(75),
             conn_keep_alive: Duration::from_secs(15),
             disconnect_timeout: Duration::from_millis(3000),
             limit: 100,
+            limit_per_host: 0,
+            protocol: ProtocolMode::Auto,
+            happy_eyeballs: None,
+            socket,
+            root_certs: RootCertificates::default(),
+            identity: None,
+            host_identities: HashMap::new(),
+            default_ssl: false,
+            custom_connector: false,
             resolver,
         };
 
+        conn.default_ssl()
+    }
+
+    /// Use default secure connector, ALPN protocols depend on protocol mode
+    fn default_ssl(self) -> Self {
         #[cfg(feature = "openssl")]
         {
-            use crate::connect::openssl::SslMethod;
-
-            let mut ssl = OpensslConnector::builder(SslMethod::tls()).unwrap();
-            let _ = ssl
-                .set_alpn_protos(b"\x02h2\x08http/1.1")
-                .map_err(|e| error!("Can not set ALPN protocol: {:?}", e));
-            conn.openssl(ssl.build())
+            let default = openssl_connector(
+                self.openssl_config(self.identity.as_ref()),
+                &self.resolver,
+                self.happy_eyeballs,
+                self.socket,
+            );
+            let hosts = self
+                .host_identities
+                .iter()
+                .map(|(host, identity)| {
+                    let conn = openssl_connector(
+                        self.openssl_config(Some(identity)),
+                        &self.resolver,
+                        self.happy_eyeballs,
+                        self.socket,
+                    );
+                    (host.clone(), conn)
+                })
+                .collect();
+            self.set_default_ssl(default, hosts)
         }
         #[cfg(all(not(feature = "openssl"), feature = "rustls"))]
         {
-            let protos = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
-            let mut config = ClientConfig::new();
-            config.set_protocols(&protos);
-            config
-                .root_store
-                .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
-            conn.rustls(Arc::new(config))
+            let default = rustls_connector(
+                self.rustls_config(self.identity.as_ref()),
+                &self.resolver,
+                self.happy_eyeballs,
+                self.socket,
+            );
+            let hosts = self
+                .host_identities
+                .iter()
+                .map(|(host, identity)| {
+                    let conn = rustls_connector(
+                        self.rustls_config(Some(identity)),
+                        &self.resolver,
+                        self.happy_eyeballs,
+                        self.socket,
+                    );
+                    (host.clone(), conn)
+                })
+                .collect();
+            self.set_default_ssl(default, hosts)
         }
         #[cfg(not(any(feature = "openssl", feature = "rustls")))]
         {
-            conn
+            self
+        }
+    }
+
+    #[cfg(feature = "openssl")]
+    /// Openssl connector of default secure connector
+    fn openssl_config(&self, identity: Option<&ClientIdentity>) -> OpensslConnector {
+        use crate::connect::openssl::SslMethod;
+
+        let protos: &[u8] = match self.protocol {
+            ProtocolMode::Auto => b"\x02h2\x08http/1.1",
+            ProtocolMode::Http1Only => b"\x08http/1.1",
+            ProtocolMode::Http2Only => b"\x02h2",
+        };
+        let mut ssl = OpensslConnector::builder(SslMethod::tls()).unwrap();
+        let _ = ssl
+            .set_alpn_protos(protos)
+            .map_err(|e| error!("Can not set ALPN protocol: {:?}", e));
+        self.root_certs.configure_openssl(&mut ssl);
+        if let Some(identity) = identity {
+            identity.configure_openssl(&mut ssl);
+        }
+        ssl.build()
+    }
+
+    #[cfg(all(not(feature = "openssl"), feature = "rustls"))]
+    /// Rustls config of default secure connector
+    fn rustls_config(&self, identity: Option<&ClientIdentity>) -> Arc<ClientConfig> {
+        let protos = match self.protocol {
+            ProtocolMode::Auto => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+            ProtocolMode::Http1Only => vec![b"http/1.1".to_vec()],
+            ProtocolMode::Http2Only => vec![b"h2".to_vec()],
+        };
+        let mut config = ClientConfig::new();
+        config.set_protocols(&protos);
+        self.root_certs.configure_rustls(&mut config);
+        if let Some(identity) = identity {
+            identity.configure_rustls(&mut config);
         }
+        Arc::new(config)
+    }
+
+    #[cfg(any(feature = "openssl", feature = "rustls"))]
+    /// Use secure connectors, connector is selected by host name
+    fn set_default_ssl(
+        mut self,
+        default: TlsConnector,
+        hosts: HashMap<String, TlsConnector>,
+    ) -> Self {
+        let (connector, upgrade) = default;
+        if hosts.is_empty() {
+            self.ssl_connector = Some(connector);
+            self.ssl_upgrade = Some(upgrade);
+        } else {
+            let mut connectors = HashMap::new();
+            let mut upgrades = HashMap::new();
+            for (host, (connector, upgrade)) in hosts {
+                connectors.insert(host.clone(), connector);
+                upgrades.insert(host, upgrade);
+            }
+            self.ssl_connector = Some(boxed::service(HostConnector {
+                connectors,
+                default: connector,
+            }));
+            self.ssl_upgrade = Some(Rc::new(move |io, host: String| {
+                let upgrade = upgrades
+                    .get(&host.to_lowercase())
+                    .unwrap_or(&upgrade)
+                    .clone();
+                upgrade(io, host)
+            }));
+        }
+        self.default_ssl = true;
+        self
     }
 }
 
@@ -112,53 +353,129 @@ impl Connector {
         self
     }
 
+    /// Race connection attempts to host addresses (happy eyeballs).
+    ///
+    /// If host resolves to multiple addresses, addresses are interleaved by
+    /// address family and next connection attempt starts if previous one
+    /// does not succeed within `delay`. First established connection is used.
+    /// Option does not apply to custom connectors, secure connectors set by
+    /// `openssl()` or `rustls()` must be set after this option.
+    /// By default addresses are tried one by one.
+    pub fn happy_eyeballs(mut self, delay: Duration) -> Self {
+        self.happy_eyeballs = Some(delay);
+        self.default_tcp()
+    }
+
+    /// Set `TCP_NODELAY` option of opened connections.
+    ///
+    /// If enabled, Nagle's algorithm is disabled and small writes are
+    /// sent immediately. Option does not apply to custom connectors.
+    /// Enabled by default.
+    pub fn tcp_nodelay(mut self, nodelay: bool) -> Self {
+        self.socket.nodelay = nodelay;
+        self.default_tcp()
+    }
+
+    /// Enable `SO_KEEPALIVE` option of opened connections.
+    ///
+    /// `idle` is the time connection stays idle before keep-alive probes
+    /// are sent. Option does not apply to custom connectors.
+    /// By default tcp keep-alive is disabled.
+    pub fn tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.socket.keepalive = Some(idle);
+        self.default_tcp()
+    }
+
+    /// Rebuild default connectors with current tcp options,
+    /// custom connectors are kept
+    fn default_tcp(mut self) -> Self {
+        if !self.custom_connector {
+            let mut connector = TcpConnector::new(self.resolver.clone());
+            if let Some(delay) = self.happy_eyeballs {
+                connector = connector.happy_eyeballs(delay);
+            }
+            self.connector = tcp_connector(connector, self.socket);
+        }
+        if self.default_ssl {
+            self.default_ssl()
+        } else {
+            self
+        }
+    }
+
+    /// Set client certificate of default secure connector.
+    ///
+    /// Identity is used for hosts without host specific identity.
+    /// Custom secure connectors are not affected and must be set after
+    /// this option.
+    pub fn client_identity(mut self, identity: ClientIdentity) -> Self {
+        self.identity = Some(identity);
+        if self.default_ssl {
+            self.default_ssl()
+        } else {
+            self
+        }
+    }
+
+    /// Set client certificate of default secure connector for specific host.
+    ///
+    /// Host name of request url is matched case-insensitively.
+    pub fn host_identity<T: Into<String>>(
+        mut self,
+        host: T,
+        identity: ClientIdentity,
+    ) -> Self {
+        self.host_identities
+            .insert(host.into().to_lowercase(), identity);
+        if self.default_ssl {
+            self.default_ssl()
+        } else {
+            self
+        }
+    }
+
+    /// Set trusted root certificates of default secure connector.
+    ///
+    /// Certificates are added to system roots, unless strict mode is
+    /// enabled. Custom secure connectors are not affected and must be set
+    /// after this option.
+    pub fn root_certificates(mut self, certs: RootCertificates) -> Self {
+        self.root_certs = certs;
+        if self.default_ssl {
+            self.default_ssl()
+        } else {
+            self
+        }
+    }
+
     #[cfg(feature = "openssl")]
     /// Use openssl connector for secured connections.
-    pub fn openssl(self, connector: OpensslConnector) -> Self {
-        use crate::connect::openssl::OpensslConnector;
-
-        let resolver = self.resolver.clone();
-
-        const H2: &[u8] = b"h2";
-        self.secure_connector(OpensslConnector::with_resolver(connector, resolver).map(
-            |sock| {
-                let h2 = sock
-                    .ssl()
-                    .selected_alpn_protocol()
-                    .map(|protos| protos.windows(2).any(|w| w == H2))
-                    .unwrap_or(false);
-                if h2 {
-                    (sock, Protocol::Http2)
-                } else {
-                    (sock, Protocol::Http1)
-                }
-            },
-        ))
+    pub fn openssl(mut self, connector: OpensslConnector) -> Self {
+        let (connector, upgrade) = openssl_connector(
+            connector,
+            &self.resolver,
+            self.happy_eyeballs,
+            self.socket,
+        );
+        self.default_ssl = false;
+        self.ssl_connector = Some(connector);
+        self.ssl_upgrade = Some(upgrade);
+        self
     }
 
     #[cfg(feature = "rustls")]
     /// Use rustls connector for secured connections.
-    pub fn rustls(self, connector: Arc<ClientConfig>) -> Self {
-        use crate::connect::rustls::{RustlsConnector, Session};
-
-        let resolver = self.resolver.clone();
-
-        const H2: &[u8] = b"h2";
-        self.secure_connector(RustlsConnector::with_resolver(connector, resolver).map(
-            |sock| {
-                let h2 = sock
-                    .get_ref()
-                    .1
-                    .get_alpn_protocol()
-                    .map(|protos| protos.windows(2).any(|w| w == H2))
-                    .unwrap_or(false);
-                if h2 {
-                    (Box::new(sock) as Box<dyn Io>, Protocol::Http2)
-                } else {
-                    (Box::new(sock) as Box<dyn Io>, Protocol::Http1)
-                }
-            },
-        ))
+    pub fn rustls(mut self, connector: Arc<ClientConfig>) -> Self {
+        let (connector, upgrade) = rustls_connector(
+            connector,
+            &self.resolver,
+            self.happy_eyeballs,
+            self.socket,
+        );
+        self.default_ssl = false;
+        self.ssl_connector = Some(connector);
+        self.ssl_upgrade = Some(upgrade);
+        self
     }
 
     /// Set total number of simultaneous connections per type of scheme.
@@ -170,6 +487,32 @@ impl Connector {
         self
     }
 
+    /// Set number of simultaneous connections per host.
+    ///
+    /// If limit is reached, new requests wait for available connection.
+    /// If limit is 0, the connector has no per host limit.
+    /// By default per host limit is not set.
+    pub fn limit_per_host(mut self, limit: usize) -> Self {
+        self.limit_per_host = limit;
+        self
+    }
+
+    /// Set http protocol selection mode.
+    ///
+    /// In `Http2Only` mode http/2 is used for plain connections as well
+    /// (prior knowledge), requests to the same host wait for opening
+    /// connection and share it. ALPN protocols of default secure connector
+    /// are adjusted to the mode, custom secure connectors must be configured
+    /// separately. Default mode is `Auto`.
+    pub fn protocol(mut self, protocol: ProtocolMode) -> Self {
+        self.protocol = protocol;
+        if self.default_ssl {
+            self.default_ssl()
+        } else {
+            self
+        }
+    }
+
     /// Set keep-alive period for opened connection.
     ///
     /// Keep-alive period is the period between connection usage. If
@@ -216,13 +559,19 @@ impl Connector {
     {
         self.connector = boxed::service(
             connector
-                .map(|(io, proto)| (Box::new(io) as Box<dyn Io>, proto))
+                .map(|(io, proto)| {
+                    (Box::new(io) as Box<dyn Io>, proto, ConnectInfo::default())
+                })
                 .map_err(ConnectError::from),
         );
+        self.custom_connector = true;
         self
     }
 
     /// Use custom connector to open secure connections.
+    ///
+    /// Custom secure connector could not be used for connections
+    /// tunneled through proxy server.
     pub fn secure_connector<T, U>(mut self, connector: T) -> Self
     where
         U: AsyncRead + AsyncWrite + Unpin + 'static,
@@ -232,9 +581,13 @@ impl Connector {
                 Error = crate::connect::ConnectError,
             > + 'static,
     {
+        self.default_ssl = false;
+        self.ssl_upgrade = None;
         self.ssl_connector = Some(boxed::service(
             connector
-                .map(|(io, proto)| (Box::new(io) as Box<dyn Io>, proto))
+                .map(|(io, proto)| {
+                    (Box::new(io) as Box<dyn Io>, proto, ConnectInfo::default())
+                })
                 .map_err(ConnectError::from),
         ));
         self
@@ -247,16 +600,35 @@ impl Connector {
         self,
     ) -> impl Service<Request = Connect, Response = impl Connection, Error = ConnectError>
            + Clone {
-        let tcp_service = connector(self.connector, self.timeout);
+        let protocol = self.protocol;
+        let h2_only = protocol == ProtocolMode::Http2Only;
+        let tcp_connector = Rc::new(self.connector);
+        let tcp_service = connector(
+            tcp_connector.clone(),
+            tcp_connector.clone(),
+            None,
+            self.resolver.clone(),
+            self.timeout,
+            protocol,
+        );
 
         let ssl_pool = if let Some(ssl_connector) = self.ssl_connector {
-            let srv = connector(ssl_connector, self.timeout);
+            let srv = connector(
+                Rc::new(ssl_connector),
+                tcp_connector,
+                self.ssl_upgrade,
+                self.resolver,
+                self.timeout,
+                protocol,
+            );
             Some(ConnectionPool::new(
                 srv,
                 self.conn_lifetime,
                 self.conn_keep_alive,
                 self.disconnect_timeout,
                 self.limit,
+                self.limit_per_host,
+                h2_only,
             ))
         } else {
             None
@@ -269,6 +641,8 @@ impl Connector {
                 self.conn_keep_alive,
                 self.disconnect_timeout,
                 self.limit,
+                self.limit_per_host,
+                h2_only,
             ),
             ssl_pool,
         })
@@ -276,25 +650,327 @@ impl Connector {
 }
 
 fn connector(
-    connector: BoxedConnector,
+    connector: Rc<BoxedConnector>,
+    tcp_connector: Rc<BoxedConnector>,
+    ssl_upgrade: Option<TlsUpgrade>,
+    resolver: connect::AsyncResolver,
     timeout: Duration,
+    protocol: ProtocolMode,
 ) -> impl Service<
     Request = Connect,
-    Response = (Box<dyn Io>, Protocol),
+    Response = (Box<dyn Io>, Protocol, ConnectInfo),
     Error = ConnectError,
     Future = impl Unpin,
 > + Unpin {
     TimeoutService::new(
         timeout,
-        apply_fn(connector, |msg: Connect, srv| {
-            srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr))
-        })
-        .map_err(ConnectError::from),
+        apply_fn(connector, move |msg: Connect, srv| {
+            if let Some(proxy) = msg.proxy.clone() {
+                let fut = proxy_connect(
+                    msg,
+                    proxy,
+                    tcp_connector.clone(),
+                    ssl_upgrade.clone(),
+                    resolver.clone(),
+                );
+                return Either::Right(fut.boxed_local());
+            }
+
+            let host = msg.uri.host().map(|h| h.to_string());
+            match msg.resolver {
+                custom
+                    if msg.addr.is_none()
+                        && (custom.is_some() || msg.dns_timeout.is_some()) =>
+                {
+                    let fut = resolve(
+                        msg.uri,
+                        custom,
+                        resolver.clone(),
+                        msg.dns_timeout,
+                        srv.clone(),
+                    );
+                    Either::Right(fut.boxed_local())
+                }
+                _ => Either::Left(
+                    srv.call(
+                        TcpConnect::new(msg.uri)
+                            .set_addrs(msg.addr.into_iter().chain(msg.fallback_addrs)),
+                    )
+                    .map_err(move |e| e.with_host(host.as_deref())),
+                ),
+            }
+        }),
     )
     .map_err(|e| match e {
         TimeoutError::Service(e) => e,
         TimeoutError::Timeout => ConnectError::Timeout,
     })
+    .map(move |(io, proto, info)| (io, protocol.select(proto), info))
+}
+
+/// Resolve host with custom or default resolver and connect
+/// to resolved addresses
+async fn resolve(
+    uri: Uri,
+    custom: Option<Rc<dyn Resolve>>,
+    resolver: connect::AsyncResolver,
+    dns_timeout: Option<Duration>,
+    srv: Rc<BoxedConnector>,
+) -> Result<(Box<dyn Io>, Protocol, ConnectInfo), ConnectError> {
+    let host = uri.host().unwrap_or("").to_string();
+    let lookup = lookup(uri, custom, resolver, &host);
+    let req = match dns_timeout {
+        Some(to) => match timeout(to, lookup).await {
+            Ok(res) => res?,
+            Err(_) => return Err(ConnectError::DnsTimeout(host)),
+        },
+        None => lookup.await?,
+    };
+    srv.call(req).await
+}
+
+async fn lookup(
+    uri: Uri,
+    custom: Option<Rc<dyn Resolve>>,
+    resolver: connect::AsyncResolver,
+    host: &str,
+) -> Result<TcpConnect<Uri>, ConnectError> {
+    let resolver = match custom {
+        Some(resolver) => resolver,
+        None => {
+            return connect::Resolver::new(resolver)
+                .lookup(TcpConnect::new(uri))
+                .await
+                .map_err(|e| ConnectError::from(e).with_host(Some(host)))
+        }
+    };
+    let port = connect::Address::port(&uri).unwrap_or(80);
+
+    let addrs = match resolver.lookup(host, port).await {
+        Ok(addrs) => addrs,
+        Err(e) => {
+            return Err(ConnectError::Dns {
+                host: host.to_string(),
+                error: Box::new(ConnectError::Io(e)),
+            })
+        }
+    };
+    if addrs.is_empty() {
+        return Err(ConnectError::NoRecords.with_host(Some(host)));
+    }
+    Ok(TcpConnect::new(uri).set_addrs(addrs))
+}
+
+/// Connect to the host through proxy server
+///
+/// Plain http connections to http proxy are used as is, other
+/// connections are tunneled and secure handshake is performed
+/// over the tunnel.
+async fn proxy_connect(
+    msg: Connect,
+    proxy: Rc<Proxy>,
+    tcp_connector: Rc<BoxedConnector>,
+    ssl_upgrade: Option<TlsUpgrade>,
+    resolver: connect::AsyncResolver,
+) -> Result<(Box<dyn Io>, Protocol, ConnectInfo), ConnectError> {
+    let proxy_uri = proxy.uri().clone();
+    let proxy_host = proxy_uri.host().map(|h| h.to_string());
+    let (mut io, _, mut info) = match msg.resolver {
+        custom if custom.is_some() || msg.dns_timeout.is_some() => {
+            resolve(proxy_uri, custom, resolver, msg.dns_timeout, tcp_connector).await?
+        }
+        _ => tcp_connector
+            .call(TcpConnect::new(proxy_uri))
+            .await
+            .map_err(|e| e.with_host(proxy_host.as_deref()))?,
+    };
+    if proxy.is_forwarding(&msg.uri) {
+        return Ok((io, Protocol::Http1, info));
+    }
+
+    let host = msg.uri.host().unwrap_or("").to_string();
+    let port = connect::Address::port(&msg.uri).unwrap_or(80);
+    proxy.tunnel(&mut io, &host, port).await?;
+
+    match msg.uri.scheme_str() {
+        Some("https") | Some("wss") => {
+            if let Some(upgrade) = ssl_upgrade {
+                let (io, proto, tls) = upgrade(io, host).await?;
+                info.tls = Some(tls);
+                Ok((io, proto, info))
+            } else {
+                Err(ConnectError::SslIsNotSupported)
+            }
+        }
+        _ => Ok((io, Protocol::Http1, info)),
+    }
+}
+
+#[cfg(feature = "openssl")]
+/// Openssl secure connector
+fn openssl_connector(
+    connector: OpensslConnector,
+    resolver: &connect::AsyncResolver,
+    happy_eyeballs: Option<Duration>,
+    socket: SocketOptions,
+) -> TlsConnector {
+    use crate::connect::openssl::OpensslConnector;
+
+    const H2: &[u8] = b"h2";
+    let upgrade = openssl_upgrade(connector.clone());
+    let mut ssl = OpensslConnector::with_resolver(connector, resolver.clone());
+    if let Some(delay) = happy_eyeballs {
+        ssl = ssl.happy_eyeballs(delay);
+    }
+    let connector = boxed::service(
+        ssl.map(move |sock| {
+            socket.apply(sock.get_ref());
+            let h2 = sock
+                .ssl()
+                .selected_alpn_protocol()
+                .map(|protos| protos.windows(2).any(|w| w == H2))
+                .unwrap_or(false);
+            let info = ConnectInfo {
+                peer_addr: sock.get_ref().peer_addr().ok(),
+                tls: Some(TlsInfo::openssl(sock.ssl())),
+            };
+            if h2 {
+                (Box::new(sock) as Box<dyn Io>, Protocol::Http2, info)
+            } else {
+                (Box::new(sock) as Box<dyn Io>, Protocol::Http1, info)
+            }
+        })
+        .map_err(|e| ConnectError::from(e).tls_alert()),
+    );
+    (connector, upgrade)
+}
+
+#[cfg(feature = "rustls")]
+/// Rustls secure connector
+fn rustls_connector(
+    connector: Arc<ClientConfig>,
+    resolver: &connect::AsyncResolver,
+    happy_eyeballs: Option<Duration>,
+    socket: SocketOptions,
+) -> TlsConnector {
+    use crate::connect::rustls::{RustlsConnector, Session};
+
+    const H2: &[u8] = b"h2";
+    let upgrade = rustls_upgrade(connector.clone());
+    let mut ssl = RustlsConnector::with_resolver(connector, resolver.clone());
+    if let Some(delay) = happy_eyeballs {
+        ssl = ssl.happy_eyeballs(delay);
+    }
+    let connector = boxed::service(
+        ssl.map(move |sock| {
+            socket.apply(sock.get_ref().0);
+            let h2 = sock
+                .get_ref()
+                .1
+                .get_alpn_protocol()
+                .map(|protos| protos.windows(2).any(|w| w == H2))
+                .unwrap_or(false);
+            let info = ConnectInfo {
+                peer_addr: sock.get_ref().0.peer_addr().ok(),
+                tls: Some(TlsInfo::rustls(sock.get_ref().1)),
+            };
+            if h2 {
+                (Box::new(sock) as Box<dyn Io>, Protocol::Http2, info)
+            } else {
+                (Box::new(sock) as Box<dyn Io>, Protocol::Http1, info)
+            }
+        })
+        .map_err(|e| ConnectError::from(e).tls_alert()),
+    );
+    (connector, upgrade)
+}
+
+#[cfg(feature = "openssl")]
+/// Openssl handshake over established connection
+fn openssl_upgrade(connector: OpensslConnector) -> TlsUpgrade {
+    use crate::connect::openssl::handshake_error;
+
+    Rc::new(move |io, host| {
+        let config = connector.configure();
+        async move {
+            let config = config.map_err(io::Error::other)?;
+            let sock = tokio_openssl::connect(config, &host, io)
+                .await
+                .map_err(|e| ConnectError::from(handshake_error(e)).tls_alert())?;
+            let h2 = sock
+                .ssl()
+                .selected_alpn_protocol()
+                .map(|protos| protos.windows(2).any(|w| w == b"h2"))
+                .unwrap_or(false);
+            let proto = if h2 { Protocol::Http2 } else { Protocol::Http1 };
+            let tls = TlsInfo::openssl(sock.ssl());
+            Ok((Box::new(sock) as Box<dyn Io>, proto, tls))
+        }
+        .boxed_local()
+    })
+}
+
+#[cfg(feature = "rustls")]
+/// Rustls handshake over established connection
+fn rustls_upgrade(connector: Arc<ClientConfig>) -> TlsUpgrade {
+    use crate::connect::rustls::Session;
+
+    Rc::new(move |io, host| {
+        let connector = tokio_rustls::TlsConnector::from(connector.clone());
+        async move {
+            let name = webpki::DNSNameRef::try_from_ascii_str(&host)
+                .map_err(|e| io::Error::other(format!("{}", e)))?;
+            let sock = connector
+                .connect(name, io)
+                .await
+                .map_err(|e| ConnectError::from(e).tls_alert())?;
+            let h2 = sock
+                .get_ref()
+                .1
+                .get_alpn_protocol()
+                .map(|protos| protos.windows(2).any(|w| w == b"h2"))
+                .unwrap_or(false);
+            let proto = if h2 { Protocol::Http2 } else { Protocol::Http1 };
+            let tls = TlsInfo::rustls(sock.get_ref().1);
+            Ok((Box::new(sock) as Box<dyn Io>, proto, tls))
+        }
+        .boxed_local()
+    })
+}
+
+/// Secure connector that selects connector by host name of request url
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+struct HostConnector {
+    default: BoxedConnector,
+    connectors: HashMap<String, BoxedConnector>,
+}
+
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+impl Service for HostConnector {
+    type Request = TcpConnect<Uri>;
+    type Response = (Box<dyn Io>, Protocol, ConnectInfo);
+    type Error = ConnectError;
+    type Future = <BoxedConnector as Service>::Future;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut ready = self.default.poll_ready(cx)?.is_ready();
+        for connector in self.connectors.values() {
+            ready = connector.poll_ready(cx)?.is_ready() && ready;
+        }
+        if ready {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn call(&self, req: TcpConnect<Uri>) -> Self::Future {
+        let host = req.host().to_lowercase();
+        self.connectors
+            .get(&host)
+            .unwrap_or(&self.default)
+            .call(req)
+    }
 }
 
 type Pool<T> = ConnectionPool<T, Box<dyn Io>>;
@@ -308,7 +984,7 @@ impl<T> Service for InnerConnector<T>
 where
     T: Service<
             Request = Connect,
-            Response = (Box<dyn Io>, Protocol),
+            Response = (Box<dyn Io>, Protocol, ConnectInfo),
             Error = ConnectError,
         > + Unpin
         + 'static,
@@ -375,4 +1051,27 @@ mod tests {
         assert!(lazy(|cx| conn.poll_ready(cx).is_ready()).await);
         assert!(lazy(|cx| conn.poll_shutdown(cx, true).is_ready()).await);
     }
+
+    #[ntex_rt::test]
+    async fn test_socket_options() {
+        let conn = Connector::default()
+            .tcp_nodelay(false)
+            .tcp_keepalive(Duration::from_secs(30));
+        assert!(!conn.socket.nodelay);
+        assert_eq!(conn.socket.keepalive, Some(Duration::from_secs(30)));
+        assert!(Connector::default().socket.nodelay);
+
+        let srv = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let io = TcpStream::connect(srv.local_addr().unwrap()).await.unwrap();
+        SocketOptions {
+            nodelay: true,
+            keepalive: Some(Duration::from_secs(30)),
+        }
+        .apply(&io);
+        assert!(io.nodelay().unwrap());
+        assert_eq!(io.keepalive().unwrap(), Some(Duration::from_secs(30)));
+
+        conn.socket.apply(&io);
+        assert!(!io.nodelay().unwrap());
+    }
 }
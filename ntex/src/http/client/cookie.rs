@@ -0,0 +1,353 @@
+//! Http client cookie jar
+use std::convert::TryFrom;
+
+use coo_kie::Cookie;
+use time::OffsetDateTime;
+
+use crate::http::header::{self, HeaderMap, HeaderValue};
+use crate::http::{RequestHeadType, Uri};
+
+use super::response::ClientResponse;
+
+/// Cookie jar, stores cookies received from servers.
+///
+/// Jar could be attached to a client with `ClientBuilder::cookie_jar()`,
+/// in that case matching cookies are sent with each request and
+/// `Set-Cookie` headers of each response are stored back to the jar.
+/// Jar could be shared between clients.
+///
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+/// use ntex::http::client::{Client, CookieJar};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let jar = Arc::new(Mutex::new(CookieJar::new()));
+///     let client = Client::build().cookie_jar(jar.clone()).finish();
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: Vec<StoredCookie>,
+}
+
+#[derive(Debug)]
+struct StoredCookie {
+    cookie: Cookie<'static>,
+    domain: String,
+    host_only: bool,
+    path: String,
+    expires: Option<OffsetDateTime>,
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires.map(|t| t <= now).unwrap_or(false)
+    }
+
+    fn matches(&self, host: &str, path: &str, secure: bool) -> bool {
+        if self.cookie.secure().unwrap_or(false) && !secure {
+            return false;
+        }
+        let domain_match = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(host, &self.domain)
+        };
+        domain_match && path_match(path, &self.path)
+    }
+}
+
+impl CookieJar {
+    /// Create empty cookie jar
+    pub fn new() -> Self {
+        CookieJar::default()
+    }
+
+    /// Returns number of stored cookies, including expired ones.
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    /// Check if jar is empty
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    /// Get first stored cookie with the name
+    pub fn get(&self, name: &str) -> Option<&Cookie<'static>> {
+        self.iter().find(|c| c.name() == name)
+    }
+
+    /// Iterate over stored cookies
+    pub fn iter(&self) -> impl Iterator<Item = &Cookie<'static>> {
+        self.cookies.iter().map(|c| &c.cookie)
+    }
+
+    /// Remove all cookies
+    pub fn clear(&mut self) {
+        self.cookies.clear()
+    }
+
+    /// Store cookies from response `Set-Cookie` headers
+    pub(super) fn store_response(&mut self, uri: &Uri, res: &ClientResponse) {
+        for hdr in res.headers().get_all(header::SET_COOKIE) {
+            if let Ok(s) = hdr.to_str() {
+                if let Ok(cookie) = Cookie::parse(s) {
+                    self.store(uri, cookie.into_owned());
+                }
+            }
+        }
+    }
+
+    fn store(&mut self, uri: &Uri, cookie: Cookie<'static>) {
+        let host = match uri.host() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return,
+        };
+
+        let (domain, host_only) = match cookie.domain() {
+            Some(domain) if !domain.is_empty() => {
+                let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+                // do not allow to set cookies for other domains
+                if !domain_match(&host, &domain) {
+                    return;
+                }
+                (domain, false)
+            }
+            _ => (host, true),
+        };
+        let path = match cookie.path() {
+            Some(path) if path.starts_with('/') => path.to_string(),
+            _ => default_path(uri.path()),
+        };
+
+        let now = OffsetDateTime::now_utc();
+        let expires = if let Some(max_age) = cookie.max_age() {
+            Some(now + max_age)
+        } else {
+            cookie.expires()
+        };
+
+        self.cookies.retain(|c| {
+            !(c.cookie.name() == cookie.name() && c.domain == domain && c.path == path)
+        });
+
+        let stored = StoredCookie {
+            cookie,
+            domain,
+            host_only,
+            path,
+            expires,
+        };
+        if !stored.is_expired(now) {
+            self.cookies.push(stored);
+        }
+    }
+
+    /// Build `Cookie` header value for the uri
+    fn header_value(&mut self, uri: &Uri) -> Option<String> {
+        let host = uri.host()?.to_ascii_lowercase();
+        let secure = matches!(uri.scheme_str(), Some("https") | Some("wss"));
+        let now = OffsetDateTime::now_utc();
+
+        self.cookies.retain(|c| !c.is_expired(now));
+
+        // cookies with longer paths are listed first
+        let mut cookies: Vec<_> = self
+            .cookies
+            .iter()
+            .filter(|c| c.matches(&host, uri.path(), secure))
+            .collect();
+        cookies.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+
+        let mut value = String::new();
+        for c in cookies {
+            if !value.is_empty() {
+                value.push_str("; ");
+            }
+            value.push_str(c.cookie.name());
+            value.push('=');
+            value.push_str(c.cookie.value());
+        }
+
+        if value.is_empty() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    /// Add stored cookies to the request
+    pub(super) fn apply(&mut self, head: &mut RequestHeadType) {
+        let value = match self.header_value(&head.as_ref().uri) {
+            Some(value) => value,
+            None => return,
+        };
+
+        match head {
+            RequestHeadType::Owned(head) => {
+                if let Some(value) = merge(&head.headers, None, value) {
+                    head.headers.insert(header::COOKIE, value);
+                }
+            }
+            RequestHeadType::Rc(head, extra_headers) => {
+                if let Some(value) = merge(&head.headers, extra_headers.as_ref(), value)
+                {
+                    extra_headers
+                        .get_or_insert_with(HeaderMap::new)
+                        .insert(header::COOKIE, value);
+                }
+            }
+        }
+    }
+}
+
+/// Merge stored cookies with cookies set explicitly for the request
+fn merge(
+    headers: &HeaderMap,
+    extra_headers: Option<&HeaderMap>,
+    value: String,
+) -> Option<HeaderValue> {
+    let current = extra_headers
+        .and_then(|h| h.get(header::COOKIE))
+        .or_else(|| headers.get(header::COOKIE))
+        .and_then(|v| v.to_str().ok());
+
+    match current {
+        Some(current) if !current.is_empty() => {
+            HeaderValue::try_from(format!("{}; {}", current, value)).ok()
+        }
+        _ => HeaderValue::try_from(value).ok(),
+    }
+}
+
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host.as_bytes()[host.len() - domain.len() - 1] == b'.')
+}
+
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/')
+                || path.as_bytes()[cookie_path.len()] == b'/'))
+}
+
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(idx) => path[..idx].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::test::TestResponse;
+    use crate::http::RequestHead;
+
+    fn jar_with(uri: &str, cookies: &[&str]) -> CookieJar {
+        let mut res = TestResponse::default();
+        for c in cookies {
+            res = res.header(header::SET_COOKIE, *c);
+        }
+        let mut jar = CookieJar::new();
+        jar.store_response(&Uri::try_from(uri).unwrap(), &res.finish());
+        jar
+    }
+
+    fn value(jar: &mut CookieJar, uri: &str) -> Option<String> {
+        jar.header_value(&Uri::try_from(uri).unwrap()).map(|v| {
+            let mut cookies: Vec<_> = v.split("; ").collect();
+            cookies.sort();
+            cookies.join("; ")
+        })
+    }
+
+    #[test]
+    fn test_domain() {
+        let mut jar = jar_with(
+            "http://www.example.com/",
+            &["a=1", "b=2; Domain=.example.com", "c=3; Domain=other.com"],
+        );
+        assert_eq!(jar.len(), 2);
+        assert_eq!(
+            value(&mut jar, "http://www.example.com/").unwrap(),
+            "a=1; b=2"
+        );
+        assert_eq!(value(&mut jar, "http://api.example.com/").unwrap(), "b=2");
+        assert_eq!(value(&mut jar, "http://example.com/").unwrap(), "b=2");
+        assert!(value(&mut jar, "http://badexample.com/").is_none());
+        assert!(value(&mut jar, "http://other.com/").is_none());
+    }
+
+    #[test]
+    fn test_path_and_secure() {
+        let mut jar = jar_with(
+            "https://example.com/docs/index.html",
+            &["a=1", "b=2; Path=/", "c=3; Path=/api; Secure"],
+        );
+        assert_eq!(jar.get("a").unwrap().value(), "1");
+        assert_eq!(value(&mut jar, "http://example.com/").unwrap(), "b=2");
+        assert_eq!(
+            value(&mut jar, "http://example.com/docs/test").unwrap(),
+            "a=1; b=2"
+        );
+        assert_eq!(value(&mut jar, "http://example.com/api").unwrap(), "b=2");
+        assert_eq!(value(&mut jar, "http://example.com/apis").unwrap(), "b=2");
+        assert_eq!(
+            value(&mut jar, "https://example.com/api/v1").unwrap(),
+            "b=2; c=3"
+        );
+    }
+
+    #[test]
+    fn test_expires() {
+        let mut jar = jar_with("http://example.com/", &["a=1", "b=2", "c=3"]);
+        assert_eq!(jar.len(), 3);
+
+        let res = TestResponse::default()
+            .header(header::SET_COOKIE, "a=10")
+            .header(header::SET_COOKIE, "b=2; Max-Age=0")
+            .header(
+                header::SET_COOKIE,
+                "c=3; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+            )
+            .finish();
+        jar.store_response(&Uri::from_static("http://example.com/"), &res);
+        assert_eq!(jar.len(), 1);
+        assert_eq!(value(&mut jar, "http://example.com/").unwrap(), "a=10");
+
+        jar.clear();
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut jar = jar_with("http://example.com/", &["a=1"]);
+
+        let mut head = RequestHead::default();
+        head.uri = Uri::from_static("http://example.com/");
+        head.headers
+            .insert(header::COOKIE, HeaderValue::from_static("x=0"));
+        let mut head = RequestHeadType::Owned(head);
+        jar.apply(&mut head);
+        assert_eq!(
+            head.as_ref().headers.get(header::COOKIE).unwrap(),
+            "x=0; a=1"
+        );
+
+        let mut head = RequestHead::default();
+        head.uri = Uri::from_static("http://example.com/");
+        let mut head = RequestHeadType::Rc(std::rc::Rc::new(head), None);
+        jar.apply(&mut head);
+        match head {
+            RequestHeadType::Rc(_, Some(extra)) => {
+                assert_eq!(extra.get(header::COOKIE).unwrap(), "a=1")
+            }
+            _ => panic!(),
+        }
+    }
+}
@@ -1,17 +1,20 @@
 //! Http client errors
+use std::any::Any;
 use std::error::Error;
-use std::io;
+use std::{fmt, io};
 
+use bytes::Bytes;
 use derive_more::{Display, From};
 use serde_json::error::Error as JsonError;
 
 #[cfg(feature = "openssl")]
 use crate::connect::openssl::{HandshakeError, SslError};
 use crate::connect::ResolveError;
+use trust_dns_resolver::error::ResolveErrorKind;
 
 use crate::http::error::{HttpError, ParseError, PayloadError};
 use crate::http::header::HeaderValue;
-use crate::http::StatusCode;
+use crate::http::{StatusCode, Uri};
 use crate::ws::ProtocolError;
 
 /// Websocket client error
@@ -35,6 +38,10 @@ pub enum WsClientError {
     /// Invalid challenge response
     #[display(fmt = "Invalid challenge response")]
     InvalidChallengeResponse(String, HeaderValue),
+    /// Server selected protocol that is not requested
+    #[display(fmt = "Invalid websocket protocol")]
+    #[from(ignore)]
+    InvalidProtocol(HeaderValue),
     /// Protocol error
     #[display(fmt = "{}", _0)]
     Protocol(ProtocolError),
@@ -43,7 +50,14 @@ pub enum WsClientError {
     SendRequest(SendRequestError),
 }
 
-impl std::error::Error for WsClientError {}
+impl std::error::Error for WsClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WsClientError::SendRequest(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl From<InvalidUrl> for WsClientError {
     fn from(err: InvalidUrl) -> Self {
@@ -71,7 +85,160 @@ pub enum JsonPayloadError {
     Payload(PayloadError),
 }
 
-impl std::error::Error for JsonPayloadError {}
+impl std::error::Error for JsonPayloadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JsonPayloadError::Deserialize(e) => Some(e),
+            JsonPayloadError::Payload(e) => Some(e),
+            JsonPayloadError::ContentType => None,
+        }
+    }
+}
+
+/// A set of errors that can occur during decoding text payloads
+#[derive(Debug, Display, From)]
+pub enum TextPayloadError {
+    /// Charset of content type is not supported
+    #[display(fmt = "Unsupported charset: {}", _0)]
+    #[from(ignore)]
+    Charset(String),
+    /// Payload is not valid in the charset
+    #[display(fmt = "Invalid {} byte sequence", _0)]
+    #[from(ignore)]
+    Decode(&'static str),
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+impl std::error::Error for TextPayloadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TextPayloadError::Payload(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A set of errors that can occur during deserializing response body
+/// according to its content type
+#[derive(Debug, Display, From)]
+pub enum DeserializeError {
+    /// Content type is not supported
+    #[display(fmt = "Unsupported content type: {}", _0)]
+    #[from(ignore)]
+    ContentType(String),
+    /// Json deserialize error
+    #[display(fmt = "Json deserialize error: {}", _0)]
+    Json(JsonError),
+    /// Urlencoded deserialize error
+    #[display(fmt = "Urlencoded deserialize error: {}", _0)]
+    Form(serde_urlencoded::de::Error),
+    #[cfg(feature = "msgpack")]
+    /// MessagePack deserialize error
+    #[display(fmt = "MessagePack deserialize error: {}", _0)]
+    MsgPack(rmp_serde::decode::Error),
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+impl std::error::Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeserializeError::Json(e) => Some(e),
+            DeserializeError::Form(e) => Some(e),
+            #[cfg(feature = "msgpack")]
+            DeserializeError::MsgPack(e) => Some(e),
+            DeserializeError::Payload(e) => Some(e),
+            DeserializeError::ContentType(_) => None,
+        }
+    }
+}
+
+/// A set of errors that can occur during reading partial content payloads
+#[derive(Debug, Display, From)]
+pub enum ByteRangesError {
+    /// Response status is not `206 Partial Content`
+    #[display(fmt = "Response is not partial content")]
+    NotPartial,
+    /// Missing or invalid `Content-Range` header
+    #[display(fmt = "Invalid Content-Range header")]
+    ContentRange,
+    /// Missing boundary of `multipart/byteranges` content type
+    #[display(fmt = "Missing multipart boundary")]
+    Boundary,
+    /// Malformed `multipart/byteranges` body
+    #[display(fmt = "Multipart parse error: {}", _0)]
+    #[from(ignore)]
+    Multipart(&'static str),
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+/// A set of errors that can occur during reading multipart payloads
+#[derive(Debug, Display, From)]
+pub enum MultipartError {
+    /// Content type is not `multipart/*`
+    #[display(fmt = "Content type is not multipart")]
+    NotMultipart,
+    /// Missing boundary of multipart content type
+    #[display(fmt = "Missing multipart boundary")]
+    Boundary,
+    /// Malformed multipart body
+    #[display(fmt = "Multipart parse error: {}", _0)]
+    #[from(ignore)]
+    Parse(&'static str),
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+/// A set of errors that can occur during saving payload to a file
+#[derive(Debug, Display, From)]
+pub enum SaveError {
+    /// File error
+    #[display(fmt = "File error: {}", _0)]
+    Io(io::Error),
+    /// Missing or invalid `Content-MD5` header
+    #[display(fmt = "Invalid Content-MD5 header")]
+    ContentMd5,
+    /// Payload digest does not match expected digest
+    #[display(fmt = "Payload digest mismatch")]
+    Digest,
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SaveError::Io(e) => Some(e),
+            SaveError::Payload(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for ByteRangesError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ByteRangesError::Payload(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for MultipartError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MultipartError::Payload(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 /// A set of errors that can occur while connecting to an HTTP host
 #[derive(Debug, Display, From)]
@@ -98,6 +265,14 @@ pub enum ConnectError {
     #[display(fmt = "No dns records found for the input")]
     NoRecords,
 
+    /// Failed to resolve the hostname of the request
+    #[display(fmt = "Failed resolving hostname {}: {}", host, error)]
+    #[from(ignore)]
+    Dns {
+        host: String,
+        error: Box<ConnectError>,
+    },
+
     /// Http2 error
     #[display(fmt = "{}", _0)]
     H2(h2::Error),
@@ -106,6 +281,11 @@ pub enum ConnectError {
     #[display(fmt = "Timeout out while establishing connection")]
     Timeout,
 
+    /// Resolving the hostname took too long
+    #[display(fmt = "Timeout out while resolving hostname {}", _0)]
+    #[from(ignore)]
+    DnsTimeout(String),
+
     /// Connector has been disconnected
     #[display(fmt = "Internal error: connector has been disconnected")]
     Disconnected,
@@ -114,12 +294,141 @@ pub enum ConnectError {
     #[display(fmt = "Connector received `Connect` method with unresolved host")]
     Unresolved,
 
+    /// Proxy server failed to open connection
+    #[display(fmt = "Proxy error: {}", _0)]
+    #[from(ignore)]
+    Proxy(String),
+
+    /// Server rejected client certificate during secure handshake
+    #[display(fmt = "Client certificate rejected: {}", _0)]
+    #[from(ignore)]
+    ClientCertificate(String),
+
     /// Connection io error
     #[display(fmt = "{}", _0)]
     Io(io::Error),
 }
 
-impl std::error::Error for ConnectError {}
+impl std::error::Error for ConnectError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "openssl")]
+            ConnectError::SslError(e) => Some(e),
+            ConnectError::Resolver(e) => Some(e),
+            ConnectError::Dns { error, .. } => Some(error.as_ref()),
+            ConnectError::H2(e) => Some(e),
+            ConnectError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl ConnectError {
+    /// Get kind of the underlying io error, if any
+    pub fn io_error_kind(&self) -> Option<io::ErrorKind> {
+        match self {
+            ConnectError::Io(e) => Some(e.kind()),
+            ConnectError::Resolver(e) => match e.kind() {
+                ResolveErrorKind::Io(e) => Some(e.kind()),
+                ResolveErrorKind::Timeout => Some(io::ErrorKind::TimedOut),
+                _ => None,
+            },
+            ConnectError::Dns { error, .. } => error.io_error_kind(),
+            ConnectError::DnsTimeout(_) => Some(io::ErrorKind::TimedOut),
+            _ => None,
+        }
+    }
+
+    /// Get hostname that failed to resolve
+    pub fn dns_host(&self) -> Option<&str> {
+        match self {
+            ConnectError::Dns { host, .. } => Some(host.as_str()),
+            ConnectError::DnsTimeout(host) => Some(host.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Attach hostname to dns resolution errors
+    pub(super) fn with_host(self, host: Option<&str>) -> ConnectError {
+        match (self, host) {
+            (e @ ConnectError::Resolver(_), Some(host))
+            | (e @ ConnectError::NoRecords, Some(host)) => ConnectError::Dns {
+                host: host.to_string(),
+                error: Box::new(e),
+            },
+            (e, _) => e,
+        }
+    }
+
+    /// Detect secure handshake failure caused by rejected client certificate
+    #[cfg_attr(not(any(feature = "openssl", feature = "rustls")), allow(dead_code))]
+    pub(super) fn tls_alert(self) -> ConnectError {
+        match self {
+            ConnectError::Io(ref e) if is_certificate_alert(e) => {
+                ConnectError::ClientCertificate(e.to_string())
+            }
+            e => e,
+        }
+    }
+}
+
+/// Server reports rejected client certificate with tls alert, like
+/// `bad_certificate`, `unknown_ca` or `certificate_required`.
+fn is_certificate_alert(err: &io::Error) -> bool {
+    let mut source = err.get_ref().map(|e| e as &(dyn Error + 'static));
+    while let Some(e) = source {
+        #[cfg(feature = "rustls")]
+        {
+            use rust_tls::internal::msgs::enums::AlertDescription;
+
+            if let Some(rust_tls::TLSError::AlertReceived(alert)) = e.downcast_ref() {
+                return matches!(
+                    alert,
+                    AlertDescription::BadCertificate
+                        | AlertDescription::UnsupportedCertificate
+                        | AlertDescription::CertificateRevoked
+                        | AlertDescription::CertificateExpired
+                        | AlertDescription::CertificateUnknown
+                        | AlertDescription::UnknownCA
+                        | AlertDescription::CertificateRequired
+                );
+            }
+        }
+        #[cfg(feature = "openssl")]
+        {
+            let stack = match e.downcast_ref::<SslError>() {
+                Some(e) => e.ssl_error(),
+                None => e.downcast_ref::<open_ssl::error::ErrorStack>(),
+            };
+            if let Some(stack) = stack {
+                return stack
+                    .errors()
+                    .iter()
+                    .any(|e| is_openssl_certificate_alert(e.reason_code()));
+            }
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// Openssl reports received tls alert with `SSL_AD_REASON_OFFSET + alert`
+/// error reason
+#[cfg(feature = "openssl")]
+fn is_openssl_certificate_alert(reason: std::os::raw::c_int) -> bool {
+    const SSL_AD_REASON_OFFSET: std::os::raw::c_int = 1000;
+
+    match reason - SSL_AD_REASON_OFFSET {
+        // bad_certificate, unsupported_certificate, certificate_revoked,
+        // certificate_expired, certificate_unknown
+        42..=46 => true,
+        // unknown_ca
+        48 => true,
+        // certificate_required
+        116 => true,
+        _ => false,
+    }
+}
 
 impl From<crate::connect::ConnectError> for ConnectError {
     fn from(err: crate::connect::ConnectError) -> ConnectError {
@@ -140,19 +449,38 @@ impl<T: std::fmt::Debug> From<HandshakeError<T>> for ConnectError {
     }
 }
 
-#[derive(Debug, Display, From)]
+#[derive(Debug, From)]
 pub enum InvalidUrl {
-    #[display(fmt = "Missing url scheme")]
     MissingScheme,
-    #[display(fmt = "Unknown url scheme")]
+    #[deprecated(since = "0.1.24", note = "Use `InvalidUrl::UnsupportedScheme`")]
     UnknownScheme,
-    #[display(fmt = "Missing host name")]
+    /// Url scheme is not `http`, `https`, `ws` or `wss`
+    UnsupportedScheme,
     MissingHost,
-    #[display(fmt = "Url parse error: {}", _0)]
     Http(HttpError),
 }
 
-impl std::error::Error for InvalidUrl {}
+impl fmt::Display for InvalidUrl {
+    #[allow(deprecated)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidUrl::MissingScheme => write!(f, "Missing url scheme"),
+            InvalidUrl::UnknownScheme => write!(f, "Unknown url scheme"),
+            InvalidUrl::UnsupportedScheme => write!(f, "Unsupported url scheme"),
+            InvalidUrl::MissingHost => write!(f, "Missing host name"),
+            InvalidUrl::Http(e) => write!(f, "Url parse error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for InvalidUrl {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InvalidUrl::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 /// A set of errors that can occur during request sending and response reading
 #[derive(Debug, Display, From)]
@@ -162,10 +490,12 @@ pub enum SendRequestError {
     Url(InvalidUrl),
     /// Failed to connect to host
     #[display(fmt = "Failed to connect to host: {}", _0)]
+    #[from(ignore)]
     Connect(ConnectError),
     /// Error sending request
     Send(io::Error),
     /// Error parsing response
+    #[from(ignore)]
     Response(ParseError),
     /// Http error
     #[display(fmt = "{}", _0)]
@@ -173,17 +503,239 @@ pub enum SendRequestError {
     /// Http2 error
     #[display(fmt = "{}", _0)]
     H2(h2::Error),
-    /// Response took too long
-    #[display(fmt = "Timeout out while waiting for response")]
-    Timeout,
+    /// Request took too long
+    #[display(fmt = "Timeout out while {}", phase)]
+    #[from(ignore)]
+    Timeout { phase: TimeoutPhase },
+    /// Establishing connection took too long
+    #[display(fmt = "Timeout out while establishing connection")]
+    ConnectTimeout,
+    /// Resolving the hostname took too long
+    #[display(fmt = "Timeout out while resolving hostname {}", host)]
+    #[from(ignore)]
+    DnsTimeout { host: String },
     /// Tunnels are not supported for http2 connection
     #[display(fmt = "Tunnels are not supported for http2 connection")]
     TunnelNotSupported,
+    /// Response payload exceeds configured max size
+    #[display(fmt = "Response payload is larger than {} bytes", limit)]
+    #[from(ignore)]
+    ResponsePayloadTooLarge { limit: usize },
+    /// Decompressed response payload exceeds configured max ratio
+    #[display(fmt = "Response decompression ratio exceeds {}:1", ratio)]
+    #[from(ignore)]
+    DecompressionRatioExceeded { ratio: usize },
+    /// Response headers exceed configured max size
+    #[display(fmt = "Response headers are larger than {} bytes", limit)]
+    #[from(ignore)]
+    ResponseHeadersTooLarge { limit: usize },
+    /// Redirect limit has been exceeded
+    #[display(fmt = "Too many redirects")]
+    TooManyRedirects,
+    /// Redirect leads to already visited url, `chain` contains
+    /// all visited urls starting from the request url
+    #[display(fmt = "Redirect loop: {:?}", chain)]
+    #[from(ignore)]
+    RedirectLoop { chain: Vec<Uri> },
+    /// Redirect requires sending request body again, but the body is a stream
+    #[display(fmt = "Streaming request body could not be sent again on redirect")]
+    BodyNotReplayable,
+    /// Response is a redirect, but redirects are denied by
+    /// `ClientBuilder::deny_redirects()`
+    #[display(fmt = "Unexpected redirect with status {}", status)]
+    #[from(ignore)]
+    UnexpectedRedirect {
+        status: StatusCode,
+        location: Option<String>,
+    },
+    /// Response has error status, body is deserialized error
+    #[display(fmt = "Response has error status {}", status)]
+    #[from(ignore)]
+    Status { status: StatusCode, body: ErrorBody },
+    /// Response has error status, body could not be deserialized
+    #[display(fmt = "Response has error status {}, invalid body: {}", status, error)]
+    #[from(ignore)]
+    UnexpectedStatus {
+        status: StatusCode,
+        body: Bytes,
+        error: JsonPayloadError,
+    },
+    /// Client is closed
+    #[display(fmt = "Client is closed")]
+    Closed,
+    /// Circuit breaker of the host is open
+    #[display(fmt = "Circuit breaker is open")]
+    CircuitOpen,
+    /// Streaming body of unknown size could not be sent with HTTP/1.0 request
+    #[display(fmt = "Chunked request body is not supported by HTTP/1.0")]
+    ChunkedNotSupported,
+    /// Body serializer is not registered for content type
+    #[display(fmt = "No body serializer for content type {}", _0)]
+    #[from(ignore)]
+    NoSerializer(String),
+    /// Basic auth username contains colon
+    #[display(fmt = "Basic auth username must not contain colon")]
+    InvalidCredentials,
     /// Error sending request body
     Error(Box<dyn Error>),
 }
 
-impl std::error::Error for SendRequestError {}
+impl From<ParseError> for SendRequestError {
+    fn from(err: ParseError) -> SendRequestError {
+        match err {
+            ParseError::HeadTooLarge(limit) => {
+                SendRequestError::ResponseHeadersTooLarge { limit }
+            }
+            e => SendRequestError::Response(e),
+        }
+    }
+}
+
+impl From<PayloadError> for SendRequestError {
+    fn from(err: PayloadError) -> SendRequestError {
+        match err {
+            PayloadError::TooLarge(limit) => {
+                SendRequestError::ResponsePayloadTooLarge { limit }
+            }
+            PayloadError::DecompressionRatioExceeded(ratio) => {
+                SendRequestError::DecompressionRatioExceeded { ratio }
+            }
+            e => SendRequestError::Error(Box::new(e)),
+        }
+    }
+}
+
+impl From<ConnectError> for SendRequestError {
+    fn from(err: ConnectError) -> SendRequestError {
+        match err {
+            ConnectError::DnsTimeout(host) => SendRequestError::DnsTimeout { host },
+            e => SendRequestError::Connect(e),
+        }
+    }
+}
+
+/// Deserialized body of error response.
+///
+/// See `ClientResponse::error_for_status()`.
+pub struct ErrorBody(Box<dyn AnyDebug>);
+
+trait AnyDebug: Any + fmt::Debug {
+    fn as_any(&self) -> &dyn Any;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Any + fmt::Debug> AnyDebug for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl ErrorBody {
+    pub(super) fn new<E: fmt::Debug + 'static>(body: E) -> Self {
+        ErrorBody(Box::new(body))
+    }
+
+    /// Returns reference to the error body if it is of type `E`
+    pub fn downcast_ref<E: 'static>(&self) -> Option<&E> {
+        (*self.0).as_any().downcast_ref()
+    }
+
+    /// Returns error body if it is of type `E`
+    pub fn downcast<E: 'static>(self) -> Result<E, Self> {
+        if (*self.0).as_any().is::<E>() {
+            Ok(*self.0.into_any().downcast().unwrap())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Debug for ErrorBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (*self.0).fmt(f)
+    }
+}
+
+/// Phase of sending request when request timeout elapsed
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq)]
+pub enum TimeoutPhase {
+    /// Waiting for connection
+    #[display(fmt = "establishing connection")]
+    Connect,
+    /// Sending request head or body
+    #[display(fmt = "sending request")]
+    Request,
+    /// Peer does not accept request body, see `ClientBuilder::write_timeout()`
+    #[display(fmt = "writing request body")]
+    Write,
+    /// Waiting for response head
+    #[display(fmt = "waiting for response")]
+    Response,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for TimeoutPhase {
+    fn default() -> Self {
+        TimeoutPhase::Connect
+    }
+}
+
+impl std::error::Error for SendRequestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SendRequestError::Url(e) => Some(e),
+            SendRequestError::Connect(e) => Some(e),
+            SendRequestError::Send(e) => Some(e),
+            SendRequestError::Response(e) => Some(e),
+            SendRequestError::Http(e) => Some(e),
+            SendRequestError::H2(e) => Some(e),
+            SendRequestError::UnexpectedStatus { error, .. } => Some(error),
+            SendRequestError::Error(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl SendRequestError {
+    /// Get kind of the underlying io error, if any
+    pub fn io_error_kind(&self) -> Option<io::ErrorKind> {
+        match self {
+            SendRequestError::Connect(e) => e.io_error_kind(),
+            SendRequestError::Send(e) => Some(e.kind()),
+            _ => None,
+        }
+    }
+
+    /// Detect client certificate rejected after secure handshake
+    ///
+    /// With tls 1.3 server verifies client certificate after client
+    /// finished handshake, rejection is reported on first read.
+    pub(super) fn tls_alert(self) -> SendRequestError {
+        match self {
+            SendRequestError::Send(ref e)
+            | SendRequestError::Response(ParseError::Io(ref e))
+                if is_certificate_alert(e) =>
+            {
+                SendRequestError::Connect(ConnectError::ClientCertificate(e.to_string()))
+            }
+            e => e,
+        }
+    }
+
+    /// Get status of error response, if any
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            SendRequestError::Status { status, .. }
+            | SendRequestError::UnexpectedStatus { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
 
 /// A set of errors that can occur during freezing a request
 #[derive(Debug, Display, From)]
@@ -194,15 +746,176 @@ pub enum FreezeRequestError {
     /// Http error
     #[display(fmt = "{}", _0)]
     Http(HttpError),
+    /// Query serialization error
+    #[display(fmt = "Query serialization error: {}", _0)]
+    Query(serde_urlencoded::ser::Error),
+    /// Basic auth username contains colon
+    #[display(fmt = "Basic auth username must not contain colon")]
+    InvalidCredentials,
 }
 
-impl std::error::Error for FreezeRequestError {}
+impl std::error::Error for FreezeRequestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FreezeRequestError::Url(e) => Some(e),
+            FreezeRequestError::Http(e) => Some(e),
+            FreezeRequestError::Query(e) => Some(e),
+            FreezeRequestError::InvalidCredentials => None,
+        }
+    }
+}
 
 impl From<FreezeRequestError> for SendRequestError {
     fn from(e: FreezeRequestError) -> Self {
         match e {
             FreezeRequestError::Url(e) => e.into(),
             FreezeRequestError::Http(e) => e.into(),
+            FreezeRequestError::Query(e) => SendRequestError::Error(Box::new(e)),
+            FreezeRequestError::InvalidCredentials => {
+                SendRequestError::InvalidCredentials
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connect_error() {
+        let err = ConnectError::NoRecords.with_host(Some("example.com"));
+        assert_eq!(err.dns_host(), Some("example.com"));
+        assert_eq!(
+            err.to_string(),
+            "Failed resolving hostname example.com: No dns records found for the input"
+        );
+        assert!(ConnectError::NoRecords.with_host(None).dns_host().is_none());
+
+        let err = ConnectError::Io(io::Error::new(io::ErrorKind::ConnectionRefused, ""))
+            .with_host(Some("example.com"));
+        assert!(err.dns_host().is_none());
+        let err = SendRequestError::Connect(err);
+        assert_eq!(err.io_error_kind(), Some(io::ErrorKind::ConnectionRefused));
+        let err = SendRequestError::Timeout {
+            phase: TimeoutPhase::Response,
+        };
+        assert!(err.io_error_kind().is_none());
+        assert_eq!(err.to_string(), "Timeout out while waiting for response");
+        assert_eq!(TimeoutPhase::default(), TimeoutPhase::Connect);
+    }
+
+    #[test]
+    fn test_tls_alert() {
+        // error message is not inspected
+        let err = ConnectError::Io(io::Error::new(
+            io::ErrorKind::Other,
+            "ssl3_read_bytes:tlsv1 alert unknown ca",
+        ));
+        assert!(matches!(err.tls_alert(), ConnectError::Io(_)));
+
+        #[cfg(feature = "rustls")]
+        {
+            use rust_tls::internal::msgs::enums::AlertDescription;
+            use rust_tls::TLSError;
+
+            let alert = |alert| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    TLSError::AlertReceived(alert),
+                )
+            };
+            let err = SendRequestError::from(
+                ConnectError::Io(alert(AlertDescription::UnknownCA)).tls_alert(),
+            );
+            assert!(matches!(
+                err,
+                SendRequestError::Connect(ConnectError::ClientCertificate(_))
+            ));
+
+            let err =
+                SendRequestError::Send(alert(AlertDescription::CertificateRequired));
+            assert!(matches!(
+                err.tls_alert(),
+                SendRequestError::Connect(ConnectError::ClientCertificate(_))
+            ));
+
+            let err = ConnectError::Io(alert(AlertDescription::HandshakeFailure));
+            assert!(matches!(err.tls_alert(), ConnectError::Io(_)));
+        }
+
+        #[cfg(feature = "openssl")]
+        {
+            // SSL_R_TLSV1_ALERT_UNKNOWN_CA, SSL_R_TLSV13_ALERT_CERTIFICATE_REQUIRED
+            assert!(is_openssl_certificate_alert(1048));
+            assert!(is_openssl_certificate_alert(1116));
+            // SSL_R_SSLV3_ALERT_HANDSHAKE_FAILURE
+            assert!(!is_openssl_certificate_alert(1040));
+        }
+    }
+
+    #[test]
+    fn test_source() {
+        let err = SendRequestError::Connect(
+            ConnectError::NoRecords.with_host(Some("example.com")),
+        );
+        let source = err.source().unwrap();
+        assert!(matches!(
+            source.downcast_ref::<ConnectError>(),
+            Some(ConnectError::Dns { .. })
+        ));
+        let source = source.source().unwrap();
+        assert!(matches!(
+            source.downcast_ref::<ConnectError>(),
+            Some(ConnectError::NoRecords)
+        ));
+        assert!(source.source().is_none());
+
+        let err = SendRequestError::Send(io::Error::new(io::ErrorKind::Other, "err"));
+        assert!(err.source().unwrap().downcast_ref::<io::Error>().is_some());
+
+        let err = SendRequestError::from(InvalidUrl::MissingHost);
+        assert!(err.source().unwrap().source().is_none());
+        let err = SendRequestError::Timeout {
+            phase: TimeoutPhase::Connect,
+        };
+        assert!(err.source().is_none());
+
+        let err: SendRequestError =
+            FreezeRequestError::Url(InvalidUrl::MissingScheme).into();
+        assert!(err.source().unwrap().downcast_ref::<InvalidUrl>().is_some());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_invalid_url() {
+        assert_eq!(
+            InvalidUrl::UnsupportedScheme.to_string(),
+            "Unsupported url scheme"
+        );
+        assert_eq!(InvalidUrl::UnknownScheme.to_string(), "Unknown url scheme");
+    }
+
+    #[test]
+    fn test_payload_error() {
+        match SendRequestError::from(PayloadError::TooLarge(10)) {
+            SendRequestError::ResponsePayloadTooLarge { limit } => assert_eq!(limit, 10),
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let err = SendRequestError::from(PayloadError::DecompressionRatioExceeded(100));
+        match err {
+            SendRequestError::DecompressionRatioExceeded { ratio } => {
+                assert_eq!(ratio, 100)
+            }
+            ref e => panic!("unexpected error: {:?}", e),
+        }
+        assert_eq!(
+            err.to_string(),
+            "Response decompression ratio exceeds 100:1"
+        );
+        match SendRequestError::from(PayloadError::EncodingCorrupted) {
+            SendRequestError::Error(_) => (),
+            e => panic!("unexpected error: {:?}", e),
         }
     }
 }
@@ -13,6 +13,7 @@ use crate::http::error::HttpError;
 use crate::http::header::{HeaderMap, HeaderName, HeaderValue};
 use crate::http::{Method, RequestHead, RequestHeadType, Uri};
 
+use super::multipart::Multipart;
 use super::sender::SendClientRequest;
 use super::ClientConfig;
 
@@ -52,7 +53,7 @@ impl FrozenClientRequest {
             self.addr,
             self.response_decompress,
             self.timeout,
-            self.config.as_ref(),
+            &self.config,
             body,
         )
     }
@@ -63,7 +64,34 @@ impl FrozenClientRequest {
             self.addr,
             self.response_decompress,
             self.timeout,
-            self.config.as_ref(),
+            &self.config,
+            value,
+        )
+    }
+
+    /// Send a json body serialized with custom serializer.
+    pub fn send_json_with<T, F>(&self, value: &T, serializer: F) -> SendClientRequest
+    where
+        F: FnOnce(&T) -> Result<Vec<u8>, serde_json::Error>,
+    {
+        RequestHeadType::Rc(self.head.clone(), None).send_json_with(
+            self.addr,
+            self.response_decompress,
+            self.timeout,
+            &self.config,
+            value,
+            serializer,
+        )
+    }
+
+    #[cfg(feature = "msgpack")]
+    /// Send a MessagePack body.
+    pub fn send_msgpack<T: Serialize>(&self, value: &T) -> SendClientRequest {
+        RequestHeadType::Rc(self.head.clone(), None).send_msgpack(
+            self.addr,
+            self.response_decompress,
+            self.timeout,
+            &self.config,
             value,
         )
     }
@@ -74,8 +102,52 @@ impl FrozenClientRequest {
             self.addr,
             self.response_decompress,
             self.timeout,
-            self.config.as_ref(),
+            &self.config,
+            value,
+        )
+    }
+
+    #[cfg(feature = "body-serializer")]
+    /// Send a body serialized by registered serializer for content type.
+    pub fn send_typed<T: Serialize>(
+        &self,
+        value: &T,
+        content_type: &str,
+    ) -> SendClientRequest {
+        RequestHeadType::Rc(self.head.clone(), None).send_typed(
+            self.addr,
+            self.response_decompress,
+            self.timeout,
+            &self.config,
             value,
+            content_type,
+        )
+    }
+
+    /// Send a bytes body with content type.
+    pub fn send_bytes<V>(&self, content_type: V, bytes: Bytes) -> SendClientRequest
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<HttpError>,
+    {
+        RequestHeadType::Rc(self.head.clone(), None).send_bytes(
+            self.addr,
+            self.response_decompress,
+            self.timeout,
+            &self.config,
+            content_type,
+            bytes,
+        )
+    }
+
+    /// Send a `multipart/form-data` body.
+    pub fn send_multipart(&self, multipart: Multipart) -> SendClientRequest {
+        RequestHeadType::Rc(self.head.clone(), None).send_multipart(
+            self.addr,
+            self.response_decompress,
+            self.timeout,
+            &self.config,
+            multipart,
         )
     }
 
@@ -89,21 +161,97 @@ impl FrozenClientRequest {
             self.addr,
             self.response_decompress,
             self.timeout,
-            self.config.as_ref(),
+            &self.config,
             stream,
         )
     }
 
+    /// Send a streaming body of known size.
+    pub fn send_sized_stream<S, E>(&self, stream: S, size: u64) -> SendClientRequest
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+        E: Error + 'static,
+    {
+        RequestHeadType::Rc(self.head.clone(), None).send_sized_stream(
+            self.addr,
+            self.response_decompress,
+            self.timeout,
+            &self.config,
+            stream,
+            size,
+        )
+    }
+
     /// Send an empty body.
     pub fn send(&self) -> SendClientRequest {
         RequestHeadType::Rc(self.head.clone(), None).send(
             self.addr,
             self.response_decompress,
             self.timeout,
-            self.config.as_ref(),
+            &self.config,
         )
     }
 
+    /// Send an empty body to specific server address.
+    ///
+    /// Request head is shared between requests, per-request headers are
+    /// stored in `extra_headers` and override headers of the request.
+    /// Connections are pooled per server address.
+    ///
+    /// ```rust
+    /// use std::net::SocketAddr;
+    /// use ntex::http::{client::Client, header::HeaderMap};
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let request = Client::new()
+    ///         .get("http://backend.local/health")
+    ///         .freeze()
+    ///         .unwrap();
+    ///
+    ///     let backends: Vec<SocketAddr> =
+    ///         vec!["10.0.0.1:8080".parse().unwrap(), "10.0.0.2:8080".parse().unwrap()];
+    ///     let checks: Vec<_> = backends
+    ///         .into_iter()
+    ///         .map(|addr| request.send_to(addr, HeaderMap::new()))
+    ///         .collect();
+    /// }
+    /// ```
+    pub fn send_to(
+        &self,
+        addr: net::SocketAddr,
+        extra_headers: HeaderMap,
+    ) -> SendClientRequest {
+        self.extra_headers(extra_headers).address(addr).send()
+    }
+
+    /// Create a `FrozenSendBuilder` with server address for single send.
+    pub fn address(&self, addr: net::SocketAddr) -> FrozenSendBuilder {
+        self.extra_headers(HeaderMap::new()).address(addr)
+    }
+
+    /// Create a `FrozenSendBuilder` with request timeout for single send.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let health = Client::new()
+    ///         .get("http://backend.local/health")
+    ///         .freeze()
+    ///         .unwrap();
+    ///
+    ///     for _ in 0..3 {
+    ///         let _ = health.timeout(Duration::from_millis(500)).send().await;
+    ///     }
+    /// }
+    /// ```
+    pub fn timeout(&self, timeout: Duration) -> FrozenSendBuilder {
+        self.extra_headers(HeaderMap::new()).timeout(timeout)
+    }
+
     /// Create a `FrozenSendBuilder` with extra headers
     pub fn extra_headers(&self, extra_headers: HeaderMap) -> FrozenSendBuilder {
         FrozenSendBuilder::new(self.clone(), extra_headers)
@@ -156,6 +304,22 @@ impl FrozenSendBuilder {
         self
     }
 
+    /// Set socket address of the server for this request.
+    ///
+    /// Fallback addresses of `FrozenClientRequest` are not changed.
+    pub fn address(mut self, addr: net::SocketAddr) -> Self {
+        self.req.addr = Some(addr);
+        self
+    }
+
+    /// Set request timeout for this send.
+    ///
+    /// Overrides timeout of `FrozenClientRequest`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.req.timeout = Some(timeout);
+        self
+    }
+
     /// Complete request construction and send a body.
     pub fn send_body<B>(self, body: B) -> SendClientRequest
     where
@@ -169,7 +333,7 @@ impl FrozenSendBuilder {
             self.req.addr,
             self.req.response_decompress,
             self.req.timeout,
-            self.req.config.as_ref(),
+            &self.req.config,
             body,
         )
     }
@@ -184,7 +348,43 @@ impl FrozenSendBuilder {
             self.req.addr,
             self.req.response_decompress,
             self.req.timeout,
-            self.req.config.as_ref(),
+            &self.req.config,
+            value,
+        )
+    }
+
+    /// Complete request construction and send a json body serialized
+    /// with custom serializer.
+    pub fn send_json_with<T, F>(self, value: &T, serializer: F) -> SendClientRequest
+    where
+        F: FnOnce(&T) -> Result<Vec<u8>, serde_json::Error>,
+    {
+        if let Some(e) = self.err {
+            return e.into();
+        }
+
+        RequestHeadType::Rc(self.req.head, Some(self.extra_headers)).send_json_with(
+            self.req.addr,
+            self.req.response_decompress,
+            self.req.timeout,
+            &self.req.config,
+            value,
+            serializer,
+        )
+    }
+
+    #[cfg(feature = "msgpack")]
+    /// Complete request construction and send a MessagePack body.
+    pub fn send_msgpack<T: Serialize>(self, value: &T) -> SendClientRequest {
+        if let Some(e) = self.err {
+            return e.into();
+        }
+
+        RequestHeadType::Rc(self.req.head, Some(self.extra_headers)).send_msgpack(
+            self.req.addr,
+            self.req.response_decompress,
+            self.req.timeout,
+            &self.req.config,
             value,
         )
     }
@@ -199,11 +399,68 @@ impl FrozenSendBuilder {
             self.req.addr,
             self.req.response_decompress,
             self.req.timeout,
-            self.req.config.as_ref(),
+            &self.req.config,
             value,
         )
     }
 
+    #[cfg(feature = "body-serializer")]
+    /// Complete request construction and send a body serialized by
+    /// registered serializer for content type.
+    pub fn send_typed<T: Serialize>(
+        self,
+        value: &T,
+        content_type: &str,
+    ) -> SendClientRequest {
+        if let Some(e) = self.err {
+            return e.into();
+        }
+
+        RequestHeadType::Rc(self.req.head, Some(self.extra_headers)).send_typed(
+            self.req.addr,
+            self.req.response_decompress,
+            self.req.timeout,
+            &self.req.config,
+            value,
+            content_type,
+        )
+    }
+
+    /// Complete request construction and send a bytes body with content type.
+    pub fn send_bytes<V>(self, content_type: V, bytes: Bytes) -> SendClientRequest
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<HttpError>,
+    {
+        if let Some(e) = self.err {
+            return e.into();
+        }
+
+        RequestHeadType::Rc(self.req.head, Some(self.extra_headers)).send_bytes(
+            self.req.addr,
+            self.req.response_decompress,
+            self.req.timeout,
+            &self.req.config,
+            content_type,
+            bytes,
+        )
+    }
+
+    /// Complete request construction and send a `multipart/form-data` body.
+    pub fn send_multipart(self, multipart: Multipart) -> SendClientRequest {
+        if let Some(e) = self.err {
+            return e.into();
+        }
+
+        RequestHeadType::Rc(self.req.head, Some(self.extra_headers)).send_multipart(
+            self.req.addr,
+            self.req.response_decompress,
+            self.req.timeout,
+            &self.req.config,
+            multipart,
+        )
+    }
+
     /// Complete request construction and send a streaming body.
     pub fn send_stream<S, E>(self, stream: S) -> SendClientRequest
     where
@@ -218,8 +475,28 @@ impl FrozenSendBuilder {
             self.req.addr,
             self.req.response_decompress,
             self.req.timeout,
-            self.req.config.as_ref(),
+            &self.req.config,
+            stream,
+        )
+    }
+
+    /// Complete request construction and send a streaming body of known size.
+    pub fn send_sized_stream<S, E>(self, stream: S, size: u64) -> SendClientRequest
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+        E: Error + 'static,
+    {
+        if let Some(e) = self.err {
+            return e.into();
+        }
+
+        RequestHeadType::Rc(self.req.head, Some(self.extra_headers)).send_sized_stream(
+            self.req.addr,
+            self.req.response_decompress,
+            self.req.timeout,
+            &self.req.config,
             stream,
+            size,
         )
     }
 
@@ -233,7 +510,7 @@ impl FrozenSendBuilder {
             self.req.addr,
             self.req.response_decompress,
             self.req.timeout,
-            self.req.config.as_ref(),
+            &self.req.config,
         )
     }
 }
@@ -1,6 +1,8 @@
+use std::future::Future;
 use std::io::Write;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use std::{io, mem, time};
 
 use bytes::buf::BufMutExt;
@@ -12,18 +14,25 @@ use crate::codec::{AsyncRead, AsyncWrite, Framed};
 use crate::http::body::{BodySize, MessageBody};
 use crate::http::error::PayloadError;
 use crate::http::h1;
-use crate::http::header::{HeaderMap, HeaderValue, HOST};
+use crate::http::header::{HeaderMap, HeaderName, HeaderValue, EXPECT, HOST};
 use crate::http::message::{RequestHeadType, ResponseHead};
 use crate::http::payload::{Payload, PayloadStream};
+use crate::http::{Method, StatusCode};
+use crate::rt::time::{delay_for, timeout, Instant};
 
 use super::connection::{ConnectionLifetime, ConnectionType, IoConnection};
-use super::error::{ConnectError, SendRequestError};
+use super::error::{ConnectError, SendRequestError, TimeoutPhase};
 use super::pool::Acquired;
+use super::sender::{
+    body_size, ByteCounters, HeadResponse, ProgressTracker, RequestHeaders,
+    RequestOptions, SendContext, Trailers,
+};
 
 pub(super) async fn send_request<T, B>(
     io: T,
     mut head: RequestHeadType,
     body: B,
+    ctx: SendContext,
     created: time::Instant,
     pool: Option<Acquired<T>>,
 ) -> Result<(ResponseHead, Payload), SendRequestError>
@@ -58,40 +67,176 @@ where
         }
     }
 
+    let counters = ByteCounters::default();
     let io = H1Connection {
         created,
         pool,
         io: Some(io),
+        counters: counters.clone(),
     };
 
+    let has_body = !matches!(
+        body.size(),
+        BodySize::None | BodySize::Empty | BodySize::Sized(0)
+    );
+    let expect = has_body && expect_continue(&head);
+    let length = body_size(head.as_ref(), body.size());
+    let max_buffer = RequestOptions::get(head.as_ref(), |opts| opts.stream_buffer_size);
+    let write_timeout = ctx.write_timeout;
+    let continue_timeout = ctx.expect_continue_timeout;
+    let phase = ctx.phase;
+    let progress = ProgressTracker::new(head.as_ref(), body.size());
+    let head_req = head.as_ref().method == Method::HEAD;
+
     // create Framed and send request
-    let mut framed = Framed::new(io, h1::ClientCodec::default());
-    framed.send((head, body.size()).into()).await?;
+    let mut codec = h1::ClientCodec::default();
+    if ctx.preserve_raw_headers {
+        codec.preserve_raw_headers();
+    }
+    if ctx.strict_framing {
+        codec.strict_framing();
+    }
+    if let Some(size) = ctx.max_header_size {
+        codec.max_head_size(size);
+    }
+    if ctx.absolute_form {
+        codec.absolute_form();
+    }
+    let mut framed = Framed::new(io, codec);
+    framed.write((head, length).into())?;
+    let sent = sent_headers(framed.write_buf());
+    poll_fn(|cx| framed.flush(cx)).await?;
+
+    // wait for `100 Continue` before sending body, if server does
+    // not respond in time send body anyway and skip late `100 Continue`
+    let mut late_continue = false;
+    if expect {
+        phase.set(TimeoutPhase::Response);
+        let head = match timeout(continue_timeout, read_continue(&mut framed)).await {
+            Ok(res) => Some(res?),
+            Err(_) => {
+                trace!("No response to expect continue, sending request body");
+                late_continue = true;
+                None
+            }
+        };
+        if let Some(head) = head.filter(|head| head.status != StatusCode::CONTINUE) {
+            // server rejected request, body is not sent so
+            // connection could not be reused
+            trace!("Server responded before request body: {:?}", head.status);
+            head.extensions_mut().insert(RequestHeaders(sent));
+            head.extensions_mut().insert(counters);
+            if head_req {
+                head.extensions_mut().insert(HeadResponse);
+            }
+            return Ok(response(head, framed, true));
+        }
+    }
 
     // send request body
-    match body.size() {
-        BodySize::None | BodySize::Empty | BodySize::Sized(0) => (),
-        _ => send_body(body, &mut framed).await?,
-    };
+    if has_body {
+        phase.set(TimeoutPhase::Request);
+        send_body(body, &mut framed, max_buffer, write_timeout, progress).await?;
+    }
 
     // read response and init read body
-    let res = framed.into_future().await;
-    let (head, framed) = if let (Some(result), framed) = res {
-        let item = result.map_err(SendRequestError::from)?;
-        (item, framed)
-    } else {
-        return Err(SendRequestError::from(ConnectError::Disconnected));
-    };
+    phase.set(TimeoutPhase::Response);
+    let mut head = read_response(&mut framed).await?;
+    if late_continue && head.status == StatusCode::CONTINUE {
+        head = read_response(&mut framed).await?;
+    }
+    head.extensions_mut().insert(RequestHeaders(sent));
+    head.extensions_mut().insert(counters);
+    if head_req {
+        head.extensions_mut().insert(HeadResponse);
+    }
+    Ok(response(head, framed, false))
+}
+
+/// Request headers as they are written by the codec
+fn sent_headers(buf: &[u8]) -> HeaderMap {
+    let lines = buf.windows(2).filter(|w| w == b"\r\n").count();
+    let mut parsed = vec![httparse::EMPTY_HEADER; lines];
+    let mut req = httparse::Request::new(&mut parsed);
+
+    let mut sent = HeaderMap::new();
+    if let Err(e) = req.parse(buf) {
+        log::error!("Can not parse sent request head: {}", e);
+        return sent;
+    }
+    for h in req.headers.iter() {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(h.name.as_bytes()),
+            HeaderValue::from_bytes(h.value),
+        ) {
+            sent.append(name, value);
+        }
+    }
+    sent
+}
+
+/// Check if request expects `100 Continue` interim response
+fn expect_continue(head: &RequestHeadType) -> bool {
+    head.extra_headers()
+        .and_then(|h| h.get(EXPECT))
+        .or_else(|| head.as_ref().headers.get(EXPECT))
+        .map(|v| v.as_bytes().eq_ignore_ascii_case(b"100-continue"))
+        .unwrap_or(false)
+}
+
+/// Read response head
+async fn read_response<T>(
+    framed: &mut Framed<T, h1::ClientCodec>,
+) -> Result<ResponseHead, SendRequestError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    match framed.next().await {
+        Some(result) => result.map_err(SendRequestError::from),
+        None => Err(SendRequestError::from(ConnectError::Disconnected)),
+    }
+}
+
+/// Read `100 Continue` or final response head, skip other interim responses
+async fn read_continue<T>(
+    framed: &mut Framed<T, h1::ClientCodec>,
+) -> Result<ResponseHead, SendRequestError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        let head = read_response(framed).await?;
+        match head.status {
+            StatusCode::CONTINUE | StatusCode::SWITCHING_PROTOCOLS => return Ok(head),
+            status if !status.is_informational() => return Ok(head),
+            _ => trace!("Skip interim response: {:?}", head.status),
+        }
+    }
+}
+
+/// Init response payload
+fn response<T>(
+    head: ResponseHead,
+    framed: Framed<T, h1::ClientCodec>,
+    force_close: bool,
+) -> (ResponseHead, Payload)
+where
+    T: ConnectionLifetime,
+{
+    let trailers = Trailers::default();
+    head.extensions_mut().insert(trailers.clone());
 
     match framed.get_codec().message_type() {
         h1::MessageType::None => {
-            let force_close = !framed.get_codec().keepalive();
+            let force_close = force_close || !framed.get_codec().keepalive();
             release_connection(framed, force_close);
-            Ok((head, Payload::None))
+            trailers.set(HeaderMap::new());
+            (head, Payload::None)
         }
         _ => {
-            let pl: PayloadStream = PlStream::new(framed).boxed_local();
-            Ok((head, pl.into()))
+            let pl: PayloadStream =
+                PlStream::new(framed, force_close, trailers).boxed_local();
+            (head, pl.into())
         }
     }
 }
@@ -99,12 +244,20 @@ where
 pub(super) async fn open_tunnel<T>(
     io: T,
     head: RequestHeadType,
+    ctx: SendContext,
 ) -> Result<(ResponseHead, Framed<T, h1::ClientCodec>), SendRequestError>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     // create Framed and send request
-    let mut framed = Framed::new(io, h1::ClientCodec::default());
+    let mut codec = h1::ClientCodec::default();
+    if let Some(size) = ctx.max_header_size {
+        codec.max_head_size(size);
+    }
+    if ctx.absolute_form {
+        codec.absolute_form();
+    }
+    let mut framed = Framed::new(io, codec);
     framed.send((head, BodySize::None).into()).await?;
 
     // read response
@@ -116,55 +269,104 @@ where
     }
 }
 
-/// send request body to the peer
+/// Send request body, source is not polled while write buffer is full
 pub(super) async fn send_body<I, B>(
     mut body: B,
     framed: &mut Framed<I, h1::ClientCodec>,
+    max_buffer: Option<usize>,
+    write_timeout: Option<Duration>,
+    mut progress: Option<ProgressTracker>,
 ) -> Result<(), SendRequestError>
 where
     I: ConnectionLifetime,
     B: MessageBody,
 {
+    let is_full = |framed: &mut Framed<I, h1::ClientCodec>| match max_buffer {
+        Some(size) => !framed.is_write_buf_empty() && framed.write_buf().len() >= size,
+        None => framed.is_write_buf_full(),
+    };
+
     let mut eof = false;
     while !eof {
-        while !eof && !framed.is_write_buf_full() {
+        while !eof && !is_full(framed) {
             match poll_fn(|cx| body.poll_next_chunk(cx)).await {
                 Some(result) => {
-                    framed.write(h1::Message::Chunk(Some(result?)))?;
+                    let chunk = result?;
+                    if let Some(ref mut progress) = progress {
+                        progress.written(chunk.len());
+                    }
+                    framed.write(h1::Message::Chunk(Some(chunk)))?;
                 }
                 None => {
                     eof = true;
+                    if let Some(trailers) = body.trailers() {
+                        framed.get_codec_mut().set_trailers(trailers);
+                    }
                     framed.write(h1::Message::Chunk(None))?;
                 }
             }
         }
 
         if !framed.is_write_buf_empty() {
-            poll_fn(|cx| match framed.flush(cx) {
-                Poll::Ready(Ok(_)) => Poll::Ready(Ok(())),
-                Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
-                Poll::Pending => {
-                    if !framed.is_write_buf_full() {
-                        Poll::Ready(Ok(()))
-                    } else {
-                        Poll::Pending
-                    }
-                }
+            flush(framed, write_timeout, &mut progress, |framed| {
+                !is_full(framed)
             })
             .await?;
         }
     }
 
-    SinkExt::flush(framed).await?;
-    Ok(())
+    flush(framed, write_timeout, &mut progress, |_| false).await
+}
+
+/// Flush write buffer until it is empty or `done` returns true,
+/// fails if no data is written within `write_timeout`
+async fn flush<I, F>(
+    framed: &mut Framed<I, h1::ClientCodec>,
+    write_timeout: Option<Duration>,
+    progress: &mut Option<ProgressTracker>,
+    done: F,
+) -> Result<(), SendRequestError>
+where
+    I: ConnectionLifetime,
+    F: Fn(&mut Framed<I, h1::ClientCodec>) -> bool,
+{
+    let mut delay = write_timeout.map(delay_for);
+    poll_fn(|cx| {
+        let buffered = framed.write_buf().len();
+        let res = framed.flush(cx);
+        if let Some(ref mut progress) = progress {
+            progress.report(framed.write_buf().len());
+        }
+        match res {
+            Poll::Ready(Ok(_)) => return Poll::Ready(Ok(())),
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+            Poll::Pending => (),
+        }
+        if done(framed) {
+            return Poll::Ready(Ok(()));
+        }
+        if let (Some(delay), Some(timeout)) = (delay.as_mut(), write_timeout) {
+            if framed.write_buf().len() < buffered {
+                delay.reset(Instant::now() + timeout);
+            }
+            if Pin::new(delay).poll(cx).is_ready() {
+                return Poll::Ready(Err(SendRequestError::Timeout {
+                    phase: TimeoutPhase::Write,
+                }));
+            }
+        }
+        Poll::Pending
+    })
+    .await
 }
 
 #[doc(hidden)]
 /// HTTP client connection
-pub(super) struct H1Connection<T> {
+pub(super) struct H1Connection<T: AsyncRead + AsyncWrite + Unpin + 'static> {
     io: Option<T>,
     created: time::Instant,
     pool: Option<Acquired<T>>,
+    counters: ByteCounters,
 }
 
 impl<T> ConnectionLifetime for H1Connection<T>
@@ -198,6 +400,14 @@ where
     }
 }
 
+impl<T: AsyncRead + AsyncWrite + Unpin + 'static> Drop for H1Connection<T> {
+    /// Connection is dropped before response is fully read,
+    /// it is in unknown state and could not be reused
+    fn drop(&mut self) {
+        self.close()
+    }
+}
+
 impl<T: AsyncRead + AsyncWrite + Unpin + 'static> AsyncRead for H1Connection<T> {
     unsafe fn prepare_uninitialized_buffer(
         &self,
@@ -211,7 +421,11 @@ impl<T: AsyncRead + AsyncWrite + Unpin + 'static> AsyncRead for H1Connection<T>
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.io.as_mut().unwrap()).poll_read(cx, buf)
+        let res = Pin::new(&mut self.io.as_mut().unwrap()).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            self.counters.received(n);
+        }
+        res
     }
 }
 
@@ -221,7 +435,11 @@ impl<T: AsyncRead + AsyncWrite + Unpin + 'static> AsyncWrite for H1Connection<T>
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.io.as_mut().unwrap()).poll_write(cx, buf)
+        let res = Pin::new(&mut self.io.as_mut().unwrap()).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            self.counters.sent(n);
+        }
+        res
     }
 
     fn poll_flush(
@@ -241,12 +459,20 @@ impl<T: AsyncRead + AsyncWrite + Unpin + 'static> AsyncWrite for H1Connection<T>
 
 pub(super) struct PlStream<Io> {
     framed: Option<Framed<Io, h1::ClientPayloadCodec>>,
+    force_close: bool,
+    trailers: Trailers,
 }
 
 impl<Io: ConnectionLifetime> PlStream<Io> {
-    fn new(framed: Framed<Io, h1::ClientCodec>) -> Self {
+    fn new(
+        framed: Framed<Io, h1::ClientCodec>,
+        force_close: bool,
+        trailers: Trailers,
+    ) -> Self {
         PlStream {
             framed: Some(framed.map_codec(|codec| codec.into_payload_codec())),
+            force_close,
+            trailers,
         }
     }
 }
@@ -266,8 +492,11 @@ impl<Io: ConnectionLifetime> Stream for PlStream<Io> {
                 if let Some(chunk) = chunk {
                     Poll::Ready(Some(Ok(chunk)))
                 } else {
-                    let framed = this.framed.take().unwrap();
-                    let force_close = !framed.get_codec().keepalive();
+                    let mut framed = this.framed.take().unwrap();
+                    let trailers = framed.get_codec_mut().take_trailers();
+                    this.trailers.set(trailers.unwrap_or_default());
+                    let force_close =
+                        this.force_close || !framed.get_codec().keepalive();
                     release_connection(framed, force_close);
                     Poll::Ready(None)
                 }
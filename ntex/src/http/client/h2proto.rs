@@ -1,26 +1,34 @@
 use std::convert::TryFrom;
-use std::time;
+use std::time::{self, Duration};
 
 use bytes::Bytes;
 use futures::future::poll_fn;
+use futures::StreamExt;
 use h2::{client::SendRequest, SendStream};
 use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, TRANSFER_ENCODING};
 use http::{request::Request, Method, Version};
 
 use crate::codec::{AsyncRead, AsyncWrite};
 use crate::http::body::{BodySize, MessageBody};
+use crate::http::h1::RawHeaders;
+use crate::http::h2::Payload as H2Payload;
 use crate::http::header::HeaderMap;
 use crate::http::message::{RequestHeadType, ResponseHead};
-use crate::http::payload::Payload;
+use crate::http::payload::{Payload, PayloadStream};
+use crate::rt::time::timeout;
 
 use super::connection::{ConnectionType, IoConnection};
-use super::error::SendRequestError;
+use super::error::{SendRequestError, TimeoutPhase};
 use super::pool::Acquired;
+use super::sender::{
+    body_size, ByteCounters, HeadResponse, ProgressTracker, RequestHeaders, SendContext,
+};
 
 pub(super) async fn send_request<T, B>(
     mut io: SendRequest<Bytes>,
     head: RequestHeadType,
     body: B,
+    ctx: SendContext,
     created: time::Instant,
     pool: Option<Acquired<T>>,
 ) -> Result<(ResponseHead, Payload), SendRequestError>
@@ -29,8 +37,13 @@ where
     B: MessageBody,
 {
     trace!("Sending client request: {:?} {:?}", head, body.size());
+    let phase = ctx.phase;
     let head_req = head.as_ref().method == Method::HEAD;
-    let length = body.size();
+    let preserve_raw = ctx.preserve_raw_headers;
+    let write_timeout = ctx.write_timeout;
+    let max_header_size = ctx.max_header_size;
+    let length = body_size(head.as_ref(), body.size());
+    let progress = ProgressTracker::new(head.as_ref(), body.size());
     let eof = match length {
         BodySize::None | BodySize::Empty | BodySize::Sized(0) => true,
         _ => false,
@@ -86,19 +99,23 @@ where
         req.headers_mut().append(key, value.clone());
     }
 
+    let sent = HeaderMap::from(req.headers().clone());
+
     let res = poll_fn(|cx| io.poll_ready(cx)).await;
     if let Err(e) = res {
         release(io, pool, created, e.is_io());
         return Err(SendRequestError::from(e));
     }
 
+    let counters = ByteCounters::default();
     let resp = match io.send_request(req, eof) {
         Ok((fut, send)) => {
             release(io, pool, created, false);
 
             if !eof {
-                send_body(body, send).await?;
+                send_body(body, send, write_timeout, &counters, progress).await?;
             }
+            phase.set(TimeoutPhase::Response);
             fut.await.map_err(SendRequestError::from)?
         }
         Err(e) => {
@@ -108,17 +125,59 @@ where
     };
 
     let (parts, body) = resp.into_parts();
-    let payload = if head_req { Payload::None } else { body.into() };
+    if let Some(limit) = max_header_size {
+        // header list size as defined by http/2, including `:status` field
+        let size = parts.headers.iter().fold(42, |size, (name, value)| {
+            size + name.as_str().len() + value.len() + 32
+        });
+        if size > limit {
+            return Err(SendRequestError::ResponseHeadersTooLarge { limit });
+        }
+    }
+    let payload = if head_req {
+        Payload::None
+    } else {
+        let received = counters.clone();
+        let pl: PayloadStream = H2Payload::new(body)
+            .inspect(move |chunk| {
+                if let Ok(chunk) = chunk {
+                    received.received(chunk.len())
+                }
+            })
+            .boxed_local();
+        pl.into()
+    };
 
     let mut head = ResponseHead::new(parts.status);
     head.version = parts.version;
+    if preserve_raw {
+        let raw = parts
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    Bytes::copy_from_slice(name.as_str().as_bytes()),
+                    Bytes::copy_from_slice(value.as_bytes()),
+                )
+            })
+            .collect();
+        head.extensions_mut().insert(RawHeaders(raw));
+    }
     head.headers = parts.headers.into();
+    head.extensions_mut().insert(RequestHeaders(sent));
+    head.extensions_mut().insert(counters);
+    if head_req {
+        head.extensions_mut().insert(HeadResponse);
+    }
     Ok((head, payload))
 }
 
 async fn send_body<B: MessageBody>(
     mut body: B,
     mut send: SendStream<Bytes>,
+    write_timeout: Option<Duration>,
+    counters: &ByteCounters,
+    mut progress: Option<ProgressTracker>,
 ) -> Result<(), SendRequestError> {
     let mut buf = None;
     loop {
@@ -130,7 +189,16 @@ async fn send_body<B: MessageBody>(
                 }
                 Some(Err(e)) => return Err(e.into()),
                 None => {
-                    if let Err(e) = send.send_data(Bytes::new(), true) {
+                    let res = if let Some(trailers) = body.trailers() {
+                        let mut map = http::HeaderMap::with_capacity(trailers.len());
+                        for (name, value) in trailers.iter() {
+                            map.append(name.clone(), value.clone());
+                        }
+                        send.send_trailers(map)
+                    } else {
+                        send.send_data(Bytes::new(), true)
+                    };
+                    if let Err(e) = res {
                         return Err(e.into());
                     }
                     send.reserve_capacity(0);
@@ -139,12 +207,29 @@ async fn send_body<B: MessageBody>(
             }
         }
 
-        match poll_fn(|cx| send.poll_capacity(cx)).await {
+        let capacity = poll_fn(|cx| send.poll_capacity(cx));
+        let capacity = match write_timeout {
+            Some(to) => match timeout(to, capacity).await {
+                Ok(capacity) => capacity,
+                Err(_) => {
+                    return Err(SendRequestError::Timeout {
+                        phase: TimeoutPhase::Write,
+                    })
+                }
+            },
+            None => capacity.await,
+        };
+        match capacity {
             None => return Ok(()),
             Some(Ok(cap)) => {
                 let b = buf.as_mut().unwrap();
                 let len = b.len();
                 let bytes = b.split_to(std::cmp::min(cap, len));
+                counters.sent(bytes.len());
+                if let Some(ref mut progress) = progress {
+                    progress.written(bytes.len());
+                    progress.report(0);
+                }
 
                 if let Err(e) = send.send_data(bytes, false) {
                     return Err(e.into());
@@ -0,0 +1,102 @@
+//! Http client interceptors
+use futures::future::{ready, FutureExt, LocalBoxFuture};
+
+use crate::http::RequestHeadType;
+
+use super::error::SendRequestError;
+use super::response::ClientResponse;
+
+/// Request interceptor is called before request is sent.
+///
+/// Interceptor could modify request head or abort request by returning
+/// an error, in that case connection is not opened. Multiple interceptors
+/// are called in order of registration, for every redirect hop as well.
+/// It is implemented for functions with
+/// `Fn(&mut RequestHeadType) -> Result<(), SendRequestError>` signature.
+///
+/// ```rust
+/// use ntex::http::client::Client;
+/// use ntex::http::header::{HeaderName, HeaderValue};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .request_interceptor(|head: &mut ntex::http::RequestHeadType| {
+///             head.insert_header(
+///                 HeaderName::from_static("x-request-source"),
+///                 HeaderValue::from_static("ntex"),
+///             );
+///             Ok(())
+///         })
+///         .finish();
+/// }
+/// ```
+pub trait RequestInterceptor {
+    /// Inspect or modify request head before sending
+    fn on_request<'a>(
+        &'a self,
+        head: &'a mut RequestHeadType,
+    ) -> LocalBoxFuture<'a, Result<(), SendRequestError>>;
+}
+
+impl<F> RequestInterceptor for F
+where
+    F: Fn(&mut RequestHeadType) -> Result<(), SendRequestError>,
+{
+    fn on_request<'a>(
+        &'a self,
+        head: &'a mut RequestHeadType,
+    ) -> LocalBoxFuture<'a, Result<(), SendRequestError>> {
+        ready((self)(head)).boxed_local()
+    }
+}
+
+/// Response interceptor is called once response head is received.
+///
+/// Interceptor is called before response payload is read, it could
+/// inspect response status and headers, replace response or map it to
+/// an error. Errors of sending request are passed to interceptors as well,
+/// request timeout error is not. Multiple interceptors are called in order
+/// of registration. It is implemented for functions with
+/// `Fn(Result<ClientResponse, SendRequestError>) -> Result<ClientResponse, SendRequestError>`
+/// signature.
+///
+/// ```rust
+/// use ntex::http::client::{Client, ClientResponse, error::SendRequestError};
+/// use ntex::http::StatusCode;
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .response_interceptor(|res: Result<ClientResponse, SendRequestError>| {
+///             match res {
+///                 Ok(res) if res.status() == StatusCode::UNAUTHORIZED => {
+///                     Err(SendRequestError::Error("Unauthorized".into()))
+///                 }
+///                 res => res,
+///             }
+///         })
+///         .finish();
+/// }
+/// ```
+pub trait ResponseInterceptor {
+    /// Inspect or replace response
+    fn on_response(
+        &self,
+        res: Result<ClientResponse, SendRequestError>,
+    ) -> Result<ClientResponse, SendRequestError>;
+}
+
+impl<F> ResponseInterceptor for F
+where
+    F: Fn(
+        Result<ClientResponse, SendRequestError>,
+    ) -> Result<ClientResponse, SendRequestError>,
+{
+    fn on_response(
+        &self,
+        res: Result<ClientResponse, SendRequestError>,
+    ) -> Result<ClientResponse, SendRequestError> {
+        (self)(res)
+    }
+}
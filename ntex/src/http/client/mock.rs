@@ -0,0 +1,304 @@
+//! Mock connector for testing http client without network.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use futures::future::{err, ok, poll_fn, FutureExt, LocalBoxFuture, Ready};
+use serde::Serialize;
+
+use crate::codec::Framed;
+use crate::http::body::MessageBody;
+use crate::http::error::HttpError;
+use crate::http::h1::ClientCodec;
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::http::{h1, Method, Payload, Protocol, RequestHeadType, ResponseHead};
+use crate::http::{StatusCode, Uri, Version};
+use crate::testing::Io;
+use crate::Service;
+
+use super::error::{ConnectError, SendRequestError};
+use super::{Connect, Connection};
+
+/// Connector that resolves requests against registered canned responses
+///
+/// Responses are registered by request method and path, requests are
+/// captured and could be inspected after sending. Requests without
+/// registered response get `404 Not Found` response.
+///
+/// ```rust
+/// use ntex::http::client::{Client, MockConnector, MockResponse};
+/// use ntex::http::Method;
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let mock = MockConnector::new().response(
+///         Method::GET,
+///         "/users",
+///         MockResponse::ok().json(&serde_json::json!({"name": "ntex"})),
+///     );
+///     let client = Client::build().connector(mock.clone()).finish();
+///
+///     let mut res = client.get("http://localhost/users").send().await.unwrap();
+///     let body: serde_json::Value = res.json().await.unwrap();
+///     assert_eq!(body["name"], "ntex");
+///     assert_eq!(mock.requests()[0].uri.path(), "/users");
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct MockConnector(Rc<RefCell<MockInner>>);
+
+#[derive(Default)]
+struct MockInner {
+    responses: HashMap<(Method, String), MockResponse>,
+    requests: Vec<MockRequest>,
+}
+
+impl MockConnector {
+    /// Create mock connector without registered responses
+    pub fn new() -> Self {
+        MockConnector::default()
+    }
+
+    /// Register response for requests with `method` and `path`
+    ///
+    /// Request query is not used for matching. Previously registered
+    /// response for the same method and path is replaced.
+    pub fn response<P: Into<String>>(
+        self,
+        method: Method,
+        path: P,
+        response: MockResponse,
+    ) -> Self {
+        self.0
+            .borrow_mut()
+            .responses
+            .insert((method, path.into()), response);
+        self
+    }
+
+    /// Returns requests captured so far
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.0.borrow().requests.clone()
+    }
+
+    fn handle(&self, req: MockRequest) -> MockResponse {
+        let mut inner = self.0.borrow_mut();
+        let res = inner
+            .responses
+            .get(&(req.method.clone(), req.uri.path().to_string()))
+            .cloned()
+            .unwrap_or_else(|| MockResponse::new(StatusCode::NOT_FOUND));
+        inner.requests.push(req);
+        res
+    }
+}
+
+impl Service for MockConnector {
+    type Request = Connect;
+    type Response = MockConnection;
+    type Error = ConnectError;
+    type Future = Ready<Result<MockConnection, ConnectError>>;
+
+    #[inline]
+    fn poll_ready(&self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&self, _: Connect) -> Self::Future {
+        ok(MockConnection(self.clone()))
+    }
+}
+
+/// Request captured by `MockConnector`
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    /// Request method
+    pub method: Method,
+    /// Request url
+    pub uri: Uri,
+    /// Request headers, including extra headers of frozen requests
+    pub headers: HeaderMap,
+    /// Request body
+    pub body: Bytes,
+}
+
+/// Canned response of `MockConnector`
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    version: Version,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl MockResponse {
+    /// Create response with status code
+    pub fn new(status: StatusCode) -> Self {
+        MockResponse {
+            status,
+            version: Version::HTTP_11,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+        }
+    }
+
+    /// Create `200 OK` response
+    pub fn ok() -> Self {
+        MockResponse::new(StatusCode::OK)
+    }
+
+    /// Set HTTP version of this response
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Append a header
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        HeaderValue: TryFrom<V>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+    {
+        if let Ok(key) = HeaderName::try_from(key) {
+            if let Ok(value) = HeaderValue::try_from(value) {
+                self.headers.append(key, value);
+                return self;
+            }
+        }
+        panic!("Can not create header");
+    }
+
+    /// Set response body
+    pub fn body<B: Into<Bytes>>(mut self, body: B) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Set json response body and `Content-Type` header
+    pub fn json<T: Serialize>(self, value: &T) -> Self {
+        let body = serde_json::to_vec(value).expect("Can not serialize json body");
+        self.header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+    }
+
+    fn into_parts(self) -> (ResponseHead, Payload) {
+        let mut head = ResponseHead::new(self.status);
+        head.version = self.version;
+        head.headers = self.headers;
+        head.headers
+            .insert(header::CONTENT_LENGTH, HeaderValue::from(self.body.len()));
+
+        let mut payload = h1::Payload::empty();
+        if !self.body.is_empty() {
+            payload.unread_data(self.body);
+        }
+        (head, payload.into())
+    }
+}
+
+/// Connection of `MockConnector`
+pub struct MockConnection(MockConnector);
+
+impl Connection for MockConnection {
+    type Io = Io;
+    type Future =
+        LocalBoxFuture<'static, Result<(ResponseHead, Payload), SendRequestError>>;
+
+    fn protocol(&self) -> Protocol {
+        Protocol::Http1
+    }
+
+    fn send_request<B: MessageBody + 'static, H: Into<RequestHeadType>>(
+        self,
+        head: H,
+        mut body: B,
+    ) -> Self::Future {
+        let head = head.into();
+
+        async move {
+            let mut buf = BytesMut::new();
+            while let Some(chunk) = poll_fn(|cx| body.poll_next_chunk(cx)).await {
+                buf.extend_from_slice(&chunk.map_err(SendRequestError::Error)?);
+            }
+
+            let mut headers = head.as_ref().headers.clone();
+            if let Some(extra) = head.extra_headers() {
+                for name in extra.keys() {
+                    headers.remove(name);
+                }
+                for (name, value) in extra.iter() {
+                    headers.append(name.clone(), value.clone());
+                }
+            }
+
+            let req = MockRequest {
+                headers,
+                method: head.as_ref().method.clone(),
+                uri: head.as_ref().uri.clone(),
+                body: buf.freeze(),
+            };
+            Ok(self.0.handle(req).into_parts())
+        }
+        .boxed_local()
+    }
+
+    type TunnelFuture =
+        Ready<Result<(ResponseHead, Framed<Self::Io, ClientCodec>), SendRequestError>>;
+
+    fn open_tunnel<H: Into<RequestHeadType>>(self, _: H) -> Self::TunnelFuture {
+        err(SendRequestError::TunnelNotSupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::Client;
+
+    #[ntex_rt::test]
+    async fn test_mock_connector() {
+        let mock = MockConnector::new()
+            .response(
+                Method::GET,
+                "/test",
+                MockResponse::ok().json(&serde_json::json!({"id": 1})),
+            )
+            .response(
+                Method::POST,
+                "/test",
+                MockResponse::new(StatusCode::CREATED),
+            );
+        let client = Client::build().connector(mock.clone()).finish();
+
+        let mut res = client
+            .get("http://localhost/test?q=1")
+            .header("x-test", "1")
+            .send()
+            .await
+            .unwrap();
+        assert!(res.status().is_success());
+        let body: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(body["id"], 1);
+
+        let res = client
+            .post("http://localhost/test")
+            .send_body("data")
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        let res = client.get("http://localhost/unknown").send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].method, Method::GET);
+        assert_eq!(requests[0].uri.query(), Some("q=1"));
+        assert_eq!(requests[0].headers.get("x-test").unwrap(), "1");
+        assert_eq!(requests[1].body, Bytes::from_static(b"data"));
+    }
+}
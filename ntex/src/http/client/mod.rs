@@ -16,44 +16,155 @@
 //!     println!("Response: {:?}", response);
 //! }
 //! ```
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::future::Future;
 use std::rc::Rc;
 use std::time::Duration;
 
+#[cfg(feature = "cookie")]
+use std::sync::{Arc, Mutex};
+
+mod backoff;
+mod breaker;
+mod browser;
 mod builder;
 mod connect;
 mod connection;
 mod connector;
+#[cfg(feature = "cookie")]
+mod cookie;
 pub mod error;
 mod frozen;
 mod h1proto;
 mod h2proto;
+mod interceptor;
+#[cfg(feature = "test-util")]
+mod mock;
+mod multipart;
+mod parts;
 mod pool;
+mod proxy;
+mod range;
+mod ratelimit;
+mod redirect;
 mod request;
 mod response;
+mod retry;
+mod save;
 mod sender;
+#[cfg(feature = "body-serializer")]
+mod serialize;
+mod sign;
 mod test;
+#[cfg(feature = "test-util")]
+mod timer;
+mod tls;
+mod trace;
+mod useragent;
 pub mod ws;
 
+pub use self::backoff::Backoff;
+pub use self::breaker::CircuitBreaker;
+pub use self::browser::BrowserProfile;
 pub use self::builder::ClientBuilder;
 pub use self::connect::BoxedSocket;
 pub use self::connection::Connection;
-pub use self::connector::Connector;
+pub use self::connector::{Connector, ProtocolMode, Resolve};
+#[cfg(feature = "cookie")]
+pub use self::cookie::CookieJar;
 pub use self::frozen::{FrozenClientRequest, FrozenSendBuilder};
+pub use self::interceptor::{RequestInterceptor, ResponseInterceptor};
+#[cfg(feature = "test-util")]
+pub use self::mock::{MockConnection, MockConnector, MockRequest, MockResponse};
+pub use self::multipart::Multipart;
+pub use self::parts::{MultipartPart, MultipartStream};
+pub use self::proxy::Proxy;
+pub use self::range::{ByteRanges, ContentRange, RangePart};
+pub use self::ratelimit::RateLimit;
 pub use self::request::ClientRequest;
-pub use self::response::{ClientResponse, JsonBody, MessageBody};
-pub use self::sender::SendClientRequest;
+pub use self::response::{
+    AutoBody, ClientResponse, JsonBody, JsonStream, MessageBody, TextBody, Timings,
+};
+pub use self::retry::RetryPolicy;
+pub use self::save::SaveTo;
+pub use self::sender::{
+    RawSendClientRequest, RequestMetrics, SendClientRequest, StreamOptions,
+};
+#[cfg(all(feature = "body-serializer", feature = "msgpack"))]
+pub use self::serialize::MsgPack;
+#[cfg(feature = "body-serializer")]
+pub use self::serialize::{BodySerializer, ErasedSerialize, Form, Json};
+pub use self::sign::{RequestSigner, SignPayload, StreamingBody};
 pub use self::test::TestResponse;
+#[cfg(feature = "test-util")]
+pub use self::timer::{Timer, VirtualClock};
+pub use self::tls::{ClientIdentity, RootCertificates, TlsInfo};
+pub use self::trace::TraceContext;
+pub use self::useragent::UserAgents;
 
 use crate::http::error::HttpError;
 use crate::http::{HeaderMap, Method, RequestHead, Uri};
 
+use self::breaker::CircuitBreakers;
 use self::connect::{Connect as InnerConnect, ConnectorWrapper};
+use self::error::{InvalidUrl, SendRequestError};
+use self::ratelimit::RateLimiter;
+use self::sender::OnComplete;
+#[cfg(feature = "body-serializer")]
+use self::serialize::Serializers;
+use self::useragent::UserAgentRotation;
 
 #[derive(Clone)]
 pub struct Connect {
     pub uri: Uri,
     pub addr: Option<std::net::SocketAddr>,
+    pub(self) fallback_addrs: Vec<std::net::SocketAddr>,
+    pub(self) pool_key: Option<String>,
+    pub(self) resolver: Option<Rc<dyn Resolve>>,
+    pub(self) dns_timeout: Option<Duration>,
+    pub(self) proxy: Option<Rc<Proxy>>,
+}
+
+impl Connect {
+    /// Create connect message for uri
+    pub fn new(uri: Uri, addr: Option<std::net::SocketAddr>) -> Self {
+        Connect {
+            uri,
+            addr,
+            fallback_addrs: Vec::new(),
+            pool_key: None,
+            resolver: None,
+            dns_timeout: None,
+            proxy: None,
+        }
+    }
+
+    /// Addresses to try if connection to `addr` fails
+    pub fn fallback_addrs(&self) -> &[std::net::SocketAddr] {
+        &self.fallback_addrs
+    }
+
+    /// Connection pool key, see `ClientRequest::pool_key()`
+    pub fn pool_key(&self) -> Option<&str> {
+        self.pool_key.as_deref()
+    }
+
+    /// Custom dns resolver, see `ClientBuilder::resolver()`
+    pub fn resolver(&self) -> Option<&Rc<dyn Resolve>> {
+        self.resolver.as_ref()
+    }
+
+    /// Max time of dns name resolution, see `ClientBuilder::dns_timeout()`
+    pub fn dns_timeout(&self) -> Option<Duration> {
+        self.dns_timeout
+    }
+
+    /// Proxy server to connect through, see `ClientBuilder::proxy()`
+    pub fn proxy(&self) -> Option<&Proxy> {
+        self.proxy.as_deref()
+    }
 }
 
 /// An HTTP Client
@@ -76,10 +187,56 @@ pub struct Connect {
 #[derive(Clone)]
 pub struct Client(Rc<ClientConfig>);
 
+/// Default max ratio of decompressed to compressed response payload
+const DEFAULT_DECOMPRESSION_RATIO: usize = 1000;
+
+/// Default max time to wait for `100 Continue` interim response
+const DEFAULT_EXPECT_CONTINUE_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub(self) struct ClientConfig {
     pub(self) connector: Box<dyn InnerConnect>,
     pub(self) headers: HeaderMap,
     pub(self) timeout: Option<Duration>,
+    pub(self) connect_timeout: Option<Duration>,
+    pub(self) dns_timeout: Option<Duration>,
+    pub(self) write_timeout: Option<Duration>,
+    pub(self) expect_continue_timeout: Duration,
+    pub(self) retry: Option<RetryPolicy>,
+    pub(self) max_redirects: usize,
+    pub(self) deny_redirects: bool,
+    pub(self) max_response_size: Option<usize>,
+    pub(self) max_response_header_size: Option<usize>,
+    pub(self) lenient_decompression: bool,
+    pub(self) preserve_raw_headers: bool,
+    pub(self) strict_framing: bool,
+    pub(self) max_decompression_ratio: Option<usize>,
+    pub(self) resolver: Option<Rc<dyn Resolve>>,
+    pub(self) proxy: Option<Rc<Proxy>>,
+    pub(self) rate_limiter: Option<RateLimiter>,
+    pub(self) circuit_breaker: Option<CircuitBreakers>,
+    pub(self) user_agents: Option<UserAgentRotation>,
+    pub(self) max_connections: Option<usize>,
+    pub(self) max_connections_per_host: Option<usize>,
+    pub(self) keep_alive_timeout: Option<Duration>,
+    pub(self) happy_eyeballs: Option<Duration>,
+    pub(self) tcp_nodelay: Option<bool>,
+    pub(self) tcp_keepalive: Option<Duration>,
+    pub(self) root_certs: Option<RootCertificates>,
+    pub(self) identity: Option<ClientIdentity>,
+    pub(self) host_identities: HashMap<String, ClientIdentity>,
+    pub(self) protocol: Option<ProtocolMode>,
+    pub(self) trace_context: Option<Rc<dyn Fn() -> Option<TraceContext>>>,
+    pub(self) request_interceptors: Vec<Rc<dyn RequestInterceptor>>,
+    pub(self) response_interceptors: Vec<Rc<dyn ResponseInterceptor>>,
+    pub(self) signer: Option<Rc<dyn RequestSigner>>,
+    #[cfg(feature = "body-serializer")]
+    pub(self) serializers: Serializers,
+    pub(self) closed: Cell<bool>,
+    pub(self) on_complete: Option<OnComplete>,
+    #[cfg(feature = "cookie")]
+    pub(self) cookie_jar: Option<Arc<Mutex<CookieJar>>>,
+    #[cfg(feature = "test-util")]
+    pub(self) timer: Option<Rc<dyn Timer>>,
 }
 
 impl Default for Client {
@@ -88,6 +245,46 @@ impl Default for Client {
             connector: Box::new(ConnectorWrapper(Connector::default().finish())),
             headers: HeaderMap::new(),
             timeout: Some(Duration::from_secs(5)),
+            connect_timeout: None,
+            dns_timeout: None,
+            write_timeout: None,
+            expect_continue_timeout: DEFAULT_EXPECT_CONTINUE_TIMEOUT,
+            retry: None,
+            max_redirects: 0,
+            deny_redirects: false,
+            max_response_size: None,
+            max_response_header_size: None,
+            lenient_decompression: false,
+            preserve_raw_headers: false,
+            strict_framing: false,
+            max_decompression_ratio: Some(DEFAULT_DECOMPRESSION_RATIO),
+            resolver: None,
+            proxy: None,
+            rate_limiter: None,
+            circuit_breaker: None,
+            user_agents: None,
+            max_connections: None,
+            max_connections_per_host: None,
+            keep_alive_timeout: None,
+            happy_eyeballs: None,
+            tcp_nodelay: None,
+            tcp_keepalive: None,
+            root_certs: None,
+            identity: None,
+            host_identities: HashMap::new(),
+            protocol: None,
+            trace_context: None,
+            request_interceptors: Vec::new(),
+            response_interceptors: Vec::new(),
+            signer: None,
+            #[cfg(feature = "body-serializer")]
+            serializers: Serializers::default(),
+            closed: Cell::new(false),
+            on_complete: None,
+            #[cfg(feature = "cookie")]
+            cookie_jar: None,
+            #[cfg(feature = "test-util")]
+            timer: None,
         }))
     }
 }
@@ -103,18 +300,19 @@ impl Client {
         ClientBuilder::new()
     }
 
+    #[cfg(feature = "cookie")]
+    /// Cookie jar attached to the client
+    pub fn cookie_jar(&self) -> Option<&Arc<Mutex<CookieJar>>> {
+        self.0.cookie_jar.as_ref()
+    }
+
     /// Construct HTTP request.
     pub fn request<U>(&self, method: Method, url: U) -> ClientRequest
     where
         Uri: TryFrom<U>,
         <Uri as TryFrom<U>>::Error: Into<HttpError>,
     {
-        let mut req = ClientRequest::new(method, url, self.0.clone());
-
-        for (key, value) in self.0.headers.iter() {
-            req = req.set_header_if_none(key.clone(), value.clone());
-        }
-        req
+        ClientRequest::new(method, url, self.0.clone())
     }
 
     /// Create `ClientRequest` from `RequestHead`
@@ -208,4 +406,66 @@ impl Client {
         }
         req
     }
+
+    /// Open connection to the host ahead of the first request.
+    ///
+    /// Connection is established and released to the connection pool
+    /// without sending a request, so subsequent requests to the host
+    /// could reuse it. Only scheme, host and port of the url are used.
+    /// Connection pool limits and connect timeout apply as for regular
+    /// requests.
+    ///
+    /// ```rust,no_run
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let client = Client::new();
+    ///     client.preconnect("https://www.rust-lang.org").await.unwrap();
+    /// }
+    /// ```
+    pub fn preconnect<U>(
+        &self,
+        url: U,
+    ) -> impl Future<Output = Result<(), SendRequestError>>
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<HttpError>,
+    {
+        let uri = Uri::try_from(url).map_err(Into::into);
+        let config = self.0.clone();
+
+        async move {
+            let uri: Uri = uri.map_err(SendRequestError::Http)?;
+            match uri.scheme_str() {
+                Some("http") | Some("ws") | Some("https") | Some("wss") => (),
+                Some(_) => return Err(InvalidUrl::UnsupportedScheme.into()),
+                None => return Err(InvalidUrl::MissingScheme.into()),
+            }
+            if uri.host().is_none() {
+                return Err(InvalidUrl::MissingHost.into());
+            }
+            if config.closed.get() {
+                return Err(SendRequestError::Closed);
+            }
+            config.connector.preconnect(uri, &config).await
+        }
+    }
+
+    /// Close the client.
+    ///
+    /// New requests of this client and its clones fail with
+    /// `SendRequestError::Closed` error, requests that are already sent
+    /// run to completion. Idle connections are closed and connections
+    /// of in-flight requests are not returned to the connection pool.
+    pub fn close(&self) {
+        if !self.0.closed.replace(true) {
+            self.0.connector.close();
+        }
+    }
+
+    /// Check if the client is closed.
+    pub fn is_closed(&self) -> bool {
+        self.0.closed.get()
+    }
 }
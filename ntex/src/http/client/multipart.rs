@@ -0,0 +1,260 @@
+//! Http client multipart/form-data body
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::task::{Context, Poll};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::http::body::{Body, BodySize, MessageBody};
+
+/// `multipart/form-data` request body builder.
+///
+/// Parts are streamed one by one, part bodies are not buffered.
+/// Boundary is generated randomly.
+///
+/// ```rust
+/// use ntex::http::client::{Client, Multipart};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let form = Multipart::new()
+///         .text("name", "value")
+///         .file("file", "data.txt", mime::TEXT_PLAIN, "file content");
+///
+///     let res = Client::new()
+///         .post("http://www.rust-lang.org")
+///         .send_multipart(form)
+///         .await;
+/// }
+/// ```
+pub struct Multipart {
+    boundary: String,
+    parts: VecDeque<Part>,
+    state: State,
+    sent: bool,
+}
+
+struct Part {
+    headers: Bytes,
+    body: Body,
+}
+
+enum State {
+    Headers,
+    Body(Body),
+    Eof,
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Multipart::new()
+    }
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart")
+            .field("boundary", &self.boundary)
+            .field("parts", &self.parts.len())
+            .finish()
+    }
+}
+
+impl Multipart {
+    /// Create empty multipart body with random boundary
+    pub fn new() -> Self {
+        Multipart {
+            boundary: format!(
+                "{:016x}{:016x}",
+                rand::random::<u64>(),
+                rand::random::<u64>()
+            ),
+            parts: VecDeque::new(),
+            state: State::Headers,
+            sent: false,
+        }
+    }
+
+    /// Get multipart boundary
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Get value for `Content-Type` header
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Add text field
+    pub fn text<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: AsRef<str>,
+        V: Into<String>,
+    {
+        let headers = self.part_headers(name.as_ref(), None, None);
+        self.parts.push_back(Part {
+            headers,
+            body: Body::from(value.into()),
+        });
+        self
+    }
+
+    /// Add file part
+    pub fn file<N, F, B>(
+        mut self,
+        name: N,
+        filename: F,
+        content_type: mime::Mime,
+        body: B,
+    ) -> Self
+    where
+        N: AsRef<str>,
+        F: AsRef<str>,
+        B: Into<Body>,
+    {
+        let headers = self.part_headers(
+            name.as_ref(),
+            Some(filename.as_ref()),
+            Some(&content_type),
+        );
+        self.parts.push_back(Part {
+            headers,
+            body: body.into(),
+        });
+        self
+    }
+
+    fn part_headers(
+        &self,
+        name: &str,
+        filename: Option<&str>,
+        content_type: Option<&mime::Mime>,
+    ) -> Bytes {
+        let mut buf = BytesMut::new();
+        if !self.parts.is_empty() {
+            buf.put_slice(b"\r\n");
+        }
+        buf.put_slice(b"--");
+        buf.put_slice(self.boundary.as_bytes());
+        buf.put_slice(b"\r\nContent-Disposition: form-data; name=\"");
+        escape(name, &mut buf);
+        buf.put_slice(b"\"");
+        if let Some(filename) = filename {
+            buf.put_slice(b"; filename=\"");
+            escape(filename, &mut buf);
+            buf.put_slice(b"\"");
+        }
+        if let Some(content_type) = content_type {
+            buf.put_slice(b"\r\nContent-Type: ");
+            buf.put_slice(content_type.as_ref().as_bytes());
+        }
+        buf.put_slice(b"\r\n\r\n");
+        buf.freeze()
+    }
+
+    fn closing(&self) -> Bytes {
+        let mut buf = BytesMut::new();
+        if self.sent {
+            buf.put_slice(b"\r\n");
+        }
+        buf.put_slice(b"--");
+        buf.put_slice(self.boundary.as_bytes());
+        buf.put_slice(b"--\r\n");
+        buf.freeze()
+    }
+}
+
+/// Escape field name or filename for quoted string
+fn escape(s: &str, buf: &mut BytesMut) {
+    for b in s.bytes() {
+        match b {
+            b'"' => buf.put_slice(b"%22"),
+            b'\r' => buf.put_slice(b"%0D"),
+            b'\n' => buf.put_slice(b"%0A"),
+            b => buf.put_u8(b),
+        }
+    }
+}
+
+impl MessageBody for Multipart {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        loop {
+            match self.state {
+                State::Headers => {
+                    if let Some(part) = self.parts.pop_front() {
+                        self.sent = true;
+                        self.state = State::Body(part.body);
+                        return Poll::Ready(Some(Ok(part.headers)));
+                    }
+                    // all parts are sent, write closing boundary
+                    self.state = State::Eof;
+                    return Poll::Ready(Some(Ok(self.closing())));
+                }
+                State::Body(ref mut body) => match body.poll_next_chunk(cx) {
+                    Poll::Ready(None) => self.state = State::Headers,
+                    Poll::Ready(Some(Ok(chunk))) => {
+                        if !chunk.is_empty() {
+                            return Poll::Ready(Some(Ok(chunk)));
+                        }
+                    }
+                    res => return res,
+                },
+                State::Eof => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::poll_fn;
+
+    use super::*;
+
+    async fn read(mut body: Multipart) -> Bytes {
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = poll_fn(|cx| body.poll_next_chunk(cx)).await {
+            buf.extend_from_slice(&chunk.unwrap());
+        }
+        buf.freeze()
+    }
+
+    #[ntex_rt::test]
+    async fn test_multipart() {
+        let body = Multipart::new().text("name", "value").file(
+            "fi\"le",
+            "a\r\n.txt",
+            mime::TEXT_PLAIN,
+            "content",
+        );
+        let boundary = body.boundary().to_string();
+        assert_eq!(boundary.len(), 32);
+        assert_ne!(Multipart::new().boundary(), boundary);
+        assert_eq!(
+            body.content_type(),
+            format!("multipart/form-data; boundary={}", boundary)
+        );
+        assert_eq!(body.size(), BodySize::Stream);
+
+        let expected = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nvalue\r\n\
+             --{b}\r\nContent-Disposition: form-data; name=\"fi%22le\"; \
+             filename=\"a%0D%0A.txt\"\r\nContent-Type: text/plain\r\n\r\ncontent\r\n\
+             --{b}--\r\n",
+            b = boundary
+        );
+        assert_eq!(read(body).await, Bytes::from(expected));
+
+        let body = Multipart::new();
+        let expected = format!("--{}--\r\n", body.boundary());
+        assert_eq!(read(body).await, Bytes::from(expected));
+    }
+}
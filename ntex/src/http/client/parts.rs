@@ -0,0 +1,509 @@
+//! Multipart bodies of http client responses
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::{cmp, fmt};
+
+use bytes::{Bytes, BytesMut};
+use futures::Stream;
+
+use crate::http::error::PayloadError;
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::http::Payload;
+
+use super::error::MultipartError;
+use super::ClientResponse;
+
+const MAX_HEADERS: usize = 32;
+const MAX_HEADERS_SIZE: usize = 16_384;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    Preamble,
+    Delimiter,
+    Headers,
+    Body,
+    Done,
+}
+
+struct Inner<S> {
+    stream: Option<S>,
+    state: State,
+    delimiter: Vec<u8>,
+    buf: BytesMut,
+    part: usize,
+}
+
+/// Stream of `multipart/*` body parts.
+///
+/// Body is parsed incrementally, each part yields its headers and
+/// streams its body. Unread body of a part is skipped when the next part
+/// is requested. Preamble and epilogue of the body are discarded.
+pub struct MultipartStream<S> {
+    inner: Option<Rc<RefCell<Inner<S>>>>,
+    err: Option<MultipartError>,
+}
+
+/// Part of multipart body.
+///
+/// Part is a stream of body chunks, nested multipart body could be
+/// parsed with `MultipartPart::multipart()`.
+pub struct MultipartPart<S> {
+    headers: HeaderMap,
+    inner: Rc<RefCell<Inner<S>>>,
+    part: usize,
+}
+
+impl<S> MultipartStream<Payload<S>>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    /// Create `MultipartStream` for response.
+    pub fn new(res: &mut ClientResponse<S>) -> Self {
+        let parts = MultipartStream::from_headers(res.headers());
+        if let Some(ref inner) = parts.inner {
+            inner.borrow_mut().stream = Some(res.take_payload());
+        }
+        parts
+    }
+}
+
+impl<S> MultipartStream<S> {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let mt = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<mime::Mime>().ok());
+
+        let boundary = match mt {
+            Some(ref mt) if mt.type_() == mime::MULTIPART => {
+                if let Some(boundary) = mt.get_param(mime::BOUNDARY) {
+                    boundary.as_str().to_string()
+                } else {
+                    return MultipartStream::error(MultipartError::Boundary);
+                }
+            }
+            _ => return MultipartStream::error(MultipartError::NotMultipart),
+        };
+
+        // first delimiter could be at the start of the body
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"\r\n");
+
+        MultipartStream {
+            inner: Some(Rc::new(RefCell::new(Inner {
+                buf,
+                stream: None,
+                state: State::Preamble,
+                delimiter: format!("\r\n--{}", boundary).into_bytes(),
+                part: 0,
+            }))),
+            err: None,
+        }
+    }
+
+    fn error(err: MultipartError) -> Self {
+        MultipartStream {
+            inner: None,
+            err: Some(err),
+        }
+    }
+}
+
+impl<S> MultipartPart<S> {
+    /// Returns part's headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Content type of the part, if set and valid.
+    pub fn mime_type(&self) -> Option<mime::Mime> {
+        self.headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+    }
+
+    /// Parse body of the part as nested `multipart/*` body.
+    ///
+    /// Boundary is taken from `Content-Type` header of the part.
+    pub fn multipart(self) -> MultipartStream<MultipartPart<S>> {
+        let parts = MultipartStream::from_headers(&self.headers);
+        if let Some(ref inner) = parts.inner {
+            inner.borrow_mut().stream = Some(self);
+        }
+        parts
+    }
+}
+
+impl<S> fmt::Debug for MultipartPart<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\nMultipartPart")?;
+        writeln!(f, "  headers:")?;
+        for (key, val) in self.headers.iter() {
+            writeln!(f, "    {:?}: {:?}", key, val)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S, E> Inner<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Into<MultipartError>,
+{
+    fn fail(&mut self) {
+        self.state = State::Done;
+        self.stream = None;
+        self.buf.clear();
+    }
+
+    /// Read next chunk of underlying stream
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), MultipartError>> {
+        let res = if let Some(ref mut stream) = self.stream {
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    self.buf.extend_from_slice(&chunk);
+                    return Poll::Ready(Ok(()));
+                }
+                Poll::Ready(Some(Err(err))) => err.into(),
+                Poll::Ready(None) => MultipartError::Parse("incomplete multipart body"),
+                Poll::Pending => return Poll::Pending,
+            }
+        } else {
+            MultipartError::Parse("incomplete multipart body")
+        };
+        self.fail();
+        Poll::Ready(Err(res))
+    }
+
+    /// Read headers of the next part
+    fn poll_part(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<HeaderMap, MultipartError>>> {
+        loop {
+            let len = self.delimiter.len();
+            match self.state {
+                State::Preamble => {
+                    if let Some(pos) = find(&self.buf, &self.delimiter) {
+                        let _ = self.buf.split_to(pos);
+                        self.state = State::Delimiter;
+                        continue;
+                    }
+                    // preamble is discarded
+                    let keep = cmp::min(self.buf.len(), len);
+                    let _ = self.buf.split_to(self.buf.len() - keep);
+                }
+                State::Body => {
+                    // skip unread body of current part
+                    match self.poll_body(cx) {
+                        Poll::Ready(Some(Ok(_))) | Poll::Ready(None) => continue,
+                        Poll::Ready(Some(Err(err))) => {
+                            return Poll::Ready(Some(Err(err)))
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                State::Delimiter => {
+                    if self.buf.len() >= len {
+                        if self.buf[..len] != self.delimiter[..] {
+                            self.fail();
+                            return Poll::Ready(Some(Err(MultipartError::Parse(
+                                "missing boundary",
+                            ))));
+                        }
+                        let rest = &self.buf[len..];
+                        if rest.starts_with(b"--") {
+                            // close delimiter, epilogue is discarded
+                            self.state = State::Done;
+                            self.stream = None;
+                            self.buf.clear();
+                            continue;
+                        }
+                        // delimiter could be followed by transport padding
+                        let pad = rest
+                            .iter()
+                            .take_while(|b| **b == b' ' || **b == b'\t')
+                            .count();
+                        if rest.len() >= pad + 2 {
+                            if &rest[pad..pad + 2] != b"\r\n" {
+                                self.fail();
+                                return Poll::Ready(Some(Err(MultipartError::Parse(
+                                    "invalid boundary",
+                                ))));
+                            }
+                            let _ = self.buf.split_to(len + pad + 2);
+                            self.state = State::Headers;
+                            continue;
+                        } else if pad > MAX_HEADERS_SIZE {
+                            self.fail();
+                            return Poll::Ready(Some(Err(MultipartError::Parse(
+                                "invalid boundary",
+                            ))));
+                        }
+                    }
+                }
+                State::Headers => match parse_headers(&self.buf) {
+                    Ok(Some((size, headers))) => {
+                        let _ = self.buf.split_to(size);
+                        self.state = State::Body;
+                        self.part += 1;
+                        return Poll::Ready(Some(Ok(headers)));
+                    }
+                    Ok(None) if self.buf.len() <= MAX_HEADERS_SIZE => (),
+                    Ok(None) => {
+                        self.fail();
+                        return Poll::Ready(Some(Err(MultipartError::Parse(
+                            "part headers are too large",
+                        ))));
+                    }
+                    Err(err) => {
+                        self.fail();
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                },
+                State::Done => return Poll::Ready(None),
+            }
+
+            if let Err(err) = futures::ready!(self.poll_fill(cx)) {
+                return Poll::Ready(Some(Err(err)));
+            }
+        }
+    }
+
+    /// Read body chunk of current part
+    fn poll_body(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, MultipartError>>> {
+        loop {
+            if self.state != State::Body {
+                return Poll::Ready(None);
+            }
+
+            let len = self.delimiter.len();
+            if let Some(pos) = find(&self.buf, &self.delimiter) {
+                self.state = State::Delimiter;
+                if pos > 0 {
+                    return Poll::Ready(Some(Ok(self.buf.split_to(pos).freeze())));
+                }
+                return Poll::Ready(None);
+            } else if self.buf.len() >= len {
+                // keep bytes that could be start of the delimiter
+                let size = self.buf.len() - len + 1;
+                return Poll::Ready(Some(Ok(self.buf.split_to(size).freeze())));
+            }
+
+            if let Err(err) = futures::ready!(self.poll_fill(cx)) {
+                return Poll::Ready(Some(Err(err)));
+            }
+        }
+    }
+}
+
+fn find(buf: &[u8], pat: &[u8]) -> Option<usize> {
+    buf.windows(pat.len()).position(|w| w == pat)
+}
+
+/// Parse part headers, returns `None` if headers are not complete
+fn parse_headers(buf: &[u8]) -> Result<Option<(usize, HeaderMap)>, MultipartError> {
+    let mut parsed = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let (size, parsed) = match httparse::parse_headers(buf, &mut parsed) {
+        Ok(httparse::Status::Complete(res)) => res,
+        Ok(httparse::Status::Partial) => return Ok(None),
+        Err(_) => return Err(MultipartError::Parse("invalid part headers")),
+    };
+
+    let mut headers = HeaderMap::new();
+    for h in parsed {
+        let name = HeaderName::from_bytes(h.name.as_bytes());
+        let value = HeaderValue::from_bytes(h.value);
+        if let (Ok(name), Ok(value)) = (name, value) {
+            headers.append(name, value);
+        } else {
+            return Err(MultipartError::Parse("invalid part headers"));
+        }
+    }
+    Ok(Some((size, headers)))
+}
+
+impl<S, E> Stream for MultipartStream<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Into<MultipartError>,
+{
+    type Item = Result<MultipartPart<S>, MultipartError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(err) = this.err.take() {
+            return Poll::Ready(Some(Err(err)));
+        }
+        let inner = if let Some(ref inner) = this.inner {
+            inner
+        } else {
+            return Poll::Ready(None);
+        };
+
+        let mut state = inner.borrow_mut();
+        match futures::ready!(state.poll_part(cx)) {
+            Some(Ok(headers)) => Poll::Ready(Some(Ok(MultipartPart {
+                headers,
+                inner: inner.clone(),
+                part: state.part,
+            }))),
+            Some(Err(err)) => Poll::Ready(Some(Err(err))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl<S, E> Stream for MultipartPart<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Into<MultipartError>,
+{
+    type Item = Result<Bytes, MultipartError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.part != self.part {
+            // next part is already requested
+            return Poll::Ready(None);
+        }
+        inner.poll_body(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{stream, StreamExt};
+
+    use super::*;
+    use crate::http::client::test::TestResponse;
+
+    async fn body<S, E>(part: &mut MultipartPart<S>) -> Bytes
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: Into<MultipartError>,
+    {
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = part.next().await {
+            buf.extend_from_slice(&chunk.unwrap());
+        }
+        buf.freeze()
+    }
+
+    const BODY: &str = "preamble\r\n--sep \r\n\
+                        Content-Type: text/plain\r\n\r\n\
+                        first\r\n--se\r\n--sep\r\n\
+                        Content-Type: multipart/mixed; boundary=inner\r\n\r\n\
+                        --inner\r\n\r\nnested\r\n--inner--\r\n\
+                        \r\n--sep\r\n\r\n\
+                        third\r\n--sep--\r\nepilogue";
+
+    #[ntex_rt::test]
+    async fn test_parts() {
+        let mut res = TestResponse::with_header(
+            header::CONTENT_TYPE,
+            "multipart/mixed; boundary=sep",
+        )
+        .set_payload(BODY)
+        .finish();
+
+        let mut parts = res.multipart();
+        let mut part = parts.next().await.unwrap().unwrap();
+        assert_eq!(part.mime_type().unwrap(), mime::TEXT_PLAIN);
+        assert_eq!(body(&mut part).await, Bytes::from_static(b"first\r\n--se"));
+
+        let part = parts.next().await.unwrap().unwrap();
+        let mut nested = part.multipart();
+        let mut part = nested.next().await.unwrap().unwrap();
+        assert!(part.headers().is_empty());
+        assert_eq!(body(&mut part).await, Bytes::from_static(b"nested"));
+        assert!(nested.next().await.is_none());
+
+        let mut part = parts.next().await.unwrap().unwrap();
+        assert_eq!(body(&mut part).await, Bytes::from_static(b"third"));
+        assert!(parts.next().await.is_none());
+    }
+
+    #[ntex_rt::test]
+    async fn test_chunked_payload() {
+        // body is received byte by byte, unread parts are skipped
+        let chunks: Vec<Result<_, PayloadError>> = BODY
+            .as_bytes()
+            .iter()
+            .map(|b| Ok(Bytes::copy_from_slice(&[*b])))
+            .collect();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("multipart/mixed; boundary=sep"),
+        );
+        let mut parts = MultipartStream::from_headers(&headers);
+        parts.inner.as_ref().unwrap().borrow_mut().stream = Some(stream::iter(chunks));
+
+        let first = parts.next().await.unwrap().unwrap();
+        let _ = parts.next().await.unwrap().unwrap();
+        let mut part = parts.next().await.unwrap().unwrap();
+        assert_eq!(body(&mut part).await, Bytes::from_static(b"third"));
+        assert!(parts.next().await.is_none());
+
+        // stale part
+        let mut first = first;
+        assert!(first.next().await.is_none());
+    }
+
+    #[ntex_rt::test]
+    async fn test_errors() {
+        let mut res =
+            TestResponse::with_header(header::CONTENT_TYPE, "text/plain").finish();
+        match res.multipart().next().await.unwrap() {
+            Err(MultipartError::NotMultipart) => (),
+            _ => panic!(),
+        }
+
+        let mut res =
+            TestResponse::with_header(header::CONTENT_TYPE, "multipart/mixed").finish();
+        match res.multipart().next().await.unwrap() {
+            Err(MultipartError::Boundary) => (),
+            _ => panic!(),
+        }
+
+        let mut res = TestResponse::with_header(
+            header::CONTENT_TYPE,
+            "multipart/mixed; boundary=sep",
+        )
+        .set_payload("--sep\r\n\r\nincomplete")
+        .finish();
+        let mut parts = res.multipart();
+        let mut part = parts.next().await.unwrap().unwrap();
+        let _ = part.next().await.unwrap().unwrap();
+        match part.next().await.unwrap() {
+            Err(MultipartError::Parse(_)) => (),
+            _ => panic!(),
+        }
+        assert!(parts.next().await.is_none());
+
+        let mut res = TestResponse::with_header(
+            header::CONTENT_TYPE,
+            "multipart/mixed; boundary=sep",
+        )
+        .set_payload("--sepx\r\n\r\n")
+        .finish();
+        match res.multipart().next().await.unwrap() {
+            Err(MultipartError::Parse(_)) => (),
+            _ => panic!(),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
@@ -8,7 +9,7 @@ use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 use futures::future::{poll_fn, FutureExt, LocalBoxFuture};
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use h2::client::{handshake, Connection, SendRequest};
 use http::uri::Authority;
 
@@ -22,18 +23,25 @@ use crate::rt::{
 use crate::service::Service;
 use crate::task::LocalWaker;
 
-use super::connection::{ConnectionType, IoConnection};
+use super::connection::{ConnectInfo, ConnectionType, IoConnection};
 use super::error::ConnectError;
+use super::tls::TlsInfo;
 use super::Connect;
 
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub(super) struct Key {
     authority: Authority,
+    pool: Option<String>,
+    addr: Option<SocketAddr>,
 }
 
-impl From<Authority> for Key {
-    fn from(authority: Authority) -> Key {
-        Key { authority }
+impl Key {
+    fn new(req: &Connect) -> Option<Key> {
+        req.uri.authority().map(|authority| Key {
+            authority: authority.clone(),
+            pool: req.pool_key.clone(),
+            addr: req.addr,
+        })
     }
 }
 
@@ -47,8 +55,11 @@ pub(super) struct ConnectionPool<T, Io: 'static>(Rc<T>, Rc<RefCell<Inner<Io>>>);
 impl<T, Io> ConnectionPool<T, Io>
 where
     Io: AsyncRead + AsyncWrite + Unpin + 'static,
-    T: Service<Request = Connect, Response = (Io, Protocol), Error = ConnectError>
-        + Unpin
+    T: Service<
+            Request = Connect,
+            Response = (Io, Protocol, ConnectInfo),
+            Error = ConnectError,
+        > + Unpin
         + 'static,
     T::Future: Unpin,
 {
@@ -58,6 +69,8 @@ where
         conn_keep_alive: Duration,
         disconnect_timeout: Duration,
         limit: usize,
+        limit_per_host: usize,
+        h2_only: bool,
     ) -> Self {
         let connector = Rc::new(connector);
         let inner = Rc::new(RefCell::new(Inner {
@@ -65,7 +78,14 @@ where
             conn_keep_alive,
             disconnect_timeout,
             limit,
+            limit_per_host,
+            h2_only,
+            closed: false,
             acquired: 0,
+            acquired_pools: FxHashMap::default(),
+            acquired_hosts: FxHashMap::default(),
+            opening: FxHashSet::default(),
+            h2: FxHashMap::default(),
             waiters: VecDeque::new(),
             available: FxHashMap::default(),
             pool: pool::new(),
@@ -103,8 +123,11 @@ where
 impl<T, Io> Service for ConnectionPool<T, Io>
 where
     Io: AsyncRead + AsyncWrite + Unpin + 'static,
-    T: Service<Request = Connect, Response = (Io, Protocol), Error = ConnectError>
-        + 'static,
+    T: Service<
+            Request = Connect,
+            Response = (Io, Protocol, ConnectInfo),
+            Error = ConnectError,
+        > + 'static,
     T::Future: Unpin,
 {
     type Request = Connect;
@@ -119,6 +142,7 @@ where
 
     #[inline]
     fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.1.borrow_mut().close();
         self.0.poll_shutdown(cx, is_error)
     }
 
@@ -129,8 +153,8 @@ where
         let inner = self.1.clone();
 
         let fut = async move {
-            let key = if let Some(authority) = req.uri.authority() {
-                authority.clone().into()
+            let key = if let Some(key) = Key::new(&req) {
+                key
             } else {
                 return Err(ConnectError::Unresolved);
             };
@@ -138,18 +162,27 @@ where
             // acquire connection
             match poll_fn(|cx| Poll::Ready(inner.borrow_mut().acquire(&key, cx))).await {
                 // use existing connection
-                Acquire::Acquired(io, created) => {
+                Acquire::Acquired(io, created, info) => {
                     trace!("Use existing connection for {:?}", req.uri);
                     return Ok(IoConnection::new(
                         io,
                         created,
-                        Some(Acquired(key, Some(inner))),
+                        Some(Acquired(key, Some(inner), info)),
                     ));
                 }
+                // open new stream of existing http2 connection
+                Acquire::Shared(io, created, info) => {
+                    trace!("Use shared http2 connection for {:?}", req.uri);
+                    Ok(IoConnection::new(
+                        io,
+                        created,
+                        Some(Acquired(key, None, info)),
+                    ))
+                }
                 // open new tcp connection
                 Acquire::Available => {
                     trace!("Connecting to {:?}", req.uri);
-                    let (tx, rx) = inner.borrow_mut().pool.channel();
+                    let (tx, rx) = inner.borrow_mut().channel(&key);
                     OpenConnection::spawn(key, tx, inner, connector.call(req));
 
                     match rx.await {
@@ -177,7 +210,8 @@ where
 }
 
 enum Acquire<T> {
-    Acquired(ConnectionType<T>, Instant),
+    Acquired(ConnectionType<T>, Instant, ConnectInfo),
+    Shared(ConnectionType<T>, Instant, ConnectInfo),
     Available,
     NotAvailable,
 }
@@ -186,6 +220,7 @@ struct AvailableConnection<Io> {
     io: ConnectionType<Io>,
     used: Instant,
     created: Instant,
+    info: ConnectInfo,
 }
 
 pub(super) struct Inner<Io> {
@@ -193,7 +228,14 @@ pub(super) struct Inner<Io> {
     conn_keep_alive: Duration,
     disconnect_timeout: Duration,
     limit: usize,
+    limit_per_host: usize,
+    h2_only: bool,
+    closed: bool,
     acquired: usize,
+    acquired_pools: FxHashMap<String, usize>,
+    acquired_hosts: FxHashMap<Key, usize>,
+    opening: FxHashSet<Key>,
+    h2: FxHashMap<Key, AvailableConnection<Io>>,
     available: FxHashMap<Key, VecDeque<AvailableConnection<Io>>>,
     waiters: VecDeque<(Key, Connect, Waiter<Io>)>,
     waker: LocalWaker,
@@ -201,12 +243,54 @@ pub(super) struct Inner<Io> {
 }
 
 impl<Io> Inner<Io> {
-    fn reserve(&mut self) {
-        self.acquired += 1;
+    /// number of acquired connections in the pool of the key
+    fn acquired(&self, key: &Key) -> usize {
+        match key.pool {
+            Some(ref pool) => self.acquired_pools.get(pool).copied().unwrap_or(0),
+            None => self.acquired,
+        }
+    }
+
+    /// check pool and per host limits of the key
+    fn is_full(&self, key: &Key) -> bool {
+        (self.limit > 0 && self.acquired(key) >= self.limit)
+            || (self.limit_per_host > 0
+                && self.acquired_hosts.get(key).copied().unwrap_or(0)
+                    >= self.limit_per_host)
     }
 
-    fn release(&mut self) {
-        self.acquired -= 1;
+    /// check if connection for the key could be acquired
+    fn can_acquire(&self, key: &Key) -> bool {
+        self.h2.contains_key(key)
+            || !((self.h2_only && self.opening.contains(key)) || self.is_full(key))
+    }
+
+    fn reserve(&mut self, key: &Key) {
+        *self.acquired_hosts.entry(key.clone()).or_insert(0) += 1;
+        match key.pool {
+            Some(ref pool) => *self.acquired_pools.entry(pool.clone()).or_insert(0) += 1,
+            None => self.acquired += 1,
+        }
+    }
+
+    fn release(&mut self, key: &Key) {
+        if let Some(acquired) = self.acquired_hosts.get_mut(key) {
+            *acquired -= 1;
+            if *acquired == 0 {
+                self.acquired_hosts.remove(key);
+            }
+        }
+        match key.pool {
+            Some(ref pool) => {
+                if let Some(acquired) = self.acquired_pools.get_mut(pool) {
+                    *acquired -= 1;
+                    if *acquired == 0 {
+                        self.acquired_pools.remove(pool);
+                    }
+                }
+            }
+            None => self.acquired -= 1,
+        }
     }
 }
 
@@ -217,7 +301,7 @@ where
     /// connection is not available, wait
     fn wait_for(&mut self, connect: Connect) -> WaiterReceiver<Io> {
         let (tx, rx) = self.pool.channel();
-        let key: Key = connect.uri.authority().unwrap().clone().into();
+        let key = Key::new(&connect).unwrap();
         self.waiters.push_back((key, connect, tx));
 
         rx
@@ -237,15 +321,52 @@ where
         }
     }
 
+    /// channel for opening connection
+    fn channel(&mut self, key: &Key) -> (Waiter<Io>, WaiterReceiver<Io>) {
+        if self.h2_only {
+            self.opening.insert(key.clone());
+        }
+        self.pool.channel()
+    }
+
     fn acquire(&mut self, key: &Key, cx: &mut Context<'_>) -> Acquire<Io> {
         self.cleanup();
 
+        // http2 connection is shared between requests
+        if let Some(conn) = self.h2.get_mut(key) {
+            let now = Instant::now();
+            let usable = (now - conn.used) <= self.conn_keep_alive
+                && (now - conn.created) <= self.conn_lifetime
+                && match conn.io {
+                    ConnectionType::H2(ref mut io) => {
+                        !matches!(io.poll_ready(cx), Poll::Ready(Err(_)))
+                    }
+                    ConnectionType::H1(_) => false,
+                };
+            if usable {
+                conn.used = now;
+                if let ConnectionType::H2(ref io) = conn.io {
+                    return Acquire::Shared(
+                        ConnectionType::H2(io.clone()),
+                        conn.created,
+                        conn.info.clone(),
+                    );
+                }
+            }
+            self.h2.remove(key);
+        }
+
+        // wait for http2 connection that is being opened
+        if self.h2_only && self.opening.contains(key) {
+            return Acquire::NotAvailable;
+        }
+
         // check limits
-        if self.limit > 0 && self.acquired >= self.limit {
+        if self.is_full(key) {
             return Acquire::NotAvailable;
         }
 
-        self.reserve();
+        self.reserve(key);
 
         // check if open connection is available
         // cleanup stale connections at the same time
@@ -274,37 +395,72 @@ where
                             _ => continue,
                         }
                     }
-                    return Acquire::Acquired(io, conn.created);
+                    return Acquire::Acquired(io, conn.created, conn.info.clone());
                 }
             }
         }
         Acquire::Available
     }
 
-    fn release_conn(&mut self, key: &Key, io: ConnectionType<Io>, created: Instant) {
-        self.acquired -= 1;
+    /// close idle connections, released connections are closed as well
+    fn close(&mut self) {
+        self.closed = true;
+        self.h2.clear();
+        for (_, connections) in self.available.drain() {
+            for conn in connections {
+                if let ConnectionType::H1(io) = conn.io {
+                    CloseConnection::spawn(io, self.disconnect_timeout);
+                }
+            }
+        }
+    }
+
+    fn release_conn(
+        &mut self,
+        key: &Key,
+        io: ConnectionType<Io>,
+        created: Instant,
+        info: ConnectInfo,
+    ) {
+        if self.closed {
+            return self.release_close(key, io);
+        }
+        self.release(key);
+        // shared http2 connection is kept separately
+        if let ConnectionType::H2(_) = io {
+            if self.h2.contains_key(key) {
+                self.check_availibility();
+                return;
+            }
+        }
         self.available
             .entry(key.clone())
             .or_insert_with(VecDeque::new)
             .push_back(AvailableConnection {
                 io,
                 created,
+                info,
                 used: Instant::now(),
             });
         self.check_availibility();
     }
 
-    fn release_close(&mut self, io: ConnectionType<Io>) {
-        self.acquired -= 1;
-        if let ConnectionType::H1(io) = io {
-            CloseConnection::spawn(io, self.disconnect_timeout);
+    fn release_close(&mut self, key: &Key, io: ConnectionType<Io>) {
+        self.release(key);
+        match io {
+            ConnectionType::H1(io) => {
+                CloseConnection::spawn(io, self.disconnect_timeout)
+            }
+            ConnectionType::H2(_) => {
+                self.h2.remove(key);
+            }
         }
         self.check_availibility();
     }
 
     fn check_availibility(&mut self) {
         self.cleanup();
-        if !self.waiters.is_empty() && self.acquired < self.limit {
+        if self.waiters.iter().any(|(key, _, _)| self.can_acquire(key)) {
             self.waker.wake();
         }
     }
@@ -321,8 +477,11 @@ where
 impl<T, Io> Future for ConnectionPoolSupport<T, Io>
 where
     Io: AsyncRead + AsyncWrite + Unpin + 'static,
-    T: Service<Request = Connect, Response = (Io, Protocol), Error = ConnectError>
-        + Unpin,
+    T: Service<
+            Request = Connect,
+            Response = (Io, Protocol, ConnectInfo),
+            Error = ConnectError,
+        > + Unpin,
     T::Future: Unpin + 'static,
 {
     type Output = ();
@@ -338,27 +497,40 @@ where
         let mut inner = this.inner.as_ref().borrow_mut();
         inner.waker.register(cx.waker());
 
-        // check waiters
-        while let Some((key, _, tx)) = inner.waiters.front() {
+        // check waiters, each pool has its own limit so waiters
+        // of exhausted pool do not block waiters of other pools
+        let mut idx = 0;
+        while let Some((key, _, tx)) = inner.waiters.get(idx) {
             // is waiter still alive
             if tx.is_canceled() {
-                inner.waiters.pop_front();
+                inner.waiters.remove(idx);
                 continue;
             };
             let key = key.clone();
 
             match inner.acquire(&key, cx) {
-                Acquire::NotAvailable => break,
-                Acquire::Acquired(io, created) => {
-                    let (key, _, tx) = inner.waiters.pop_front().unwrap();
+                Acquire::NotAvailable => idx += 1,
+                Acquire::Acquired(io, created, info) => {
+                    let (key, _, tx) = inner.waiters.remove(idx).unwrap();
+                    let _ = tx.send(Ok(IoConnection::new(
+                        io,
+                        created,
+                        Some(Acquired(key.clone(), Some(this.inner.clone()), info)),
+                    )));
+                }
+                Acquire::Shared(io, created, info) => {
+                    let (key, _, tx) = inner.waiters.remove(idx).unwrap();
                     let _ = tx.send(Ok(IoConnection::new(
                         io,
                         created,
-                        Some(Acquired(key.clone(), Some(this.inner.clone()))),
+                        Some(Acquired(key, None, info)),
                     )));
                 }
                 Acquire::Available => {
-                    let (key, connect, tx) = inner.waiters.pop_front().unwrap();
+                    let (key, connect, tx) = inner.waiters.remove(idx).unwrap();
+                    if inner.h2_only {
+                        inner.opening.insert(key.clone());
+                    }
                     OpenConnection::spawn(
                         key,
                         tx,
@@ -455,7 +627,9 @@ where
 
 impl<F, Io> OpenConnection<F, Io>
 where
-    F: Future<Output = Result<(Io, Protocol), ConnectError>> + Unpin + 'static,
+    F: Future<Output = Result<(Io, Protocol, ConnectInfo), ConnectError>>
+        + Unpin
+        + 'static,
     Io: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     fn spawn(key: Key, tx: Waiter<Io>, inner: Rc<RefCell<Inner<Io>>>, fut: F) {
@@ -466,6 +640,7 @@ where
             guard: Some(OpenGuard {
                 key,
                 inner: Some(inner),
+                info: ConnectInfo::default(),
             }),
         });
     }
@@ -473,7 +648,7 @@ where
 
 impl<F, Io> Future for OpenConnection<F, Io>
 where
-    F: Future<Output = Result<(Io, Protocol), ConnectError>> + Unpin,
+    F: Future<Output = Result<(Io, Protocol, ConnectInfo), ConnectError>> + Unpin,
     Io: AsyncRead + AsyncWrite + Unpin,
 {
     type Output = ();
@@ -485,11 +660,27 @@ where
         if let Some(ref mut h2) = this.h2 {
             return match Pin::new(h2).poll(cx) {
                 Poll::Ready(Ok((snd, connection))) => {
-                    // h2 connection is ready
+                    // h2 connection is ready, share it with other requests
+                    let guard = this.guard.take().unwrap();
+                    let created = Instant::now();
+                    if let Some(ref inner) = guard.inner {
+                        let mut inner = inner.borrow_mut();
+                        if !inner.closed {
+                            inner.h2.insert(
+                                guard.key.clone(),
+                                AvailableConnection {
+                                    created,
+                                    io: ConnectionType::H2(snd.clone()),
+                                    used: created,
+                                    info: guard.info.clone(),
+                                },
+                            );
+                        }
+                    }
                     let conn = IoConnection::new(
                         ConnectionType::H2(snd),
-                        Instant::now(),
-                        Some(this.guard.take().unwrap().consume()),
+                        created,
+                        Some(guard.consume()),
                     );
                     if let Err(Ok(conn)) = this.tx.take().unwrap().send(Ok(conn)) {
                         // waiter is gone, return connection to pool
@@ -517,8 +708,11 @@ where
                 }
                 Poll::Ready(())
             }
-            Poll::Ready(Ok((io, proto))) => {
+            Poll::Ready(Ok((io, proto, info))) => {
                 trace!("Connection is established");
+                if let Some(ref mut guard) = this.guard {
+                    guard.info = info;
+                }
                 // handle http1 proto
                 if proto == Protocol::Http1 {
                     let conn = IoConnection::new(
@@ -548,6 +742,7 @@ where
 {
     key: Key,
     inner: Option<Rc<RefCell<Inner<Io>>>>,
+    info: ConnectInfo,
 }
 
 impl<Io> OpenGuard<Io>
@@ -555,7 +750,13 @@ where
     Io: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     fn consume(mut self) -> Acquired<Io> {
-        Acquired(self.key.clone(), self.inner.take())
+        if let Some(ref inner) = self.inner {
+            let mut inner = inner.borrow_mut();
+            inner.opening.remove(&self.key);
+            inner.check_availibility();
+        }
+        let info = std::mem::take(&mut self.info);
+        Acquired(self.key.clone(), self.inner.take(), info)
     }
 }
 
@@ -566,32 +767,45 @@ where
     fn drop(&mut self) {
         if let Some(i) = self.inner.take() {
             let mut inner = i.as_ref().borrow_mut();
-            inner.release();
+            inner.release(&self.key);
+            inner.opening.remove(&self.key);
             inner.check_availibility();
         }
     }
 }
 
-pub(super) struct Acquired<T>(Key, Option<Rc<RefCell<Inner<T>>>>);
+pub(super) struct Acquired<T>(Key, Option<Rc<RefCell<Inner<T>>>>, ConnectInfo);
 
 impl<T> Acquired<T>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
+    /// Remote address of the connection
+    pub(super) fn peer_addr(&self) -> Option<SocketAddr> {
+        self.2.peer_addr
+    }
+
+    /// Tls session details of the connection
+    pub(super) fn tls_info(&self) -> Option<TlsInfo> {
+        self.2.tls.clone()
+    }
+
     pub(super) fn close(&mut self, conn: IoConnection<T>) {
         if let Some(inner) = self.1.take() {
             let (io, _) = conn.into_inner();
-            inner.as_ref().borrow_mut().release_close(io);
+            inner.as_ref().borrow_mut().release_close(&self.0, io);
         }
     }
 
     pub(super) fn release(&mut self, conn: IoConnection<T>) {
         if let Some(inner) = self.1.take() {
             let (io, created) = conn.into_inner();
-            inner
-                .as_ref()
-                .borrow_mut()
-                .release_conn(&self.0, io, created);
+            inner.as_ref().borrow_mut().release_conn(
+                &self.0,
+                io,
+                created,
+                self.2.clone(),
+            );
         }
     }
 }
@@ -599,7 +813,7 @@ where
 impl<T> Drop for Acquired<T> {
     fn drop(&mut self) {
         if let Some(inner) = self.1.take() {
-            inner.borrow_mut().release();
+            inner.borrow_mut().release(&self.0);
         }
     }
 }
@@ -628,30 +842,26 @@ mod tests {
             fn_service(move |req| {
                 let (client, server) = Io::create();
                 store2.borrow_mut().push((req, server));
-                ok((client, Protocol::Http1))
+                ok((client, Protocol::Http1, ConnectInfo::default()))
             }),
             Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_millis(0),
             1,
+            0,
+            false,
         )
         .clone();
 
         // uri must contain authority
-        let req = Connect {
-            uri: Uri::try_from("/test").unwrap(),
-            addr: None,
-        };
+        let req = Connect::new(Uri::try_from("/test").unwrap(), None);
         match pool.call(req).await {
             Err(ConnectError::Unresolved) => (),
             _ => panic!(),
         }
 
         // connect one
-        let req = Connect {
-            uri: Uri::try_from("http://localhost/test").unwrap(),
-            addr: None,
-        };
+        let req = Connect::new(Uri::try_from("http://localhost/test").unwrap(), None);
         let conn = pool.call(req.clone()).await.unwrap();
         assert_eq!(store.borrow().len(), 1);
         assert!(format!("{:?}", conn).contains("H1Connection"));
@@ -680,4 +890,109 @@ mod tests {
         assert!(lazy(|cx| pool.poll_ready(cx)).await.is_ready());
         assert!(lazy(|cx| pool.poll_shutdown(cx, false)).await.is_ready());
     }
+
+    #[ntex_rt::test]
+    async fn test_pool_key() {
+        let store = Rc::new(RefCell::new(Vec::new()));
+        let store2 = store.clone();
+
+        let pool = ConnectionPool::new(
+            fn_service(move |req| {
+                let (client, server) = Io::create();
+                store2.borrow_mut().push((req, server));
+                ok((client, Protocol::Http1, ConnectInfo::default()))
+            }),
+            Duration::from_secs(10),
+            Duration::from_secs(10),
+            Duration::from_millis(0),
+            1,
+            0,
+            false,
+        )
+        .clone();
+
+        let req = Connect::new(Uri::try_from("http://localhost/test").unwrap(), None);
+        let req2 = Connect {
+            pool_key: Some("background".to_string()),
+            ..req.clone()
+        };
+        assert_eq!(req2.pool_key(), Some("background"));
+        assert!(req2.fallback_addrs().is_empty());
+        let conn = pool.call(req.clone()).await.unwrap();
+
+        // default pool is full, waiting
+        let mut fut = pool.call(req.clone());
+        assert!(lazy(|cx| Pin::new(&mut fut).poll(cx)).await.is_pending());
+
+        // separate pool has its own limit
+        let conn2 = pool.call(req2.clone()).await.unwrap();
+        assert_eq!(store.borrow().len(), 2);
+        assert_eq!(pool.1.borrow().acquired, 1);
+        assert_eq!(pool.1.borrow().acquired_pools["background"], 1);
+
+        let mut fut2 = pool.call(req2.clone());
+        assert!(lazy(|cx| Pin::new(&mut fut2).poll(cx)).await.is_pending());
+        assert_eq!(pool.1.borrow().waiters.len(), 2);
+
+        // waiter of the second pool is not blocked by the first one
+        conn2.release();
+        let conn2 = fut2.await.unwrap();
+        assert_eq!(store.borrow().len(), 2);
+        assert_eq!(pool.1.borrow().waiters.len(), 1);
+
+        // connections are not shared between pools
+        drop(conn2);
+        assert!(pool.1.borrow().acquired_pools.is_empty());
+        conn.release();
+        let _conn = fut.await.unwrap();
+        assert_eq!(store.borrow().len(), 2);
+        assert!(pool.1.borrow().waiters.is_empty());
+    }
+
+    #[ntex_rt::test]
+    async fn test_limit_per_host() {
+        let store = Rc::new(RefCell::new(Vec::new()));
+        let store2 = store.clone();
+
+        let pool = ConnectionPool::new(
+            fn_service(move |req| {
+                let (client, server) = Io::create();
+                store2.borrow_mut().push((req, server));
+                ok((client, Protocol::Http1, ConnectInfo::default()))
+            }),
+            Duration::from_secs(10),
+            Duration::from_secs(10),
+            Duration::from_millis(0),
+            0,
+            1,
+            false,
+        )
+        .clone();
+
+        let req = Connect::new(Uri::try_from("http://localhost/test").unwrap(), None);
+        let req2 = Connect {
+            uri: Uri::try_from("http://127.0.0.1/test").unwrap(),
+            ..req.clone()
+        };
+        let conn = pool.call(req.clone()).await.unwrap();
+
+        // host is at its limit, waiting
+        let mut fut = pool.call(req.clone());
+        assert!(lazy(|cx| Pin::new(&mut fut).poll(cx)).await.is_pending());
+        assert_eq!(pool.1.borrow().waiters.len(), 1);
+
+        // other host is not limited
+        let _conn2 = pool.call(req2.clone()).await.unwrap();
+        assert_eq!(store.borrow().len(), 2);
+        assert_eq!(pool.1.borrow().acquired, 2);
+
+        // released connection is reused by waiter
+        conn.release();
+        let conn = fut.await.unwrap();
+        assert_eq!(store.borrow().len(), 2);
+        assert!(pool.1.borrow().waiters.is_empty());
+
+        drop(conn);
+        assert_eq!(pool.1.borrow().acquired_hosts.len(), 1);
+    }
 }
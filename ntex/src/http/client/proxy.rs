@@ -0,0 +1,428 @@
+//! Http client proxy support.
+use std::convert::TryFrom;
+use std::io;
+use std::pin::Pin;
+
+use futures::future::poll_fn;
+
+use crate::codec::{AsyncRead, AsyncWrite};
+use crate::http::error::HttpError;
+use crate::http::header::HeaderValue;
+use crate::http::Uri;
+
+use super::error::{ConnectError, InvalidUrl};
+
+/// Max size of proxy `CONNECT` response head
+const MAX_RESPONSE_SIZE: usize = 8 * 1024;
+
+/// Proxy server configuration
+///
+/// Plain http requests are sent to http proxy with absolute-form request
+/// urls, secure requests are tunneled through http proxy with `CONNECT`
+/// method. For SOCKS5 proxy, all requests are tunneled.
+///
+/// ```rust
+/// use ntex::http::client::{Client, Proxy};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .proxy(
+///             Proxy::http("http://proxy.local:3128")
+///                 .unwrap()
+///                 .no_proxy("localhost, .internal"),
+///         )
+///         .finish();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Proxy {
+    kind: ProxyKind,
+    uri: Uri,
+    auth: Option<(String, String)>,
+    no_proxy: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) enum ProxyKind {
+    Http,
+    #[cfg(feature = "socks")]
+    Socks5,
+}
+
+impl Proxy {
+    /// Use http proxy
+    pub fn http<U>(uri: U) -> Result<Proxy, InvalidUrl>
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<HttpError>,
+    {
+        Proxy::new(ProxyKind::Http, uri)
+    }
+
+    #[cfg(feature = "socks")]
+    /// Use SOCKS5 proxy
+    pub fn socks5<U>(uri: U) -> Result<Proxy, InvalidUrl>
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<HttpError>,
+    {
+        Proxy::new(ProxyKind::Socks5, uri)
+    }
+
+    fn new<U>(kind: ProxyKind, uri: U) -> Result<Proxy, InvalidUrl>
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<HttpError>,
+    {
+        let uri = Uri::try_from(uri).map_err(|e| InvalidUrl::Http(e.into()))?;
+        let host = uri.host().ok_or(InvalidUrl::MissingHost)?;
+        let port = uri.port_u16().unwrap_or(match kind {
+            ProxyKind::Http => 80,
+            #[cfg(feature = "socks")]
+            ProxyKind::Socks5 => 1080,
+        });
+
+        // address of the proxy server for tcp connector
+        let uri = format!("http://{}:{}", host, port)
+            .parse::<Uri>()
+            .map_err(|e| InvalidUrl::Http(e.into()))?;
+        Ok(Proxy {
+            kind,
+            uri,
+            auth: None,
+            no_proxy: Vec::new(),
+        })
+    }
+
+    /// Set proxy credentials
+    ///
+    /// Credentials are sent with `Proxy-Authorization` header to http proxy
+    /// and with username/password authentication to SOCKS5 proxy.
+    pub fn basic_auth<U: Into<String>>(mut self, username: U, password: &str) -> Self {
+        self.auth = Some((username.into(), password.to_string()));
+        self
+    }
+
+    /// Exclude hosts from proxying
+    ///
+    /// List is comma separated, in `NO_PROXY` environment variable format.
+    /// Entry matches host itself and its subdomains, entry with leading dot
+    /// matches subdomains only. `*` disables proxy for all hosts.
+    pub fn no_proxy(mut self, hosts: &str) -> Self {
+        self.no_proxy.extend(
+            hosts
+                .split(',')
+                .map(|host| host.trim().to_ascii_lowercase())
+                .filter(|host| !host.is_empty()),
+        );
+        self
+    }
+
+    /// Address of the proxy server
+    pub(super) fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Check if request to the url should be sent through the proxy
+    pub(super) fn is_proxied(&self, uri: &Uri) -> bool {
+        let host = match uri.host() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return false,
+        };
+
+        !self.no_proxy.iter().any(|entry| {
+            if entry == "*" {
+                true
+            } else if entry.starts_with('.') {
+                host.ends_with(entry.as_str())
+            } else {
+                host == *entry
+                    || (host.ends_with(entry.as_str())
+                        && host.as_bytes()[host.len() - entry.len() - 1] == b'.')
+            }
+        })
+    }
+
+    /// Check if request should be sent to http proxy with absolute-form url
+    pub(super) fn is_forwarding(&self, uri: &Uri) -> bool {
+        self.kind == ProxyKind::Http && uri.scheme_str() == Some("http")
+    }
+
+    /// `Proxy-Authorization` header value
+    pub(super) fn authorization(&self) -> Option<HeaderValue> {
+        self.auth.as_ref().and_then(|(username, password)| {
+            let auth = format!("{}:{}", username, password);
+            HeaderValue::try_from(format!("Basic {}", base64::encode(&auth))).ok()
+        })
+    }
+
+    /// Open tunnel to the host through established proxy connection
+    pub(super) async fn tunnel<T>(
+        &self,
+        io: &mut T,
+        host: &str,
+        port: u16,
+    ) -> Result<(), ConnectError>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+    {
+        match self.kind {
+            ProxyKind::Http => http_tunnel(io, host, port, self.authorization()).await,
+            #[cfg(feature = "socks")]
+            ProxyKind::Socks5 => socks5_tunnel(io, host, port, self.auth.as_ref()).await,
+        }
+    }
+}
+
+/// Open tunnel with http `CONNECT` method
+async fn http_tunnel<T>(
+    io: &mut T,
+    host: &str,
+    port: u16,
+    auth: Option<HeaderValue>,
+) -> Result<(), ConnectError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut req = format!(
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+        host = host,
+        port = port
+    )
+    .into_bytes();
+    if let Some(auth) = auth {
+        req.extend_from_slice(b"Proxy-Authorization: ");
+        req.extend_from_slice(auth.as_bytes());
+        req.extend_from_slice(b"\r\n");
+    }
+    req.extend_from_slice(b"\r\n");
+    write_all(io, &req).await?;
+
+    // read response head byte by byte, tunneled data must stay in the stream
+    let mut buf = Vec::with_capacity(128);
+    let mut byte = [0u8];
+    while !buf.ends_with(b"\r\n\r\n") {
+        if buf.len() >= MAX_RESPONSE_SIZE {
+            return Err(ConnectError::Proxy("response is too large".to_string()));
+        }
+        read_exact(io, &mut byte).await?;
+        buf.push(byte[0]);
+    }
+
+    let mut headers = [httparse::EMPTY_HEADER; 32];
+    let mut res = httparse::Response::new(&mut headers);
+    match res.parse(&buf) {
+        Ok(httparse::Status::Complete(_)) => match res.code {
+            Some(code) if (200..300).contains(&code) => Ok(()),
+            code => Err(ConnectError::Proxy(format!(
+                "tunnel is refused with status {}",
+                code.unwrap_or(0)
+            ))),
+        },
+        _ => Err(ConnectError::Proxy("can not parse response".to_string())),
+    }
+}
+
+#[cfg(feature = "socks")]
+/// Open tunnel with SOCKS5 `CONNECT` command
+async fn socks5_tunnel<T>(
+    io: &mut T,
+    host: &str,
+    port: u16,
+    auth: Option<&(String, String)>,
+) -> Result<(), ConnectError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    // greeting, offer no auth or username/password auth
+    let method = if auth.is_some() { 0x02 } else { 0x00 };
+    write_all(io, &[0x05, 0x01, method]).await?;
+    let mut buf = [0u8; 2];
+    read_exact(io, &mut buf).await?;
+    if buf[0] != 0x05 || buf[1] != method {
+        return Err(ConnectError::Proxy(
+            "authentication method is not accepted".to_string(),
+        ));
+    }
+
+    if let Some((username, password)) = auth {
+        if username.len() > 255 || password.len() > 255 {
+            return Err(ConnectError::Proxy("credentials are too long".to_string()));
+        }
+        let mut req = vec![0x01, username.len() as u8];
+        req.extend_from_slice(username.as_bytes());
+        req.push(password.len() as u8);
+        req.extend_from_slice(password.as_bytes());
+        write_all(io, &req).await?;
+        read_exact(io, &mut buf).await?;
+        if buf[1] != 0x00 {
+            return Err(ConnectError::Proxy("authentication failed".to_string()));
+        }
+    }
+
+    // connect command with domain name or ip address
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let mut req = vec![0x05, 0x01, 0x00];
+    match host.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => {
+            req.push(0x01);
+            req.extend_from_slice(&ip.octets());
+        }
+        Ok(std::net::IpAddr::V6(ip)) => {
+            req.push(0x04);
+            req.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.len() > 255 {
+                return Err(ConnectError::Proxy("host name is too long".to_string()));
+            }
+            req.push(0x03);
+            req.push(host.len() as u8);
+            req.extend_from_slice(host.as_bytes());
+        }
+    }
+    req.extend_from_slice(&port.to_be_bytes());
+    write_all(io, &req).await?;
+
+    let mut head = [0u8; 4];
+    read_exact(io, &mut head).await?;
+    if head[1] != 0x00 {
+        return Err(ConnectError::Proxy(format!(
+            "tunnel is refused with reply {}",
+            head[1]
+        )));
+    }
+    // skip bound address and port
+    let len = match head[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => {
+            let mut len = [0u8];
+            read_exact(io, &mut len).await?;
+            len[0] as usize
+        }
+        _ => return Err(ConnectError::Proxy("can not parse reply".to_string())),
+    };
+    let mut addr = vec![0u8; len + 2];
+    read_exact(io, &mut addr).await
+}
+
+async fn write_all<T>(io: &mut T, mut buf: &[u8]) -> Result<(), ConnectError>
+where
+    T: AsyncWrite + Unpin,
+{
+    while !buf.is_empty() {
+        let n = poll_fn(|cx| Pin::new(&mut *io).poll_write(cx, buf)).await?;
+        if n == 0 {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "").into());
+        }
+        buf = &buf[n..];
+    }
+    Ok(poll_fn(|cx| Pin::new(&mut *io).poll_flush(cx)).await?)
+}
+
+async fn read_exact<T>(io: &mut T, buf: &mut [u8]) -> Result<(), ConnectError>
+where
+    T: AsyncRead + Unpin,
+{
+    let mut pos = 0;
+    while pos < buf.len() {
+        let n = poll_fn(|cx| Pin::new(&mut *io).poll_read(cx, &mut buf[pos..])).await?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "proxy closed connection",
+            )
+            .into());
+        }
+        pos += n;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Io;
+
+    #[test]
+    fn test_no_proxy() {
+        let proxy = Proxy::http("http://proxy:3128")
+            .unwrap()
+            .no_proxy("localhost, .internal,example.com");
+        let url = |s: &str| Uri::try_from(s).unwrap();
+
+        assert!(!proxy.is_proxied(&url("http://localhost/")));
+        assert!(!proxy.is_proxied(&url("http://api.internal/")));
+        assert!(proxy.is_proxied(&url("http://internal/")));
+        assert!(!proxy.is_proxied(&url("http://example.com/")));
+        assert!(!proxy.is_proxied(&url("https://www.Example.com/")));
+        assert!(proxy.is_proxied(&url("http://myexample.com/")));
+        assert!(proxy.is_proxied(&url("http://rust-lang.org/")));
+
+        assert!(proxy.is_forwarding(&url("http://rust-lang.org/")));
+        assert!(!proxy.is_forwarding(&url("https://rust-lang.org/")));
+
+        let proxy = proxy.no_proxy("*");
+        assert!(!proxy.is_proxied(&url("http://rust-lang.org/")));
+
+        assert_eq!(
+            Proxy::http("http://proxy").unwrap().uri().port_u16(),
+            Some(80)
+        );
+        assert!(matches!(Proxy::http("/test"), Err(InvalidUrl::MissingHost)));
+    }
+
+    #[ntex_rt::test]
+    async fn test_http_tunnel() {
+        let proxy = Proxy::http("http://proxy:3128")
+            .unwrap()
+            .basic_auth("user", "pass");
+
+        let (mut client, server) = Io::create();
+        server.remote_buffer_cap(1024);
+        server.write("HTTP/1.1 200 Connection established\r\n\r\ndata");
+        proxy
+            .tunnel(&mut client, "rust-lang.org", 443)
+            .await
+            .unwrap();
+        assert_eq!(
+            &server.read_any()[..],
+            &b"CONNECT rust-lang.org:443 HTTP/1.1\r\nHost: rust-lang.org:443\r\n\
+               Proxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n"[..]
+        );
+        // tunneled data is not consumed
+        assert_eq!(&client.read_any()[..], b"data");
+
+        let (mut client, server) = Io::create();
+        server.remote_buffer_cap(1024);
+        server.write("HTTP/1.1 407 Proxy Authentication Required\r\n\r\n");
+        let res = proxy.tunnel(&mut client, "rust-lang.org", 443).await;
+        assert!(matches!(res, Err(ConnectError::Proxy(_))));
+    }
+
+    #[cfg(feature = "socks")]
+    #[ntex_rt::test]
+    async fn test_socks5_tunnel() {
+        let proxy = Proxy::socks5("socks5://proxy:1080").unwrap();
+
+        let (mut client, server) = Io::create();
+        server.remote_buffer_cap(1024);
+        server.write([0x05, 0x00, 0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0, 80]);
+        proxy
+            .tunnel(&mut client, "rust-lang.org", 80)
+            .await
+            .unwrap();
+        let mut expected = vec![0x05, 0x01, 0x00, 0x05, 0x01, 0x00, 0x03, 13];
+        expected.extend_from_slice(b"rust-lang.org");
+        expected.extend_from_slice(&[0, 80]);
+        assert_eq!(&server.read_any()[..], &expected[..]);
+
+        let (mut client, server) = Io::create();
+        server.remote_buffer_cap(1024);
+        server.write([0x05, 0x00, 0x05, 0x05, 0x00, 0x01, 0, 0, 0, 0, 0, 0]);
+        let res = proxy.tunnel(&mut client, "127.0.0.1", 80).await;
+        assert!(matches!(res, Err(ConnectError::Proxy(_))));
+    }
+}
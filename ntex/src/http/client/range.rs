@@ -0,0 +1,439 @@
+//! Partial content of http client responses
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use futures::Stream;
+
+use crate::http::error::PayloadError;
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::http::{HttpMessage, Payload, StatusCode};
+
+use super::error::ByteRangesError;
+use super::ClientResponse;
+
+const MAX_HEADERS: usize = 16;
+
+/// Byte range of partial content, value of `Content-Range` header
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ContentRange {
+    /// Offset of the first byte, inclusive
+    pub start: u64,
+    /// Offset of the last byte, inclusive
+    pub end: u64,
+    /// Complete length of the resource, if known
+    pub total: Option<u64>,
+}
+
+impl ContentRange {
+    /// Parse `Content-Range` header value, i.e. `bytes 0-499/1234`
+    ///
+    /// Unsatisfied range (`bytes */1234`) is not parsed.
+    pub fn parse(value: &str) -> Option<ContentRange> {
+        let value = value.trim();
+        if !value.starts_with("bytes ") {
+            return None;
+        }
+        let mut parts = value[6..].splitn(2, '/');
+        let mut range = parts.next()?.splitn(2, '-');
+        let start = range.next()?.trim().parse::<u64>().ok()?;
+        let end = range.next()?.trim().parse::<u64>().ok()?;
+        let total = match parts.next()?.trim() {
+            "*" => None,
+            total => Some(total.parse::<u64>().ok()?),
+        };
+
+        if end < start || total.map(|total| end >= total).unwrap_or(false) {
+            None
+        } else {
+            Some(ContentRange { start, end, total })
+        }
+    }
+
+    /// Number of bytes in the range
+    pub fn size(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn from_headers(headers: &HeaderMap) -> Option<ContentRange> {
+        headers
+            .get(header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(ContentRange::parse)
+    }
+}
+
+/// Part of partial content response
+#[derive(Clone, Debug)]
+pub struct RangePart {
+    /// Byte range of the part
+    pub range: ContentRange,
+    /// Part headers, for single range response contains `Content-Type`
+    /// of the response
+    pub headers: HeaderMap,
+    /// Part content
+    pub body: Bytes,
+}
+
+#[derive(Debug)]
+enum State {
+    Single(ContentRange, HeaderMap),
+    Preamble,
+    Headers,
+    Body(ContentRange, HeaderMap),
+    Delimiter,
+    Done,
+}
+
+/// Stream of partial content response parts.
+///
+/// Single range response yields one part, `multipart/byteranges`
+/// response yields a part for each range. Parts are buffered,
+/// max size of a part is 256k.
+pub struct ByteRanges<S> {
+    stream: Option<Payload<S>>,
+    state: State,
+    err: Option<ByteRangesError>,
+    delimiter: Vec<u8>,
+    buf: BytesMut,
+    limit: usize,
+}
+
+impl<S> ByteRanges<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    /// Create `ByteRanges` for response.
+    pub fn new(res: &mut ClientResponse<S>) -> Self {
+        let mut ranges = ByteRanges {
+            stream: None,
+            state: State::Done,
+            err: None,
+            delimiter: Vec::new(),
+            buf: BytesMut::new(),
+            limit: 262_144,
+        };
+
+        if res.status() != StatusCode::PARTIAL_CONTENT {
+            ranges.err = Some(ByteRangesError::NotPartial);
+            return ranges;
+        }
+
+        match res.mime_type() {
+            Ok(Some(ref mt))
+                if mt.type_() == mime::MULTIPART && mt.subtype() == "byteranges" =>
+            {
+                if let Some(boundary) = mt.get_param(mime::BOUNDARY) {
+                    // first delimiter could be at the start of the body
+                    ranges.buf.extend_from_slice(b"\r\n");
+                    ranges.delimiter = format!("\r\n--{}", boundary).into_bytes();
+                    ranges.state = State::Preamble;
+                } else {
+                    ranges.err = Some(ByteRangesError::Boundary);
+                    return ranges;
+                }
+            }
+            _ => {
+                if let Some(range) = ContentRange::from_headers(res.headers()) {
+                    let mut headers = HeaderMap::new();
+                    if let Some(ct) = res.headers().get(header::CONTENT_TYPE) {
+                        headers.insert(header::CONTENT_TYPE, ct.clone());
+                    }
+                    ranges.state = State::Single(range, headers);
+                } else {
+                    ranges.err = Some(ByteRangesError::ContentRange);
+                    return ranges;
+                }
+            }
+        }
+        ranges.stream = Some(res.take_payload());
+        ranges
+    }
+
+    /// Change max size of a part. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<S> ByteRanges<S> {
+    /// Parse buffered data, returns `None` if more data is required
+    fn parse(&mut self) -> Result<Option<RangePart>, ByteRangesError> {
+        let eof = self.stream.is_none();
+
+        loop {
+            match self.state {
+                State::Single(..) => {
+                    if self.buf.len() > self.limit {
+                        return Err(PayloadError::Overflow.into());
+                    }
+                    if !eof {
+                        return Ok(None);
+                    }
+                    if let State::Single(range, headers) =
+                        std::mem::replace(&mut self.state, State::Done)
+                    {
+                        return Ok(Some(RangePart {
+                            range,
+                            headers,
+                            body: self.buf.split().freeze(),
+                        }));
+                    }
+                }
+                State::Preamble => {
+                    let pos = self
+                        .buf
+                        .windows(self.delimiter.len())
+                        .position(|w| w == &self.delimiter[..]);
+                    if let Some(pos) = pos {
+                        let _ = self.buf.split_to(pos);
+                        self.state = State::Delimiter;
+                    } else {
+                        // preamble is discarded
+                        let keep = std::cmp::min(self.buf.len(), self.delimiter.len());
+                        let _ = self.buf.split_to(self.buf.len() - keep);
+                        return Ok(None);
+                    }
+                }
+                State::Delimiter => {
+                    let len = self.delimiter.len();
+                    if self.buf.len() < len + 2 {
+                        return Ok(None);
+                    }
+                    if self.buf[..len] != self.delimiter[..] {
+                        return Err(ByteRangesError::Multipart("missing boundary"));
+                    }
+                    match &self.buf[len..len + 2] {
+                        b"--" => {
+                            self.state = State::Done;
+                            self.buf.clear();
+                        }
+                        b"\r\n" => {
+                            let _ = self.buf.split_to(len + 2);
+                            self.state = State::Headers;
+                        }
+                        _ => return Err(ByteRangesError::Multipart("invalid boundary")),
+                    }
+                }
+                State::Headers => {
+                    let (size, headers) = if let Some(res) = parse_headers(&self.buf)? {
+                        res
+                    } else if self.buf.len() > self.limit {
+                        return Err(PayloadError::Overflow.into());
+                    } else {
+                        return Ok(None);
+                    };
+                    let range = ContentRange::from_headers(&headers)
+                        .ok_or(ByteRangesError::ContentRange)?;
+                    if range.size() > self.limit as u64 {
+                        return Err(PayloadError::Overflow.into());
+                    }
+                    let _ = self.buf.split_to(size);
+                    self.state = State::Body(range, headers);
+                }
+                State::Body(ref range, _) => {
+                    let size = range.size() as usize;
+                    if self.buf.len() < size {
+                        return Ok(None);
+                    }
+                    if let State::Body(range, headers) =
+                        std::mem::replace(&mut self.state, State::Delimiter)
+                    {
+                        return Ok(Some(RangePart {
+                            range,
+                            headers,
+                            body: self.buf.split_to(size).freeze(),
+                        }));
+                    }
+                }
+                State::Done => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Parse part headers, returns `None` if headers are not complete
+fn parse_headers(buf: &[u8]) -> Result<Option<(usize, HeaderMap)>, ByteRangesError> {
+    let mut parsed = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let (size, parsed) = match httparse::parse_headers(buf, &mut parsed) {
+        Ok(httparse::Status::Complete(res)) => res,
+        Ok(httparse::Status::Partial) => return Ok(None),
+        Err(_) => return Err(ByteRangesError::Multipart("invalid part headers")),
+    };
+
+    let mut headers = HeaderMap::new();
+    for h in parsed {
+        let name = HeaderName::from_bytes(h.name.as_bytes());
+        let value = HeaderValue::from_bytes(h.value);
+        if let (Ok(name), Ok(value)) = (name, value) {
+            headers.append(name, value);
+        } else {
+            return Err(ByteRangesError::Multipart("invalid part headers"));
+        }
+    }
+    Ok(Some((size, headers)))
+}
+
+impl<S> Unpin for ByteRanges<S> where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin
+{
+}
+
+impl<S> Stream for ByteRanges<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<RangePart, ByteRangesError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let res = if let Some(err) = this.err.take() {
+                Err(err)
+            } else {
+                this.parse()
+            };
+            match res {
+                Ok(Some(part)) => return Poll::Ready(Some(Ok(part))),
+                Ok(None) => (),
+                Err(err) => {
+                    this.stream = None;
+                    this.state = State::Done;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+
+            let stream = if let State::Done = this.state {
+                return Poll::Ready(None);
+            } else if let Some(ref mut stream) = this.stream {
+                stream
+            } else {
+                this.state = State::Done;
+                return Poll::Ready(Some(Err(ByteRangesError::Multipart(
+                    "incomplete multipart body",
+                ))));
+            };
+
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.buf.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(err))) => {
+                    this.stream = None;
+                    this.state = State::Done;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                Poll::Ready(None) => this.stream = None,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::http::client::test::TestResponse;
+
+    #[test]
+    fn test_content_range() {
+        let range = ContentRange::parse("bytes 0-499/1234").unwrap();
+        assert_eq!(range.start, 0);
+        assert_eq!(range.end, 499);
+        assert_eq!(range.total, Some(1234));
+        assert_eq!(range.size(), 500);
+
+        let range = ContentRange::parse("bytes 10-10/*").unwrap();
+        assert_eq!(range.total, None);
+        assert_eq!(range.size(), 1);
+
+        assert!(ContentRange::parse("bytes */1234").is_none());
+        assert!(ContentRange::parse("bytes 10-5/1234").is_none());
+        assert!(ContentRange::parse("bytes 0-1234/1234").is_none());
+        assert!(ContentRange::parse("items 0-1/2").is_none());
+    }
+
+    #[ntex_rt::test]
+    async fn test_single_range() {
+        let mut res = TestResponse::with_header(header::CONTENT_RANGE, "bytes 2-5/10")
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_TYPE, "text/plain")
+            .set_payload(Bytes::from_static(b"2345"))
+            .finish();
+        assert!(res.is_partial());
+        assert_eq!(res.content_range().unwrap().start, 2);
+
+        let mut ranges = res.byte_ranges();
+        let part = ranges.next().await.unwrap().unwrap();
+        assert_eq!(part.range.end, 5);
+        assert_eq!(
+            part.headers.get(header::CONTENT_TYPE).unwrap(),
+            "text/plain"
+        );
+        assert_eq!(part.body, Bytes::from_static(b"2345"));
+        assert!(ranges.next().await.is_none());
+
+        let mut res = TestResponse::default().finish();
+        assert!(!res.is_partial());
+        match res.byte_ranges().next().await.unwrap() {
+            Err(ByteRangesError::NotPartial) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[ntex_rt::test]
+    async fn test_multipart_ranges() {
+        let body = "preamble\r\n--sep\r\n\
+                    Content-Type: text/plain\r\n\
+                    Content-Range: bytes 0-3/20\r\n\r\n\
+                    0123\r\n--sep\r\n\
+                    Content-Range: bytes 10-14/20\r\n\r\n\
+                    a\r\n--\r\n--sep--\r\nepilogue";
+        let mut res = TestResponse::with_header(
+            header::CONTENT_TYPE,
+            "multipart/byteranges; boundary=sep",
+        )
+        .status(StatusCode::PARTIAL_CONTENT)
+        .set_payload(body)
+        .finish();
+
+        let parts: Vec<_> = res.byte_ranges().collect().await;
+        assert_eq!(parts.len(), 2);
+        let part = parts[0].as_ref().unwrap();
+        assert_eq!(part.range.start, 0);
+        assert_eq!(
+            part.headers.get(header::CONTENT_TYPE).unwrap(),
+            "text/plain"
+        );
+        assert_eq!(part.body, Bytes::from_static(b"0123"));
+        let part = parts[1].as_ref().unwrap();
+        assert_eq!(part.range.start, 10);
+        assert_eq!(part.body, Bytes::from_static(b"a\r\n--"));
+
+        let mut res = TestResponse::with_header(
+            header::CONTENT_TYPE,
+            "multipart/byteranges; boundary=sep",
+        )
+        .status(StatusCode::PARTIAL_CONTENT)
+        .set_payload("--sep\r\nContent-Range: bytes 0-3/20\r\n\r\n01")
+        .finish();
+        match res.byte_ranges().next().await.unwrap() {
+            Err(ByteRangesError::Multipart(_)) => (),
+            _ => panic!(),
+        }
+
+        let mut res =
+            TestResponse::with_header(header::CONTENT_TYPE, "multipart/byteranges")
+                .status(StatusCode::PARTIAL_CONTENT)
+                .finish();
+        match res.byte_ranges().next().await.unwrap() {
+            Err(ByteRangesError::Boundary) => (),
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Http client per-host rate limiting
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::http::Uri;
+
+/// Max number of tracked hosts, full buckets get dropped after that
+const MAX_BUCKETS: usize = 1024;
+
+/// Per-host outbound request rate limit.
+///
+/// Limit uses token bucket algorithm. Each host gets a bucket with `burst`
+/// tokens that is refilled with `requests_per_second` rate, every request
+/// takes one token. If bucket is empty, request waits for next token before
+/// connection is acquired. Request timeout covers wait time.
+///
+/// ```rust
+/// use ntex::http::client::{Client, RateLimit};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .rate_limit(RateLimit::new(10).burst(20))
+///         .finish();
+/// }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct RateLimit {
+    rate: u32,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// Create new rate limit with `requests_per_second` per host.
+    ///
+    /// By default burst size is equal to the rate.
+    pub fn new(requests_per_second: u32) -> Self {
+        let rate = cmp::max(requests_per_second, 1);
+        RateLimit { rate, burst: rate }
+    }
+
+    /// Set max number of requests that could be sent at once.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = cmp::max(burst, 1);
+        self
+    }
+}
+
+/// Token buckets for rate limited hosts
+pub(super) struct RateLimiter {
+    limit: RateLimit,
+    buckets: RefCell<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub(super) fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            limit,
+            buckets: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Take token for the host of `uri`.
+    ///
+    /// Returns time to wait before request could be sent.
+    pub(super) fn acquire(&self, uri: &Uri) -> Option<Duration> {
+        let key = if let Some(authority) = uri.authority() {
+            authority.as_str().to_ascii_lowercase()
+        } else {
+            return None;
+        };
+        let now = Instant::now();
+        let rate = f64::from(self.limit.rate);
+        let burst = f64::from(self.limit.burst);
+
+        let mut buckets = self.buckets.borrow_mut();
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&key) {
+            buckets.retain(|_, b| {
+                b.tokens + now.duration_since(b.updated).as_secs_f64() * rate < burst
+            });
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst) - 1.0;
+        bucket.updated = now;
+
+        if bucket.tokens >= 0.0 {
+            None
+        } else {
+            // token is reserved, request must wait until it is refilled
+            Some(Duration::from_secs_f64(-bucket.tokens / rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_acquire() {
+        let limiter = RateLimiter::new(RateLimit::new(10).burst(2));
+        let uri = Uri::try_from("http://rust-lang.org/test").unwrap();
+
+        assert_eq!(limiter.acquire(&uri), None);
+        assert_eq!(limiter.acquire(&uri), None);
+        let wait = limiter.acquire(&uri).unwrap();
+        assert!(wait > Duration::from_millis(90) && wait <= Duration::from_millis(100));
+        let wait = limiter.acquire(&uri).unwrap();
+        assert!(wait > Duration::from_millis(190) && wait <= Duration::from_millis(200));
+
+        // other hosts have own buckets
+        let uri = Uri::try_from("http://RUST-lang.org:8080/").unwrap();
+        assert_eq!(limiter.acquire(&uri), None);
+        assert_eq!(limiter.acquire(&Uri::try_from("/test").unwrap()), None);
+    }
+}
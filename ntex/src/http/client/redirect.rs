@@ -0,0 +1,386 @@
+//! Http client redirects handling
+use std::convert::TryFrom;
+use std::net;
+use std::rc::Rc;
+
+use crate::http::body::Body;
+use crate::http::header::{self, HeaderMap};
+use crate::http::{
+    ConnectionType, Method, RequestHead, RequestHeadType, StatusCode, Uri,
+};
+
+use super::error::SendRequestError;
+use super::response::ClientResponse;
+use super::retry::{self, RetryPolicy};
+use super::sender::{send_request, RequestOptions, RequestPhase};
+use super::sign;
+use super::ClientConfig;
+
+/// Send request and follow redirect responses
+pub(super) async fn send(
+    config: Rc<ClientConfig>,
+    head: RequestHeadType,
+    body: Body,
+    mut addr: Option<net::SocketAddr>,
+    policy: Option<RetryPolicy>,
+    phase: RequestPhase,
+) -> Result<ClientResponse, SendRequestError> {
+    let (mut head, mut extra_headers) = match head {
+        RequestHeadType::Owned(head) => (Rc::new(head), None),
+        RequestHeadType::Rc(head, extra_headers) => (head, extra_headers),
+    };
+    let mut replay = retry::replay(&body);
+    let mut body = Some(body);
+    let mut hops = 0;
+    let mut visited: Vec<(Method, Uri)> = Vec::new();
+
+    loop {
+        let res = send_request(
+            &config,
+            RequestHeadType::Rc(head.clone(), extra_headers.clone()),
+            body.take().unwrap(),
+            addr,
+            policy.clone(),
+            phase.clone(),
+        )
+        .await?;
+
+        let uri = match location(&head.uri, &res) {
+            Some(uri) => uri,
+            None => return Ok(res),
+        };
+        if hops >= config.max_redirects {
+            return Err(SendRequestError::TooManyRedirects);
+        }
+        hops += 1;
+        trace!("Redirecting from {:?} to {:?}", head.uri, uri);
+
+        // 303 always switches to GET, 301 and 302 switch POST to GET
+        // as most user agents do, 307 and 308 preserve method and body
+        let method = match res.status() {
+            StatusCode::SEE_OTHER if head.method != Method::HEAD => Method::GET,
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND
+                if head.method == Method::POST =>
+            {
+                Method::GET
+            }
+            _ => head.method.clone(),
+        };
+        let keep_body = method == head.method;
+        drop(res);
+
+        // same request has been sent already
+        visited.push((head.method.clone(), head.uri.clone()));
+        if visited.iter().any(|(m, u)| *m == method && *u == uri) {
+            let mut chain: Vec<Uri> = visited.into_iter().map(|(_, u)| u).collect();
+            chain.push(uri);
+            return Err(SendRequestError::RedirectLoop { chain });
+        }
+
+        if keep_body {
+            body = match replay {
+                Some(ref b) => retry::replay(b),
+                None => return Err(SendRequestError::BodyNotReplayable),
+            };
+        } else {
+            body = Some(Body::None);
+            replay = Some(Body::None);
+        }
+
+        let same_origin = is_same_origin(&head.uri, &uri);
+        if !same_origin {
+            addr = None;
+        }
+        let mut next = RequestHeadType::Owned(redirect_head(
+            &head,
+            extra_headers.take(),
+            uri,
+            method,
+            keep_body,
+            same_origin,
+        ));
+
+        // every hop is intercepted and signed again, signature
+        // of previous hop does not match new method and url
+        for interceptor in &config.request_interceptors {
+            interceptor.on_request(&mut next).await?;
+        }
+        if let Some(ref signer) = config.signer {
+            body = Some(
+                sign::sign(signer.as_ref(), &mut next, body.take().unwrap()).await?,
+            );
+        }
+        head = match next {
+            RequestHeadType::Owned(next) => Rc::new(next),
+            RequestHeadType::Rc(next, extra) => {
+                extra_headers = extra;
+                next
+            }
+        };
+    }
+}
+
+/// Fail redirect response with `SendRequestError::UnexpectedRedirect`
+pub(super) fn deny(res: ClientResponse) -> Result<ClientResponse, SendRequestError> {
+    let status = res.status();
+    if status.is_redirection() && status != StatusCode::NOT_MODIFIED {
+        let location = res
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        Err(SendRequestError::UnexpectedRedirect { status, location })
+    } else {
+        Ok(res)
+    }
+}
+
+/// Resolve redirect location
+fn location(base: &Uri, res: &ClientResponse) -> Option<Uri> {
+    match res.status() {
+        StatusCode::MOVED_PERMANENTLY
+        | StatusCode::FOUND
+        | StatusCode::SEE_OTHER
+        | StatusCode::TEMPORARY_REDIRECT
+        | StatusCode::PERMANENT_REDIRECT => (),
+        _ => return None,
+    }
+
+    let location = res.headers().get(header::LOCATION)?.to_str().ok()?;
+    let base = url::Url::parse(&base.to_string()).ok()?;
+    let url = base.join(location).ok()?;
+    match url.scheme() {
+        "http" | "https" => Uri::try_from(url.as_str()).ok(),
+        _ => None,
+    }
+}
+
+/// Serialized origin of the url, default port is omitted
+pub(super) fn origin(uri: &Uri) -> String {
+    let scheme = uri.scheme_str().unwrap_or("http");
+    let host = uri.host().unwrap_or_default().to_ascii_lowercase();
+    let default_port = if scheme == "https" { 443 } else { 80 };
+    match uri.port_u16() {
+        Some(port) if port != default_port => format!("{}://{}:{}", scheme, host, port),
+        _ => format!("{}://{}", scheme, host),
+    }
+}
+
+/// Referer value of the url, without user info
+pub(super) fn referer(uri: &Uri) -> String {
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    format!("{}{}", origin(uri), path)
+}
+
+fn is_same_origin(a: &Uri, b: &Uri) -> bool {
+    a.scheme_str() == b.scheme_str()
+        && a.host() == b.host()
+        && a.port_u16() == b.port_u16()
+}
+
+/// Build request head for the next hop
+fn redirect_head(
+    head: &RequestHead,
+    extra_headers: Option<HeaderMap>,
+    uri: Uri,
+    method: Method,
+    keep_body: bool,
+    same_origin: bool,
+) -> RequestHead {
+    let mut headers = head.headers.clone();
+    if let Some(extra_headers) = extra_headers {
+        for key in extra_headers.keys() {
+            headers.remove(key);
+        }
+        for (key, value) in extra_headers.iter() {
+            headers.append(key.clone(), value.clone());
+        }
+    }
+
+    if !keep_body {
+        headers.remove(header::CONTENT_TYPE);
+        headers.remove(header::CONTENT_ENCODING);
+        headers.remove(header::CONTENT_LENGTH);
+        headers.remove(header::TRANSFER_ENCODING);
+    }
+
+    // do not leak credentials to other hosts
+    if !same_origin {
+        headers.remove(header::HOST);
+        headers.remove(header::AUTHORIZATION);
+        headers.remove(header::PROXY_AUTHORIZATION);
+        headers.remove(header::COOKIE);
+    }
+
+    // referer set by client points to the previous hop,
+    // it is not sent from https to http
+    let mut options = head
+        .extensions()
+        .get::<RequestOptions>()
+        .cloned()
+        .unwrap_or_default();
+    if options.auto_referer {
+        if head.uri.scheme_str() == Some("https") && uri.scheme_str() != Some("https") {
+            headers.remove(header::REFERER);
+        } else if let Ok(value) = header::HeaderValue::try_from(referer(&head.uri)) {
+            headers.insert(header::REFERER, value);
+        }
+    }
+
+    let mut new_head = RequestHead::default();
+    new_head.uri = uri;
+    new_head.method = method;
+    new_head.version = head.version;
+    new_head.headers = headers;
+    new_head.peer_addr = head.peer_addr;
+    // request settings apply to every hop, fallback
+    // addresses belong to the original origin
+    if !same_origin {
+        options.fallback_addrs.clear();
+    }
+    new_head.extensions_mut().insert(options);
+    if head.connection_type() == ConnectionType::Close {
+        new_head.set_connection_type(ConnectionType::Close);
+    }
+    new_head
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::test::TestResponse;
+
+    #[test]
+    fn test_location() {
+        let base = Uri::from_static("http://example.com/a/b?q=1");
+
+        let res = TestResponse::default()
+            .status(StatusCode::FOUND)
+            .header(header::LOCATION, "/c")
+            .finish();
+        assert_eq!(location(&base, &res).unwrap(), "http://example.com/c");
+
+        let res = TestResponse::default()
+            .status(StatusCode::TEMPORARY_REDIRECT)
+            .header(header::LOCATION, "d?x=2")
+            .finish();
+        assert_eq!(location(&base, &res).unwrap(), "http://example.com/a/d?x=2");
+
+        let res = TestResponse::default()
+            .status(StatusCode::MOVED_PERMANENTLY)
+            .header(header::LOCATION, "https://other.com/")
+            .finish();
+        assert_eq!(location(&base, &res).unwrap(), "https://other.com/");
+
+        let res = TestResponse::default()
+            .status(StatusCode::FOUND)
+            .header(header::LOCATION, "ftp://other.com/")
+            .finish();
+        assert!(location(&base, &res).is_none());
+
+        let res = TestResponse::default().status(StatusCode::FOUND).finish();
+        assert!(location(&base, &res).is_none());
+
+        let res = TestResponse::default()
+            .header(header::LOCATION, "/c")
+            .finish();
+        assert!(location(&base, &res).is_none());
+    }
+
+    #[test]
+    fn test_redirect_head() {
+        let mut head = RequestHead::default();
+        head.method = Method::POST;
+        head.uri = Uri::from_static("http://example.com/");
+        head.headers
+            .insert(header::AUTHORIZATION, "Bearer t".parse().unwrap());
+        head.headers
+            .insert(header::CONTENT_TYPE, "text/plain".parse().unwrap());
+        let mut extra = HeaderMap::new();
+        extra.insert(header::ACCEPT, "*/*".parse().unwrap());
+
+        let new_head = redirect_head(
+            &head,
+            Some(extra),
+            Uri::from_static("http://example.com/b"),
+            Method::GET,
+            false,
+            true,
+        );
+        assert_eq!(new_head.method, Method::GET);
+        assert!(new_head.headers.contains_key(header::AUTHORIZATION));
+        assert!(new_head.headers.contains_key(header::ACCEPT));
+        assert!(!new_head.headers.contains_key(header::CONTENT_TYPE));
+
+        let new_head = redirect_head(
+            &head,
+            None,
+            Uri::from_static("http://other.com/"),
+            Method::POST,
+            true,
+            false,
+        );
+        assert!(!new_head.headers.contains_key(header::AUTHORIZATION));
+        assert!(new_head.headers.contains_key(header::CONTENT_TYPE));
+    }
+
+    #[test]
+    fn test_redirect_referer() {
+        let mut head = RequestHead::default();
+        head.uri = Uri::from_static("https://example.com/a?q=1");
+        head.headers
+            .insert(header::REFERER, "https://example.com/".parse().unwrap());
+
+        // referer set by user is not changed
+        let new_head = redirect_head(
+            &head,
+            None,
+            Uri::from_static("https://example.com/b"),
+            Method::GET,
+            false,
+            true,
+        );
+        assert_eq!(
+            new_head.headers.get(header::REFERER).unwrap(),
+            "https://example.com/"
+        );
+
+        RequestOptions::set(&head, |opts| {
+            opts.auto_referer = true;
+            opts.pool_key = Some("key".to_string());
+            opts.fallback_addrs = vec!["127.0.0.1:8080".parse().unwrap()];
+        });
+        let new_head = redirect_head(
+            &head,
+            None,
+            Uri::from_static("https://other.com/b"),
+            Method::GET,
+            false,
+            false,
+        );
+        assert_eq!(
+            new_head.headers.get(header::REFERER).unwrap(),
+            "https://example.com/a?q=1"
+        );
+        // request settings are carried to other origin, except fallback addresses
+        assert!(RequestOptions::get(&new_head, |opts| opts.auto_referer));
+        assert_eq!(
+            RequestOptions::get(&new_head, |opts| opts.pool_key.clone()),
+            Some("key".to_string())
+        );
+        assert!(RequestOptions::get(&new_head, |opts| opts
+            .fallback_addrs
+            .is_empty()));
+
+        // no referer on downgrade to http
+        let new_head = redirect_head(
+            &head,
+            None,
+            Uri::from_static("http://example.com/b"),
+            Method::GET,
+            false,
+            false,
+        );
+        assert!(!new_head.headers.contains_key(header::REFERER));
+    }
+}
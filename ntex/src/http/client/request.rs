@@ -1,8 +1,8 @@
 use std::convert::TryFrom;
 use std::error::Error;
 use std::rc::Rc;
-use std::time::Duration;
-use std::{fmt, net};
+use std::time::{Duration, Instant};
+use std::{cmp, fmt, mem, net};
 
 use bytes::Bytes;
 use futures::Stream;
@@ -11,6 +11,7 @@ use serde::Serialize;
 #[cfg(feature = "cookie")]
 use coo_kie::{Cookie, CookieJar};
 
+use crate::codec::AsyncRead;
 use crate::http::body::Body;
 use crate::http::error::HttpError;
 use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
@@ -18,15 +19,29 @@ use crate::http::{
     uri, ConnectionType, Method, RequestHead, RequestHeadType, Uri, Version,
 };
 
+use super::browser::BrowserProfile;
 use super::error::{FreezeRequestError, InvalidUrl};
 use super::frozen::FrozenClientRequest;
-use super::sender::{PrepForSendingError, SendClientRequest};
+use super::multipart::Multipart;
+use super::redirect;
+use super::retry::{self, RetryPolicy};
+use super::sender::{
+    CoalesceStream, PrepForSendingError, RequestOptions, SendClientRequest,
+    StreamOptions,
+};
 use super::ClientConfig;
 
 #[cfg(feature = "compress")]
-const HTTPS_ENCODING: &str = "br, gzip, deflate";
+#[cfg(feature = "compress")]
+use crate::http::header::ContentEncoding;
+
+/// Encodings supported by response decoder
+#[cfg(feature = "zstd")]
+const ACCEPT_ENCODING: &str = "zstd, br, gzip, deflate";
+#[cfg(all(feature = "compress", not(feature = "zstd")))]
+const ACCEPT_ENCODING: &str = "br, gzip, deflate";
 #[cfg(not(feature = "compress"))]
-const HTTPS_ENCODING: &str = "br";
+const ACCEPT_ENCODING: &str = "identity";
 
 /// An HTTP Client request builder
 ///
@@ -53,11 +68,15 @@ const HTTPS_ENCODING: &str = "br";
 pub struct ClientRequest {
     pub(crate) head: RequestHead,
     err: Option<HttpError>,
+    query_err: Option<serde_urlencoded::ser::Error>,
+    url_err: Option<InvalidUrl>,
+    credentials_err: bool,
     addr: Option<net::SocketAddr>,
     #[cfg(feature = "cookie")]
     cookies: Option<CookieJar>,
     response_decompress: bool,
     timeout: Option<Duration>,
+    deadline: Option<Instant>,
     config: Rc<ClientConfig>,
 }
 
@@ -72,10 +91,14 @@ impl ClientRequest {
             config,
             head: RequestHead::default(),
             err: None,
+            query_err: None,
+            url_err: None,
+            credentials_err: false,
             addr: None,
             #[cfg(feature = "cookie")]
             cookies: None,
             timeout: None,
+            deadline: None,
             response_decompress: true,
         }
         .method(method)
@@ -104,9 +127,41 @@ impl ClientRequest {
     /// Set socket address of the server.
     ///
     /// This address is used for connection. If address is not
-    /// provided url's host name get resolved.
+    /// provided url's host name get resolved. Connections to
+    /// the address are pooled separately.
     pub fn address(mut self, addr: net::SocketAddr) -> Self {
         self.addr = Some(addr);
+        RequestOptions::set(&self.head, |opts| opts.fallback_addrs.clear());
+        self
+    }
+
+    /// Set socket addresses of the server.
+    ///
+    /// Addresses are tried in order until connection succeeds, connect
+    /// timeout applies to all attempts. If happy eyeballs is enabled
+    /// attempts are raced, see `ClientBuilder::happy_eyeballs()`.
+    /// Addresses are not used if request is redirected to other origin.
+    ///
+    /// ```rust
+    /// use std::net::SocketAddr;
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let primary: SocketAddr = "10.0.0.1:8080".parse().unwrap();
+    ///     let backup: SocketAddr = "10.0.0.2:8080".parse().unwrap();
+    ///     let req = Client::new()
+    ///         .get("http://service.local/")
+    ///         .addresses(vec![primary, backup]);
+    /// }
+    /// ```
+    pub fn addresses<I>(mut self, addrs: I) -> Self
+    where
+        I: IntoIterator<Item = net::SocketAddr>,
+    {
+        let mut addrs = addrs.into_iter();
+        self.addr = addrs.next();
+        RequestOptions::set(&self.head, |opts| opts.fallback_addrs = addrs.collect());
         self
     }
 
@@ -122,10 +177,14 @@ impl ClientRequest {
         &self.head.method
     }
 
-    #[doc(hidden)]
     /// Set HTTP version of this request.
     ///
-    /// By default requests's HTTP version depends on network stream
+    /// By default requests's HTTP version depends on network stream.
+    /// `HTTP/1.0` requests could not use chunked transfer encoding, request
+    /// with streaming body of unknown size fails with
+    /// `SendRequestError::ChunkedNotSupported`. Connection of `HTTP/1.0`
+    /// request is kept alive only if server responds with keep-alive
+    /// connection.
     #[inline]
     pub fn version(mut self, version: Version) -> Self {
         self.head.version = version;
@@ -223,6 +282,63 @@ impl ClientRequest {
         self
     }
 
+    /// Set headers of browser profile.
+    ///
+    /// Sets `User-Agent`, `Accept`, `Accept-Language` and `Sec-Fetch-*`
+    /// headers the browser sends for page navigation. Headers that are
+    /// already set are not changed, so explicit values always win.
+    ///
+    /// ```rust
+    /// use ntex::http::client::{BrowserProfile, Client};
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let res = Client::new()
+    ///         .get("http://www.rust-lang.org")
+    ///         .header("Accept-Language", "de-DE,de;q=0.9")
+    ///         .browser_headers(BrowserProfile::Firefox)
+    ///         .send()
+    ///         .await;
+    /// }
+    /// ```
+    pub fn browser_headers(self, profile: BrowserProfile) -> Self {
+        profile
+            .headers()
+            .fold(self, |slf, (key, value)| slf.set_header_if_none(key, value))
+    }
+
+    /// Set `Host` header, virtual host of the request.
+    ///
+    /// By default `Host` header is derived from url host. Header is not
+    /// changed if it is already set. Connection is established to url host
+    /// or to `address()` if it is set, tls server name (SNI) and certificate
+    /// verification always use url host.
+    ///
+    /// ```rust
+    /// use std::net::SocketAddr;
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let backend: SocketAddr = "10.0.0.1:8080".parse().unwrap();
+    ///     let req = Client::new()
+    ///         .get("http://10.0.0.1:8080/")
+    ///         .host_header("www.rust-lang.org");
+    ///
+    ///     // tls connection to a specific backend of virtual host
+    ///     let req = Client::new()
+    ///         .get("https://www.rust-lang.org/")
+    ///         .address(backend);
+    /// }
+    /// ```
+    pub fn host_header<V>(self, value: V) -> Self
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<HttpError>,
+    {
+        self.set_header_if_none(header::HOST, value)
+    }
+
     /// Force close connection instead of returning it back to connections pool.
     /// This setting affect only http/1 connections.
     #[inline]
@@ -231,6 +347,20 @@ impl ClientRequest {
         self
     }
 
+    /// Send `Expect: 100-continue` header and wait for `100 Continue`
+    /// interim response before sending request body.
+    ///
+    /// If server responds with final response first, request body is not sent.
+    /// If server does not respond within `ClientBuilder::expect_continue_timeout()`
+    /// request body is sent anyway. This setting affect only http/1 connections.
+    #[inline]
+    pub fn expect_continue(mut self) -> Self {
+        self.head
+            .headers
+            .insert(header::EXPECT, HeaderValue::from_static("100-continue"));
+        self
+    }
+
     /// Set request's content type
     #[inline]
     pub fn content_type<V>(mut self, value: V) -> Self
@@ -252,26 +382,204 @@ impl ClientRequest {
     }
 
     /// Set HTTP basic authorization header
-    pub fn basic_auth<U>(self, username: U, password: Option<&str>) -> Self
+    ///
+    /// Header is not set if request already has `Authorization` header,
+    /// including client wide one. Username must not contain colon, otherwise
+    /// request fails with `SendRequestError::InvalidCredentials` error.
+    /// Credentials are sent in clear text, a warning is logged if
+    /// url scheme is not secure.
+    pub fn basic_auth<U>(mut self, username: U, password: Option<&str>) -> Self
     where
         U: fmt::Display,
     {
-        let auth = match password {
-            Some(password) => format!("{}:{}", username, password),
-            None => format!("{}:", username),
-        };
-        self.header(
-            header::AUTHORIZATION,
-            format!("Basic {}", base64::encode(&auth)),
-        )
+        let username = username.to_string();
+        if username.contains(':') {
+            self.credentials_err = true;
+            return self;
+        }
+        if self.config.headers.contains_key(header::AUTHORIZATION) {
+            return self;
+        }
+        self.map_head(|head| head.basic_auth(&username, password))
     }
 
     /// Set HTTP bearer authentication header
+    ///
+    /// Header is not set if request already has `Authorization` header,
+    /// including client wide one.
     pub fn bearer_auth<T>(self, token: T) -> Self
     where
         T: fmt::Display,
     {
-        self.header(header::AUTHORIZATION, format!("Bearer {}", token))
+        if self.config.headers.contains_key(header::AUTHORIZATION) {
+            return self;
+        }
+        let token = token.to_string();
+        self.map_head(|head| head.bearer_auth(&token))
+    }
+
+    /// Set `Origin` header from absolute http url.
+    ///
+    /// Only scheme, host and port of the url are sent. Header is not set
+    /// if request already has `Origin` header. Relative urls and urls
+    /// with schemes other than `http` and `https` fail the request with
+    /// `SendRequestError::Url` error. Origin is not changed on redirects.
+    ///
+    /// ```rust
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let res = Client::new()
+    ///         .post("http://www.rust-lang.org/login")
+    ///         .origin("http://www.rust-lang.org/index.html")
+    ///         .send()
+    ///         .await;
+    /// }
+    /// ```
+    pub fn origin<U>(mut self, url: U) -> Self
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<HttpError>,
+    {
+        match absolute_url(url) {
+            Ok(url) => self.set_header_if_none(header::ORIGIN, redirect::origin(&url)),
+            Err(e) => {
+                self.url_err = Some(e);
+                self
+            }
+        }
+    }
+
+    /// Set `Referer` header from absolute http url.
+    ///
+    /// User info of the url is not sent. Header is not set if request
+    /// already has `Referer` header. Relative urls and urls with schemes
+    /// other than `http` and `https` fail the request with
+    /// `SendRequestError::Url` error.
+    ///
+    /// On redirects header is updated to url of the redirected request,
+    /// header is removed if redirect changes scheme from `https` to `http`.
+    pub fn referer<U>(mut self, url: U) -> Self
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<HttpError>,
+    {
+        match absolute_url(url) {
+            Ok(url) => {
+                if !self.head.headers.contains_key(header::REFERER) {
+                    RequestOptions::set(&self.head, |opts| opts.auto_referer = true);
+                }
+                self.set_header_if_none(header::REFERER, redirect::referer(&url))
+            }
+            Err(e) => {
+                self.url_err = Some(e);
+                self
+            }
+        }
+    }
+
+    /// Make request conditional on cached response validators
+    ///
+    /// Sets `If-None-Match` header from cached `ETag` value and
+    /// `If-Modified-Since` header from cached `Last-Modified` value.
+    /// If server responds with `304 Not Modified`, response has empty body
+    /// and cached copy should be reused, see `ClientResponse::is_not_modified()`.
+    ///
+    /// ```rust
+    /// use ntex::http::{client::Client, header};
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let client = Client::new();
+    ///     if let Ok(cached) = client.get("http://www.rust-lang.org").send().await {
+    ///         let res = client.get("http://www.rust-lang.org")
+    ///             .conditional(
+    ///                 cached.headers().get(header::ETAG),
+    ///                 cached.headers().get(header::LAST_MODIFIED),
+    ///             )
+    ///             .send()
+    ///             .await;
+    ///     }
+    /// }
+    /// ```
+    pub fn conditional(
+        mut self,
+        etag: Option<&HeaderValue>,
+        last_modified: Option<&HeaderValue>,
+    ) -> Self {
+        if let Some(etag) = etag {
+            self.head
+                .headers
+                .insert(header::IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = last_modified {
+            self.head
+                .headers
+                .insert(header::IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        self
+    }
+
+    /// Request byte range of the resource
+    ///
+    /// Sets `Range: bytes=start-end` header, `end` offset is inclusive.
+    /// Calling it several times requests several ranges, server could
+    /// respond with `multipart/byteranges` body. Ranges of `206 Partial Content`
+    /// response could be read with `ClientResponse::byte_ranges()`.
+    ///
+    /// ```rust,no_run
+    /// use futures::StreamExt;
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let mut res = Client::new()
+    ///         .get("http://www.rust-lang.org")
+    ///         .range(0, 99)
+    ///         .range(200, 299)
+    ///         .send()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     if res.is_partial() {
+    ///         let mut ranges = res.byte_ranges();
+    ///         while let Some(Ok(part)) = ranges.next().await {
+    ///             println!("{}-{}: {:?}", part.range.start, part.range.end, part.body);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn range(mut self, start: u64, end: u64) -> Self {
+        let value = match self
+            .head
+            .headers
+            .get(header::RANGE)
+            .and_then(|value| value.to_str().ok())
+        {
+            Some(ranges) => format!("{},{}-{}", ranges, start, end),
+            None => format!("bytes={}-{}", start, end),
+        };
+        match HeaderValue::try_from(value) {
+            Ok(value) => self.head.headers.insert(header::RANGE, value),
+            Err(e) => self.err = Some(e.into()),
+        }
+        self
+    }
+
+    /// Update request head with `RequestHeadType` helper
+    fn map_head<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut RequestHeadType) -> Result<(), HttpError>,
+    {
+        let mut head = RequestHeadType::Owned(mem::take(&mut self.head));
+        if let Err(e) = f(&mut head) {
+            self.err = Some(e);
+        }
+        if let RequestHeadType::Owned(head) = head {
+            self.head = head;
+        }
+        self
     }
 
     #[cfg(feature = "cookie")]
@@ -310,8 +618,100 @@ impl ClientRequest {
     }
 
     /// Disable automatic decompress of response's body
-    pub fn no_decompress(mut self) -> Self {
-        self.response_decompress = false;
+    ///
+    /// Same as `decompress(false)`.
+    pub fn no_decompress(self) -> Self {
+        self.decompress(false)
+    }
+
+    /// Enable or disable automatic decompress of response's body
+    ///
+    /// If enabled, `Accept-Encoding` header with encodings supported
+    /// by the client is added to the request. If disabled, response payload
+    /// is returned as is regardless of response's `Content-Encoding` header
+    /// and `Accept-Encoding: identity` header is added. Explicitly set
+    /// `Accept-Encoding` header is not overridden. By default decompress
+    /// is enabled.
+    pub fn decompress(mut self, enable: bool) -> Self {
+        self.response_decompress = enable;
+        self
+    }
+
+    /// Set max size of response payload. Overrides client wide setting.
+    ///
+    /// See `ClientBuilder::max_response_size()` for details.
+    pub fn max_response_size(self, size: usize) -> Self {
+        RequestOptions::set(&self.head, |opts| opts.max_response_size = Some(size));
+        self
+    }
+
+    /// Set max ratio of decompressed response payload size to compressed size.
+    /// Overrides client wide setting.
+    ///
+    /// See `ClientBuilder::max_decompression_ratio()` for details.
+    pub fn max_decompression_ratio(self, ratio: usize) -> Self {
+        RequestOptions::set(&self.head, |opts| {
+            opts.max_decompression_ratio = Some(ratio)
+        });
+        self
+    }
+
+    /// Set max size of response headers. Overrides client wide setting.
+    ///
+    /// See `ClientBuilder::max_response_header_size()` for details.
+    pub fn max_response_header_size(self, size: usize) -> Self {
+        RequestOptions::set(&self.head, |opts| opts.max_header_size = Some(size));
+        self
+    }
+
+    /// Use separate connection pool for the request.
+    ///
+    /// Connector maintains separate set of connections for each pool key,
+    /// connections limit is applied to each pool separately. By default
+    /// all requests share the same pool.
+    pub fn pool_key<K: Into<String>>(self, key: K) -> Self {
+        RequestOptions::set(&self.head, |opts| opts.pool_key = Some(key.into()));
+        self
+    }
+
+    /// Set upload progress callback.
+    ///
+    /// Callback is called while request body is being sent with number of
+    /// body bytes sent so far and total body size, if it is known. Bytes
+    /// are counted after compression, framing of chunked encoding is not
+    /// counted. Retried and redirected requests report progress from zero.
+    ///
+    /// ```rust
+    /// use ntex::http::client::Client;
+    ///
+    /// # async fn send() {
+    /// let response = Client::new()
+    ///     .post("http://www.rust-lang.org")
+    ///     .upload_progress(|sent, total| {
+    ///         if let Some(total) = total {
+    ///             println!("uploaded {}%", sent * 100 / total);
+    ///         }
+    ///     })
+    ///     .send_body(vec![0u8; 1024 * 1024])
+    ///     .await;
+    /// # }
+    /// ```
+    pub fn upload_progress<F>(self, f: F) -> Self
+    where
+        F: Fn(u64, Option<u64>) + 'static,
+    {
+        RequestOptions::set(&self.head, |opts| opts.upload_progress = Some(Rc::new(f)));
+        self
+    }
+
+    #[cfg(feature = "compress")]
+    /// Compress request body with specified encoding.
+    ///
+    /// `Content-Encoding` header is set accordingly. Streaming bodies are
+    /// compressed on the fly. Body is sent as is if `Content-Encoding`
+    /// header is already set.
+    pub fn compress(self, encoding: ContentEncoding) -> Self {
+        RequestOptions::set(&self.head, |opts| opts.compress = Some(encoding));
         self
     }
 
@@ -324,6 +724,44 @@ impl ClientRequest {
         self
     }
 
+    /// Allow request to be cloned with `SendClientRequest::try_clone()`.
+    ///
+    /// Request parts are kept until the send future is polled, only
+    /// requests with replayable body (empty or `Body::Bytes`) could be cloned.
+    pub fn replayable(self) -> Self {
+        RequestOptions::set(&self.head, |opts| opts.replayable = true);
+        self
+    }
+
+    /// Set request deadline. Overrides client wide timeout setting.
+    ///
+    /// Remaining time until deadline is used as request timeout, so one
+    /// deadline could be shared by several sequential requests. If deadline
+    /// has already passed, request fails with `SendRequestError::Timeout`.
+    /// If request timeout is set as well, the earliest one applies.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set retry policy for this request. Overrides client wide retry policy.
+    ///
+    /// Request is retried only if its body could be sent more than once,
+    /// streaming bodies are always sent once.
+    pub fn retry(self, policy: RetryPolicy) -> Self {
+        RequestOptions::set(&self.head, |opts| opts.retry = Some(policy));
+        self
+    }
+
+    /// Attach `Idempotency-Key` header with random UUID v4 key.
+    ///
+    /// Key is generated once, the same key is sent with every retry attempt,
+    /// so server could detect duplicate requests. If header is already set,
+    /// for example with a key supplied by user, it is left intact.
+    pub fn idempotency_key(self) -> Self {
+        self.set_header_if_none(retry::IDEMPOTENCY_KEY, retry::idempotency_key())
+    }
+
     /// This method calls provided closure with builder reference if
     /// value is `true`.
     pub fn if_true<F>(self, value: bool, f: F) -> Self
@@ -350,29 +788,63 @@ impl ClientRequest {
         }
     }
 
-    /// Sets the query part of the request
-    pub fn query<T: Serialize>(
-        mut self,
-        query: &T,
-    ) -> Result<Self, serde_urlencoded::ser::Error> {
-        let mut parts = self.head.uri.clone().into_parts();
+    /// Append serialized value to the query part of the request
+    ///
+    /// Existing query parameters are preserved, repeated keys are
+    /// appended. Serialization error is returned as
+    /// `SendRequestError::Error` during request sending.
+    ///
+    /// ```rust
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let req = Client::new()
+    ///         .get("http://www.rust-lang.org/?page=1")
+    ///         .query(&[("page", "2"), ("lang", "en")]);
+    ///     assert_eq!(req.get_uri().query(), Some("page=1&page=2&lang=en"));
+    /// }
+    /// ```
+    pub fn query<T: Serialize>(mut self, query: &T) -> Self {
+        let query = match serde_urlencoded::to_string(query) {
+            Ok(query) => query,
+            Err(e) => {
+                self.query_err = Some(e);
+                return self;
+            }
+        };
+        if query.is_empty() {
+            return self;
+        }
 
-        if let Some(path_and_query) = parts.path_and_query {
-            let query = serde_urlencoded::to_string(query)?;
-            let path = path_and_query.path();
-            parts.path_and_query = format!("{}?{}", path, query).parse().ok();
+        let mut parts = self.head.uri.clone().into_parts();
+        let (path, query) = match parts.path_and_query {
+            Some(ref pq) => match pq.query() {
+                Some(q) if !q.is_empty() => (pq.path(), format!("{}&{}", q, query)),
+                _ => (pq.path(), query),
+            },
+            None => ("/", query),
+        };
 
-            match Uri::from_parts(parts) {
-                Ok(uri) => self.head.uri = uri,
-                Err(e) => self.err = Some(e.into()),
+        match format!("{}?{}", path, query).parse::<uri::PathAndQuery>() {
+            Ok(pq) => {
+                parts.path_and_query = Some(pq);
+                match Uri::from_parts(parts) {
+                    Ok(uri) => self.head.uri = uri,
+                    Err(e) => self.err = Some(e.into()),
+                }
             }
+            Err(e) => self.err = Some(e.into()),
         }
-
-        Ok(self)
+        self
     }
 
     /// Freeze request builder and construct `FrozenClientRequest`,
     /// which could be used for sending same request multiple times.
+    ///
+    /// Request is validated once, frozen request shares its head between
+    /// sends, so only server address and timeout could be changed per send.
+    /// Validation errors are returned as `FreezeRequestError`.
     pub fn freeze(self) -> Result<FrozenClientRequest, FreezeRequestError> {
         let slf = match self.prep_for_sending() {
             Ok(slf) => slf,
@@ -404,7 +876,7 @@ impl ClientRequest {
             slf.addr,
             slf.response_decompress,
             slf.timeout,
-            slf.config.as_ref(),
+            &slf.config,
             body,
         )
     }
@@ -420,7 +892,57 @@ impl ClientRequest {
             slf.addr,
             slf.response_decompress,
             slf.timeout,
-            slf.config.as_ref(),
+            &slf.config,
+            value,
+        )
+    }
+
+    /// Set a JSON body serialized with custom serializer and generate
+    /// `ClientRequest`
+    ///
+    /// ```rust
+    /// use ntex::http::client::Client;
+    ///
+    /// # async fn send() {
+    /// let value = serde_json::json!({"b": 1, "a": 2});
+    /// let response = Client::new()
+    ///     .post("http://www.rust-lang.org")
+    ///     .send_json_with(&value, |v| serde_json::to_vec_pretty(v))
+    ///     .await;
+    /// # }
+    /// ```
+    pub fn send_json_with<T, F>(self, value: &T, serializer: F) -> SendClientRequest
+    where
+        F: FnOnce(&T) -> Result<Vec<u8>, serde_json::Error>,
+    {
+        let slf = match self.prep_for_sending() {
+            Ok(slf) => slf,
+            Err(e) => return e.into(),
+        };
+
+        RequestHeadType::Owned(slf.head).send_json_with(
+            slf.addr,
+            slf.response_decompress,
+            slf.timeout,
+            &slf.config,
+            value,
+            serializer,
+        )
+    }
+
+    #[cfg(feature = "msgpack")]
+    /// Set a MessagePack body and generate `ClientRequest`
+    pub fn send_msgpack<T: Serialize>(self, value: &T) -> SendClientRequest {
+        let slf = match self.prep_for_sending() {
+            Ok(slf) => slf,
+            Err(e) => return e.into(),
+        };
+
+        RequestHeadType::Owned(slf.head).send_msgpack(
+            slf.addr,
+            slf.response_decompress,
+            slf.timeout,
+            &slf.config,
             value,
         )
     }
@@ -438,11 +960,94 @@ impl ClientRequest {
             slf.addr,
             slf.response_decompress,
             slf.timeout,
-            slf.config.as_ref(),
+            &slf.config,
             value,
         )
     }
 
+    #[cfg(feature = "body-serializer")]
+    /// Set a body serialized by registered serializer for content type
+    /// and generate `ClientRequest`
+    ///
+    /// Serializers are registered with `ClientBuilder::body_serializer()`,
+    /// if serializer is not registered for content type request fails with
+    /// `SendRequestError::NoSerializer` error.
+    ///
+    /// ```rust
+    /// use ntex::http::client::Client;
+    ///
+    /// # async fn send() {
+    /// let value = serde_json::json!({"a": 1});
+    /// let response = Client::new()
+    ///     .post("http://www.rust-lang.org")
+    ///     .send_typed(&value, "application/json")
+    ///     .await;
+    /// # }
+    /// ```
+    pub fn send_typed<T: Serialize>(
+        self,
+        value: &T,
+        content_type: &str,
+    ) -> SendClientRequest {
+        let slf = match self.prep_for_sending() {
+            Ok(slf) => slf,
+            Err(e) => return e.into(),
+        };
+
+        RequestHeadType::Owned(slf.head).send_typed(
+            slf.addr,
+            slf.response_decompress,
+            slf.timeout,
+            &slf.config,
+            value,
+            content_type,
+        )
+    }
+
+    /// Set a bytes body with content type and generate `ClientRequest`
+    ///
+    /// `Content-Type` header is set unless it is already present,
+    /// `Content-Length` header is always set to the length of `bytes`.
+    ///
+    /// `ClientRequestBuilder` can not be used after this call.
+    pub fn send_bytes<V>(self, content_type: V, bytes: Bytes) -> SendClientRequest
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<HttpError>,
+    {
+        let slf = match self.prep_for_sending() {
+            Ok(slf) => slf,
+            Err(e) => return e.into(),
+        };
+
+        RequestHeadType::Owned(slf.head).send_bytes(
+            slf.addr,
+            slf.response_decompress,
+            slf.timeout,
+            &slf.config,
+            content_type,
+            bytes,
+        )
+    }
+
+    /// Set a `multipart/form-data` body and generate `ClientRequest`
+    ///
+    /// `ClientRequestBuilder` can not be used after this call.
+    pub fn send_multipart(self, multipart: Multipart) -> SendClientRequest {
+        let slf = match self.prep_for_sending() {
+            Ok(slf) => slf,
+            Err(e) => return e.into(),
+        };
+
+        RequestHeadType::Owned(slf.head).send_multipart(
+            slf.addr,
+            slf.response_decompress,
+            slf.timeout,
+            &slf.config,
+            multipart,
+        )
+    }
+
     /// Set an streaming body and generate `ClientRequest`.
     pub fn send_stream<S, E>(self, stream: S) -> SendClientRequest
     where
@@ -458,12 +1063,116 @@ impl ClientRequest {
             slf.addr,
             slf.response_decompress,
             slf.timeout,
-            slf.config.as_ref(),
+            &slf.config,
+            stream,
+        )
+    }
+
+    /// Set an streaming body of known size and generate `ClientRequest`.
+    ///
+    /// Body is sent with `Content-Length` header instead of chunked
+    /// transfer encoding. Request fails with body error if stream yields
+    /// more or fewer bytes than `size`.
+    pub fn send_sized_stream<S, E>(self, stream: S, size: u64) -> SendClientRequest
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+        E: Error + 'static,
+    {
+        let slf = match self.prep_for_sending() {
+            Ok(slf) => slf,
+            Err(e) => return e.into(),
+        };
+
+        RequestHeadType::Owned(slf.head).send_sized_stream(
+            slf.addr,
+            slf.response_decompress,
+            slf.timeout,
+            &slf.config,
             stream,
+            size,
+        )
+    }
+
+    /// Set an streaming body with custom buffering options and
+    /// generate `ClientRequest`.
+    ///
+    /// See `StreamOptions` for details.
+    pub fn send_stream_with<S, E>(
+        self,
+        stream: S,
+        options: StreamOptions,
+    ) -> SendClientRequest
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+        E: Error + 'static,
+    {
+        let slf = match self.prep_for_sending() {
+            Ok(slf) => slf,
+            Err(e) => return e.into(),
+        };
+        let (min_chunk_size, max_buffer_size, buffer_body) = options.into_parts();
+        RequestOptions::set(&slf.head, |opts| {
+            opts.stream_buffer_size = Some(max_buffer_size);
+            opts.buffer_body = buffer_body;
+        });
+
+        RequestHeadType::Owned(slf.head).send_stream(
+            slf.addr,
+            slf.response_decompress,
+            slf.timeout,
+            &slf.config,
+            CoalesceStream::new(stream, min_chunk_size),
+        )
+    }
+
+    /// Set a body read from `AsyncRead` and generate `ClientRequest`.
+    ///
+    /// Reader is polled for chunks of up to `buffer_size` bytes. If `size` of the
+    /// body is known, `Content-Length` header is sent, otherwise chunked transfer
+    /// encoding is used. Read errors fail the request.
+    ///
+    /// ```rust
+    /// use ntex::http::client::Client;
+    ///
+    /// # async fn send() {
+    /// let data: &[u8] = b"data";
+    /// let response = Client::new()
+    ///     .post("http://www.rust-lang.org")
+    ///     .send_reader(data, Some(4), 8192)
+    ///     .await;
+    /// # }
+    /// ```
+    pub fn send_reader<R>(
+        self,
+        reader: R,
+        size: Option<u64>,
+        buffer_size: usize,
+    ) -> SendClientRequest
+    where
+        R: AsyncRead + Unpin + 'static,
+    {
+        let slf = match self.prep_for_sending() {
+            Ok(slf) => slf,
+            Err(e) => return e.into(),
+        };
+
+        RequestHeadType::Owned(slf.head).send_reader(
+            slf.addr,
+            slf.response_decompress,
+            slf.timeout,
+            &slf.config,
+            reader,
+            size,
+            buffer_size,
         )
     }
 
     /// Set an empty body and generate `ClientRequest`.
+    ///
+    /// `Content-Length: 0` header is sent for methods with request payload
+    /// semantics, i.e. `POST` or `PUT`, and omitted for `GET`, `HEAD`,
+    /// `DELETE` and `CONNECT`. Use `send_body(Body::None)` to send request
+    /// without `Content-Length` header for any method.
     pub fn send(self) -> SendClientRequest {
         let slf = match self.prep_for_sending() {
             Ok(slf) => slf,
@@ -474,7 +1183,7 @@ impl ClientRequest {
             slf.addr,
             slf.response_decompress,
             slf.timeout,
-            slf.config.as_ref(),
+            &slf.config,
         )
     }
 
@@ -483,20 +1192,36 @@ impl ClientRequest {
         if let Some(e) = self.err {
             return Err(e.into());
         }
+        if let Some(e) = self.query_err {
+            return Err(e.into());
+        }
+        if let Some(e) = self.url_err {
+            return Err(e.into());
+        }
+        if self.credentials_err {
+            return Err(PrepForSendingError::Credentials);
+        }
+
+        if let Some(deadline) = self.deadline.take() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            self.timeout = Some(match self.timeout {
+                Some(timeout) => cmp::min(timeout, remaining),
+                None => remaining,
+            });
+        }
 
         // validate uri
         let uri = &self.head.uri;
+        match uri.scheme_str() {
+            Some("http") | Some("ws") | Some("https") | Some("wss") => (),
+            Some(_) => return Err(InvalidUrl::UnsupportedScheme.into()),
+            None => return Err(InvalidUrl::MissingScheme.into()),
+        }
         if uri.host().is_none() {
             return Err(InvalidUrl::MissingHost.into());
-        } else if uri.scheme().is_none() {
-            return Err(InvalidUrl::MissingScheme.into());
-        } else if let Some(scheme) = uri.scheme() {
-            match scheme.as_str() {
-                "http" | "ws" | "https" | "wss" => (),
-                _ => return Err(InvalidUrl::UnknownScheme.into()),
-            }
-        } else {
-            return Err(InvalidUrl::UnknownScheme.into());
+        }
+        if let Some(uri) = normalize_uri(uri).map_err(InvalidUrl::Http)? {
+            self.head.uri = uri;
         }
 
         // set cookies
@@ -525,31 +1250,98 @@ impl ClientRequest {
             }
         }
 
+        // merge client default headers
         let mut slf = self;
+        let config = slf.config.clone();
+        for (key, value) in config.headers.iter() {
+            if key == header::USER_AGENT && config.user_agents.is_some() {
+                continue;
+            }
+            slf = slf.set_header_if_none(key.clone(), value.clone());
+        }
 
         if slf.response_decompress {
-            let https = slf
-                .head
-                .uri
-                .scheme()
-                .map(|s| s == &uri::Scheme::HTTPS)
-                .unwrap_or(true);
-
-            if https {
-                slf = slf.set_header_if_none(header::ACCEPT_ENCODING, HTTPS_ENCODING)
-            } else {
-                #[cfg(any(feature = "compress"))]
-                {
-                    slf =
-                        slf.set_header_if_none(header::ACCEPT_ENCODING, "gzip, deflate")
-                }
-            };
+            slf = slf.set_header_if_none(header::ACCEPT_ENCODING, ACCEPT_ENCODING);
+        } else {
+            slf = slf.set_header_if_none(header::ACCEPT_ENCODING, "identity");
         }
 
         Ok(slf)
     }
 }
 
+/// Parse absolute `http` or `https` url
+fn absolute_url<U>(url: U) -> Result<Uri, InvalidUrl>
+where
+    Uri: TryFrom<U>,
+    <Uri as TryFrom<U>>::Error: Into<HttpError>,
+{
+    let url = Uri::try_from(url).map_err(|e| InvalidUrl::Http(e.into()))?;
+    match url.scheme_str() {
+        Some("http") | Some("https") => (),
+        Some(_) => return Err(InvalidUrl::UnsupportedScheme),
+        None => return Err(InvalidUrl::MissingScheme),
+    }
+    if url.host().is_none() {
+        return Err(InvalidUrl::MissingHost);
+    }
+    Ok(url)
+}
+
+/// Strip default port and remove dot segments from the path,
+/// returns `None` if uri is already normalized.
+fn normalize_uri(uri: &Uri) -> Result<Option<Uri>, HttpError> {
+    let default_port = match uri.scheme_str() {
+        Some("http") | Some("ws") => 80,
+        _ => 443,
+    };
+    let strip_port = uri.port_u16() == Some(default_port);
+    let path = uri.path();
+    let dot_segments = path
+        .split('/')
+        .any(|segment| segment == "." || segment == "..");
+    if !strip_port && !dot_segments {
+        return Ok(None);
+    }
+
+    let mut parts = uri.clone().into_parts();
+    if strip_port {
+        if let Some(ref authority) = parts.authority {
+            let authority = authority.as_str();
+            let len = authority.rfind(':').unwrap_or(authority.len());
+            parts.authority = Some(authority[..len].parse()?);
+        }
+    }
+    if dot_segments {
+        let mut segments = Vec::new();
+        let mut trailing_slash = false;
+        for segment in path.split('/').skip(1) {
+            trailing_slash = segment == "." || segment == "..";
+            match segment {
+                "." => (),
+                ".." => {
+                    segments.pop();
+                }
+                _ => segments.push(segment),
+            }
+        }
+        let mut path = String::with_capacity(path.len());
+        for segment in segments {
+            path.push('/');
+            path.push_str(segment);
+        }
+        if trailing_slash || path.is_empty() {
+            path.push('/');
+        }
+        if let Some(query) = uri.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+        parts.path_and_query = Some(path.parse()?);
+    }
+    Ok(Some(Uri::from_parts(parts)?))
+}
+
 impl fmt::Debug for ClientRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
@@ -568,7 +1360,7 @@ impl fmt::Debug for ClientRequest {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::http::client::Client;
+    use crate::http::client::{error::SendRequestError, Client};
 
     #[ntex_rt::test]
     async fn test_debug() {
@@ -607,13 +1399,32 @@ mod tests {
         let _ = req.send_body("");
     }
 
+    #[ntex_rt::test]
+    async fn test_browser_headers() {
+        let req = Client::build()
+            .header(header::USER_AGENT, "client")
+            .finish()
+            .get("http://localhost/")
+            .header(header::ACCEPT_LANGUAGE, "de")
+            .browser_headers(BrowserProfile::Firefox);
+        assert_eq!(req.headers().get(header::ACCEPT_LANGUAGE).unwrap(), "de");
+        assert_eq!(req.headers().get("sec-fetch-mode").unwrap(), "navigate");
+        assert!(!req.headers().contains_key("sec-ch-ua"));
+
+        let req = req.prep_for_sending().ok().unwrap();
+        let ua = req.head.headers.get(header::USER_AGENT).unwrap();
+        assert!(ua.to_str().unwrap().contains("Firefox/"));
+    }
+
     #[ntex_rt::test]
     async fn test_client_header() {
         let req = Client::build()
             .header(header::CONTENT_TYPE, "111")
             .finish()
-            .get("/");
+            .get("http://localhost/");
+        assert!(!req.headers().contains_key(header::CONTENT_TYPE));
 
+        let req = req.prep_for_sending().ok().unwrap();
         assert_eq!(
             req.head
                 .headers
@@ -644,6 +1455,185 @@ mod tests {
         );
     }
 
+    #[ntex_rt::test]
+    async fn test_client_header_precedence() {
+        let req = Client::build()
+            .header(header::USER_AGENT, "default")
+            .header(header::CONTENT_TYPE, "111")
+            .finish()
+            .get("http://localhost/")
+            .header(header::CONTENT_TYPE, "222")
+            .prep_for_sending()
+            .ok()
+            .unwrap();
+
+        assert_eq!(req.headers().get(header::USER_AGENT).unwrap(), "default");
+        let values: Vec<_> = req.headers().get_all(header::CONTENT_TYPE).collect();
+        assert_eq!(values, vec!["222"]);
+    }
+
+    #[ntex_rt::test]
+    async fn test_normalize_uri() {
+        let req = Client::new()
+            .get("http://example.com:80/a/b/../c/./d?x=../1")
+            .prep_for_sending()
+            .ok()
+            .unwrap();
+        assert_eq!(req.get_uri(), "http://example.com/a/c/d?x=../1");
+
+        let norm = |uri: &str| {
+            normalize_uri(&uri.parse().unwrap())
+                .unwrap()
+                .map(|uri| uri.to_string())
+        };
+        assert_eq!(
+            norm("https://example.com:443/"),
+            Some("https://example.com/".into())
+        );
+        assert_eq!(
+            norm("https://user@[::1]:443/"),
+            Some("https://user@[::1]/".into())
+        );
+        assert_eq!(
+            norm("wss://example.com:8443/a/.."),
+            Some("wss://example.com:8443/".into())
+        );
+        assert_eq!(
+            norm("http://example.com/a/b/.."),
+            Some("http://example.com/a/".into())
+        );
+        assert_eq!(
+            norm("http://example.com/../../a"),
+            Some("http://example.com/a".into())
+        );
+        assert_eq!(norm("http://example.com:443/a/..b/"), None);
+    }
+
+    #[ntex_rt::test]
+    async fn test_url_scheme() {
+        let scheme = |url: &str| match Client::new().get(url).prep_for_sending() {
+            Ok(_) => None,
+            Err(PrepForSendingError::Url(e)) => Some(e),
+            Err(e) => panic!("unexpected error: {:?}", e),
+        };
+        for url in &[
+            "file://localhost/etc/passwd",
+            "ftp://example.com/file.txt",
+            "gopher://example.com/",
+            "HTTPX://example.com/",
+        ] {
+            assert!(
+                matches!(scheme(url), Some(InvalidUrl::UnsupportedScheme)),
+                "{}",
+                url
+            );
+        }
+        assert!(matches!(scheme("/test"), Some(InvalidUrl::MissingScheme)));
+        // url without host could not be parsed
+        assert!(matches!(
+            Client::new().get("file:///etc/passwd").prep_for_sending(),
+            Err(PrepForSendingError::Http(_))
+        ));
+        for url in &[
+            "http://example.com/",
+            "HTTPS://example.com/",
+            "ws://example.com/",
+            "wss://example.com/",
+        ] {
+            assert!(scheme(url).is_none(), "{}", url);
+        }
+
+        let res = Client::new().get("ftp://example.com/").send().await;
+        assert!(matches!(
+            res,
+            Err(SendRequestError::Url(InvalidUrl::UnsupportedScheme))
+        ));
+    }
+
+    #[ntex_rt::test]
+    async fn test_range() {
+        let req = Client::new().get("/").range(0, 99);
+        assert_eq!(req.headers().get(header::RANGE).unwrap(), "bytes=0-99");
+
+        let req = req.range(200, 299);
+        assert_eq!(
+            req.headers().get(header::RANGE).unwrap(),
+            "bytes=0-99,200-299"
+        );
+    }
+
+    #[ntex_rt::test]
+    async fn test_origin_referer() {
+        let req = Client::new()
+            .post("http://example.com/login")
+            .origin("https://User:pw@Example.com:443/index.html?q=1")
+            .referer("http://user:pw@example.com:8080/index.html?q=1");
+        assert_eq!(
+            req.headers().get(header::ORIGIN).unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            req.headers().get(header::REFERER).unwrap(),
+            "http://example.com:8080/index.html?q=1"
+        );
+        assert!(RequestOptions::get(&req.head, |opts| opts.auto_referer));
+
+        // existing headers are not replaced
+        let req = Client::new()
+            .get("http://example.com/")
+            .header(header::ORIGIN, "http://other.com")
+            .header(header::REFERER, "http://other.com/")
+            .origin("http://example.com/")
+            .referer("http://example.com/");
+        assert_eq!(
+            req.headers().get(header::ORIGIN).unwrap(),
+            "http://other.com"
+        );
+        assert_eq!(
+            req.headers().get(header::REFERER).unwrap(),
+            "http://other.com/"
+        );
+        assert!(!RequestOptions::get(&req.head, |opts| opts.auto_referer));
+
+        let err = |req: ClientRequest| match req.prep_for_sending() {
+            Err(PrepForSendingError::Url(e)) => e,
+            _ => panic!("url error is expected"),
+        };
+        let req = Client::new().get("http://example.com/");
+        assert!(matches!(
+            err(req.origin("/index.html")),
+            InvalidUrl::MissingScheme
+        ));
+        let req = Client::new().get("http://example.com/");
+        assert!(matches!(
+            err(req.referer("ftp://example.com/")),
+            InvalidUrl::UnsupportedScheme
+        ));
+        let req = Client::new().get("http://example.com/");
+        assert!(matches!(
+            err(req.referer("http://exa mple.com/")),
+            InvalidUrl::Http(_)
+        ));
+    }
+
+    #[ntex_rt::test]
+    async fn test_addresses() {
+        let addr1: net::SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        let addr2: net::SocketAddr = "127.0.0.1:8081".parse().unwrap();
+        let req = Client::new().get("/").addresses(vec![addr1, addr2]);
+        assert_eq!(req.addr, Some(addr1));
+        assert_eq!(
+            RequestOptions::get(&req.head, |opts| opts.fallback_addrs.clone()),
+            vec![addr2]
+        );
+
+        let req = req.address(addr2);
+        assert_eq!(req.addr, Some(addr2));
+        assert!(RequestOptions::get(&req.head, |opts| opts
+            .fallback_addrs
+            .is_empty()));
+    }
+
     #[ntex_rt::test]
     async fn client_basic_auth() {
         let req = Client::new()
@@ -683,14 +1673,120 @@ mod tests {
                 .unwrap(),
             "Bearer someS3cr3tAutht0k3n"
         );
+
+        // existing header is not overridden
+        let req = Client::new()
+            .get("/")
+            .header(header::AUTHORIZATION, "Token 1")
+            .bearer_auth("token");
+        assert_eq!(req.head.headers.get_all(header::AUTHORIZATION).count(), 1);
+        assert_eq!(
+            req.head.headers.get(header::AUTHORIZATION).unwrap(),
+            "Token 1"
+        );
+    }
+
+    #[ntex_rt::test]
+    async fn client_basic_auth_encoding() {
+        let auth = |user, pass| {
+            Client::new()
+                .get("https://example.com/")
+                .basic_auth(user, pass)
+                .head
+                .headers
+                .get(header::AUTHORIZATION)
+                .map(|v| v.to_str().unwrap().to_string())
+        };
+        assert_eq!(
+            auth("Aladdin", Some("open sesame")).unwrap(),
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+        assert_eq!(
+            auth("user", Some("pa:ss")).unwrap(),
+            "Basic dXNlcjpwYTpzcw=="
+        );
+        assert!(auth("us:er", Some("pass")).is_none());
+        let res = Client::new()
+            .get("https://example.com/")
+            .basic_auth("us:er", Some("pass"))
+            .send()
+            .await;
+        assert!(matches!(res, Err(SendRequestError::InvalidCredentials)));
+
+        let req = Client::build()
+            .basic_auth("client", None)
+            .finish()
+            .get("http://localhost/")
+            .basic_auth("username", None)
+            .prep_for_sending()
+            .ok()
+            .unwrap();
+        assert_eq!(
+            req.head.headers.get(header::AUTHORIZATION).unwrap(),
+            "Basic Y2xpZW50Og=="
+        );
     }
 
     #[ntex_rt::test]
     async fn client_query() {
         let req = Client::new()
             .get("/")
-            .query(&[("key1", "val1"), ("key2", "val2")])
-            .unwrap();
+            .query(&[("key1", "val1"), ("key2", "val2")]);
         assert_eq!(req.get_uri().query().unwrap(), "key1=val1&key2=val2");
+
+        // existing params are preserved
+        let req = Client::new()
+            .get("http://localhost/path?key1=val0")
+            .query(&[("key1", "val1"), ("key2", "val2")]);
+        assert_eq!(req.get_uri().path(), "/path");
+        assert_eq!(
+            req.get_uri().query().unwrap(),
+            "key1=val0&key1=val1&key2=val2"
+        );
+
+        // serialization error
+        let err = Client::new()
+            .get("http://localhost/")
+            .query(&[("key1", vec!["val1"])])
+            .send()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, SendRequestError::Error(_)));
+    }
+
+    #[ntex_rt::test]
+    async fn client_accept_encoding() {
+        let accept = |req: ClientRequest| {
+            let req = req.prep_for_sending().ok().unwrap();
+            req.head
+                .headers
+                .get(header::ACCEPT_ENCODING)
+                .unwrap()
+                .clone()
+        };
+        let client = Client::new();
+
+        #[cfg(feature = "zstd")]
+        assert_eq!(
+            accept(client.get("http://localhost/")),
+            "zstd, br, gzip, deflate"
+        );
+        #[cfg(all(feature = "compress", not(feature = "zstd")))]
+        assert_eq!(accept(client.get("http://localhost/")), "br, gzip, deflate");
+        #[cfg(not(feature = "compress"))]
+        assert_eq!(accept(client.get("http://localhost/")), "identity");
+        assert_eq!(
+            accept(client.get("https://localhost/").no_decompress()),
+            "identity"
+        );
+        assert_eq!(
+            accept(
+                client
+                    .get("http://localhost/")
+                    .header(header::ACCEPT_ENCODING, "gzip")
+            ),
+            "gzip"
+        );
     }
 }
@@ -1,10 +1,13 @@
 use std::cell::{Ref, RefMut};
-use std::fmt;
 use std::marker::PhantomData;
+use std::path::Path;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use std::{fmt, net};
 
 use bytes::{Bytes, BytesMut};
+use encoding_rs::{Encoding, UTF_8};
 use futures::{ready, Future, Stream};
 use serde::de::DeserializeOwned;
 
@@ -12,16 +15,69 @@ use serde::de::DeserializeOwned;
 use coo_kie::{Cookie, ParseError as CookieParseError};
 
 use crate::http::error::PayloadError;
-use crate::http::header::CONTENT_LENGTH;
+use crate::http::header::{HeaderValue, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE};
 use crate::http::{Extensions, HttpMessage, Payload, PayloadStream, ResponseHead};
-use crate::http::{HeaderMap, StatusCode, Version};
+use crate::http::{HeaderMap, StatusCode, Uri, Version};
 
-use super::error::JsonPayloadError;
+use super::error::{
+    DeserializeError, ErrorBody, JsonPayloadError, SendRequestError, TextPayloadError,
+};
+use super::parts::MultipartStream;
+use super::range::{ByteRanges, ContentRange};
+use super::save::SaveTo;
+use super::sender::{ByteCounters, HeadResponse, Trailers};
+use super::tls::TlsInfo;
 
 /// Client Response
 pub struct ClientResponse<S = PayloadStream> {
     pub(crate) head: ResponseHead,
     pub(crate) payload: Payload<S>,
+    pub(crate) url: Uri,
+    pub(crate) timings: Timings,
+    pub(crate) peer_addr: Option<net::SocketAddr>,
+    pub(crate) tls_info: Option<TlsInfo>,
+    pub(crate) request_headers: Option<HeaderMap>,
+    pub(crate) raw_headers: Option<Vec<(Bytes, Bytes)>>,
+}
+
+/// Request timings
+///
+/// All durations are measured from the moment the request future
+/// is polled for the first time. If request has been retried or redirected,
+/// connect time and time to first byte belong to the last attempt.
+#[derive(Copy, Clone, Debug)]
+pub struct Timings {
+    pub(crate) start: Instant,
+    pub(crate) connect: Duration,
+    pub(crate) first_byte: Instant,
+    pub(crate) end: Instant,
+}
+
+impl Timings {
+    pub(crate) fn new(start: Instant) -> Self {
+        Timings {
+            start,
+            connect: Duration::from_secs(0),
+            first_byte: start,
+            end: start,
+        }
+    }
+
+    /// Time spent to establish connection, or to get one from the pool.
+    pub fn connect(&self) -> Duration {
+        self.connect
+    }
+
+    /// Time until response head has been received.
+    pub fn time_to_first_byte(&self) -> Duration {
+        self.first_byte.saturating_duration_since(self.start)
+    }
+
+    /// Total time until response has been returned, response body
+    /// reading is not included.
+    pub fn total(&self) -> Duration {
+        self.end.saturating_duration_since(self.start)
+    }
 }
 
 impl<S> HttpMessage for ClientResponse<S> {
@@ -62,7 +118,31 @@ impl<S> HttpMessage for ClientResponse<S> {
 impl<S> ClientResponse<S> {
     /// Create new Request instance
     pub(crate) fn new(head: ResponseHead, payload: Payload<S>) -> Self {
-        ClientResponse { head, payload }
+        ClientResponse {
+            head,
+            payload,
+            url: Uri::default(),
+            timings: Timings::new(Instant::now()),
+            peer_addr: None,
+            tls_info: None,
+            request_headers: None,
+            raw_headers: None,
+        }
+    }
+
+    /// Set url of the request this response belongs to
+    pub(crate) fn set_url(mut self, url: Uri) -> Self {
+        self.url = url;
+        self
+    }
+
+    /// `Content-Length` of the payload, response to `HEAD` request has no payload
+    fn content_length(&self) -> Option<&HeaderValue> {
+        if self.head.extensions().contains::<HeadResponse>() {
+            None
+        } else {
+            self.head.headers.get(&CONTENT_LENGTH)
+        }
     }
 
     #[inline]
@@ -70,7 +150,10 @@ impl<S> ClientResponse<S> {
         &self.head
     }
 
-    /// Read the Request Version.
+    /// Get HTTP version of the response.
+    ///
+    /// Version is taken from the response status line for http/1 responses,
+    /// responses of http/2 connections are always `Version::HTTP_2`.
     #[inline]
     pub fn version(&self) -> Version {
         self.head().version
@@ -82,12 +165,130 @@ impl<S> ClientResponse<S> {
         self.head().status
     }
 
+    /// Check if server responded with `304 Not Modified`.
+    ///
+    /// Such response has empty body, cached copy of the resource
+    /// should be used instead. See `ClientRequest::conditional()`.
+    #[inline]
+    pub fn is_not_modified(&self) -> bool {
+        self.head().status == StatusCode::NOT_MODIFIED
+    }
+
+    /// Check if server responded with `206 Partial Content`.
+    ///
+    /// See `ClientRequest::range()`.
+    #[inline]
+    pub fn is_partial(&self) -> bool {
+        self.head().status == StatusCode::PARTIAL_CONTENT
+    }
+
+    /// Parse `Content-Range` header of the response.
+    ///
+    /// `multipart/byteranges` response does not have this header,
+    /// each part has its own range, see `ClientResponse::byte_ranges()`.
+    pub fn content_range(&self) -> Option<ContentRange> {
+        self.headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(ContentRange::parse)
+    }
+
     #[inline]
     /// Returns request's headers.
     pub fn headers(&self) -> &HeaderMap {
         &self.head().headers
     }
 
+    #[inline]
+    /// Returns url of the final request.
+    ///
+    /// Url is normalized before sending, default port is stripped
+    /// and dot segments of the path are resolved. If redirects are
+    /// followed, it is the url of the last hop.
+    pub fn url(&self) -> &Uri {
+        &self.url
+    }
+
+    #[inline]
+    /// Returns remote address of the connection used for the request.
+    ///
+    /// Address is not available for connections opened by custom connectors.
+    pub fn peer_addr(&self) -> Option<net::SocketAddr> {
+        self.peer_addr
+    }
+
+    #[inline]
+    /// Returns tls session details of the connection used for the request.
+    ///
+    /// Returns `None` for plain http connections and for connections
+    /// opened by custom connectors.
+    pub fn tls_info(&self) -> Option<&TlsInfo> {
+        self.tls_info.as_ref()
+    }
+
+    #[inline]
+    /// Returns request headers as they were sent to the server.
+    ///
+    /// Headers include `Host`, `Content-Length` and other headers
+    /// added during sending. Headers are not available for responses
+    /// of custom connections.
+    pub fn request_headers(&self) -> Option<&HeaderMap> {
+        self.request_headers.as_ref()
+    }
+
+    #[inline]
+    /// Returns response headers in on-wire order and original case.
+    ///
+    /// Headers are captured only if `ClientBuilder::preserve_raw_headers()`
+    /// is enabled, duplicate headers are kept as separate entries.
+    pub fn raw_headers(&self) -> Option<&[(Bytes, Bytes)]> {
+        self.raw_headers.as_deref()
+    }
+
+    /// Returns trailer fields of the response.
+    ///
+    /// Trailers are available only after response payload is read,
+    /// until then `None` is returned. Trailers are supported for
+    /// http/1 responses only.
+    pub fn trailers(&self) -> Option<HeaderMap> {
+        self.head
+            .extensions()
+            .get::<Trailers>()
+            .and_then(|trailers| trailers.get())
+    }
+
+    /// Returns number of request bytes written to the connection.
+    ///
+    /// For http/1 connections all bytes of request head and body are
+    /// counted, including chunked encoding framing. For http/2 connections
+    /// only body data is counted. Tls overhead is not included, zero is
+    /// returned for responses of custom connections.
+    pub fn request_bytes_sent(&self) -> u64 {
+        self.head
+            .extensions()
+            .get::<ByteCounters>()
+            .map(|counters| counters.bytes_sent())
+            .unwrap_or(0)
+    }
+
+    /// Returns number of response bytes read from the connection.
+    ///
+    /// Bytes are counted before decompression, value grows while response
+    /// payload is read. Same rules as for `request_bytes_sent()` apply.
+    pub fn response_bytes_received(&self) -> u64 {
+        self.head
+            .extensions()
+            .get::<ByteCounters>()
+            .map(|counters| counters.bytes_received())
+            .unwrap_or(0)
+    }
+
+    #[inline]
+    /// Returns request timings.
+    pub fn timings(&self) -> &Timings {
+        &self.timings
+    }
+
     /// Set a body and return previous body value
     pub fn map_body<F, U>(mut self, f: F) -> ClientResponse<U>
     where
@@ -98,6 +299,12 @@ impl<S> ClientResponse<S> {
         ClientResponse {
             payload,
             head: self.head,
+            url: self.url,
+            timings: self.timings,
+            peer_addr: self.peer_addr,
+            tls_info: self.tls_info,
+            request_headers: self.request_headers,
+            raw_headers: self.raw_headers,
         }
     }
 
@@ -106,6 +313,11 @@ impl<S> ClientResponse<S> {
         std::mem::replace(&mut self.payload, Payload::None)
     }
 
+    /// Consume response and return its payload
+    pub fn into_body(self) -> Payload<S> {
+        self.payload
+    }
+
     /// Request extensions
     #[inline]
     pub fn extensions(&self) -> Ref<'_, Extensions> {
@@ -128,6 +340,32 @@ where
         MessageBody::new(self)
     }
 
+    /// Loads http response's body and decodes it to a `String`.
+    ///
+    /// Body is decoded according to `charset` parameter of `Content-Type`
+    /// header, UTF-8 is used if charset is not set. Invalid byte sequences
+    /// fail with `TextPayloadError::Decode` error, unless `TextBody::lossy()`
+    /// is used.
+    ///
+    /// Returns error:
+    ///
+    /// * charset is not supported
+    /// * content length is greater than 256k or `max_response_size`
+    ///
+    /// ```rust,no_run
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let mut res = Client::new().get("http://www.rust-lang.org").send().await.unwrap();
+    ///     let text = res.text().lossy().await.unwrap();
+    ///     println!("{}", text);
+    /// }
+    /// ```
+    pub fn text(&mut self) -> TextBody<S> {
+        TextBody::new(self)
+    }
+
     /// Loads and parse `application/json` encoded body.
     /// Return `JsonBody<T>` future. It resolves to a `T` value.
     ///
@@ -138,6 +376,172 @@ where
     pub fn json<T: DeserializeOwned>(&mut self) -> JsonBody<S, T> {
         JsonBody::new(self)
     }
+
+    /// Loads and deserialize body according to response content type.
+    /// Return `AutoBody<T>` future. It resolves to a `T` value.
+    ///
+    /// Json, urlencoded and, with `msgpack` feature, MessagePack bodies
+    /// are supported. Body without `Content-Type` header is deserialized
+    /// as json, see `AutoBody::default_content_type()`.
+    ///
+    /// Returns error:
+    ///
+    /// * content type is not supported
+    /// * content length is greater than 64k
+    pub fn auto_deserialize<T: DeserializeOwned>(&mut self) -> AutoBody<S, T> {
+        AutoBody::new(self)
+    }
+
+    /// Parse newline delimited json body.
+    /// Return `JsonStream<T>` stream. It yields a `T` value for each line.
+    ///
+    /// Deserialization errors are returned per line, stream continues
+    /// with the next line. Max size of a line is 64k.
+    pub fn json_stream<T: DeserializeOwned>(&mut self) -> JsonStream<S, T> {
+        JsonStream::new(self)
+    }
+
+    /// Read parts of partial content response.
+    /// Return `ByteRanges` stream. It yields a part with its byte range
+    /// for each range of the response.
+    ///
+    /// `multipart/byteranges` body is split to parts using boundary
+    /// of `Content-Type` header, otherwise the body is a single part
+    /// described by `Content-Range` header. Max size of a part is 256k.
+    pub fn byte_ranges(&mut self) -> ByteRanges<S> {
+        ByteRanges::new(self)
+    }
+
+    /// Read parts of `multipart/*` response.
+    /// Return `MultipartStream` stream. It yields a part with its headers
+    /// for each part of the body, part is a stream of body chunks.
+    ///
+    /// Body is split to parts using boundary of `Content-Type` header,
+    /// parts are not buffered.
+    ///
+    /// ```rust,no_run
+    /// use futures::StreamExt;
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let mut res = Client::new()
+    ///         .get("http://www.rust-lang.org")
+    ///         .send()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let mut parts = res.multipart();
+    ///     while let Some(Ok(mut part)) = parts.next().await {
+    ///         println!("part: {:?}", part.mime_type());
+    ///         while let Some(Ok(chunk)) = part.next().await {
+    ///             println!("chunk: {:?}", chunk);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn multipart(&mut self) -> MultipartStream<Payload<S>> {
+        MultipartStream::new(self)
+    }
+}
+
+impl<S> ClientResponse<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+{
+    /// Save response payload to a file.
+    /// Return `SaveTo` future. It resolves to the number of written bytes.
+    ///
+    /// Payload is streamed to a temporary file which replaces file at
+    /// `path` once payload is complete, partial file is removed on error.
+    /// Payload size is limited by `max_response_size` setting.
+    ///
+    /// ```rust,no_run
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let mut res = Client::new()
+    ///         .get("https://www.rust-lang.org/logos/rust-logo-512x512.png")
+    ///         .send()
+    ///         .await
+    ///         .unwrap();
+    ///     let size = res.save_to("/tmp/rust-logo.png").await.unwrap();
+    ///     println!("Saved {} bytes", size);
+    /// }
+    /// ```
+    pub fn save_to<P: AsRef<Path>>(&mut self, path: P) -> SaveTo<S> {
+        SaveTo::new(self, path)
+    }
+}
+
+impl<S> ClientResponse<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    /// Turn error status into an error.
+    ///
+    /// Response with success status is returned untouched. Otherwise
+    /// response body is deserialized to json error `E` and returned as
+    /// `SendRequestError::Status` error, if body could not be read or
+    /// parsed `SendRequestError::UnexpectedStatus` error is returned.
+    /// Max size of error body is 64k.
+    ///
+    /// ```rust,no_run
+    /// use ntex::http::client::{error::SendRequestError, Client};
+    ///
+    /// #[derive(Debug, serde::Deserialize)]
+    /// struct ApiError {
+    ///     message: String,
+    /// }
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let res = Client::new()
+    ///         .get("http://www.rust-lang.org")
+    ///         .send()
+    ///         .await
+    ///         .unwrap()
+    ///         .error_for_status::<ApiError>()
+    ///         .await;
+    ///
+    ///     if let Err(SendRequestError::Status { body, .. }) = res {
+    ///         let err = body.downcast::<ApiError>().unwrap();
+    ///         println!("Api error: {}", err.message);
+    ///     }
+    /// }
+    /// ```
+    pub async fn error_for_status<E>(mut self) -> Result<Self, SendRequestError>
+    where
+        E: DeserializeOwned + fmt::Debug + 'static,
+    {
+        let status = self.status();
+        if status.is_success() {
+            return Ok(self);
+        }
+
+        let body = match self.body().limit(65536).await {
+            Ok(body) => body,
+            Err(e) => {
+                return Err(SendRequestError::UnexpectedStatus {
+                    status,
+                    body: Bytes::new(),
+                    error: JsonPayloadError::Payload(e),
+                })
+            }
+        };
+        match serde_json::from_slice::<E>(&body) {
+            Ok(err) => Err(SendRequestError::Status {
+                status,
+                body: ErrorBody::new(err),
+            }),
+            Err(e) => Err(SendRequestError::UnexpectedStatus {
+                status,
+                body,
+                error: JsonPayloadError::Deserialize(e),
+            }),
+        }
+    }
 }
 
 impl<S> Stream for ClientResponse<S>
@@ -179,7 +583,7 @@ where
     /// Create `MessageBody` for request.
     pub fn new(res: &mut ClientResponse<S>) -> MessageBody<S> {
         let mut len = None;
-        if let Some(l) = res.headers().get(&CONTENT_LENGTH) {
+        if let Some(l) = res.content_length() {
             if let Ok(s) = l.to_str() {
                 if let Ok(l) = s.parse::<usize>() {
                     len = Some(l)
@@ -238,6 +642,89 @@ where
     }
 }
 
+/// Future that resolves to a response body decoded to a `String`.
+pub struct TextBody<S> {
+    length: Option<usize>,
+    encoding: Result<&'static Encoding, String>,
+    lossy: bool,
+    fut: ReadBody<S>,
+}
+
+impl<S> TextBody<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    /// Create `TextBody` for response.
+    pub fn new(res: &mut ClientResponse<S>) -> Self {
+        let encoding = match res.mime_type() {
+            Ok(Some(mime)) => match mime.get_param(mime::CHARSET) {
+                Some(charset) => {
+                    Encoding::for_label_no_replacement(charset.as_str().as_bytes())
+                        .ok_or_else(|| charset.to_string())
+                }
+                None => Ok(UTF_8),
+            },
+            _ => Ok(UTF_8),
+        };
+        let length = res
+            .content_length()
+            .and_then(|l| l.to_str().ok())
+            .and_then(|s| s.parse::<usize>().ok());
+
+        TextBody {
+            length,
+            encoding,
+            lossy: false,
+            fut: ReadBody::new(res.take_payload(), 262_144),
+        }
+    }
+
+    /// Change max size of payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.fut.limit = limit;
+        self
+    }
+
+    /// Replace invalid byte sequences with `U+FFFD` instead of failing.
+    pub fn lossy(mut self) -> Self {
+        self.lossy = true;
+        self
+    }
+}
+
+impl<S> Future for TextBody<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Output = Result<String, TextPayloadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let encoding = match this.encoding {
+            Ok(encoding) => encoding,
+            Err(ref charset) => {
+                return Poll::Ready(Err(TextPayloadError::Charset(charset.clone())))
+            }
+        };
+        if let Some(len) = this.length.take() {
+            if len > this.fut.limit {
+                return Poll::Ready(Err(PayloadError::Overflow.into()));
+            }
+        }
+
+        let body = ready!(Pin::new(&mut this.fut).poll(cx))?;
+        Poll::Ready(if this.lossy {
+            Ok(encoding.decode_without_bom_handling(&body).0.into_owned())
+        } else {
+            encoding
+                .decode_without_bom_handling_and_without_replacement(&body)
+                .map(|text| text.into_owned())
+                .ok_or_else(|| TextPayloadError::Decode(encoding.name()))
+        })
+    }
+}
+
 /// Response's payload json parser, it resolves to a deserialized `T` value.
 ///
 /// Returns error:
@@ -274,7 +761,7 @@ where
         }
 
         let mut len = None;
-        if let Some(l) = req.headers().get(&CONTENT_LENGTH) {
+        if let Some(l) = req.content_length() {
             if let Ok(s) = l.to_str() {
                 if let Ok(l) = s.parse::<usize>() {
                     len = Some(l)
@@ -331,6 +818,241 @@ where
     }
 }
 
+/// Body format, detected from content type
+#[derive(Copy, Clone, Debug)]
+enum Format {
+    Json,
+    Form,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+}
+
+impl Format {
+    fn of(mime: &mime::Mime) -> Option<Format> {
+        if mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON) {
+            Some(Format::Json)
+        } else if mime.essence_str() == mime::APPLICATION_WWW_FORM_URLENCODED {
+            Some(Format::Form)
+        } else {
+            #[cfg(feature = "msgpack")]
+            {
+                if mime.subtype() == "msgpack" || mime.subtype() == "x-msgpack" {
+                    return Some(Format::MsgPack);
+                }
+            }
+            None
+        }
+    }
+}
+
+/// Response body deserialized according to response content type.
+pub struct AutoBody<S, U> {
+    content_type: Result<Option<mime::Mime>, String>,
+    default: mime::Mime,
+    length: Option<usize>,
+    fut: ReadBody<S>,
+    _t: PhantomData<U>,
+}
+
+impl<S, U> AutoBody<S, U>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+    U: DeserializeOwned,
+{
+    /// Create `AutoBody` for response.
+    pub fn new(res: &mut ClientResponse<S>) -> Self {
+        let content_type = res.mime_type().map_err(|_| {
+            res.headers()
+                .get(CONTENT_TYPE)
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+                .unwrap_or_default()
+        });
+        let length = res
+            .content_length()
+            .and_then(|l| l.to_str().ok())
+            .and_then(|s| s.parse::<usize>().ok());
+
+        AutoBody {
+            content_type,
+            default: mime::APPLICATION_JSON,
+            length,
+            fut: ReadBody::new(res.take_payload(), 65536),
+            _t: PhantomData,
+        }
+    }
+
+    /// Content type of response without `Content-Type` header.
+    /// By default body is deserialized as json.
+    pub fn default_content_type(mut self, content_type: mime::Mime) -> Self {
+        self.default = content_type;
+        self
+    }
+
+    /// Change max size of payload. By default max size is 64Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.fut.limit = limit;
+        self
+    }
+
+    fn format(&self) -> Result<Format, DeserializeError> {
+        let content_type = match self.content_type {
+            Ok(Some(ref content_type)) => content_type,
+            Ok(None) => &self.default,
+            Err(ref content_type) => {
+                return Err(DeserializeError::ContentType(content_type.clone()))
+            }
+        };
+        Format::of(content_type)
+            .ok_or_else(|| DeserializeError::ContentType(content_type.to_string()))
+    }
+}
+
+impl<S, U> Unpin for AutoBody<S, U>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+    U: DeserializeOwned,
+{
+}
+
+impl<S, U> Future for AutoBody<S, U>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+    U: DeserializeOwned,
+{
+    type Output = Result<U, DeserializeError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let format = this.format()?;
+
+        if let Some(len) = this.length.take() {
+            if len > this.fut.limit {
+                return Poll::Ready(Err(PayloadError::Overflow.into()));
+            }
+        }
+
+        let body = ready!(Pin::new(&mut this.fut).poll(cx))?;
+        Poll::Ready(match format {
+            Format::Json => serde_json::from_slice::<U>(&body).map_err(Into::into),
+            Format::Form => serde_urlencoded::from_bytes::<U>(&body).map_err(Into::into),
+            #[cfg(feature = "msgpack")]
+            Format::MsgPack => {
+                rmp_serde::from_read_ref::<_, U>(&body).map_err(Into::into)
+            }
+        })
+    }
+}
+
+/// Newline delimited json stream.
+///
+/// Each line of the response body is deserialized to `U`,
+/// empty lines are skipped.
+pub struct JsonStream<S, U> {
+    stream: Option<Payload<S>>,
+    buf: BytesMut,
+    checked: usize,
+    limit: usize,
+    _t: PhantomData<U>,
+}
+
+impl<S, U> JsonStream<S, U>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+    U: DeserializeOwned,
+{
+    /// Create `JsonStream` for response.
+    pub fn new(res: &mut ClientResponse<S>) -> Self {
+        JsonStream {
+            stream: Some(res.take_payload()),
+            buf: BytesMut::new(),
+            checked: 0,
+            limit: 65536,
+            _t: PhantomData,
+        }
+    }
+
+    /// Change max size of a line. By default max size is 64Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn parse(line: &[u8]) -> Option<Result<U, JsonPayloadError>> {
+        let line = match line.last() {
+            Some(b'\r') => &line[..line.len() - 1],
+            _ => line,
+        };
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            None
+        } else {
+            Some(serde_json::from_slice::<U>(line).map_err(JsonPayloadError::from))
+        }
+    }
+}
+
+impl<S, U> Unpin for JsonStream<S, U>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+    U: DeserializeOwned,
+{
+}
+
+impl<S, U> Stream for JsonStream<S, U>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+    U: DeserializeOwned,
+{
+    type Item = Result<U, JsonPayloadError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            // check buffered data for complete line
+            if let Some(idx) = this.buf[this.checked..].iter().position(|b| *b == b'\n')
+            {
+                let line = this.buf.split_to(this.checked + idx + 1);
+                this.checked = 0;
+                if let Some(item) = Self::parse(&line[..line.len() - 1]) {
+                    return Poll::Ready(Some(item));
+                }
+                continue;
+            }
+            this.checked = this.buf.len();
+
+            let stream = if let Some(ref mut stream) = this.stream {
+                stream
+            } else {
+                return Poll::Ready(None);
+            };
+
+            if this.buf.len() > this.limit {
+                this.stream = None;
+                return Poll::Ready(Some(Err(PayloadError::Overflow.into())));
+            }
+
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.buf.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(err))) => {
+                    this.stream = None;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                Poll::Ready(None) => {
+                    // last line without newline
+                    this.stream = None;
+                    let line = this.buf.split();
+                    this.checked = 0;
+                    return Poll::Ready(Self::parse(&line));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
 struct ReadBody<S> {
     stream: Payload<S>,
     buf: BytesMut,
@@ -410,6 +1132,61 @@ mod tests {
         }
     }
 
+    #[ntex_rt::test]
+    async fn test_text_body() {
+        let mut res = TestResponse::default()
+            .set_payload(Bytes::from_static("héllo".as_bytes()))
+            .finish();
+        assert_eq!(res.text().await.unwrap(), "héllo");
+
+        let mut res = TestResponse::with_header(
+            header::CONTENT_TYPE,
+            "text/plain; charset=ISO-8859-1",
+        )
+        .set_payload(Bytes::from_static(b"h\xe9llo"))
+        .finish();
+        assert_eq!(res.text().await.unwrap(), "héllo");
+
+        let mut res = TestResponse::with_header(header::CONTENT_TYPE, "text/plain")
+            .set_payload(Bytes::from_static(b"h\xe9llo"))
+            .finish();
+        assert!(matches!(
+            res.text().await,
+            Err(TextPayloadError::Decode("UTF-8"))
+        ));
+
+        let mut res = TestResponse::default()
+            .set_payload(Bytes::from_static(b"h\xe9llo"))
+            .finish();
+        assert_eq!(res.text().lossy().await.unwrap(), "h\u{FFFD}llo");
+
+        let mut res = TestResponse::with_header(
+            header::CONTENT_TYPE,
+            "text/plain; charset=unknown",
+        )
+        .set_payload(Bytes::from_static(b"hello"))
+        .finish();
+        assert!(matches!(
+            res.text().await,
+            Err(TextPayloadError::Charset(ref charset)) if charset == "unknown"
+        ));
+
+        let mut res =
+            TestResponse::with_header(header::CONTENT_LENGTH, "1000000").finish();
+        assert!(matches!(
+            res.text().await,
+            Err(TextPayloadError::Payload(PayloadError::Overflow))
+        ));
+
+        let mut res = TestResponse::default()
+            .set_payload(Bytes::from_static(b"11111111111111"))
+            .finish();
+        assert!(matches!(
+            res.text().limit(5).await,
+            Err(TextPayloadError::Payload(PayloadError::Overflow))
+        ));
+    }
+
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
     struct MyObject {
         name: String,
@@ -481,4 +1258,144 @@ mod tests {
             }
         );
     }
+
+    #[ntex_rt::test]
+    async fn test_auto_deserialize() {
+        let body = |ctype: Option<&'static str>, body: &'static [u8]| {
+            let res = match ctype {
+                Some(ctype) => TestResponse::with_header(header::CONTENT_TYPE, ctype),
+                None => TestResponse::default(),
+            };
+            res.set_payload(Bytes::from_static(body)).finish()
+        };
+        let test = MyObject {
+            name: "test".to_owned(),
+        };
+
+        let mut res = body(Some("application/json"), b"{\"name\": \"test\"}");
+        assert_eq!(res.auto_deserialize::<MyObject>().await.unwrap(), test);
+        let mut res = body(Some("application/problem+json"), b"{\"name\": \"test\"}");
+        assert_eq!(res.auto_deserialize::<MyObject>().await.unwrap(), test);
+        let mut res = body(Some("application/x-www-form-urlencoded"), b"name=test");
+        assert_eq!(res.auto_deserialize::<MyObject>().await.unwrap(), test);
+
+        // missing content type
+        let mut res = body(None, b"{\"name\": \"test\"}");
+        assert_eq!(res.auto_deserialize::<MyObject>().await.unwrap(), test);
+        let mut res = body(None, b"name=test");
+        let obj = res
+            .auto_deserialize::<MyObject>()
+            .default_content_type(mime::APPLICATION_WWW_FORM_URLENCODED)
+            .await;
+        assert_eq!(obj.unwrap(), test);
+
+        let mut res = body(Some("text/html"), b"<html></html>");
+        match res.auto_deserialize::<MyObject>().await {
+            Err(DeserializeError::ContentType(ctype)) => assert_eq!(ctype, "text/html"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+        let mut res = body(Some("application/json"), b"name=test");
+        let obj = res.auto_deserialize::<MyObject>().await;
+        assert!(matches!(obj, Err(DeserializeError::Json(_))));
+        let mut res = body(Some("application/json"), b"{\"name\": \"test\"}");
+        let obj = res.auto_deserialize::<MyObject>().limit(5).await;
+        assert!(matches!(
+            obj,
+            Err(DeserializeError::Payload(PayloadError::Overflow))
+        ));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[ntex_rt::test]
+    async fn test_auto_deserialize_msgpack() {
+        let data = rmp_serde::to_vec(&MyObject {
+            name: "test".to_owned(),
+        })
+        .unwrap();
+        let mut res =
+            TestResponse::with_header(header::CONTENT_TYPE, "application/msgpack")
+                .set_payload(Bytes::from(data))
+                .finish();
+        let obj = res.auto_deserialize::<MyObject>().await.unwrap();
+        assert_eq!(obj.name, "test");
+    }
+
+    #[ntex_rt::test]
+    async fn test_error_for_status() {
+        let res = TestResponse::default()
+            .set_payload(Bytes::from_static(b"data"))
+            .finish();
+        let mut res = res.error_for_status::<MyObject>().await.unwrap();
+        assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"data"));
+
+        let res = TestResponse::default()
+            .status(StatusCode::BAD_REQUEST)
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .finish();
+        let err = res.error_for_status::<MyObject>().await.err().unwrap();
+        assert_eq!(err.status(), Some(StatusCode::BAD_REQUEST));
+        match err {
+            SendRequestError::Status { body, .. } => {
+                assert!(body.downcast_ref::<String>().is_none());
+                let body = body.downcast::<MyObject>().unwrap();
+                assert_eq!(body.name, "test");
+            }
+            _ => panic!(),
+        }
+
+        let res = TestResponse::default()
+            .status(StatusCode::BAD_GATEWAY)
+            .set_payload(Bytes::from_static(b"<html></html>"))
+            .finish();
+        let err = res.error_for_status::<MyObject>().await.err().unwrap();
+        match err {
+            SendRequestError::UnexpectedStatus {
+                status,
+                body,
+                error: JsonPayloadError::Deserialize(_),
+            } => {
+                assert_eq!(status, StatusCode::BAD_GATEWAY);
+                assert_eq!(body, Bytes::from_static(b"<html></html>"));
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[ntex_rt::test]
+    async fn test_json_stream() {
+        use futures::StreamExt;
+
+        let chunks = vec![
+            Ok(Bytes::from_static(b"{\"name\": \"a\"}\n{\"na")),
+            Ok(Bytes::from_static(b"me\": \"b\"}\r\n\r\n")),
+            Ok(Bytes::from_static(b"{\"name\": 1}\n")),
+            Ok(Bytes::from_static(b"{\"name\": \"c\"}")),
+        ];
+        let mut res = ClientResponse::new(
+            ResponseHead::new(StatusCode::OK),
+            Payload::Stream(futures::stream::iter(chunks)),
+        );
+        let items: Vec<_> = res.json_stream::<MyObject>().collect().await;
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[0].as_ref().unwrap().name, "a");
+        assert_eq!(items[1].as_ref().unwrap().name, "b");
+        assert!(matches!(items[2], Err(JsonPayloadError::Deserialize(_))));
+        assert_eq!(items[3].as_ref().unwrap().name, "c");
+
+        let mut res = TestResponse::default()
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}\n"))
+            .finish();
+        let items: Vec<_> = res.json_stream::<MyObject>().collect().await;
+        assert_eq!(items.len(), 1);
+
+        let mut res = TestResponse::default()
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .finish();
+        let items: Vec<_> = res.json_stream::<MyObject>().limit(5).collect().await;
+        assert_eq!(items.len(), 1);
+        assert!(json_eq(
+            items.into_iter().next().unwrap().err().unwrap(),
+            JsonPayloadError::Payload(PayloadError::Overflow)
+        ));
+    }
 }
@@ -0,0 +1,427 @@
+//! Http client retry policy
+use std::convert::TryFrom;
+use std::rc::Rc;
+use std::time::Duration;
+use std::{cmp, fmt, net};
+
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+use crate::http::body::Body;
+use crate::http::header::RETRY_AFTER;
+use crate::http::{RequestHeadType, StatusCode};
+use crate::rt::time::{delay_for, timeout};
+
+use super::backoff::Backoff;
+use super::error::SendRequestError;
+use super::response::ClientResponse;
+use super::sender::RequestPhase;
+use super::ClientConfig;
+
+/// Retry policy for failed requests.
+///
+/// Policy defines how many times request could be sent, which errors
+/// are considered transient and how long to wait between attempts.
+/// Only requests with replayable bodies (empty or `Body::Bytes`) get
+/// retried, streaming bodies are sent once.
+///
+/// If `429 Too Many Requests` or `503 Service Unavailable` response contains
+/// `Retry-After` header, its value is used as a delay before next attempt.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use ntex::http::client::{Client, RetryPolicy};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .retry(
+///             RetryPolicy::new(3)
+///                 .exponential_backoff(Duration::from_millis(50), Duration::from_secs(2))
+///         )
+///         .finish();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    backoff: Backoff,
+    server_errors: bool,
+    timeout: Option<Duration>,
+    max_retry_after: Duration,
+    retry_if: Option<Rc<dyn Fn(&SendRequestError) -> bool>>,
+}
+
+impl RetryPolicy {
+    /// Create new retry policy.
+    ///
+    /// `max_attempts` is total number of attempts, including the first one.
+    /// By default policy uses fixed 100 milliseconds backoff without jitter
+    /// and retries connection errors, io errors, timeouts, `5xx` responses and
+    /// `429` responses with `Retry-After` header.
+    pub fn new(max_attempts: usize) -> Self {
+        RetryPolicy {
+            max_attempts: cmp::max(max_attempts, 1),
+            backoff: Backoff::fixed(Duration::from_millis(100)),
+            server_errors: true,
+            timeout: None,
+            max_retry_after: Duration::from_secs(60),
+            retry_if: None,
+        }
+    }
+
+    /// Wait fixed period of time between attempts.
+    pub fn fixed_backoff(mut self, delay: Duration) -> Self {
+        self.backoff = Backoff::fixed(delay).jitter(self.backoff.has_jitter());
+        self
+    }
+
+    /// Double delay after each failed attempt, starting from `base`
+    /// and never exceeding `max`.
+    pub fn exponential_backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.backoff = Backoff::exponential(base, max).jitter(self.backoff.has_jitter());
+        self
+    }
+
+    /// Randomize backoff delay.
+    ///
+    /// With jitter enabled actual delay is chosen randomly between
+    /// half of computed delay and computed delay.
+    pub fn jitter(mut self, enabled: bool) -> Self {
+        self.backoff = self.backoff.jitter(enabled);
+        self
+    }
+
+    /// Use custom backoff between attempts.
+    ///
+    /// Replaces backoff set by `fixed_backoff()` or `exponential_backoff()`,
+    /// including jitter setting.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Retry requests that resolved to `5xx` responses.
+    ///
+    /// Enabled by default.
+    pub fn retry_server_errors(mut self, enabled: bool) -> Self {
+        self.server_errors = enabled;
+        self
+    }
+
+    /// Set timeout for each individual attempt.
+    ///
+    /// Attempt that exceeds this timeout fails with `SendRequestError::Timeout`
+    /// and could be retried. Request timeout still limits total time
+    /// of all attempts.
+    pub fn attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set max delay that could be requested by `Retry-After` header.
+    ///
+    /// Longer delays are capped to this value. Default value is 60 seconds.
+    pub fn max_retry_after(mut self, max: Duration) -> Self {
+        self.max_retry_after = max;
+        self
+    }
+
+    /// Use custom predicate to decide which errors are retryable.
+    pub fn retry_if<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&SendRequestError) -> bool + 'static,
+    {
+        self.retry_if = Some(Rc::new(f));
+        self
+    }
+
+    /// Max number of attempts
+    pub fn max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// Calculate delay before next attempt, `attempt` starts from 1
+    pub(super) fn delay(&self, attempt: usize) -> Duration {
+        self.backoff.delay(attempt)
+    }
+
+    /// Delay requested by server with `Retry-After` header
+    pub(super) fn retry_after(&self, res: &ClientResponse) -> Option<Duration> {
+        match res.status() {
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+                let value = res.headers().get(RETRY_AFTER)?.to_str().ok()?;
+                parse_retry_after(value, OffsetDateTime::now_utc())
+                    .map(|delay| cmp::min(delay, self.max_retry_after))
+            }
+            _ => None,
+        }
+    }
+
+    /// Check if request should be sent again
+    pub(super) fn is_retryable(
+        &self,
+        res: &Result<ClientResponse, SendRequestError>,
+    ) -> bool {
+        match res {
+            Ok(res) => {
+                (self.server_errors && res.status().is_server_error())
+                    || (res.status() == StatusCode::TOO_MANY_REQUESTS
+                        && res.headers().contains_key(RETRY_AFTER))
+            }
+            Err(e) => {
+                if let Some(ref f) = self.retry_if {
+                    f(e)
+                } else {
+                    matches!(
+                        e,
+                        SendRequestError::Connect(_)
+                            | SendRequestError::Send(_)
+                            | SendRequestError::Response(_)
+                            | SendRequestError::H2(_)
+                            | SendRequestError::Timeout { .. }
+                            | SendRequestError::ConnectTimeout
+                            | SendRequestError::DnsTimeout { .. }
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .field("server_errors", &self.server_errors)
+            .field("timeout", &self.timeout)
+            .field("max_retry_after", &self.max_retry_after)
+            .finish()
+    }
+}
+
+/// Parse `Retry-After` value, either delay in seconds or http date
+fn parse_retry_after(value: &str, now: OffsetDateTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = PrimitiveDateTime::parse(value, "%a, %d %b %Y %H:%M:%S GMT")
+        .ok()?
+        .assume_utc();
+    // date in the past means no delay
+    Some(Duration::try_from(date - now).unwrap_or_else(|_| Duration::from_secs(0)))
+}
+
+/// Copy body if it could be sent more than once
+pub(super) fn replay(body: &Body) -> Option<Body> {
+    match body {
+        Body::None => Some(Body::None),
+        Body::Empty => Some(Body::Empty),
+        Body::Bytes(ref b) => Some(Body::Bytes(b.clone())),
+        Body::Message(_) => None,
+    }
+}
+
+/// `Idempotency-Key` header name
+pub(super) const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Generate random idempotency key, formatted as UUID v4
+pub(super) fn idempotency_key() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let mut key = String::with_capacity(36);
+    for (idx, b) in bytes.iter().enumerate() {
+        if idx == 4 || idx == 6 || idx == 8 || idx == 10 {
+            key.push('-');
+        }
+        key.push_str(&format!("{:02x}", b));
+    }
+    key
+}
+
+/// Send request and re-send it according to retry policy
+pub(super) async fn send(
+    config: Rc<ClientConfig>,
+    head: RequestHeadType,
+    body: Body,
+    addr: Option<net::SocketAddr>,
+    policy: RetryPolicy,
+    phase: RequestPhase,
+) -> Result<ClientResponse, SendRequestError> {
+    let (head, extra_headers) = match head {
+        RequestHeadType::Owned(head) => (Rc::new(head), None),
+        RequestHeadType::Rc(head, extra_headers) => (head, extra_headers),
+    };
+
+    let mut attempt = 1;
+    loop {
+        let fut = config.connector.send_request(
+            RequestHeadType::Rc(head.clone(), extra_headers.clone()),
+            replay(&body).unwrap(),
+            addr,
+            &config,
+            phase.clone(),
+        );
+        let res = if let Some(t) = policy.timeout {
+            match timeout(t, fut).await {
+                Ok(res) => res,
+                Err(_) => Err(phase.timeout()),
+            }
+        } else {
+            fut.await
+        };
+
+        if attempt >= policy.max_attempts || !policy.is_retryable(&res) {
+            return res;
+        }
+        trace!("Retrying request to {:?}, attempt {}", head.uri, attempt);
+
+        let delay = match res {
+            Ok(ref res) => policy.retry_after(res),
+            Err(_) => None,
+        };
+        drop(res);
+        delay_for(delay.unwrap_or_else(|| policy.delay(attempt))).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::error::{ConnectError, TimeoutPhase};
+    use crate::http::client::test::TestResponse;
+
+    #[test]
+    fn test_idempotency_key() {
+        let key = idempotency_key();
+        assert_eq!(key.len(), 36);
+        assert_eq!(key.as_bytes()[14], b'4');
+        assert!(matches!(key.as_bytes()[19], b'8' | b'9' | b'a' | b'b'));
+        assert_eq!(
+            key.split('-').map(|s| s.len()).collect::<Vec<_>>(),
+            vec![8, 4, 4, 4, 12]
+        );
+        assert_ne!(key, idempotency_key());
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new(5).fixed_backoff(Duration::from_millis(10));
+        assert_eq!(policy.delay(1), Duration::from_millis(10));
+        assert_eq!(policy.delay(4), Duration::from_millis(10));
+
+        let policy = RetryPolicy::new(5)
+            .exponential_backoff(Duration::from_millis(10), Duration::from_millis(50));
+        assert_eq!(policy.delay(1), Duration::from_millis(10));
+        assert_eq!(policy.delay(2), Duration::from_millis(20));
+        assert_eq!(policy.delay(3), Duration::from_millis(40));
+        assert_eq!(policy.delay(4), Duration::from_millis(50));
+        assert_eq!(policy.delay(100), Duration::from_millis(50));
+
+        let policy = RetryPolicy::new(5)
+            .fixed_backoff(Duration::from_millis(100))
+            .jitter(true);
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(50));
+            assert!(delay <= Duration::from_millis(100));
+        }
+
+        let policy = RetryPolicy::new(5).backoff(
+            Backoff::exponential(Duration::from_millis(10), Duration::from_secs(1))
+                .factor(3.0),
+        );
+        assert_eq!(policy.delay(3), Duration::from_millis(90));
+        assert_eq!(RetryPolicy::new(0).max_attempts(), 1);
+    }
+
+    #[test]
+    fn test_retryable() {
+        let timeout = || {
+            Err(SendRequestError::Timeout {
+                phase: TimeoutPhase::Response,
+            })
+        };
+        let policy = RetryPolicy::new(3);
+        assert!(policy.is_retryable(&timeout()));
+        assert!(policy.is_retryable(&Err(ConnectError::Disconnected.into())));
+        assert!(!policy.is_retryable(&Err(SendRequestError::TunnelNotSupported)));
+        assert!(!policy.is_retryable(&Ok(TestResponse::default().finish())));
+        let res = TestResponse::default()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .finish();
+        assert!(policy.is_retryable(&Ok(res)));
+
+        let policy = RetryPolicy::new(3)
+            .retry_server_errors(false)
+            .retry_if(|e| matches!(e, SendRequestError::Timeout { .. }));
+        assert!(policy.is_retryable(&timeout()));
+        assert!(!policy.is_retryable(&Err(ConnectError::Disconnected.into())));
+        let res = TestResponse::default()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .finish();
+        assert!(!policy.is_retryable(&Ok(res)));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = OffsetDateTime::from_unix_timestamp(1_600_000_000);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after(
+                &(now + Duration::from_secs(30)).format("%a, %d %b %Y %H:%M:%S GMT"),
+                now
+            ),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Mon, 01 Jan 2001 00:00:00 GMT", now),
+            Some(Duration::from_secs(0))
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+
+        let policy = RetryPolicy::new(3).max_retry_after(Duration::from_secs(10));
+        let res = TestResponse::default()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header(RETRY_AFTER, "3600")
+            .finish();
+        assert!(policy.is_retryable(&Ok(res)));
+        let res = TestResponse::default()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header(RETRY_AFTER, "3600")
+            .finish();
+        assert_eq!(policy.retry_after(&res), Some(Duration::from_secs(10)));
+        let res = TestResponse::default()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header(RETRY_AFTER, "1")
+            .finish();
+        assert_eq!(policy.retry_after(&res), Some(Duration::from_secs(1)));
+        let res = TestResponse::default()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .finish();
+        assert!(!policy.is_retryable(&Ok(res)));
+        let res = TestResponse::default()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header(RETRY_AFTER, "1")
+            .finish();
+        assert_eq!(policy.retry_after(&res), None);
+    }
+
+    #[test]
+    fn test_replay() {
+        assert!(replay(&Body::Empty).is_some());
+        assert_eq!(replay(&Body::from("test")).unwrap(), Body::from("test"));
+        let body = Body::from_message(crate::http::body::BodyStream::new(
+            futures::stream::empty::<Result<bytes::Bytes, std::io::Error>>(),
+        ));
+        assert!(replay(&body).is_none());
+    }
+}
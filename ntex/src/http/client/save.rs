@@ -0,0 +1,290 @@
+//! Saving http client response payload to a file
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use actix_threadpool::{run, BlockingError};
+use bytes::Bytes;
+#[cfg(feature = "client-digest")]
+use digest::DynDigest;
+use futures::future::LocalBoxFuture;
+use futures::{FutureExt, Stream, StreamExt};
+
+use crate::http::error::PayloadError;
+#[cfg(feature = "client-digest")]
+use crate::http::header::HeaderValue;
+use crate::http::Payload;
+
+use super::error::SaveError;
+use super::ClientResponse;
+
+#[cfg(feature = "client-digest")]
+const CONTENT_MD5: &str = "content-md5";
+
+/// Payload hasher and expected digest
+#[cfg(feature = "client-digest")]
+type PayloadDigest = (Box<dyn DynDigest>, Vec<u8>);
+
+/// Future that saves response payload to a file, it resolves
+/// to the number of written bytes.
+///
+/// Payload is written to a temporary file in destination directory,
+/// temporary file is renamed to destination path once payload is
+/// complete and verified. On error temporary file is removed.
+pub struct SaveTo<S> {
+    path: PathBuf,
+    payload: Option<Payload<S>>,
+    #[cfg(feature = "client-digest")]
+    content_md5: Option<HeaderValue>,
+    #[cfg(feature = "client-digest")]
+    digest: Option<PayloadDigest>,
+    err: Option<SaveError>,
+    fut: Option<LocalBoxFuture<'static, Result<u64, SaveError>>>,
+}
+
+impl<S> SaveTo<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+{
+    /// Create `SaveTo` for response.
+    pub fn new<P: AsRef<Path>>(res: &mut ClientResponse<S>, path: P) -> Self {
+        SaveTo {
+            path: path.as_ref().to_path_buf(),
+            payload: Some(res.take_payload()),
+            #[cfg(feature = "client-digest")]
+            content_md5: res.headers().get(CONTENT_MD5).cloned(),
+            #[cfg(feature = "client-digest")]
+            digest: None,
+            err: None,
+            fut: None,
+        }
+    }
+
+    #[cfg(feature = "client-digest")]
+    /// Verify payload digest
+    ///
+    /// Digest `D` is computed over written payload and compared with
+    /// `expected` value, any `digest` crate hasher could be used,
+    /// i.e. `sha2::Sha256`. Enabled by `client-digest` feature.
+    pub fn digest<D, T>(mut self, expected: T) -> Self
+    where
+        D: DynDigest + Default + 'static,
+        T: Into<Vec<u8>>,
+    {
+        self.digest = Some((Box::new(D::default()), expected.into()));
+        self
+    }
+
+    #[cfg(feature = "client-digest")]
+    /// Verify payload digest with value of `Content-MD5` response header
+    ///
+    /// Md5 hasher is provided by `D`, i.e. `md5::Md5`. Header value is
+    /// digest of response body, it does not match decompressed payload.
+    /// Enabled by `client-digest` feature.
+    pub fn content_md5<D>(mut self) -> Self
+    where
+        D: DynDigest + Default + 'static,
+    {
+        let expected = self
+            .content_md5
+            .as_ref()
+            .and_then(|value| base64::decode(value.as_bytes()).ok());
+        if let Some(expected) = expected {
+            self.digest::<D, _>(expected)
+        } else {
+            self.err = Some(SaveError::ContentMd5);
+            self
+        }
+    }
+}
+
+impl<S> Future for SaveTo<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+{
+    type Output = Result<u64, SaveError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Some(err) = this.err.take() {
+            return Poll::Ready(Err(err));
+        }
+
+        if this.fut.is_none() {
+            let path = std::mem::take(&mut this.path);
+            let payload = this.payload.take().unwrap();
+            #[cfg(feature = "client-digest")]
+            let fut = save(path, payload, this.digest.take());
+            #[cfg(not(feature = "client-digest"))]
+            let fut = save(path, payload);
+            this.fut = Some(fut.boxed_local());
+        }
+        Pin::new(this.fut.as_mut().unwrap()).poll(cx)
+    }
+}
+
+/// Temporary file, removed on drop unless it is persisted
+struct TempFile {
+    path: Option<PathBuf>,
+}
+
+impl TempFile {
+    fn new(path: &Path) -> io::Result<TempFile> {
+        let name = path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Path is not a file")
+        })?;
+        let name = format!(
+            ".{}.{:08x}.tmp",
+            name.to_string_lossy(),
+            rand::random::<u32>()
+        );
+        Ok(TempFile {
+            path: Some(path.with_file_name(name)),
+        })
+    }
+
+    fn path(&self) -> PathBuf {
+        self.path.clone().unwrap()
+    }
+
+    async fn persist(mut self, path: PathBuf) -> io::Result<()> {
+        let tmp = self.path();
+        blocking(move || fs::rename(tmp, path)).await?;
+        self.path = None;
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if let Some(ref path) = self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+async fn save<S>(
+    path: PathBuf,
+    mut payload: Payload<S>,
+    #[cfg(feature = "client-digest")] mut digest: Option<PayloadDigest>,
+) -> Result<u64, SaveError>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    let tmp = TempFile::new(&path)?;
+    let tmp_path = tmp.path();
+    let mut file = blocking(move || File::create(tmp_path)).await?;
+    let mut size = 0;
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        #[cfg(feature = "client-digest")]
+        if let Some((ref mut digest, _)) = digest {
+            digest.update(&chunk);
+        }
+        size += chunk.len() as u64;
+        file = blocking(move || file.write_all(&chunk).map(|_| file)).await?;
+    }
+    blocking(move || file.sync_all()).await?;
+
+    #[cfg(feature = "client-digest")]
+    if let Some((digest, expected)) = digest {
+        if digest.finalize()[..] != expected[..] {
+            return Err(SaveError::Digest);
+        }
+    }
+    tmp.persist(path).await?;
+    Ok(size)
+}
+
+/// Run blocking file operation on the thread pool
+async fn blocking<F, T>(f: F) -> io::Result<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    run(f).await.map_err(|e| match e {
+        BlockingError::Error(e) => e,
+        BlockingError::Canceled => {
+            io::Error::new(io::ErrorKind::Other, "Thread pool is gone")
+        }
+    })
+}
+
+#[cfg(all(test, feature = "client-digest"))]
+mod tests {
+    use sha1::{Digest, Sha1};
+
+    use super::*;
+    use crate::http::client::test::TestResponse;
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ntex-{}-{}", name, rand::random::<u32>()))
+    }
+
+    #[ntex_rt::test]
+    async fn test_save_to() {
+        let dest = path("save");
+        let mut res = TestResponse::default()
+            .set_payload(Bytes::from_static(b"test data"))
+            .finish();
+        let size = res
+            .save_to(&dest)
+            .digest::<Sha1, _>(Sha1::digest(b"test data").to_vec())
+            .await
+            .unwrap();
+        assert_eq!(size, 9);
+        assert_eq!(fs::read(&dest).unwrap(), b"test data");
+        fs::remove_file(&dest).unwrap();
+
+        // digest mismatch, nothing is left in the directory
+        let dest = path("mismatch");
+        let mut res = TestResponse::default()
+            .set_payload(Bytes::from_static(b"test data"))
+            .finish();
+        let err = res
+            .save_to(&dest)
+            .digest::<Sha1, _>(Sha1::digest(b"other").to_vec())
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, SaveError::Digest));
+        assert!(!dest.exists());
+        let prefix = format!(".{}", dest.file_name().unwrap().to_string_lossy());
+        assert!(!fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .any(|entry| entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(&prefix)));
+    }
+
+    #[ntex_rt::test]
+    async fn test_save_to_content_md5() {
+        // sha1 stands in for md5 hasher
+        let dest = path("md5");
+        let mut res = TestResponse::with_header(
+            CONTENT_MD5,
+            base64::encode(Sha1::digest(b"test data")),
+        )
+        .set_payload(Bytes::from_static(b"test data"))
+        .finish();
+        assert_eq!(res.save_to(&dest).content_md5::<Sha1>().await.unwrap(), 9);
+        fs::remove_file(&dest).unwrap();
+
+        let mut res = TestResponse::default().finish();
+        let err = res
+            .save_to(&dest)
+            .content_md5::<Sha1>()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, SaveError::ContentMd5));
+        assert!(!dest.exists());
+    }
+}
@@ -1,36 +1,282 @@
+use std::cell::{Cell, RefCell};
 use std::convert::TryFrom;
 use std::error::Error;
-use std::net;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use std::{cmp, io, net};
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use derive_more::From;
+use futures::future::poll_fn;
 use futures::{Future, Stream};
 use serde::Serialize;
 
-use crate::http::body::{Body, BodyStream};
-use crate::http::error::HttpError;
+use crate::codec::AsyncRead;
+use crate::http::body::{
+    Body, BodySize, BodyStream, BoxedBodyStream, MessageBody, SizedStream,
+};
+use crate::http::error::{HttpError, PayloadError};
 use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
-use crate::http::RequestHeadType;
-use crate::rt::time::{delay_for, Delay};
+use crate::http::{
+    Method, Payload, PayloadStream, RequestHead, RequestHeadType, ResponseHead,
+    StatusCode, Version,
+};
+use crate::rt::time::{self, delay_for, Delay};
+
+#[cfg(feature = "test-util")]
+use super::timer::Timer;
+#[cfg(feature = "test-util")]
+use futures::future::LocalBoxFuture;
 
 #[cfg(feature = "compress")]
-use crate::http::encoding::Decoder;
+use crate::http::encoding::{Decoder, Encoder};
 #[cfg(feature = "compress")]
 use crate::http::header::ContentEncoding;
-#[cfg(feature = "compress")]
-use crate::http::{Payload, PayloadStream};
 
-use super::error::{FreezeRequestError, InvalidUrl, SendRequestError};
+use super::error::{FreezeRequestError, InvalidUrl, SendRequestError, TimeoutPhase};
+use super::interceptor::ResponseInterceptor;
+use super::multipart::Multipart;
+use super::redirect;
 use super::response::ClientResponse;
-use super::ClientConfig;
+use super::retry::{self, RetryPolicy};
+use super::sign;
+use super::trace;
+use super::{ClientConfig, DEFAULT_EXPECT_CONTINUE_TIMEOUT};
+
+/// Per-request settings of `ClientRequest`, stored in request extensions
+///
+/// Settings are kept together, so redirect hop gets all of them at once.
+#[derive(Clone, Default)]
+pub(super) struct RequestOptions {
+    /// Request body compression
+    #[cfg(feature = "compress")]
+    pub(super) compress: Option<ContentEncoding>,
+    /// Max response payload size
+    pub(super) max_response_size: Option<usize>,
+    /// Max response decompression ratio, 0 disables the check
+    pub(super) max_decompression_ratio: Option<usize>,
+    /// Max response head size
+    pub(super) max_header_size: Option<usize>,
+    /// `Referer` header is set by `ClientRequest::referer()`,
+    /// such header is updated on redirects
+    pub(super) auto_referer: bool,
+    /// Request could be cloned with `SendClientRequest::try_clone()`
+    pub(super) replayable: bool,
+    /// Max size of buffered request body
+    pub(super) stream_buffer_size: Option<usize>,
+    /// Max size of body stream that is buffered and sent with `Content-Length`
+    pub(super) buffer_body: Option<usize>,
+    /// Addresses tried if connection to request address fails
+    pub(super) fallback_addrs: Vec<net::SocketAddr>,
+    /// Upload progress callback
+    pub(super) upload_progress: Option<Rc<dyn Fn(u64, Option<u64>)>>,
+    /// Connection pool key
+    pub(super) pool_key: Option<String>,
+    /// Retry policy, overrides client policy
+    pub(super) retry: Option<RetryPolicy>,
+}
+
+impl RequestOptions {
+    /// Read setting of the request
+    pub(super) fn get<F, R>(head: &RequestHead, f: F) -> R
+    where
+        F: FnOnce(&RequestOptions) -> R,
+        R: Default,
+    {
+        head.extensions()
+            .get::<RequestOptions>()
+            .map(f)
+            .unwrap_or_default()
+    }
+
+    /// Update setting of the request
+    pub(super) fn set<F>(head: &RequestHead, f: F)
+    where
+        F: FnOnce(&mut RequestOptions),
+    {
+        let mut extensions = head.extensions_mut();
+        match extensions.get_mut::<RequestOptions>() {
+            Some(options) => f(options),
+            None => {
+                let mut options = RequestOptions::default();
+                f(&mut options);
+                extensions.insert(options);
+            }
+        }
+    }
+}
+
+/// Request headers sent to the server, stored in response extensions
+pub(super) struct RequestHeaders(pub(super) HeaderMap);
+
+/// Response to `HEAD` request, stored in response extensions
+///
+/// Such response has no payload, whatever `Content-Length` it declares.
+#[derive(Copy, Clone)]
+pub(super) struct HeadResponse;
+
+/// Response trailer fields, stored in response extensions
+///
+/// Trailers are set by payload stream once payload is read
+#[derive(Clone, Default)]
+pub(super) struct Trailers(Rc<RefCell<Option<HeaderMap>>>);
+
+impl Trailers {
+    pub(super) fn set(&self, trailers: HeaderMap) {
+        *self.0.borrow_mut() = Some(trailers);
+    }
+
+    pub(super) fn get(&self) -> Option<HeaderMap> {
+        self.0.borrow().clone()
+    }
+}
+
+/// Wire bytes of request and response, stored in response extensions
+///
+/// Counters are updated by connection, response body bytes are
+/// counted while payload is read
+#[derive(Clone, Default)]
+pub(super) struct ByteCounters(Rc<(Cell<u64>, Cell<u64>)>);
+
+impl ByteCounters {
+    pub(super) fn sent(&self, n: usize) {
+        (self.0).0.set((self.0).0.get() + n as u64);
+    }
+
+    pub(super) fn received(&self, n: usize) {
+        (self.0).1.set((self.0).1.get() + n as u64);
+    }
+
+    pub(super) fn bytes_sent(&self) -> u64 {
+        (self.0).0.get()
+    }
+
+    pub(super) fn bytes_received(&self) -> u64 {
+        (self.0).1.get()
+    }
+}
+
+/// Current phase of sending request
+///
+/// Phase is updated by connector and protocol implementations,
+/// it is reported by request timeout error
+#[derive(Clone, Default)]
+pub(super) struct RequestPhase(Rc<Cell<TimeoutPhase>>);
+
+impl RequestPhase {
+    pub(super) fn get(&self) -> TimeoutPhase {
+        self.0.get()
+    }
+
+    pub(super) fn set(&self, phase: TimeoutPhase) {
+        self.0.set(phase)
+    }
+
+    /// Timeout error for the current phase
+    pub(super) fn timeout(&self) -> SendRequestError {
+        SendRequestError::Timeout { phase: self.get() }
+    }
+}
+
+/// Per-send state of the request, passed to connection
+///
+/// Request head could be shared between requests that are sent
+/// concurrently, so per-send state is not stored in head extensions.
+/// Client settings are taken from config on each send, so they
+/// apply to every redirect hop as well.
+#[doc(hidden)]
+#[derive(Clone)]
+pub struct SendContext {
+    pub(super) phase: RequestPhase,
+    pub(super) preserve_raw_headers: bool,
+    pub(super) strict_framing: bool,
+    pub(super) write_timeout: Option<Duration>,
+    pub(super) expect_continue_timeout: Duration,
+    pub(super) max_header_size: Option<usize>,
+    pub(super) absolute_form: bool,
+}
+
+impl SendContext {
+    pub(super) fn new(
+        phase: RequestPhase,
+        head: &RequestHead,
+        config: &ClientConfig,
+    ) -> Self {
+        SendContext {
+            phase,
+            preserve_raw_headers: config.preserve_raw_headers,
+            strict_framing: config.strict_framing,
+            write_timeout: config.write_timeout,
+            expect_continue_timeout: config.expect_continue_timeout,
+            // request setting overrides client setting
+            max_header_size: RequestOptions::get(head, |opts| opts.max_header_size)
+                .or(config.max_response_header_size),
+            absolute_form: false,
+        }
+    }
+}
+
+impl Default for SendContext {
+    fn default() -> Self {
+        SendContext {
+            phase: RequestPhase::default(),
+            preserve_raw_headers: false,
+            strict_framing: false,
+            write_timeout: None,
+            expect_continue_timeout: DEFAULT_EXPECT_CONTINUE_TIMEOUT,
+            max_header_size: None,
+            absolute_form: false,
+        }
+    }
+}
+
+/// Reports request body bytes that left connection's write buffer
+pub(super) struct ProgressTracker {
+    callback: Rc<dyn Fn(u64, Option<u64>)>,
+    total: Option<u64>,
+    written: u64,
+    reported: u64,
+}
+
+impl ProgressTracker {
+    pub(super) fn new(head: &RequestHead, size: BodySize) -> Option<Self> {
+        let callback = RequestOptions::get(head, |opts| opts.upload_progress.clone())?;
+        let total = match size {
+            BodySize::Sized(len) => Some(len),
+            _ => None,
+        };
+        Some(ProgressTracker {
+            callback,
+            total,
+            written: 0,
+            reported: 0,
+        })
+    }
+
+    /// Body chunk is written to the connection's write buffer
+    pub(super) fn written(&mut self, n: usize) {
+        self.written += n as u64;
+    }
+
+    /// Call progress callback if more data has been sent,
+    /// `buffered` is a number of bytes still in write buffer
+    pub(super) fn report(&mut self, buffered: usize) {
+        let sent = self.written.saturating_sub(buffered as u64);
+        if sent > self.reported {
+            self.reported = sent;
+            (*self.callback)(sent, self.total);
+        }
+    }
+}
 
 #[derive(Debug, From)]
 pub(crate) enum PrepForSendingError {
     Url(InvalidUrl),
     Http(HttpError),
+    Query(serde_urlencoded::ser::Error),
+    Credentials,
 }
 
 impl Into<FreezeRequestError> for PrepForSendingError {
@@ -38,6 +284,8 @@ impl Into<FreezeRequestError> for PrepForSendingError {
         match self {
             PrepForSendingError::Url(e) => FreezeRequestError::Url(e),
             PrepForSendingError::Http(e) => FreezeRequestError::Http(e),
+            PrepForSendingError::Query(e) => FreezeRequestError::Query(e),
+            PrepForSendingError::Credentials => FreezeRequestError::InvalidCredentials,
         }
     }
 }
@@ -47,6 +295,113 @@ impl Into<SendRequestError> for PrepForSendingError {
         match self {
             PrepForSendingError::Url(e) => SendRequestError::Url(e),
             PrepForSendingError::Http(e) => SendRequestError::Http(e),
+            PrepForSendingError::Query(e) => SendRequestError::Error(Box::new(e)),
+            PrepForSendingError::Credentials => SendRequestError::InvalidCredentials,
+        }
+    }
+}
+
+/// Request metrics, passed to `ClientBuilder::on_complete()` callback
+#[derive(Debug)]
+pub struct RequestMetrics<'a> {
+    /// Request method
+    pub method: &'a Method,
+    /// Request host
+    pub host: &'a str,
+    /// Response status, `None` if request failed
+    pub status: Option<StatusCode>,
+    /// Request error, `None` if request succeeded
+    pub error: Option<&'a SendRequestError>,
+    /// Number of request bytes written to the connection
+    pub bytes_sent: u64,
+    /// Number of response bytes read until response head is received
+    pub bytes_received: u64,
+    /// Time elapsed since request is sent
+    pub elapsed: Duration,
+}
+
+/// Completion callback, stored in client config
+pub(super) type OnComplete = Rc<dyn Fn(&RequestMetrics<'_>)>;
+
+/// Span covering request lifecycle
+///
+/// With `tracing` feature span records method and host of the request,
+/// response status, outcome and elapsed time in milliseconds. Span also
+/// invokes `on_complete` callback once request is completed.
+#[derive(Default)]
+pub(super) struct RequestSpan {
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::Span>,
+    on_complete: Option<(OnComplete, Method, String)>,
+}
+
+impl RequestSpan {
+    fn new(head: &RequestHead, on_complete: Option<OnComplete>) -> Self {
+        let host = head.uri.host().unwrap_or("");
+        RequestSpan {
+            #[cfg(feature = "tracing")]
+            span: Some(tracing::info_span!(
+                "http_client_request",
+                method = %head.method,
+                host = host,
+                status = tracing::field::Empty,
+                outcome = tracing::field::Empty,
+                elapsed_ms = tracing::field::Empty,
+            )),
+            on_complete: on_complete.map(|f| (f, head.method.clone(), host.to_string())),
+        }
+    }
+
+    /// Instrument send future, span is entered on each poll
+    #[cfg(feature = "tracing")]
+    fn instrument(
+        &self,
+        send: Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>>,
+    ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+        use tracing::Instrument;
+
+        match self.span {
+            Some(ref span) => Box::pin(send.instrument(span.clone())),
+            None => send,
+        }
+    }
+
+    /// Record request outcome, callback is invoked only once
+    fn record<S>(
+        &mut self,
+        res: &Result<ClientResponse<S>, SendRequestError>,
+        start: Instant,
+    ) {
+        #[cfg(feature = "tracing")]
+        {
+            if let Some(ref span) = self.span {
+                match res {
+                    Ok(res) => {
+                        span.record("status", res.status().as_u16());
+                        span.record("outcome", "success");
+                    }
+                    Err(e) => {
+                        span.record("outcome", tracing::field::display(e));
+                    }
+                }
+                span.record("elapsed_ms", start.elapsed().as_millis() as u64);
+            }
+        }
+
+        if let Some((on_complete, method, host)) = self.on_complete.take() {
+            let (bytes_sent, bytes_received) = match res {
+                Ok(res) => (res.request_bytes_sent(), res.response_bytes_received()),
+                Err(_) => (0, 0),
+            };
+            on_complete(&RequestMetrics {
+                method: &method,
+                host: &host,
+                status: res.as_ref().ok().map(|res| res.status()),
+                error: res.as_ref().err(),
+                bytes_sent,
+                bytes_received,
+                elapsed: start.elapsed(),
+            });
         }
     }
 }
@@ -54,22 +409,313 @@ impl Into<SendRequestError> for PrepForSendingError {
 /// Future that sends request's payload and resolves to a server response.
 #[must_use = "futures do nothing unless polled"]
 pub enum SendClientRequest {
-    Fut(
-        Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>>,
-        Option<Delay>,
-        bool,
-    ),
+    Fut(SendState),
     Err(Option<SendRequestError>),
 }
 
+/// State of request that is being sent
+pub struct SendState {
+    send: Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>>,
+    delay: Option<RequestDelay>,
+    #[cfg_attr(not(feature = "compress"), allow(dead_code))]
+    response_decompress: bool,
+    /// Time of first poll
+    start: Option<Instant>,
+    max_size: Option<usize>,
+    #[cfg_attr(not(feature = "compress"), allow(dead_code))]
+    lenient_decompression: bool,
+    #[cfg_attr(not(feature = "compress"), allow(dead_code))]
+    max_ratio: Option<usize>,
+    span: RequestSpan,
+    interceptors: Vec<Rc<dyn ResponseInterceptor>>,
+    phase: RequestPhase,
+    /// Request parts for `try_clone()`, released on first poll
+    replay: Option<Box<Replay>>,
+    /// Client config, request timeout delay is created with its timer
+    config: Option<Rc<ClientConfig>>,
+}
+
+/// Request timeout delay
+pub(super) struct RequestDelay(DelayKind);
+
+enum DelayKind {
+    Rt(Delay),
+    #[cfg(feature = "test-util")]
+    Timer(Rc<dyn Timer>, Duration, LocalBoxFuture<'static, ()>),
+}
+
+impl RequestDelay {
+    /// Create delay with client timer, runtime timer is used by default
+    fn new(config: Option<&ClientConfig>, timeout: Duration) -> Self {
+        #[cfg(feature = "test-util")]
+        {
+            if let Some(timer) = config.and_then(|config| config.timer.as_ref()) {
+                return RequestDelay::timer(timer.clone(), timeout);
+            }
+        }
+        #[cfg(not(feature = "test-util"))]
+        let _ = config;
+        RequestDelay(DelayKind::Rt(delay_for(timeout)))
+    }
+
+    #[cfg(feature = "test-util")]
+    fn timer(timer: Rc<dyn Timer>, timeout: Duration) -> Self {
+        let deadline = timer.elapsed() + timeout;
+        let delay = timer.delay(timeout);
+        RequestDelay(DelayKind::Timer(timer, deadline, delay))
+    }
+
+    /// Restart delay with new timeout
+    fn reset(&mut self, timeout: Duration) {
+        match self.0 {
+            DelayKind::Rt(ref mut delay) => {
+                delay.reset(time::Instant::now() + timeout);
+            }
+            #[cfg(feature = "test-util")]
+            DelayKind::Timer(ref timer, ..) => {
+                *self = RequestDelay::timer(timer.clone(), timeout);
+            }
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        match self.0 {
+            DelayKind::Rt(ref delay) => delay
+                .deadline()
+                .saturating_duration_since(time::Instant::now()),
+            #[cfg(feature = "test-util")]
+            DelayKind::Timer(ref timer, deadline, _) => {
+                deadline.checked_sub(timer.elapsed()).unwrap_or_default()
+            }
+        }
+    }
+}
+
+impl Future for RequestDelay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        match self.get_mut().0 {
+            DelayKind::Rt(ref mut delay) => Pin::new(delay).poll(cx),
+            #[cfg(feature = "test-util")]
+            DelayKind::Timer(_, _, ref mut delay) => delay.as_mut().poll(cx),
+        }
+    }
+}
+
+/// Request parts needed to send the request again
+pub(super) struct Replay {
+    head: Rc<RequestHead>,
+    extra_headers: Option<HeaderMap>,
+    body: Body,
+    addr: Option<net::SocketAddr>,
+    response_decompress: bool,
+    timeout: Option<Duration>,
+    config: Rc<ClientConfig>,
+}
+
 impl SendClientRequest {
-    pub(crate) fn new(
+    pub(super) fn new(
         send: Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>>,
         response_decompress: bool,
-        timeout: Option<Duration>,
+        delay: Option<RequestDelay>,
+        max_size: Option<usize>,
+        lenient_decompression: bool,
+        max_ratio: Option<usize>,
+        span: RequestSpan,
+        interceptors: Vec<Rc<dyn ResponseInterceptor>>,
+        phase: RequestPhase,
     ) -> SendClientRequest {
-        let delay = timeout.map(delay_for);
-        SendClientRequest::Fut(send, delay, response_decompress)
+        #[cfg(feature = "tracing")]
+        let send = span.instrument(send);
+
+        SendClientRequest::Fut(SendState {
+            send,
+            delay,
+            response_decompress,
+            start: None,
+            max_size,
+            lenient_decompression,
+            max_ratio,
+            span,
+            interceptors,
+            phase,
+            replay: None,
+            config: None,
+        })
+    }
+
+    /// Future that resolves to error, error is recorded on first poll
+    fn failed(err: SendRequestError, span: RequestSpan) -> SendClientRequest {
+        SendClientRequest::new(
+            Box::pin(futures::future::err(err)),
+            false,
+            None,
+            None,
+            false,
+            None,
+            span,
+            Vec::new(),
+            RequestPhase::default(),
+        )
+    }
+
+    /// Replace request timeout.
+    ///
+    /// Timeout could be changed only before the future is polled
+    /// for the first time, otherwise this method does nothing.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        if let SendClientRequest::Fut(ref mut state) = self {
+            if state.start.is_none() {
+                match state.delay {
+                    Some(ref mut delay) => delay.reset(timeout),
+                    None => {
+                        state.delay =
+                            Some(RequestDelay::new(state.config.as_deref(), timeout))
+                    }
+                }
+                if let Some(ref mut replay) = state.replay {
+                    replay.timeout = Some(timeout);
+                }
+            }
+        }
+        self
+    }
+
+    /// Replace request timeout with a deadline.
+    ///
+    /// Remaining time until deadline is used as request timeout. If deadline
+    /// has already passed, future resolves to `SendRequestError::Timeout`.
+    /// Deadline could be changed only before the future is polled
+    /// for the first time, otherwise this method does nothing.
+    pub fn with_deadline(self, deadline: Instant) -> Self {
+        self.with_timeout(deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Time remaining until request timeout.
+    ///
+    /// Returns `None` if request timeout is not set or the future
+    /// has completed.
+    pub fn remaining_timeout(&self) -> Option<Duration> {
+        match self {
+            SendClientRequest::Fut(state) => {
+                state.delay.as_ref().map(|delay| delay.remaining())
+            }
+            SendClientRequest::Err(_) => None,
+        }
+    }
+
+    /// Create new future that sends the same request.
+    ///
+    /// Request could be cloned only if it is marked with
+    /// `ClientRequest::replayable()`, its body is replayable (empty or
+    /// `Body::Bytes`) and the future has not been polled yet, otherwise
+    /// `None` is returned, so request must be cloned before it is
+    /// polled for the first time. Clone is sent independently, including
+    /// timeout, retries and redirects. Head extensions are shared between
+    /// clones, same as for frozen requests.
+    ///
+    /// ```rust
+    /// use ntex::http::client::Client;
+    ///
+    /// #[ntex::main]
+    /// async fn main() {
+    ///     let request = Client::new()
+    ///         .post("http://www.rust-lang.org")
+    ///         .replayable()
+    ///         .send_body("data");
+    ///     let second = request.try_clone().unwrap();
+    ///     let (res1, res2) = futures::join!(request, second);
+    /// }
+    /// ```
+    pub fn try_clone(&self) -> Option<SendClientRequest> {
+        match self {
+            SendClientRequest::Fut(SendState {
+                start: None,
+                replay: Some(replay),
+                ..
+            }) => {
+                let body = retry::replay(&replay.body)?;
+                let head = RequestHeadType::Rc(
+                    replay.head.clone(),
+                    replay.extra_headers.clone(),
+                );
+                Some(head.send_body(
+                    replay.addr,
+                    replay.response_decompress,
+                    replay.timeout,
+                    &replay.config,
+                    body,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolve to a response with the untouched wire payload.
+    ///
+    /// Response payload is not decompressed, `Content-Encoding` header
+    /// is preserved, so payload could be relayed as is.
+    pub fn raw(self) -> RawSendClientRequest {
+        RawSendClientRequest(self)
+    }
+
+    /// Poll send future, apply timeout, interceptors and response size limit
+    fn poll_response(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<ClientResponse, SendRequestError>> {
+        match self {
+            SendClientRequest::Fut(SendState {
+                send,
+                delay,
+                start,
+                max_size,
+                span,
+                interceptors,
+                phase,
+                replay,
+                ..
+            }) => {
+                let start = *start.get_or_insert_with(Instant::now);
+                // request could not be cloned after first poll, release shared head
+                *replay = None;
+
+                if delay.is_some() {
+                    match Pin::new(delay.as_mut().unwrap()).poll(cx) {
+                        Poll::Pending => (),
+                        _ => {
+                            let res = Err(phase.timeout());
+                            span.record(&res, start);
+                            *delay = None;
+                            return Poll::Ready(res);
+                        }
+                    }
+                }
+
+                let res = futures::ready!(Pin::new(send).poll(cx)).map(|mut res| {
+                    res.timings.start = start;
+                    res.timings.end = Instant::now();
+                    res
+                });
+                *delay = None;
+                let mut res = interceptors
+                    .iter()
+                    .fold(res, |res, interceptor| interceptor.on_response(res));
+
+                if let (Ok(ref r), Some(limit)) = (&res, *max_size) {
+                    if content_length(r).map(|len| len > limit).unwrap_or(false) {
+                        res = Err(SendRequestError::ResponsePayloadTooLarge { limit });
+                    }
+                }
+                span.record(&res, start);
+                Poll::Ready(res)
+            }
+            SendClientRequest::Err(ref mut e) => match e.take() {
+                Some(e) => Poll::Ready(Err(e)),
+                None => panic!("Attempting to call completed future"),
+            },
+        }
     }
 }
 
@@ -80,43 +726,516 @@ impl Future for SendClientRequest {
     #[cfg(not(feature = "compress"))]
     type Output = Result<ClientResponse, SendRequestError>;
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let res = futures::ready!(this.poll_response(cx));
+
+        let max_size = match this {
+            SendClientRequest::Fut(state) => state.max_size,
+            SendClientRequest::Err(_) => None,
+        };
+
+        #[cfg(feature = "compress")]
+        let res = res.map(|res| {
+            let (decompress, lenient, max_ratio) = match this {
+                SendClientRequest::Fut(state) => (
+                    state.response_decompress,
+                    state.lenient_decompression,
+                    state.max_ratio,
+                ),
+                SendClientRequest::Err(_) => (false, false, None),
+            };
+
+            res.map_body(|head, payload| {
+                let payload = track_payload(head, payload);
+                if decompress {
+                    let decoder = Decoder::from_headers(payload, &head.headers)
+                        .lenient(lenient)
+                        .max_ratio(max_ratio);
+                    // count decoded bytes
+                    if let Some(limit) = max_size {
+                        let payload: PayloadStream =
+                            Box::pin(PayloadLimit::new(decoder, limit));
+                        Payload::Stream(Decoder::new(
+                            Payload::Stream(payload),
+                            ContentEncoding::Identity,
+                        ))
+                    } else {
+                        Payload::Stream(decoder)
+                    }
+                } else {
+                    Payload::Stream(Decoder::new(
+                        limit_payload(payload, max_size),
+                        ContentEncoding::Identity,
+                    ))
+                }
+            })
+        });
+
+        #[cfg(not(feature = "compress"))]
+        let res = res.map(|res| {
+            res.map_body(|head, payload| {
+                limit_payload(track_payload(head, payload), max_size)
+            })
+        });
+
+        Poll::Ready(res)
+    }
+}
+
+/// Future that resolves to a server response with the untouched wire payload.
+///
+/// Created by `SendClientRequest::raw()` method.
+#[must_use = "futures do nothing unless polled"]
+pub struct RawSendClientRequest(SendClientRequest);
+
+impl Future for RawSendClientRequest {
+    type Output = Result<ClientResponse, SendRequestError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let res = futures::ready!(this.0.poll_response(cx));
+
+        let max_size = match this.0 {
+            SendClientRequest::Fut(ref state) => state.max_size,
+            SendClientRequest::Err(_) => None,
+        };
+        Poll::Ready(res.map(|res| {
+            res.map_body(|head, payload| {
+                limit_payload(track_payload(head, payload), max_size)
+            })
+        }))
+    }
+}
+
+fn content_length(res: &ClientResponse) -> Option<usize> {
+    if res.extensions().contains::<HeadResponse>() {
+        return None;
+    }
+    res.headers()
+        .get(&header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+fn limit_payload(payload: Payload, max_size: Option<usize>) -> Payload {
+    if let Some(limit) = max_size {
+        let payload: PayloadStream = Box::pin(PayloadLimit::new(payload, limit));
+        Payload::Stream(payload)
+    } else {
+        payload
+    }
+}
+
+/// Count received payload bytes, connection loss is reported with
+/// `PayloadError::Interrupted`
+fn track_payload(head: &ResponseHead, payload: Payload) -> Payload {
+    if let Payload::None = payload {
+        return payload;
+    }
+    let accept_ranges = head
+        .headers
+        .get(&header::ACCEPT_RANGES)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.eq_ignore_ascii_case("bytes"))
+        .unwrap_or(false);
+    let payload: PayloadStream = Box::pin(PayloadProgress {
+        accept_ranges,
+        stream: payload,
+        received: 0,
+    });
+    Payload::Stream(payload)
+}
+
+/// Streaming request body options.
+///
+/// Small chunks of the body stream could be coalesced into bigger ones
+/// before writing to the connection. Amount of buffered body data is
+/// limited, body stream is not polled until connection's write buffer
+/// drains below the limit. Buffer limit is applied to http/1 connections,
+/// http/2 streams use protocol flow control.
+///
+/// ```rust
+/// use bytes::Bytes;
+/// use futures::{future::ok, stream::once};
+/// use ntex::http::client::{Client, StreamOptions};
+///
+/// # async fn send() {
+/// let stream = once(ok::<_, std::io::Error>(Bytes::from_static(b"data")));
+/// let response = Client::new()
+///     .post("http://www.rust-lang.org")
+///     .send_stream_with(
+///         stream,
+///         StreamOptions::new().min_chunk_size(4096).max_buffer_size(65536),
+///     )
+///     .await;
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct StreamOptions {
+    min_chunk_size: usize,
+    max_buffer_size: usize,
+    buffer_body: Option<usize>,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        StreamOptions::new()
+    }
+}
+
+impl StreamOptions {
+    /// Create default stream options.
+    ///
+    /// By default chunks are not coalesced and max buffer size is 8kb.
+    pub fn new() -> Self {
+        StreamOptions {
+            min_chunk_size: 0,
+            max_buffer_size: 8 * 1024,
+            buffer_body: None,
+        }
+    }
+
+    /// Coalesce body chunks smaller than `size`.
+    ///
+    /// Chunks are accumulated until `size` bytes are collected or stream
+    /// ends, so slow stream delays sending of data.
+    pub fn min_chunk_size(mut self, size: usize) -> Self {
+        self.min_chunk_size = size;
+        self
+    }
+
+    /// Set max size of buffered body data.
+    pub fn max_buffer_size(mut self, size: usize) -> Self {
+        self.max_buffer_size = size;
+        self
+    }
+
+    /// Buffer up to `size` bytes of body stream before sending.
+    ///
+    /// If stream ends within `size` bytes, body is sent with
+    /// `Content-Length` header. Otherwise buffered data is sent followed
+    /// by the rest of the stream, with chunked transfer encoding for
+    /// http/1. By default stream is not buffered.
+    pub fn buffer_body(mut self, size: usize) -> Self {
+        self.buffer_body = Some(size);
+        self
+    }
+
+    pub(super) fn into_parts(self) -> (usize, usize, Option<usize>) {
+        (self.min_chunk_size, self.max_buffer_size, self.buffer_body)
+    }
+}
+
+/// Read up to `limit` bytes of body stream, stream that ends within
+/// the limit is replaced with bytes body
+async fn buffer_body(body: Body, limit: usize) -> Result<Body, SendRequestError> {
+    let mut stream = match body {
+        Body::Message(stream) if stream.size() == BodySize::Stream => stream,
+        body => return Ok(body),
+    };
+
+    let mut buf = BytesMut::new();
+    while let Some(chunk) = poll_fn(|cx| stream.poll_next_chunk(cx)).await {
+        buf.extend_from_slice(&chunk.map_err(SendRequestError::Error)?);
+        if buf.len() > limit {
+            break;
+        }
+    }
+
+    // body with trailers must be sent as a stream
+    if buf.len() <= limit {
+        match stream.trailers() {
+            Some(trailers) => Ok(Body::from_message(PrefixedBody {
+                prefix: Some(buf.freeze()),
+                body: Body::Empty,
+                trailers: Some(trailers),
+            })),
+            None => Ok(Body::Bytes(buf.freeze())),
+        }
+    } else {
+        Ok(Body::from_message(PrefixedBody {
+            prefix: Some(buf.freeze()),
+            body: Body::Message(stream),
+            trailers: None,
+        }))
+    }
+}
+
+/// Body stream with already read data
+struct PrefixedBody {
+    prefix: Option<Bytes>,
+    body: Body,
+    trailers: Option<HeaderMap>,
+}
+
+impl MessageBody for PrefixedBody {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        match self.prefix.take() {
+            Some(prefix) if !prefix.is_empty() => Poll::Ready(Some(Ok(prefix))),
+            _ => self.body.poll_next_chunk(cx),
+        }
+    }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take().or_else(|| self.body.trailers())
+    }
+}
+
+/// Body stream that coalesces chunks smaller than `size`
+pub(super) struct CoalesceStream<S> {
+    stream: S,
+    size: usize,
+    buf: BytesMut,
+    eof: bool,
+}
+
+impl<S> CoalesceStream<S> {
+    pub(super) fn new(stream: S, size: usize) -> Self {
+        CoalesceStream {
+            stream,
+            size,
+            buf: BytesMut::new(),
+            eof: false,
+        }
+    }
+}
+
+impl<S, E> Stream for CoalesceStream<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    type Item = Result<Bytes, E>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.as_mut().get_mut();
+
+        while !this.eof {
+            match futures::ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+                Some(Ok(chunk)) => {
+                    if this.buf.is_empty() && chunk.len() >= this.size {
+                        return Poll::Ready(Some(Ok(chunk)));
+                    }
+                    this.buf.extend_from_slice(&chunk);
+                    if this.buf.len() >= this.size {
+                        return Poll::Ready(Some(Ok(this.buf.split().freeze())));
+                    }
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => this.eof = true,
+            }
+        }
+
+        if this.buf.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Ready(Some(Ok(this.buf.split().freeze())))
+        }
+    }
+}
+
+/// Body stream that reads chunks of up to `size` bytes from `AsyncRead`
+pub(super) struct ReaderStream<R> {
+    reader: R,
+    size: usize,
+    buf: BytesMut,
+    eof: bool,
+}
+
+impl<R> ReaderStream<R> {
+    pub(super) fn new(reader: R, size: usize) -> Self {
+        ReaderStream {
+            reader,
+            size: cmp::max(size, 1),
+            buf: BytesMut::new(),
+            eof: false,
+        }
+    }
+}
+
+impl<R> Stream for ReaderStream<R>
+where
+    R: AsyncRead + Unpin,
+{
+    type Item = Result<Bytes, Box<dyn Error>>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.as_mut().get_mut();
+
+        if this.eof {
+            return Poll::Ready(None);
+        }
+        if this.buf.capacity() < this.size {
+            this.buf.reserve(this.size);
+        }
+
+        match futures::ready!(Pin::new(&mut this.reader).poll_read_buf(cx, &mut this.buf))
+        {
+            Ok(0) => {
+                this.eof = true;
+                Poll::Ready(None)
+            }
+            Ok(_) => Poll::Ready(Some(Ok(this.buf.split().freeze()))),
+            Err(e) => {
+                this.eof = true;
+                Poll::Ready(Some(Err(e.into())))
+            }
+        }
+    }
+}
+
+/// Body stream with declared size, fails if stream yields more
+/// or fewer bytes than declared
+pub(super) struct SizedBodyStream<S> {
+    stream: S,
+    size: u64,
+    remaining: u64,
+}
+
+impl<S> SizedBodyStream<S> {
+    pub(super) fn new(stream: S, size: u64) -> Self {
+        SizedBodyStream {
+            stream,
+            size,
+            remaining: size,
+        }
+    }
+
+    fn error(&self, msg: &str) -> Box<dyn Error> {
+        Box::new(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Body stream {} declared size {}", msg, self.size),
+        ))
+    }
+}
+
+impl<S, E> MessageBody for SizedBodyStream<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Error + 'static,
+{
+    fn size(&self) -> BodySize {
+        BodySize::Sized(self.size)
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        loop {
+            return match futures::ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+                Some(Ok(chunk)) if chunk.is_empty() => continue,
+                Some(Ok(chunk)) => {
+                    if chunk.len() as u64 > self.remaining {
+                        Poll::Ready(Some(Err(self.error("exceeds"))))
+                    } else {
+                        self.remaining -= chunk.len() as u64;
+                        Poll::Ready(Some(Ok(chunk)))
+                    }
+                }
+                Some(Err(e)) => Poll::Ready(Some(Err(Box::new(e)))),
+                None if self.remaining > 0 => {
+                    Poll::Ready(Some(Err(self.error("is shorter than"))))
+                }
+                None => Poll::Ready(None),
+            };
+        }
+    }
+}
+
+/// Payload stream that fails with `PayloadError::TooLarge` if it
+/// yields more than `limit` bytes
+struct PayloadLimit<S> {
+    stream: S,
+    limit: usize,
+    size: usize,
+}
+
+impl<S> PayloadLimit<S> {
+    fn new(stream: S, limit: usize) -> Self {
+        PayloadLimit {
+            stream,
+            limit,
+            size: 0,
+        }
+    }
+}
+
+impl<S> Stream for PayloadLimit<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
 
-        match this {
-            SendClientRequest::Fut(send, delay, _response_decompress) => {
-                if delay.is_some() {
-                    match Pin::new(delay.as_mut().unwrap()).poll(cx) {
-                        Poll::Pending => (),
-                        _ => return Poll::Ready(Err(SendRequestError::Timeout)),
-                    }
+        match futures::ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                this.size += chunk.len();
+                if this.size > this.limit {
+                    Poll::Ready(Some(Err(PayloadError::TooLarge(this.limit))))
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
                 }
+            }
+            res => Poll::Ready(res),
+        }
+    }
+}
 
-                let res = futures::ready!(Pin::new(send).poll(cx));
-
-                #[cfg(feature = "compress")]
-                let res = res.map(|res| {
-                    res.map_body(|head, payload| {
-                        if *_response_decompress {
-                            Payload::Stream(Decoder::from_headers(
-                                payload,
-                                &head.headers,
-                            ))
-                        } else {
-                            Payload::Stream(Decoder::new(
-                                payload,
-                                ContentEncoding::Identity,
-                            ))
-                        }
-                    })
-                });
+/// Payload stream that counts received bytes
+struct PayloadProgress<S> {
+    stream: S,
+    received: u64,
+    accept_ranges: bool,
+}
 
-                Poll::Ready(res)
+impl<S> Stream for PayloadProgress<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match futures::ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                this.received += chunk.len() as u64;
+                Poll::Ready(Some(Ok(chunk)))
             }
-            SendClientRequest::Err(ref mut e) => match e.take() {
-                Some(e) => Poll::Ready(Err(e)),
-                None => panic!("Attempting to call completed future"),
-            },
+            Some(Err(e @ PayloadError::Incomplete(_)))
+            | Some(Err(e @ PayloadError::Io(_)))
+            | Some(Err(e @ PayloadError::Http2Payload(_))) => {
+                Poll::Ready(Some(Err(PayloadError::Interrupted {
+                    received: this.received,
+                    accept_ranges: this.accept_ranges,
+                    error: Box::new(e),
+                })))
+            }
+            res => Poll::Ready(res),
         }
     }
 }
@@ -139,34 +1258,260 @@ impl From<PrepForSendingError> for SendClientRequest {
     }
 }
 
+/// Send request, follow or deny redirects if enabled
+fn send_redirect(
+    config: &Rc<ClientConfig>,
+    head: RequestHeadType,
+    body: Body,
+    addr: Option<net::SocketAddr>,
+    policy: Option<RetryPolicy>,
+    phase: RequestPhase,
+) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+    if config.deny_redirects {
+        let fut = send_request(config, head, body, addr, policy, phase);
+        Box::pin(async move { redirect::deny(fut.await?) })
+    } else if config.max_redirects > 0 {
+        Box::pin(redirect::send(
+            config.clone(),
+            head,
+            body,
+            addr,
+            policy,
+            phase,
+        ))
+    } else {
+        send_request(config, head, body, addr, policy, phase)
+    }
+}
+
+/// Send request with connector, apply cookie jar and retry policy if any
+pub(super) fn send_request(
+    config: &Rc<ClientConfig>,
+    head: RequestHeadType,
+    body: Body,
+    addr: Option<net::SocketAddr>,
+    policy: Option<RetryPolicy>,
+    phase: RequestPhase,
+) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+    #[cfg(feature = "cookie")]
+    {
+        if let Some(ref jar) = config.cookie_jar {
+            let mut head = head;
+            jar.lock().unwrap().apply(&mut head);
+            let uri = head.as_ref().uri.clone();
+            let jar = jar.clone();
+            let fut = send_request_inner(config, head, body, addr, policy, phase);
+
+            return Box::pin(async move {
+                let res = fut.await?;
+                jar.lock().unwrap().store_response(&uri, &res);
+                Ok(res)
+            });
+        }
+    }
+
+    send_request_inner(config, head, body, addr, policy, phase)
+}
+
+fn send_request_inner(
+    config: &Rc<ClientConfig>,
+    head: RequestHeadType,
+    body: Body,
+    addr: Option<net::SocketAddr>,
+    policy: Option<RetryPolicy>,
+    phase: RequestPhase,
+) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+    // only replayable bodies could be sent more than once
+    match policy {
+        Some(policy) if retry::replay(&body).is_some() => {
+            Box::pin(retry::send(config.clone(), head, body, addr, policy, phase))
+        }
+        _ => config
+            .connector
+            .send_request(head, body, addr, config, phase),
+    }
+}
+
+/// Body size as it is sent to the peer
+///
+/// Empty body of a method without request payload semantics is sent
+/// without `Content-Length` header, `BodySize::None` never has one.
+pub(super) fn body_size(head: &RequestHead, size: BodySize) -> BodySize {
+    match size {
+        BodySize::Empty => match head.method {
+            Method::GET | Method::HEAD | Method::DELETE | Method::CONNECT => {
+                BodySize::None
+            }
+            _ => BodySize::Empty,
+        },
+        size => size,
+    }
+}
+
 impl RequestHeadType {
     pub(super) fn send_body<B>(
-        self,
+        mut self,
         addr: Option<net::SocketAddr>,
         response_decompress: bool,
         timeout: Option<Duration>,
-        config: &ClientConfig,
+        config: &Rc<ClientConfig>,
         body: B,
     ) -> SendClientRequest
     where
         B: Into<Body>,
     {
-        SendClientRequest::new(
-            config.connector.send_request(self, body.into(), addr),
+        let span = RequestSpan::new(self.as_ref(), config.on_complete.clone());
+        if config.closed.get() {
+            return SendClientRequest::failed(SendRequestError::Closed, span);
+        }
+        let body = body.into();
+
+        // keep parts of the request with replayable body for `try_clone()`
+        let owned = matches!(self, RequestHeadType::Owned(_));
+        let options = self
+            .as_ref()
+            .extensions()
+            .get::<RequestOptions>()
+            .cloned()
+            .unwrap_or_default();
+        let replay = match retry::replay(&body).filter(|_| options.replayable) {
+            Some(copy) => {
+                let (head, extra_headers) = match self {
+                    RequestHeadType::Owned(head) => (Rc::new(head), None),
+                    RequestHeadType::Rc(head, extra_headers) => (head, extra_headers),
+                };
+                self = RequestHeadType::Rc(head.clone(), extra_headers.clone());
+                Some(Box::new(Replay {
+                    head,
+                    extra_headers,
+                    addr,
+                    response_decompress,
+                    timeout,
+                    body: copy,
+                    config: config.clone(),
+                }))
+            }
+            None => None,
+        };
+
+        if let Err(e) = self.check_content_length(&body) {
+            return SendClientRequest::failed(e, span);
+        }
+
+        #[cfg(feature = "compress")]
+        let body = match options.compress {
+            Some(encoding) => match self.compress_body(encoding, body) {
+                Ok(body) => body,
+                Err(e) => return SendClientRequest::failed(e, span),
+            },
+            None => body,
+        };
+
+        let policy = options.retry.or_else(|| config.retry.clone());
+        let max_size = options.max_response_size.or(config.max_response_size);
+        let max_ratio = match options.max_decompression_ratio {
+            Some(0) => None,
+            Some(ratio) => Some(ratio),
+            None => config.max_decompression_ratio,
+        };
+
+        if let Some(ref provider) = config.trace_context {
+            if let Some(ctx) = provider() {
+                if let Err(e) = trace::inject(&mut self, &ctx) {
+                    return SendClientRequest::failed(e.into(), span);
+                }
+            }
+        }
+
+        if let Some(agent) = config.user_agents.as_ref().and_then(|ua| ua.next()) {
+            if let Err(e) = self.set_header_if_none(header::USER_AGENT, agent) {
+                return SendClientRequest::failed(e.into(), span);
+            }
+        }
+
+        let phase = RequestPhase::default();
+
+        let buffer = options.buffer_body;
+
+        let fut = if config.request_interceptors.is_empty()
+            && config.signer.is_none()
+            && buffer.is_none()
+        {
+            send_redirect(config, self, body, addr, policy, phase.clone())
+        } else {
+            let config = config.clone();
+            let phase = phase.clone();
+            Box::pin(async move {
+                // interceptors see owned head, unless request has been cloned
+                let mut head = if owned { self.into_owned() } else { self };
+                let body = if let Some(limit) = buffer {
+                    buffer_body(body, limit).await?
+                } else {
+                    body
+                };
+                for interceptor in &config.request_interceptors {
+                    interceptor.on_request(&mut head).await?;
+                }
+                let body = if let Some(ref signer) = config.signer {
+                    sign::sign(signer.as_ref(), &mut head, body).await?
+                } else {
+                    body
+                };
+                send_redirect(&config, head, body, addr, policy, phase).await
+            })
+        };
+
+        let mut req = SendClientRequest::new(
+            fut,
             response_decompress,
-            timeout.or_else(|| config.timeout),
-        )
+            timeout
+                .or(config.timeout)
+                .map(|timeout| RequestDelay::new(Some(config), timeout)),
+            max_size,
+            config.lenient_decompression,
+            max_ratio,
+            span,
+            config.response_interceptors.clone(),
+            phase,
+        );
+        if let SendClientRequest::Fut(ref mut state) = req {
+            state.replay = replay;
+            state.config = Some(config.clone());
+        }
+        req
     }
 
     pub(super) fn send_json<T: Serialize>(
-        mut self,
+        self,
         addr: Option<net::SocketAddr>,
         response_decompress: bool,
         timeout: Option<Duration>,
-        config: &ClientConfig,
+        config: &Rc<ClientConfig>,
         value: &T,
     ) -> SendClientRequest {
-        let body = match serde_json::to_string(value) {
+        self.send_json_with(
+            addr,
+            response_decompress,
+            timeout,
+            config,
+            value,
+            serde_json::to_vec,
+        )
+    }
+
+    pub(super) fn send_json_with<T, F>(
+        mut self,
+        addr: Option<net::SocketAddr>,
+        response_decompress: bool,
+        timeout: Option<Duration>,
+        config: &Rc<ClientConfig>,
+        value: &T,
+        serializer: F,
+    ) -> SendClientRequest
+    where
+        F: FnOnce(&T) -> Result<Vec<u8>, serde_json::Error>,
+    {
+        let body = match serializer(value) {
             Ok(body) => body,
             Err(e) => return SendRequestError::Error(Box::new(e)).into(),
         };
@@ -185,12 +1530,41 @@ impl RequestHeadType {
         )
     }
 
+    #[cfg(feature = "msgpack")]
+    pub(super) fn send_msgpack<T: Serialize>(
+        mut self,
+        addr: Option<net::SocketAddr>,
+        response_decompress: bool,
+        timeout: Option<Duration>,
+        config: &Rc<ClientConfig>,
+        value: &T,
+    ) -> SendClientRequest {
+        let body = match rmp_serde::to_vec(value) {
+            Ok(body) => body,
+            Err(e) => return SendRequestError::Error(Box::new(e)).into(),
+        };
+
+        if let Err(e) =
+            self.set_header_if_none(header::CONTENT_TYPE, "application/msgpack")
+        {
+            return e.into();
+        }
+
+        self.send_body(
+            addr,
+            response_decompress,
+            timeout,
+            config,
+            Body::Bytes(Bytes::from(body)),
+        )
+    }
+
     pub(super) fn send_form<T: Serialize>(
         mut self,
         addr: Option<net::SocketAddr>,
         response_decompress: bool,
         timeout: Option<Duration>,
-        config: &ClientConfig,
+        config: &Rc<ClientConfig>,
         value: &T,
     ) -> SendClientRequest {
         let body = match serde_urlencoded::to_string(value) {
@@ -215,12 +1589,98 @@ impl RequestHeadType {
         )
     }
 
+    #[cfg(feature = "body-serializer")]
+    pub(super) fn send_typed<T: Serialize>(
+        mut self,
+        addr: Option<net::SocketAddr>,
+        response_decompress: bool,
+        timeout: Option<Duration>,
+        config: &Rc<ClientConfig>,
+        value: &T,
+        content_type: &str,
+    ) -> SendClientRequest {
+        let serializer = match config.serializers.get(content_type) {
+            Some(serializer) => serializer,
+            None => {
+                return SendRequestError::NoSerializer(content_type.to_string()).into()
+            }
+        };
+        let body = match serializer.serialize(value) {
+            Ok(body) => body,
+            Err(e) => return SendRequestError::Error(e).into(),
+        };
+
+        // set content-type
+        if let Err(e) = self.set_header_if_none(header::CONTENT_TYPE, content_type) {
+            return e.into();
+        }
+
+        self.send_body(
+            addr,
+            response_decompress,
+            timeout,
+            config,
+            Body::Bytes(Bytes::from(body)),
+        )
+    }
+
+    pub(super) fn send_bytes<V>(
+        mut self,
+        addr: Option<net::SocketAddr>,
+        response_decompress: bool,
+        timeout: Option<Duration>,
+        config: &Rc<ClientConfig>,
+        content_type: V,
+        bytes: Bytes,
+    ) -> SendClientRequest
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<HttpError>,
+    {
+        // set content-type
+        if let Err(e) = self.set_header_if_none(header::CONTENT_TYPE, content_type) {
+            return e.into();
+        }
+
+        self.send_body(
+            addr,
+            response_decompress,
+            timeout,
+            config,
+            Body::Bytes(bytes),
+        )
+    }
+
+    pub(super) fn send_multipart(
+        mut self,
+        addr: Option<net::SocketAddr>,
+        response_decompress: bool,
+        timeout: Option<Duration>,
+        config: &Rc<ClientConfig>,
+        multipart: Multipart,
+    ) -> SendClientRequest {
+        // set content-type
+        if let Err(e) =
+            self.set_header_if_none(header::CONTENT_TYPE, multipart.content_type())
+        {
+            return e.into();
+        }
+
+        self.send_body(
+            addr,
+            response_decompress,
+            timeout,
+            config,
+            Body::from_message(multipart),
+        )
+    }
+
     pub(super) fn send_stream<S, E>(
         self,
         addr: Option<net::SocketAddr>,
         response_decompress: bool,
         timeout: Option<Duration>,
-        config: &ClientConfig,
+        config: &Rc<ClientConfig>,
         stream: S,
     ) -> SendClientRequest
     where
@@ -236,17 +1696,186 @@ impl RequestHeadType {
         )
     }
 
+    pub(super) fn send_sized_stream<S, E>(
+        self,
+        addr: Option<net::SocketAddr>,
+        response_decompress: bool,
+        timeout: Option<Duration>,
+        config: &Rc<ClientConfig>,
+        stream: S,
+        size: u64,
+    ) -> SendClientRequest
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+        E: Error + 'static,
+    {
+        self.send_body(
+            addr,
+            response_decompress,
+            timeout,
+            config,
+            Body::from_message(SizedBodyStream::new(stream, size)),
+        )
+    }
+
+    pub(super) fn send_reader<R>(
+        self,
+        addr: Option<net::SocketAddr>,
+        response_decompress: bool,
+        timeout: Option<Duration>,
+        config: &Rc<ClientConfig>,
+        reader: R,
+        size: Option<u64>,
+        buffer_size: usize,
+    ) -> SendClientRequest
+    where
+        R: AsyncRead + Unpin + 'static,
+    {
+        let stream = ReaderStream::new(reader, buffer_size);
+        let body = match size {
+            Some(size) => Body::from_message(SizedStream::new(size, stream)),
+            None => Body::from_message(BoxedBodyStream::new(stream)),
+        };
+        self.send_body(addr, response_decompress, timeout, config, body)
+    }
+
     pub(super) fn send(
         self,
         addr: Option<net::SocketAddr>,
         response_decompress: bool,
         timeout: Option<Duration>,
-        config: &ClientConfig,
+        config: &Rc<ClientConfig>,
     ) -> SendClientRequest {
         self.send_body(addr, response_decompress, timeout, config, Body::Empty)
     }
 
-    fn set_header_if_none<V>(
+    #[cfg(feature = "compress")]
+    fn compress_body(
+        &mut self,
+        encoding: ContentEncoding,
+        body: Body,
+    ) -> Result<Body, SendRequestError> {
+        let encoded = match self {
+            RequestHeadType::Owned(head) => {
+                head.headers.contains_key(&header::CONTENT_ENCODING)
+            }
+            RequestHeadType::Rc(head, extra_headers) => {
+                head.headers.contains_key(&header::CONTENT_ENCODING)
+                    || extra_headers
+                        .iter()
+                        .any(|h| h.contains_key(&header::CONTENT_ENCODING))
+            }
+        };
+
+        // do not compress empty or already encoded bodies
+        match body {
+            Body::None | Body::Empty => Ok(body),
+            _ if encoded || !encoding.is_compressed() => Ok(body),
+            _ => {
+                let body = Encoder::request(encoding, body)?;
+                self.set_header_if_none(header::CONTENT_ENCODING, encoding.as_str())?;
+                Ok(body)
+            }
+        }
+    }
+
+    /// Set HTTP basic authorization header, if request does not have one
+    ///
+    /// Username must be checked by caller, password may contain colons.
+    pub(super) fn basic_auth(
+        &mut self,
+        username: &str,
+        password: Option<&str>,
+    ) -> Result<(), HttpError> {
+        if matches!(self.as_ref().uri.scheme_str(), Some("http") | Some("ws")) {
+            log::warn!("Basic auth credentials are sent over insecure connection");
+        }
+
+        let auth = match password {
+            Some(password) => format!("{}:{}", username, password),
+            None => format!("{}:", username),
+        };
+        self.set_header_if_none(
+            header::AUTHORIZATION,
+            format!("Basic {}", base64::encode(&auth)),
+        )
+    }
+
+    /// Set HTTP bearer authentication header, if request does not have one
+    pub(super) fn bearer_auth(&mut self, token: &str) -> Result<(), HttpError> {
+        self.set_header_if_none(header::AUTHORIZATION, format!("Bearer {}", token))
+    }
+
+    /// Check that `Content-Length` header, if set, matches size of bytes body
+    /// and that http/1.0 request body has known size
+    fn check_content_length(&self, body: &Body) -> Result<(), SendRequestError> {
+        // http/1.0 body must be delimited by content length
+        if self.as_ref().version == Version::HTTP_10 && body.size() == BodySize::Stream {
+            return Err(SendRequestError::ChunkedNotSupported);
+        }
+
+        let size = match body {
+            Body::Bytes(bytes) => bytes.len() as u64,
+            _ => return Ok(()),
+        };
+        let value = match self {
+            RequestHeadType::Owned(head) => head.headers.get(header::CONTENT_LENGTH),
+            RequestHeadType::Rc(head, extra_headers) => extra_headers
+                .as_ref()
+                .and_then(|h| h.get(header::CONTENT_LENGTH))
+                .or_else(|| head.headers.get(header::CONTENT_LENGTH)),
+        };
+
+        match value {
+            Some(value)
+                if value.to_str().ok().and_then(|v| v.parse::<u64>().ok())
+                    != Some(size) =>
+            {
+                Err(SendRequestError::Error(Box::new(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Content-Length header {:?} does not match body size {}",
+                        value, size
+                    ),
+                ))))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check if request has header, including extra headers
+    pub(super) fn has_header(&self, key: &HeaderName) -> bool {
+        match self {
+            RequestHeadType::Owned(head) => head.headers.contains_key(key),
+            RequestHeadType::Rc(head, extra_headers) => {
+                head.headers.contains_key(key)
+                    || extra_headers.iter().any(|h| h.contains_key(key))
+            }
+        }
+    }
+
+    /// Restore owned head from not shared head
+    fn into_owned(self) -> Self {
+        match self {
+            RequestHeadType::Rc(head, extra_headers) => match Rc::try_unwrap(head) {
+                Ok(mut head) => {
+                    if let Some(extra_headers) = extra_headers {
+                        for key in extra_headers.keys() {
+                            head.headers.remove(key);
+                        }
+                        for (key, value) in extra_headers.iter() {
+                            head.headers.append(key.clone(), value.clone());
+                        }
+                    }
+                    RequestHeadType::Owned(head)
+                }
+                Err(head) => RequestHeadType::Rc(head, extra_headers),
+            },
+            head => head,
+        }
+    }
+
+    pub(super) fn set_header_if_none<V>(
         &mut self,
         key: HeaderName,
         value: V,
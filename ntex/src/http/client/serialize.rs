@@ -0,0 +1,141 @@
+//! Http client request body serializers
+use std::collections::HashMap;
+use std::error::Error;
+use std::rc::Rc;
+
+pub use erased_serde::Serialize as ErasedSerialize;
+
+/// Request body serializer used by `ClientRequest::send_typed()`.
+///
+/// Serializers are registered for media type with
+/// `ClientBuilder::body_serializer()`, serializer receives value passed
+/// to `send_typed()` as type erased `Serialize` trait object, which
+/// implements `serde::Serialize`. It is implemented for functions
+/// with `Fn(&dyn ErasedSerialize) -> Result<Vec<u8>, Box<dyn Error>>` signature.
+///
+/// ```rust
+/// use ntex::http::client::{Client, ErasedSerialize};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .body_serializer("application/x-yaml", |value: &dyn ErasedSerialize| {
+///             Ok(format!("value: {}", serde_json::to_string(value)?).into_bytes())
+///         })
+///         .finish();
+/// }
+/// ```
+pub trait BodySerializer {
+    /// Serialize value to request body bytes
+    fn serialize(&self, value: &dyn ErasedSerialize) -> Result<Vec<u8>, Box<dyn Error>>;
+}
+
+impl<F> BodySerializer for F
+where
+    F: Fn(&dyn ErasedSerialize) -> Result<Vec<u8>, Box<dyn Error>>,
+{
+    fn serialize(&self, value: &dyn ErasedSerialize) -> Result<Vec<u8>, Box<dyn Error>> {
+        (self)(value)
+    }
+}
+
+/// Json body serializer, registered for `application/json` by default
+#[derive(Copy, Clone, Debug)]
+pub struct Json;
+
+impl BodySerializer for Json {
+    fn serialize(&self, value: &dyn ErasedSerialize) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(serde_json::to_vec(value)?)
+    }
+}
+
+/// Urlencoded body serializer, registered for
+/// `application/x-www-form-urlencoded` by default
+#[derive(Copy, Clone, Debug)]
+pub struct Form;
+
+impl BodySerializer for Form {
+    fn serialize(&self, value: &dyn ErasedSerialize) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(serde_urlencoded::to_string(value)?.into_bytes())
+    }
+}
+
+#[cfg(feature = "msgpack")]
+/// MessagePack body serializer
+#[derive(Copy, Clone, Debug)]
+pub struct MsgPack;
+
+#[cfg(feature = "msgpack")]
+impl BodySerializer for MsgPack {
+    fn serialize(&self, value: &dyn ErasedSerialize) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(rmp_serde::to_vec(value)?)
+    }
+}
+
+/// Registered serializers by media type
+pub(super) struct Serializers(HashMap<String, Rc<dyn BodySerializer>>);
+
+impl Default for Serializers {
+    fn default() -> Self {
+        let mut serializers = Serializers(HashMap::new());
+        serializers.register("application/json", Rc::new(Json));
+        serializers.register("application/x-www-form-urlencoded", Rc::new(Form));
+        serializers
+    }
+}
+
+impl Serializers {
+    pub(super) fn register(
+        &mut self,
+        content_type: &str,
+        serializer: Rc<dyn BodySerializer>,
+    ) {
+        self.0.insert(media_type(content_type), serializer);
+    }
+
+    /// Find serializer for content type, parameters are ignored
+    pub(super) fn get(&self, content_type: &str) -> Option<Rc<dyn BodySerializer>> {
+        self.0.get(&media_type(content_type)).cloned()
+    }
+}
+
+fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializers() {
+        let mut serializers = Serializers::default();
+        let value = serde_json::json!({"a": "b"});
+
+        let json = serializers.get("Application/JSON; charset=utf-8").unwrap();
+        assert_eq!(json.serialize(&value).unwrap(), b"{\"a\":\"b\"}");
+        let form = serializers
+            .get("application/x-www-form-urlencoded")
+            .unwrap();
+        assert_eq!(form.serialize(&value).unwrap(), b"a=b");
+        assert!(serializers.get("application/cbor").is_none());
+
+        serializers.register(
+            "application/cbor",
+            Rc::new(|_: &dyn ErasedSerialize| Ok(b"cbor".to_vec())),
+        );
+        let cbor = serializers.get("application/cbor").unwrap();
+        assert_eq!(cbor.serialize(&value).unwrap(), b"cbor");
+
+        // value is serialized directly, not converted to json value
+        assert_eq!(
+            json.serialize(&u128::MAX).unwrap(),
+            u128::MAX.to_string().as_bytes()
+        );
+    }
+}
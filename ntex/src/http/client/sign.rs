@@ -0,0 +1,121 @@
+//! Http client request signing
+use bytes::BytesMut;
+use futures::future::poll_fn;
+
+use crate::http::body::Body;
+use crate::http::RequestHeadType;
+
+use super::error::SendRequestError;
+
+/// Request body passed to request signer
+#[derive(Copy, Clone, Debug)]
+pub enum SignPayload<'a> {
+    /// Request does not have a body
+    Empty,
+    /// Request body bytes
+    Bytes(&'a [u8]),
+    /// Request body is a stream that is not buffered, signer should
+    /// use unsigned payload marker
+    Unsigned,
+}
+
+/// How request signer handles streaming bodies
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StreamingBody {
+    /// Stream is sent as is, signer gets `SignPayload::Unsigned`
+    Unsigned,
+    /// Stream is read into memory before signing, signer gets
+    /// `SignPayload::Bytes`
+    Buffer,
+}
+
+/// Request signer is called after request headers are finalized,
+/// right before request is sent.
+///
+/// Signer receives request head, including method, url and headers,
+/// and request body, it could compute a signature and add it to
+/// request headers. Request interceptors are called before signer.
+/// Every redirect hop is signed again. It is implemented for
+/// functions with `Fn(&mut RequestHeadType, SignPayload<'_>) -> Result<(), SendRequestError>`
+/// signature, such signers do not buffer streaming bodies.
+///
+/// ```rust
+/// use ntex::http::client::{Client, SignPayload};
+/// use ntex::http::header::{HeaderName, HeaderValue};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .signer(|head: &mut ntex::http::RequestHeadType, body: SignPayload<'_>| {
+///             let size = match body {
+///                 SignPayload::Empty => "0".to_string(),
+///                 SignPayload::Bytes(bytes) => bytes.len().to_string(),
+///                 SignPayload::Unsigned => "unsigned".to_string(),
+///             };
+///             head.insert_header(
+///                 HeaderName::from_static("x-content-size"),
+///                 HeaderValue::from_str(&size).unwrap(),
+///             );
+///             Ok(())
+///         })
+///         .finish();
+/// }
+/// ```
+pub trait RequestSigner {
+    /// Sign request
+    fn sign(
+        &self,
+        head: &mut RequestHeadType,
+        body: SignPayload<'_>,
+    ) -> Result<(), SendRequestError>;
+
+    /// How streaming bodies are signed, by default streams are not buffered
+    fn streaming_body(&self) -> StreamingBody {
+        StreamingBody::Unsigned
+    }
+}
+
+impl<F> RequestSigner for F
+where
+    F: Fn(&mut RequestHeadType, SignPayload<'_>) -> Result<(), SendRequestError>,
+{
+    fn sign(
+        &self,
+        head: &mut RequestHeadType,
+        body: SignPayload<'_>,
+    ) -> Result<(), SendRequestError> {
+        (self)(head, body)
+    }
+}
+
+/// Sign request, streaming body is buffered if signer requires it
+pub(super) async fn sign(
+    signer: &dyn RequestSigner,
+    head: &mut RequestHeadType,
+    body: Body,
+) -> Result<Body, SendRequestError> {
+    match body {
+        Body::None | Body::Empty => {
+            signer.sign(head, SignPayload::Empty)?;
+            Ok(body)
+        }
+        Body::Bytes(ref bytes) => {
+            signer.sign(head, SignPayload::Bytes(bytes))?;
+            Ok(body)
+        }
+        Body::Message(mut stream) => match signer.streaming_body() {
+            StreamingBody::Unsigned => {
+                signer.sign(head, SignPayload::Unsigned)?;
+                Ok(Body::Message(stream))
+            }
+            StreamingBody::Buffer => {
+                let mut buf = BytesMut::new();
+                while let Some(chunk) = poll_fn(|cx| stream.poll_next_chunk(cx)).await {
+                    buf.extend_from_slice(&chunk.map_err(SendRequestError::Error)?);
+                }
+                signer.sign(head, SignPayload::Bytes(&buf))?;
+                Ok(Body::Bytes(buf.freeze()))
+            }
+        },
+    }
+}
@@ -48,6 +48,12 @@ impl TestResponse {
         self
     }
 
+    /// Set status code of this response
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.head.status = status;
+        self
+    }
+
     /// Append a header
     pub fn header<K, V>(mut self, key: K, value: V) -> Self
     where
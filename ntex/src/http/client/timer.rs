@@ -0,0 +1,109 @@
+//! Custom timer source for request timeouts.
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::task::{Poll, Waker};
+use std::time::Duration;
+
+use futures::future::{poll_fn, LocalBoxFuture};
+
+/// Source of request timeout delays
+///
+/// By default request timeouts use runtime timer, custom timer could be
+/// set with `ClientBuilder::timer()`. Timer applies to request timeouts
+/// only, connect, write and retry delays use runtime timer.
+pub trait Timer {
+    /// Time elapsed since timer start
+    fn elapsed(&self) -> Duration;
+
+    /// Create future that resolves after `delay`
+    fn delay(&self, delay: Duration) -> LocalBoxFuture<'static, ()>;
+}
+
+/// Virtual clock, advanced manually
+///
+/// Delays of the clock resolve only when clock is advanced past
+/// their deadline, so timeouts could be triggered without real sleeps.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use ntex::http::client::{error::SendRequestError, Client, VirtualClock};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let clock = VirtualClock::new();
+///     let client = Client::build().timer(clock.clone()).finish();
+///
+///     let request = client.get("http://www.rust-lang.org").send();
+///     clock.advance(Duration::from_secs(5));
+///     let res = request.await;
+///     assert!(matches!(res, Err(SendRequestError::Timeout { .. })));
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct VirtualClock(Rc<RefCell<ClockInner>>);
+
+#[derive(Default)]
+struct ClockInner {
+    now: Duration,
+    waiters: Vec<Waker>,
+}
+
+impl VirtualClock {
+    /// Create clock at zero time
+    pub fn new() -> Self {
+        VirtualClock::default()
+    }
+
+    /// Move clock forward, wakes up expired delays
+    pub fn advance(&self, duration: Duration) {
+        let waiters = {
+            let mut inner = self.0.borrow_mut();
+            inner.now += duration;
+            std::mem::take(&mut inner.waiters)
+        };
+        for waker in waiters {
+            waker.wake();
+        }
+    }
+}
+
+impl Timer for VirtualClock {
+    fn elapsed(&self) -> Duration {
+        self.0.borrow().now
+    }
+
+    fn delay(&self, delay: Duration) -> LocalBoxFuture<'static, ()> {
+        let inner = self.0.clone();
+        let deadline = inner.borrow().now + delay;
+        Box::pin(poll_fn(move |cx| {
+            let mut inner = inner.borrow_mut();
+            if inner.now >= deadline {
+                Poll::Ready(())
+            } else {
+                inner.waiters.push(cx.waker().clone());
+                Poll::Pending
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    #[ntex_rt::test]
+    async fn test_virtual_clock() {
+        let clock = VirtualClock::new();
+        let mut delay = clock.delay(Duration::from_secs(2));
+        assert!(delay.as_mut().now_or_never().is_none());
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(clock.elapsed(), Duration::from_secs(1));
+        assert!(delay.as_mut().now_or_never().is_none());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(delay.now_or_never().is_some());
+        assert!(clock.delay(Duration::from_secs(0)).now_or_never().is_some());
+    }
+}
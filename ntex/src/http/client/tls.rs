@@ -0,0 +1,355 @@
+//! Trusted root certificates, client identity and session details of http client
+//! secure connector
+use std::{fmt, rc::Rc};
+
+#[cfg(all(not(feature = "openssl"), feature = "rustls"))]
+use crate::connect::rustls::ClientConfig;
+#[cfg(feature = "openssl")]
+use open_ssl::ssl::{SslConnectorBuilder, SslRef};
+
+#[derive(Clone, Debug)]
+#[cfg_attr(not(any(feature = "openssl", feature = "rustls")), allow(dead_code))]
+enum Certificate {
+    Pem(Vec<u8>),
+    Der(Vec<u8>),
+}
+
+/// Additional trusted root certificates of default secure connector.
+///
+/// Certificates are trusted in addition to system roots, in strict mode
+/// only added certificates are trusted. Certificates that could not be
+/// parsed are skipped and logged.
+///
+/// ```rust
+/// use ntex::http::client::{Client, RootCertificates};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let ca = std::fs::read("./tests/ca.pem").unwrap();
+///     let client = Client::build()
+///         .root_certificates(RootCertificates::new().pem(ca))
+///         .finish();
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(not(any(feature = "openssl", feature = "rustls")), allow(dead_code))]
+pub struct RootCertificates {
+    certs: Vec<Certificate>,
+    strict: bool,
+}
+
+impl RootCertificates {
+    /// Create empty set of root certificates
+    pub fn new() -> Self {
+        RootCertificates::default()
+    }
+
+    /// Add PEM encoded certificates, bundle could contain several certificates
+    pub fn pem<T: Into<Vec<u8>>>(mut self, pem: T) -> Self {
+        self.certs.push(Certificate::Pem(pem.into()));
+        self
+    }
+
+    /// Add DER encoded certificate
+    pub fn der<T: Into<Vec<u8>>>(mut self, der: T) -> Self {
+        self.certs.push(Certificate::Der(der.into()));
+        self
+    }
+
+    /// Trust only added certificates, system roots are not used
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    #[cfg(feature = "openssl")]
+    /// Add certificates to the openssl connector trust store
+    pub(super) fn configure_openssl(&self, builder: &mut SslConnectorBuilder) {
+        use open_ssl::x509::{store::X509StoreBuilder, X509};
+
+        let mut store = if self.strict {
+            match X509StoreBuilder::new() {
+                Ok(store) => Some(store),
+                Err(e) => {
+                    error!("Can not create certificate store: {:?}", e);
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+
+        for cert in &self.certs {
+            let certs = match cert {
+                Certificate::Pem(pem) => X509::stack_from_pem(pem),
+                Certificate::Der(der) => X509::from_der(der).map(|cert| vec![cert]),
+            };
+            let certs = match certs {
+                Ok(certs) => certs,
+                Err(e) => {
+                    error!("Can not parse root certificate: {:?}", e);
+                    continue;
+                }
+            };
+            for cert in certs {
+                let res = if let Some(ref mut store) = store {
+                    store.add_cert(cert)
+                } else {
+                    builder.cert_store_mut().add_cert(cert)
+                };
+                if let Err(e) = res {
+                    error!("Can not add root certificate: {:?}", e);
+                }
+            }
+        }
+
+        if let Some(store) = store {
+            builder.set_cert_store(store.build());
+        }
+    }
+
+    #[cfg(all(not(feature = "openssl"), feature = "rustls"))]
+    /// Add certificates and system roots, if not strict, to the rustls config
+    pub(super) fn configure_rustls(&self, config: &mut ClientConfig) {
+        if !self.strict {
+            config
+                .root_store
+                .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+        }
+
+        for cert in &self.certs {
+            let res = match cert {
+                Certificate::Pem(pem) => config
+                    .root_store
+                    .add_pem_file(&mut pem.as_slice())
+                    .map_err(|_| "invalid pem file".to_string())
+                    .and_then(|(_, invalid)| {
+                        if invalid == 0 {
+                            Ok(())
+                        } else {
+                            Err(format!("{} invalid certificates", invalid))
+                        }
+                    }),
+                Certificate::Der(der) => config
+                    .root_store
+                    .add(&rust_tls::Certificate(der.clone()))
+                    .map_err(|e| e.to_string()),
+            };
+            if let Err(e) = res {
+                error!("Can not add root certificate: {}", e);
+            }
+        }
+    }
+}
+
+/// Client certificate chain and private key (mutual TLS).
+///
+/// Identity is presented by default secure connector if server requests
+/// client certificate. Identity that could not be loaded is skipped and
+/// logged.
+///
+/// ```rust
+/// use ntex::http::client::{Client, ClientIdentity};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let cert = std::fs::read("./tests/localhost-cert.pem").unwrap();
+///     let key = std::fs::read("./tests/localhost-key.pem").unwrap();
+///     let client = Client::build()
+///         .client_identity(ClientIdentity::pem(cert, key))
+///         .finish();
+/// }
+/// ```
+#[derive(Clone)]
+#[cfg_attr(not(any(feature = "openssl", feature = "rustls")), allow(dead_code))]
+pub struct ClientIdentity {
+    cert: Vec<u8>,
+    key: Vec<u8>,
+}
+
+impl ClientIdentity {
+    /// Create identity from PEM encoded certificate chain and private key
+    ///
+    /// Certificate chain starts with client certificate, private key
+    /// could be PKCS8 or RSA key.
+    pub fn pem<C, K>(cert: C, key: K) -> Self
+    where
+        C: Into<Vec<u8>>,
+        K: Into<Vec<u8>>,
+    {
+        ClientIdentity {
+            cert: cert.into(),
+            key: key.into(),
+        }
+    }
+
+    #[cfg(feature = "openssl")]
+    /// Set certificate chain and private key of the openssl connector
+    pub(super) fn configure_openssl(&self, builder: &mut SslConnectorBuilder) {
+        use open_ssl::{pkey::PKey, x509::X509};
+
+        let res = X509::stack_from_pem(&self.cert).and_then(|certs| {
+            let mut certs = certs.into_iter();
+            if let Some(cert) = certs.next() {
+                builder.set_certificate(&cert)?;
+            }
+            for cert in certs {
+                builder.add_extra_chain_cert(cert)?;
+            }
+            let key = PKey::private_key_from_pem(&self.key)?;
+            builder.set_private_key(&key)?;
+            builder.check_private_key()
+        });
+        if let Err(e) = res {
+            error!("Can not load client identity: {:?}", e);
+        }
+    }
+
+    #[cfg(all(not(feature = "openssl"), feature = "rustls"))]
+    /// Set certificate chain and private key of the rustls config
+    pub(super) fn configure_rustls(&self, config: &mut ClientConfig) {
+        use rust_tls::internal::pemfile;
+
+        let certs = pemfile::certs(&mut self.cert.as_slice()).unwrap_or_default();
+        let mut keys =
+            pemfile::pkcs8_private_keys(&mut self.key.as_slice()).unwrap_or_default();
+        if keys.is_empty() {
+            keys =
+                pemfile::rsa_private_keys(&mut self.key.as_slice()).unwrap_or_default();
+        }
+        if certs.is_empty() || keys.is_empty() {
+            error!("Can not load client identity: invalid pem file");
+            return;
+        }
+        if let Err(e) = config.set_single_client_cert(certs, keys.remove(0)) {
+            error!("Can not load client identity: {}", e);
+        }
+    }
+}
+
+impl fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientIdentity").finish()
+    }
+}
+
+/// Negotiated tls session details of the connection.
+///
+/// ```rust
+/// use ntex::http::client::Client;
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let res = Client::new().get("https://www.rust-lang.org").send().await;
+///     if let Some(tls) = res.as_ref().ok().and_then(|res| res.tls_info()) {
+///         println!("{} {} {:?}", tls.version(), tls.cipher(), tls.peer_subject());
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct TlsInfo(Rc<TlsSession>);
+
+struct TlsSession {
+    version: String,
+    cipher: String,
+    peer_subject: Option<String>,
+}
+
+impl TlsInfo {
+    /// Negotiated protocol version, i.e. `TLSv1.3`
+    pub fn version(&self) -> &str {
+        &self.0.version
+    }
+
+    /// Negotiated cipher suite name
+    pub fn cipher(&self) -> &str {
+        &self.0.cipher
+    }
+
+    /// Subject of the peer certificate, i.e. `CN=www.rust-lang.org`
+    ///
+    /// Subject is available for connections of openssl connector only.
+    pub fn peer_subject(&self) -> Option<&str> {
+        self.0.peer_subject.as_deref()
+    }
+
+    #[cfg(feature = "openssl")]
+    /// Capture session details of established openssl connection
+    pub(super) fn openssl(ssl: &SslRef) -> Self {
+        let cipher = ssl
+            .current_cipher()
+            .map(|cipher| cipher.standard_name().unwrap_or_else(|| cipher.name()))
+            .unwrap_or("");
+        let peer_subject = ssl.peer_certificate().map(|cert| {
+            cert.subject_name()
+                .entries()
+                .map(|entry| {
+                    let name = entry.object().nid().short_name().unwrap_or("?");
+                    match entry.data().to_string() {
+                        Ok(value) => format!("{}={}", name, value),
+                        Err(_) => format!("{}=?", name),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+        TlsInfo(Rc::new(TlsSession {
+            version: ssl.version_str().to_string(),
+            cipher: cipher.to_string(),
+            peer_subject,
+        }))
+    }
+
+    #[cfg(feature = "rustls")]
+    /// Capture session details of established rustls connection
+    pub(super) fn rustls(session: &rust_tls::ClientSession) -> Self {
+        use rust_tls::{ProtocolVersion, Session};
+
+        let version = match session.get_protocol_version() {
+            Some(ProtocolVersion::TLSv1_2) => "TLSv1.2".to_string(),
+            Some(ProtocolVersion::TLSv1_3) => "TLSv1.3".to_string(),
+            Some(version) => format!("{:?}", version),
+            None => String::new(),
+        };
+        let cipher = session
+            .get_negotiated_ciphersuite()
+            .map(|suite| format!("{:?}", suite.suite))
+            .unwrap_or_default();
+        TlsInfo(Rc::new(TlsSession {
+            version,
+            cipher,
+            peer_subject: None,
+        }))
+    }
+}
+
+impl fmt::Debug for TlsInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsInfo")
+            .field("version", &self.0.version)
+            .field("cipher", &self.0.cipher)
+            .field("peer_subject", &self.0.peer_subject)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_certificates() {
+        let certs = RootCertificates::new().pem("pem").der(vec![1, 2]);
+        assert_eq!(certs.certs.len(), 2);
+        assert!(!certs.strict);
+        assert!(certs.strict().strict);
+    }
+
+    #[test]
+    fn test_client_identity() {
+        let identity = ClientIdentity::pem("cert", "key");
+        assert_eq!(identity.cert, b"cert");
+        assert_eq!(identity.key, b"key");
+        assert_eq!(format!("{:?}", identity), "ClientIdentity");
+    }
+}
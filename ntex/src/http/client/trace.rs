@@ -0,0 +1,157 @@
+//! W3C trace context propagation
+use std::fmt;
+
+use crate::http::error::HttpError;
+use crate::http::header::HeaderName;
+use crate::http::RequestHeadType;
+
+/// W3C trace context of outgoing request.
+///
+/// Client's trace context provider returns context of the current
+/// operation, client injects it into `traceparent` and `tracestate`
+/// request headers.
+///
+/// ```rust
+/// use ntex::http::client::{Client, TraceContext};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .trace_context(|| {
+///             Some(TraceContext::new(0x4bf92f3577b34da6a3ce929d0e0e4736, 0x00f067aa0ba902b7))
+///         })
+///         .finish();
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: u128,
+    parent_id: u64,
+    sampled: bool,
+    state: Option<String>,
+}
+
+impl TraceContext {
+    /// Create new sampled trace context.
+    ///
+    /// Context with zero trace id or parent id is invalid and
+    /// is not propagated.
+    pub fn new(trace_id: u128, parent_id: u64) -> Self {
+        TraceContext {
+            trace_id,
+            parent_id,
+            sampled: true,
+            state: None,
+        }
+    }
+
+    /// Set sampled flag of the context.
+    pub fn sampled(mut self, sampled: bool) -> Self {
+        self.sampled = sampled;
+        self
+    }
+
+    /// Set vendor specific `tracestate` value.
+    pub fn state<T: Into<String>>(mut self, state: T) -> Self {
+        self.state = Some(state.into());
+        self
+    }
+
+    /// Trace id of the context.
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    /// Parent span id of the context.
+    pub fn parent_id(&self) -> u64 {
+        self.parent_id
+    }
+
+    /// Check if context is valid
+    fn is_valid(&self) -> bool {
+        self.trace_id != 0 && self.parent_id != 0
+    }
+}
+
+/// `traceparent` header value
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id, self.parent_id, self.sampled as u8
+        )
+    }
+}
+
+/// Inject trace context into request headers.
+///
+/// Request headers set by caller are preserved, `tracestate` is not added
+/// if request already has `traceparent` header.
+pub(super) fn inject(
+    head: &mut RequestHeadType,
+    ctx: &TraceContext,
+) -> Result<(), HttpError> {
+    let traceparent = HeaderName::from_static("traceparent");
+    if !ctx.is_valid() || head.has_header(&traceparent) {
+        return Ok(());
+    }
+
+    head.set_header_if_none(traceparent, ctx.to_string())?;
+    if let Some(ref state) = ctx.state {
+        head.set_header_if_none(HeaderName::from_static("tracestate"), state.as_str())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::HeaderValue;
+    use crate::http::RequestHead;
+
+    #[test]
+    fn test_traceparent() {
+        let ctx =
+            TraceContext::new(0x4bf92f3577b34da6a3ce929d0e0e4736, 0xf067aa0ba902b7);
+        assert_eq!(
+            ctx.to_string(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        assert_eq!(
+            ctx.sampled(false).to_string(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00"
+        );
+    }
+
+    #[test]
+    fn test_inject() {
+        let ctx = TraceContext::new(1, 2).state("congo=t61rcWkgMzE");
+
+        let mut head = RequestHeadType::Owned(RequestHead::default());
+        inject(&mut head, &ctx).unwrap();
+        let headers = &head.as_ref().headers;
+        assert_eq!(
+            headers.get("traceparent").unwrap(),
+            "00-00000000000000000000000000000001-0000000000000002-01"
+        );
+        assert_eq!(headers.get("tracestate").unwrap(), "congo=t61rcWkgMzE");
+
+        // invalid context
+        let mut head = RequestHeadType::Owned(RequestHead::default());
+        inject(&mut head, &TraceContext::new(0, 2)).unwrap();
+        assert!(head.as_ref().headers.is_empty());
+
+        // caller's traceparent is preserved
+        let mut head = RequestHead::default();
+        head.headers.insert(
+            HeaderName::from_static("traceparent"),
+            HeaderValue::from_static("caller"),
+        );
+        let mut head = RequestHeadType::Owned(head);
+        inject(&mut head, &ctx).unwrap();
+        let headers = &head.as_ref().headers;
+        assert_eq!(headers.get("traceparent").unwrap(), "caller");
+        assert!(headers.get("tracestate").is_none());
+    }
+}
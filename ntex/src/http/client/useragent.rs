@@ -0,0 +1,113 @@
+//! Http client `User-Agent` rotation
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::http::header::HeaderValue;
+
+/// Pool of `User-Agent` header values rotated per request.
+///
+/// By default values are used in round-robin order, `random()` picks
+/// random value for each request. Rotation is not applied to requests
+/// with explicitly set `User-Agent` header.
+///
+/// ```rust
+/// use ntex::http::client::{Client, UserAgents};
+///
+/// #[ntex::main]
+/// async fn main() {
+///     let client = Client::build()
+///         .user_agents(UserAgents::new(vec!["agent/1.0", "agent/2.0"]).random())
+///         .finish();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct UserAgents {
+    agents: Vec<HeaderValue>,
+    random: bool,
+}
+
+impl UserAgents {
+    /// Create new pool of `User-Agent` values.
+    ///
+    /// Invalid header values are skipped.
+    pub fn new<I, V>(agents: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: fmt::Debug,
+    {
+        let agents = agents
+            .into_iter()
+            .filter_map(|agent| match HeaderValue::try_from(agent) {
+                Ok(agent) => Some(agent),
+                Err(e) => {
+                    log::error!("User-Agent value error: {:?}", e);
+                    None
+                }
+            })
+            .collect();
+        UserAgents {
+            agents,
+            random: false,
+        }
+    }
+
+    /// Pick random value for each request.
+    pub fn random(mut self) -> Self {
+        self.random = true;
+        self
+    }
+}
+
+/// Rotation state of `User-Agent` pool
+pub(super) struct UserAgentRotation {
+    agents: UserAgents,
+    next: Cell<usize>,
+}
+
+impl UserAgentRotation {
+    pub(super) fn new(agents: UserAgents) -> Self {
+        UserAgentRotation {
+            agents,
+            next: Cell::new(0),
+        }
+    }
+
+    /// Get `User-Agent` value for next request.
+    pub(super) fn next(&self) -> Option<HeaderValue> {
+        let agents = &self.agents.agents;
+        if agents.is_empty() {
+            return None;
+        }
+        let idx = if self.agents.random {
+            rand::random::<usize>() % agents.len()
+        } else {
+            let idx = self.next.get() % agents.len();
+            self.next.set(idx + 1);
+            idx
+        };
+        Some(agents[idx].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let rotation = UserAgentRotation::new(UserAgents::new(vec!["a", "b\n", "c"]));
+        let agents: Vec<_> = (0..4).map(|_| rotation.next().unwrap()).collect();
+        assert_eq!(agents, vec!["a", "c", "a", "c"]);
+
+        let rotation = UserAgentRotation::new(UserAgents::new(vec!["a", "b"]).random());
+        for _ in 0..10 {
+            let agent = rotation.next().unwrap();
+            assert!(agent == "a" || agent == "b");
+        }
+
+        let rotation = UserAgentRotation::new(UserAgents::new(Vec::<&str>::new()));
+        assert!(rotation.next().is_none());
+    }
+}
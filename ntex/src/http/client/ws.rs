@@ -21,8 +21,9 @@ use crate::ws;
 pub use crate::ws::{CloseCode, CloseReason, Frame, Message};
 
 use super::connect::BoxedSocket;
-use super::error::{InvalidUrl, SendRequestError, WsClientError};
+use super::error::{InvalidUrl, WsClientError};
 use super::response::ClientResponse;
+use super::sender::RequestPhase;
 use super::ClientConfig;
 
 /// `WebSocket` connection
@@ -80,6 +81,10 @@ impl WebsocketsRequest {
     }
 
     /// Set supported websocket protocols
+    ///
+    /// Protocol selected by the server is available in `Sec-WebSocket-Protocol`
+    /// header of the response, connection fails if server selects protocol
+    /// that is not requested.
     pub fn protocols<U, V>(mut self, protos: U) -> Self
     where
         U: IntoIterator<Item = V>,
@@ -230,24 +235,23 @@ impl WebsocketsRequest {
 
         // validate uri
         let uri = &self.head.uri;
+        match uri.scheme_str() {
+            Some("http") | Some("ws") | Some("https") | Some("wss") => (),
+            Some(_) => return Err(InvalidUrl::UnsupportedScheme.into()),
+            None => return Err(InvalidUrl::MissingScheme.into()),
+        }
         if uri.host().is_none() {
             return Err(InvalidUrl::MissingHost.into());
-        } else if uri.scheme().is_none() {
-            return Err(InvalidUrl::MissingScheme.into());
-        } else if let Some(scheme) = uri.scheme() {
-            match scheme.as_str() {
-                "http" | "ws" | "https" | "wss" => (),
-                _ => return Err(InvalidUrl::UnknownScheme.into()),
-            }
-        } else {
-            return Err(InvalidUrl::UnknownScheme.into());
         }
 
         if !self.head.headers.contains_key(header::HOST) {
-            self.head.headers.insert(
-                header::HOST,
-                HeaderValue::from_str(uri.host().unwrap()).unwrap(),
-            );
+            let host = match uri.port_u16() {
+                None | Some(80) | Some(443) => uri.host().unwrap().to_string(),
+                Some(port) => format!("{}:{}", uri.host().unwrap(), port),
+            };
+            self.head
+                .headers
+                .insert(header::HOST, HeaderValue::from_str(&host).unwrap());
         }
 
         #[cfg(feature = "cookie")]
@@ -290,7 +294,7 @@ impl WebsocketsRequest {
             HeaderValue::from_static("13"),
         );
 
-        if let Some(protocols) = self.protocols.take() {
+        if let Some(ref protocols) = self.protocols {
             self.head.headers.insert(
                 header::SEC_WEBSOCKET_PROTOCOL,
                 HeaderValue::try_from(protocols.as_str()).unwrap(),
@@ -308,17 +312,24 @@ impl WebsocketsRequest {
             HeaderValue::try_from(key.as_str()).unwrap(),
         );
 
+        let phase = RequestPhase::default();
+
         let head = self.head;
         let max_size = self.max_size;
         let server_mode = self.server_mode;
 
-        let fut = self.config.connector.open_tunnel(head.into(), self.addr);
+        let fut = self.config.connector.open_tunnel(
+            head.into(),
+            self.addr,
+            &self.config,
+            phase.clone(),
+        );
 
         // set request timeout
         let (head, framed) = if let Some(to) = self.config.timeout {
             timeout(to, fut)
                 .await
-                .map_err(|_| SendRequestError::Timeout)
+                .map_err(|_| phase.timeout())
                 .and_then(|res| res)?
         } else {
             fut.await?
@@ -378,6 +389,20 @@ impl WebsocketsRequest {
             return Err(WsClientError::MissingWebSocketAcceptHeader);
         };
 
+        // selected protocol must be one of requested protocols
+        if let Some(proto) = head.headers.get(&header::SEC_WEBSOCKET_PROTOCOL) {
+            let requested = match (proto.to_str(), self.protocols) {
+                (Ok(proto), Some(protocols)) => {
+                    protocols.split(',').any(|p| p.trim() == proto.trim())
+                }
+                _ => false,
+            };
+            if !requested {
+                log::trace!("Invalid websocket protocol: {:?}", proto);
+                return Err(WsClientError::InvalidProtocol(proto.clone()));
+            }
+        }
+
         // response and ws framed
         Ok((
             ClientResponse::new(head, Payload::None),
@@ -427,7 +452,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::http::client::Client;
+    use crate::http::client::{error::SendRequestError, Client};
 
     #[ntex_rt::test]
     async fn test_debug() {
@@ -527,5 +552,11 @@ mod tests {
         assert!(Client::new().ws("/").connect().await.is_err());
         assert!(Client::new().ws("http:///test").connect().await.is_err());
         assert!(Client::new().ws("hmm://test.com/").connect().await.is_err());
+        assert!(matches!(
+            Client::new().ws("ftp://test.com/").connect().await,
+            Err(WsClientError::SendRequest(SendRequestError::Url(
+                InvalidUrl::UnsupportedScheme
+            )))
+        ));
     }
 }
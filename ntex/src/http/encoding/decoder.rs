@@ -5,9 +5,11 @@ use std::task::{Context, Poll};
 
 use actix_threadpool::{run, CpuFuture};
 use brotli2::write::BrotliDecoder;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use flate2::write::{GzDecoder, ZlibDecoder};
 use futures::{ready, Stream};
+#[cfg(feature = "zstd")]
+use z_std::stream::write::Decoder as ZstdDecoder;
 
 use super::Writer;
 use crate::http::error::PayloadError;
@@ -18,7 +20,12 @@ const INPLACE: usize = 2049;
 pub struct Decoder<S> {
     decoder: Option<ContentDecoder>,
     stream: S,
+    error: Option<PayloadError>,
     eof: bool,
+    lenient: bool,
+    max_ratio: Option<usize>,
+    input: u64,
+    output: u64,
     fut: Option<CpuFuture<(Option<Bytes>, ContentDecoder), io::Error>>,
 }
 
@@ -29,41 +36,104 @@ where
     /// Construct a decoder.
     #[inline]
     pub fn new(stream: S, encoding: ContentEncoding) -> Decoder<S> {
-        let decoder = match encoding {
-            ContentEncoding::Br => Some(ContentDecoder::Br(Box::new(
-                BrotliDecoder::new(Writer::new()),
-            ))),
-            ContentEncoding::Deflate => Some(ContentDecoder::Deflate(Box::new(
-                ZlibDecoder::new(Writer::new()),
-            ))),
-            ContentEncoding::Gzip => Some(ContentDecoder::Gzip(Box::new(
-                GzDecoder::new(Writer::new()),
-            ))),
-            _ => None,
-        };
+        Self::with_decoder(stream, ContentDecoder::new(encoding), None)
+    }
+
+    fn with_decoder(
+        stream: S,
+        decoder: Option<ContentDecoder>,
+        error: Option<PayloadError>,
+    ) -> Decoder<S> {
         Decoder {
             decoder,
             stream,
+            error,
             fut: None,
             eof: false,
+            lenient: false,
+            max_ratio: None,
+            input: 0,
+            output: 0,
         }
     }
 
+    /// Ignore trailing data after the end of gzip stream.
+    ///
+    /// By default trailing data is an error.
+    #[inline]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Set max ratio of decoded payload size to encoded payload size.
+    ///
+    /// Stream fails with `PayloadError::DecompressionRatioExceeded` once
+    /// decoded size exceeds `ratio` times encoded size. Ratio is checked
+    /// after each decoded chunk. By default ratio is not limited.
+    #[inline]
+    pub fn max_ratio(mut self, ratio: Option<usize>) -> Self {
+        self.max_ratio = ratio;
+        self
+    }
+
+    /// Check decoded chunk against max ratio
+    fn check_ratio(&mut self, chunk: &Bytes) -> Result<(), PayloadError> {
+        self.output += chunk.len() as u64;
+        if let Some(ratio) = self.max_ratio {
+            if self.output > self.input.saturating_mul(ratio as u64) {
+                return Err(PayloadError::DecompressionRatioExceeded(ratio));
+            }
+        }
+        Ok(())
+    }
+
     /// Construct decoder based on headers.
+    ///
+    /// Content encoding could be a list of encodings in order they were
+    /// applied, payload is decoded in reverse order. If list contains
+    /// unsupported encoding, stream fails with `PayloadError::UnknownEncoding`.
     #[inline]
     pub fn from_headers(stream: S, headers: &HeaderMap) -> Decoder<S> {
         // check content-encoding
-        let encoding = if let Some(enc) = headers.get(&CONTENT_ENCODING) {
+        let mut encodings = Vec::new();
+        for enc in headers.get_all(&CONTENT_ENCODING) {
             if let Ok(enc) = enc.to_str() {
-                ContentEncoding::from(enc)
+                encodings
+                    .extend(enc.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()));
             } else {
-                ContentEncoding::Identity
+                return Self::new(stream, ContentEncoding::Identity);
+            }
+        }
+
+        if encodings.len() < 2 {
+            let encoding = encodings
+                .pop()
+                .map(ContentEncoding::from)
+                .unwrap_or(ContentEncoding::Identity);
+            return Self::new(stream, encoding);
+        }
+
+        // chained encodings, last applied encoding is decoded first
+        let mut decoders = Vec::new();
+        for enc in encodings.into_iter().rev() {
+            if enc.eq_ignore_ascii_case("identity") {
+                continue;
             }
+            match ContentDecoder::new(ContentEncoding::from(enc)) {
+                Some(decoder) => decoders.push(decoder),
+                None => {
+                    let err = PayloadError::UnknownEncoding(enc.to_string());
+                    return Self::with_decoder(stream, None, Some(err));
+                }
+            }
+        }
+        let decoder = if decoders.len() > 1 {
+            Some(ContentDecoder::Chain(decoders))
         } else {
-            ContentEncoding::Identity
+            decoders.pop()
         };
-
-        Self::new(stream, encoding)
+        Self::with_decoder(stream, decoder, None)
     }
 }
 
@@ -77,6 +147,11 @@ where
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
+        if let Some(err) = self.error.take() {
+            self.eof = true;
+            return Poll::Ready(Some(Err(err)));
+        }
+
         loop {
             if let Some(ref mut fut) = self.fut {
                 let (chunk, decoder) = match ready!(Pin::new(fut).poll(cx)) {
@@ -86,6 +161,7 @@ where
                 self.decoder = Some(decoder);
                 self.fut.take();
                 if let Some(chunk) = chunk {
+                    self.check_ratio(&chunk)?;
                     return Poll::Ready(Some(Ok(chunk)));
                 }
             }
@@ -98,15 +174,18 @@ where
                 Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
                 Poll::Ready(Some(Ok(chunk))) => {
                     if let Some(mut decoder) = self.decoder.take() {
+                        let lenient = self.lenient;
+                        self.input += chunk.len() as u64;
                         if chunk.len() < INPLACE {
-                            let chunk = decoder.feed_data(chunk)?;
+                            let chunk = decoder.feed_data(chunk, lenient)?;
                             self.decoder = Some(decoder);
                             if let Some(chunk) = chunk {
+                                self.check_ratio(&chunk)?;
                                 return Poll::Ready(Some(Ok(chunk)));
                             }
                         } else {
                             self.fut = Some(run(move || {
-                                let chunk = decoder.feed_data(chunk)?;
+                                let chunk = decoder.feed_data(chunk, lenient)?;
                                 Ok((chunk, decoder))
                             }));
                         }
@@ -118,8 +197,11 @@ where
                 Poll::Ready(None) => {
                     self.eof = true;
                     return if let Some(mut decoder) = self.decoder.take() {
-                        match decoder.feed_eof() {
-                            Ok(Some(res)) => Poll::Ready(Some(Ok(res))),
+                        match decoder.feed_eof(self.lenient) {
+                            Ok(Some(res)) => {
+                                self.check_ratio(&res)?;
+                                Poll::Ready(Some(Ok(res)))
+                            }
                             Ok(None) => Poll::Ready(None),
                             Err(err) => Poll::Ready(Some(Err(err.into()))),
                         }
@@ -138,11 +220,52 @@ enum ContentDecoder {
     Deflate(Box<ZlibDecoder<Writer>>),
     Gzip(Box<GzDecoder<Writer>>),
     Br(Box<BrotliDecoder<Writer>>),
+    #[cfg(feature = "zstd")]
+    Zstd(Box<ZstdDecoder<'static, Writer>>),
+    /// Decoders of chained encodings, in decoding order
+    Chain(Vec<ContentDecoder>),
 }
 
 impl ContentDecoder {
-    fn feed_eof(&mut self) -> io::Result<Option<Bytes>> {
+    fn new(encoding: ContentEncoding) -> Option<ContentDecoder> {
+        match encoding {
+            ContentEncoding::Br => Some(ContentDecoder::Br(Box::new(
+                BrotliDecoder::new(Writer::new()),
+            ))),
+            ContentEncoding::Deflate => Some(ContentDecoder::Deflate(Box::new(
+                ZlibDecoder::new(Writer::new()),
+            ))),
+            ContentEncoding::Gzip => Some(ContentDecoder::Gzip(Box::new(
+                GzDecoder::new(Writer::new()),
+            ))),
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => Some(ContentDecoder::Zstd(Box::new(
+                ZstdDecoder::new(Writer::new()).expect("Cannot create zstd decoder"),
+            ))),
+            _ => None,
+        }
+    }
+
+    fn feed_eof(&mut self, lenient: bool) -> io::Result<Option<Bytes>> {
         match self {
+            ContentDecoder::Chain(ref mut decoders) => {
+                let mut data = None;
+                for decoder in decoders {
+                    let mut buf = BytesMut::new();
+                    if let Some(chunk) = data.take() {
+                        if let Some(chunk) = decoder.feed_data(chunk, lenient)? {
+                            buf.extend_from_slice(&chunk);
+                        }
+                    }
+                    if let Some(chunk) = decoder.feed_eof(lenient)? {
+                        buf.extend_from_slice(&chunk);
+                    }
+                    if !buf.is_empty() {
+                        data = Some(buf.freeze());
+                    }
+                }
+                Ok(data)
+            }
             ContentDecoder::Br(ref mut decoder) => match decoder.flush() {
                 Ok(()) => {
                     let b = decoder.get_mut().take();
@@ -176,11 +299,33 @@ impl ContentDecoder {
                 }
                 Err(e) => Err(e),
             },
+            #[cfg(feature = "zstd")]
+            ContentDecoder::Zstd(ref mut decoder) => match decoder.flush() {
+                Ok(()) => {
+                    let b = decoder.get_mut().take();
+                    if !b.is_empty() {
+                        Ok(Some(b))
+                    } else {
+                        Ok(None)
+                    }
+                }
+                Err(e) => Err(e),
+            },
         }
     }
 
-    fn feed_data(&mut self, data: Bytes) -> io::Result<Option<Bytes>> {
+    fn feed_data(&mut self, data: Bytes, lenient: bool) -> io::Result<Option<Bytes>> {
         match self {
+            ContentDecoder::Chain(ref mut decoders) => {
+                let mut data = Some(data);
+                for decoder in decoders {
+                    data = match data {
+                        Some(chunk) => decoder.feed_data(chunk, lenient)?,
+                        None => return Ok(None),
+                    };
+                }
+                Ok(data)
+            }
             ContentDecoder::Br(ref mut decoder) => match decoder.write_all(&data) {
                 Ok(_) => {
                     decoder.flush()?;
@@ -193,7 +338,21 @@ impl ContentDecoder {
                 }
                 Err(e) => Err(e),
             },
-            ContentDecoder::Gzip(ref mut decoder) => match decoder.write_all(&data) {
+            ContentDecoder::Gzip(ref mut decoder) => {
+                match write(decoder, &data, lenient) {
+                    Ok(_) => {
+                        decoder.flush()?;
+                        let b = decoder.get_mut().take();
+                        if !b.is_empty() {
+                            Ok(Some(b))
+                        } else {
+                            Ok(None)
+                        }
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            ContentDecoder::Deflate(ref mut decoder) => match decoder.write_all(&data) {
                 Ok(_) => {
                     decoder.flush()?;
                     let b = decoder.get_mut().take();
@@ -205,7 +364,8 @@ impl ContentDecoder {
                 }
                 Err(e) => Err(e),
             },
-            ContentDecoder::Deflate(ref mut decoder) => match decoder.write_all(&data) {
+            #[cfg(feature = "zstd")]
+            ContentDecoder::Zstd(ref mut decoder) => match decoder.write_all(&data) {
                 Ok(_) => {
                     decoder.flush()?;
                     let b = decoder.get_mut().take();
@@ -220,3 +380,22 @@ impl ContentDecoder {
         }
     }
 }
+
+/// Write all data, in lenient mode data after the end of the stream is ignored
+fn write<W: Write>(w: &mut W, mut data: &[u8], lenient: bool) -> io::Result<()> {
+    if !lenient {
+        return w.write_all(data);
+    }
+    while !data.is_empty() {
+        match w.write(data) {
+            Ok(0) => {
+                trace!("Ignore {} bytes after the end of gzip stream", data.len());
+                break;
+            }
+            Ok(n) => data = &data[n..],
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
@@ -10,6 +10,8 @@ use brotli2::write::BrotliEncoder;
 use bytes::Bytes;
 use flate2::write::{GzEncoder, ZlibEncoder};
 use futures::ready;
+#[cfg(feature = "zstd")]
+use z_std::stream::write::Encoder as ZstdEncoder;
 
 use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
 use crate::http::header::{ContentEncoding, HeaderValue, CONTENT_ENCODING};
@@ -66,6 +68,39 @@ impl<B: MessageBody + 'static> Encoder<B> {
     }
 }
 
+impl Encoder<Body> {
+    /// Check if encoding is supported by enabled codecs.
+    pub(crate) fn is_supported(encoding: ContentEncoding) -> bool {
+        ContentEncoder::can_encode(encoding)
+    }
+
+    /// Compress request body.
+    ///
+    /// In-memory bodies are compressed eagerly, streaming bodies are
+    /// compressed while they are being sent.
+    pub fn request(encoding: ContentEncoding, body: Body) -> Result<Body, io::Error> {
+        let mut encoder = match ContentEncoder::encoder(encoding) {
+            Some(encoder) => encoder,
+            None => return Ok(body),
+        };
+
+        match body {
+            Body::None => Ok(Body::None),
+            Body::Empty => Ok(Body::Empty),
+            Body::Bytes(buf) => {
+                encoder.write(&buf)?;
+                Ok(Body::Bytes(encoder.finish()?))
+            }
+            Body::Message(stream) => Ok(Body::from_message(Encoder::<Body> {
+                body: EncoderBody::BoxedStream(stream),
+                eof: false,
+                fut: None,
+                encoder: Some(encoder),
+            })),
+        }
+    }
+}
+
 enum EncoderBody<B> {
     Bytes(Bytes),
     Stream(B),
@@ -176,6 +211,8 @@ enum ContentEncoder {
     Deflate(ZlibEncoder<Writer>),
     Gzip(GzEncoder<Writer>),
     Br(BrotliEncoder<Writer>),
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdEncoder<'static, Writer>),
 }
 
 impl ContentEncoder {
@@ -184,6 +221,8 @@ impl ContentEncoder {
             ContentEncoding::Deflate | ContentEncoding::Gzip | ContentEncoding::Br => {
                 true
             }
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => true,
             _ => false,
         }
     }
@@ -201,6 +240,10 @@ impl ContentEncoder {
             ContentEncoding::Br => {
                 Some(ContentEncoder::Br(BrotliEncoder::new(Writer::new(), 3)))
             }
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => ZstdEncoder::new(Writer::new(), 3)
+                .ok()
+                .map(ContentEncoder::Zstd),
             _ => None,
         }
     }
@@ -210,6 +253,8 @@ impl ContentEncoder {
             ContentEncoder::Br(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Deflate(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Gzip(ref mut encoder) => encoder.get_mut().take(),
+            #[cfg(feature = "zstd")]
+            ContentEncoder::Zstd(ref mut encoder) => encoder.get_mut().take(),
         }
     }
 
@@ -227,6 +272,11 @@ impl ContentEncoder {
                 Ok(writer) => Ok(writer.buf.freeze()),
                 Err(err) => Err(err),
             },
+            #[cfg(feature = "zstd")]
+            ContentEncoder::Zstd(encoder) => match encoder.finish() {
+                Ok(writer) => Ok(writer.buf.freeze()),
+                Err(err) => Err(err),
+            },
         }
     }
 
@@ -253,6 +303,14 @@ impl ContentEncoder {
                     Err(err)
                 }
             },
+            #[cfg(feature = "zstd")]
+            ContentEncoder::Zstd(ref mut encoder) => match encoder.write_all(data) {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    trace!("Error decoding zstd encoding: {}", err);
+                    Err(err)
+                }
+            },
         }
     }
 }
@@ -83,6 +83,10 @@ pub enum ParseError {
     /// A message head is too large to be reasonable.
     #[display(fmt = "Message head is too large")]
     TooLarge,
+    /// A message head exceeds configured max size.
+    #[display(fmt = "Message head is larger than {} bytes", _0)]
+    #[from(ignore)]
+    HeadTooLarge(usize),
     /// A message reached EOF, but is not complete.
     #[display(fmt = "Message is incomplete")]
     Incomplete,
@@ -141,9 +145,21 @@ pub enum PayloadError {
     /// A payload reached size limit.
     #[display(fmt = "A payload reached size limit.")]
     Overflow,
+    /// Payload exceeds max size of client response payload.
+    #[display(fmt = "Payload is larger than {} bytes.", _0)]
+    #[from(ignore)]
+    TooLarge(usize),
+    /// Decoded payload exceeds max ratio to encoded payload.
+    #[display(fmt = "Decompression ratio exceeds {}:1.", _0)]
+    #[from(ignore)]
+    DecompressionRatioExceeded(usize),
     /// A payload length is unknown.
     #[display(fmt = "A payload length is unknown.")]
     UnknownLength,
+    /// Content encoding of chained encodings is not supported
+    #[display(fmt = "Unsupported content encoding: {}", _0)]
+    #[from(ignore)]
+    UnknownEncoding(String),
     /// Http2 payload error
     #[display(fmt = "{}", _0)]
     Http2Payload(h2::Error),
@@ -152,10 +168,43 @@ pub enum PayloadError {
     /// Io error
     #[display(fmt = "{}", _0)]
     Io(io::Error),
+    /// Connection is lost during payload transfer
+    #[display(
+        fmt = "Payload transfer is interrupted after {} bytes: {}",
+        received,
+        error
+    )]
+    #[from(ignore)]
+    Interrupted {
+        /// Number of payload bytes received before connection loss
+        received: u64,
+        /// Server advertised `Accept-Ranges: bytes` header
+        accept_ranges: bool,
+        /// Connection error
+        error: Box<PayloadError>,
+    },
 }
 
 impl std::error::Error for PayloadError {}
 
+impl PayloadError {
+    /// Offset to resume interrupted payload transfer from.
+    ///
+    /// Returns number of received payload bytes if server supports range
+    /// requests, rest of payload could be requested with
+    /// `Range: bytes={offset}-` header.
+    pub fn resume_offset(&self) -> Option<u64> {
+        match self {
+            PayloadError::Interrupted {
+                received,
+                accept_ranges: true,
+                ..
+            } => Some(*received),
+            _ => None,
+        }
+    }
+}
+
 impl From<BlockingError<io::Error>> for PayloadError {
     fn from(err: BlockingError<io::Error>) -> Self {
         match err {
@@ -234,6 +283,19 @@ pub enum ContentTypeError {
     Expected,
 }
 
+/// A set of errors that can occur during changing request method
+#[derive(Debug, Display, From)]
+pub enum MethodError {
+    /// Method is not a valid http token
+    #[display(fmt = "Invalid http method: {}", _0)]
+    Invalid(HttpError),
+    /// Shared request head could not be modified
+    #[display(fmt = "Method of shared request head could not be changed")]
+    Shared,
+}
+
+impl std::error::Error for MethodError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -7,6 +7,7 @@ use crate::codec::{Decoder, Encoder};
 use crate::http::body::BodySize;
 use crate::http::config::DateService;
 use crate::http::error::{ParseError, PayloadError};
+use crate::http::header::HeaderMap;
 use crate::http::message::{ConnectionType, RequestHeadType, ResponseHead};
 use crate::http::{Method, Version};
 
@@ -18,9 +19,13 @@ bitflags! {
         const HEAD              = 0b0000_0001;
         const KEEPALIVE_ENABLED = 0b0000_1000;
         const STREAM            = 0b0001_0000;
+        const RAW_HEADERS       = 0b0010_0000;
     }
 }
 
+/// Response headers in on-wire order and case, stored in response extensions
+pub(crate) struct RawHeaders(pub(crate) Vec<(Bytes, Bytes)>);
+
 /// HTTP/1 Codec
 pub struct ClientCodec {
     inner: ClientCodecInner,
@@ -35,8 +40,11 @@ struct ClientCodecInner {
     timer: DateService,
     decoder: decoder::MessageDecoder<ResponseHead>,
     payload: Option<PayloadDecoder>,
+    trailers: Option<HeaderMap>,
     version: Version,
+    req_trailers: Option<HeaderMap>,
     ctype: ConnectionType,
+    max_head_size: Option<usize>,
 
     // encoder part
     flags: Flags,
@@ -64,8 +72,11 @@ impl ClientCodec {
                 timer,
                 decoder: decoder::MessageDecoder::default(),
                 payload: None,
+                trailers: None,
                 version: Version::HTTP_11,
+                req_trailers: None,
                 ctype: ConnectionType::Close,
+                max_head_size: None,
 
                 flags,
                 encoder: encoder::MessageEncoder::default(),
@@ -94,6 +105,33 @@ impl ClientCodec {
         }
     }
 
+    /// Set trailer fields of the request body.
+    ///
+    /// Trailers are written with the end of chunked request body.
+    pub fn set_trailers(&mut self, trailers: HeaderMap) {
+        self.inner.req_trailers = Some(trailers);
+    }
+
+    /// Capture response headers in on-wire order and case
+    pub(crate) fn preserve_raw_headers(&mut self) {
+        self.inner.flags.insert(Flags::RAW_HEADERS);
+    }
+
+    /// Reject responses with both `Content-Length` and chunked `Transfer-Encoding`
+    pub(crate) fn strict_framing(&mut self) {
+        self.inner.decoder.strict();
+    }
+
+    /// Write request target in absolute-form, used for requests sent to http proxy
+    pub(crate) fn absolute_form(&mut self) {
+        self.inner.encoder.absolute_form = true;
+    }
+
+    /// Set max size of response head, including status line and headers
+    pub(crate) fn max_head_size(&mut self, size: usize) {
+        self.inner.max_head_size = Some(size);
+    }
+
     /// Convert message codec to a payload codec
     pub fn into_payload_codec(self) -> ClientPayloadCodec {
         ClientPayloadCodec { inner: self.inner }
@@ -106,6 +144,11 @@ impl ClientPayloadCodec {
         self.inner.ctype == ConnectionType::KeepAlive
     }
 
+    /// Take trailer fields of the last chunked response payload
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.inner.trailers.take()
+    }
+
     /// Transform payload codec to a message codec
     pub fn into_message_codec(self) -> ClientCodec {
         ClientCodec { inner: self.inner }
@@ -119,14 +162,36 @@ impl Decoder for ClientCodec {
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         debug_assert!(!self.inner.payload.is_some(), "Payload decoder is set");
 
-        if let Some((req, payload)) = self.inner.decoder.decode(src)? {
-            if let Some(ctype) = req.ctype() {
-                // do not use peer's keep-alive
-                self.inner.ctype = if ctype == ConnectionType::KeepAlive {
-                    self.inner.ctype
-                } else {
-                    ctype
-                };
+        let raw = if self.inner.flags.contains(Flags::RAW_HEADERS) {
+            raw_headers(src)
+        } else {
+            None
+        };
+
+        let len = src.len();
+        let decoded = self.inner.decoder.decode(src)?;
+
+        // unparsed buffer contains response head only
+        if let Some(limit) = self.inner.max_head_size {
+            let size = if decoded.is_some() {
+                len - src.len()
+            } else {
+                src.len()
+            };
+            if size > limit {
+                return Err(ParseError::HeadTooLarge(limit));
+            }
+        }
+
+        if let Some((req, payload)) = decoded {
+            if let Some(raw) = raw {
+                req.extensions_mut().insert(RawHeaders(raw));
+            }
+            // do not use peer's keep-alive, http/1.0 response
+            // without keep-alive closes connection
+            match req.connection_type() {
+                ConnectionType::KeepAlive => (),
+                ctype => self.inner.ctype = ctype,
             }
 
             if !self.inner.flags.contains(Flags::HEAD) {
@@ -165,12 +230,32 @@ impl Decoder for ClientPayloadCodec {
                 Some(Some(chunk))
             }
             Some(PayloadItem::Eof) => {
-                self.inner.payload.take();
+                if let Some(mut payload) = self.inner.payload.take() {
+                    self.inner.trailers = payload.take_trailers();
+                }
                 Some(None)
             }
             None => None,
         })
     }
+
+    fn decode_eof(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(item) => Ok(Some(item)),
+            None => match self.inner.payload {
+                // connection close completes payload without length
+                Some(ref payload) if payload.is_eof_delimited() => {
+                    self.inner.payload.take();
+                    Ok(Some(None))
+                }
+                Some(_) => Err(PayloadError::Incomplete(None)),
+                None => Ok(None),
+            },
+        }
+    }
 }
 
 impl Encoder for ClientCodec {
@@ -218,9 +303,33 @@ impl Encoder for ClientCodec {
                 self.inner.encoder.encode_chunk(bytes.as_ref(), dst)?;
             }
             Message::Chunk(None) => {
-                self.inner.encoder.encode_eof(dst)?;
+                if let Some(trailers) = self.inner.req_trailers.take() {
+                    self.inner.encoder.encode_trailers(&trailers, dst)?;
+                } else {
+                    self.inner.encoder.encode_eof(dst)?;
+                }
             }
         }
         Ok(())
     }
 }
+
+/// Parse response headers without normalization
+fn raw_headers(src: &BytesMut) -> Option<Vec<(Bytes, Bytes)>> {
+    let mut parsed = [httparse::EMPTY_HEADER; decoder::MAX_HEADERS];
+    let mut res = httparse::Response::new(&mut parsed);
+    match res.parse(src) {
+        Ok(httparse::Status::Complete(_)) => Some(
+            res.headers
+                .iter()
+                .map(|h| {
+                    (
+                        Bytes::copy_from_slice(h.name.as_bytes()),
+                        Bytes::copy_from_slice(h.value),
+                    )
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
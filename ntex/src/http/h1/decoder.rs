@@ -16,10 +16,13 @@ use crate::http::request::Request;
 
 use super::MAX_BUFFER_SIZE;
 
-const MAX_HEADERS: usize = 96;
+pub(super) const MAX_HEADERS: usize = 96;
 
 /// Incoming messagd decoder
-pub(super) struct MessageDecoder<T: MessageType>(PhantomData<T>);
+pub(super) struct MessageDecoder<T: MessageType> {
+    strict: bool,
+    _t: PhantomData<T>,
+}
 
 #[derive(Debug)]
 /// Incoming request type
@@ -31,7 +34,17 @@ pub(super) enum PayloadType {
 
 impl<T: MessageType> Default for MessageDecoder<T> {
     fn default() -> Self {
-        MessageDecoder(PhantomData)
+        MessageDecoder {
+            strict: false,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T: MessageType> MessageDecoder<T> {
+    /// Reject messages with both `Content-Length` and chunked `Transfer-Encoding`
+    pub(super) fn strict(&mut self) {
+        self.strict = true;
     }
 }
 
@@ -40,7 +53,7 @@ impl<T: MessageType> Decoder for MessageDecoder<T> {
     type Error = ParseError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        T::decode(src)
+        T::decode(src, self.strict)
     }
 }
 
@@ -57,17 +70,22 @@ pub(super) trait MessageType: Sized {
 
     fn headers_mut(&mut self) -> &mut HeaderMap;
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError>;
+    fn decode(
+        src: &mut BytesMut,
+        strict: bool,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError>;
 
     fn set_headers(
         &mut self,
         slice: &Bytes,
         raw_headers: &[HeaderIndex],
+        strict: bool,
     ) -> Result<PayloadLength, ParseError> {
         let mut ka = None;
         let mut has_upgrade = false;
         let mut expect = false;
         let mut chunked = false;
+        let mut has_length = false;
         let mut content_length = None;
 
         {
@@ -85,6 +103,7 @@ pub(super) trait MessageType: Sized {
                 };
                 match name {
                     header::CONTENT_LENGTH => {
+                        has_length = true;
                         if let Ok(s) = value.to_str() {
                             if let Ok(len) = s.parse::<u64>() {
                                 if len != 0 {
@@ -144,6 +163,19 @@ pub(super) trait MessageType: Sized {
 
                 headers.append(name, value);
             }
+
+            // https://tools.ietf.org/html/rfc7230#section-3.3.3
+            // chunked encoding overrides content-length, message with
+            // both headers is a possible request smuggling attempt
+            if chunked && has_length {
+                if strict {
+                    debug!("Both Content-Length and chunked Transfer-Encoding are set");
+                    return Err(ParseError::InvalidInput(
+                        "Both Content-Length and chunked Transfer-Encoding are set",
+                    ));
+                }
+                headers.remove(header::CONTENT_LENGTH);
+            }
         }
         self.set_connection_type(ka);
         if expect {
@@ -185,7 +217,10 @@ impl MessageType for Request {
     }
 
     #[allow(clippy::uninit_assumed_init)]
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        strict: bool,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         // Unsafe: we read this data only after httparse parses headers into.
         // performance bump for pipeline benchmarks.
         let mut headers: [HeaderIndex; MAX_HEADERS] =
@@ -223,7 +258,8 @@ impl MessageType for Request {
         let mut msg = Request::new();
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length =
+            msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], strict)?;
 
         // payload decoder
         let decoder = match length {
@@ -264,7 +300,10 @@ impl MessageType for ResponseHead {
     }
 
     #[allow(clippy::uninit_assumed_init)]
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        strict: bool,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         // Unsafe: we read this data only after httparse parses headers into.
         // performance bump for pipeline benchmarks.
         let mut headers: [HeaderIndex; MAX_HEADERS] =
@@ -303,7 +342,8 @@ impl MessageType for ResponseHead {
         msg.version = ver;
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length =
+            msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], strict)?;
 
         // message payload
         let decoder = if let PayloadLength::Payload(pl) = length {
@@ -360,26 +400,42 @@ pub(super) enum PayloadItem {
 ///
 /// If a message body does not include a Transfer-Encoding, it *should*
 /// include a Content-Length header.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub(super) struct PayloadDecoder {
     kind: Kind,
+    trailers: Option<HeaderMap>,
 }
 
 impl PayloadDecoder {
     pub(super) fn length(x: u64) -> PayloadDecoder {
         PayloadDecoder {
             kind: Kind::Length(x),
+            trailers: None,
         }
     }
 
     pub(super) fn chunked() -> PayloadDecoder {
         PayloadDecoder {
             kind: Kind::Chunked(ChunkedState::Size, 0),
+            trailers: None,
         }
     }
 
     pub(super) fn eof() -> PayloadDecoder {
-        PayloadDecoder { kind: Kind::Eof }
+        PayloadDecoder {
+            kind: Kind::Eof,
+            trailers: None,
+        }
+    }
+
+    /// Trailer fields of chunked payload, available after payload eof
+    pub(super) fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take()
+    }
+
+    /// Payload is delimited by connection close
+    pub(super) fn is_eof_delimited(&self) -> bool {
+        matches!(self.kind, Kind::Eof)
     }
 }
 
@@ -416,8 +472,7 @@ enum ChunkedState {
     Body,
     BodyCr,
     BodyLf,
-    EndCr,
-    EndLf,
+    Trailers,
     End,
 }
 
@@ -449,6 +504,20 @@ impl Decoder for PayloadDecoder {
             }
             Kind::Chunked(ref mut state, ref mut size) => {
                 loop {
+                    if *state == ChunkedState::Trailers {
+                        return match read_trailers(src)? {
+                            Some(trailers) => {
+                                trace!("End of chunked stream");
+                                *state = ChunkedState::End;
+                                if !trailers.is_empty() {
+                                    self.trailers = Some(trailers);
+                                }
+                                Ok(Some(PayloadItem::Eof))
+                            }
+                            None => Ok(None),
+                        };
+                    }
+
                     let mut buf = None;
                     // advances the chunked state
                     *state = match state.step(src, size, &mut buf) {
@@ -507,8 +576,7 @@ impl ChunkedState {
             Body => ChunkedState::read_body(body, size, buf),
             BodyCr => ChunkedState::read_body_cr(body),
             BodyLf => ChunkedState::read_body_lf(body),
-            EndCr => ChunkedState::read_end_cr(body),
-            EndLf => ChunkedState::read_end_lf(body),
+            Trailers => Poll::Ready(Ok(ChunkedState::Trailers)),
             End => Poll::Ready(Ok(ChunkedState::End)),
         }
     }
@@ -567,7 +635,7 @@ impl ChunkedState {
     ) -> Poll<Result<ChunkedState, ParseError>> {
         match byte!(rdr) {
             b'\n' if *size > 0 => Poll::Ready(Ok(ChunkedState::Body)),
-            b'\n' if *size == 0 => Poll::Ready(Ok(ChunkedState::EndCr)),
+            b'\n' if *size == 0 => Poll::Ready(Ok(ChunkedState::Trailers)),
             _ => Poll::Ready(Err(ParseError::InvalidInput("Invalid chunk size LF"))),
         }
     }
@@ -612,18 +680,36 @@ impl ChunkedState {
             _ => Poll::Ready(Err(ParseError::InvalidInput("Invalid chunk body LF"))),
         }
     }
-    fn read_end_cr(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, ParseError>> {
-        match byte!(rdr) {
-            b'\r' => Poll::Ready(Ok(ChunkedState::EndLf)),
-            _ => Poll::Ready(Err(ParseError::InvalidInput("Invalid chunk end CR"))),
+}
+
+/// Read trailer fields of chunked payload, returns `None` if trailer
+/// section is not complete
+fn read_trailers(src: &mut BytesMut) -> Result<Option<HeaderMap>, ParseError> {
+    let mut parsed = [httparse::EMPTY_HEADER; MAX_HEADERS];
+
+    let (len, trailers) = match httparse::parse_headers(src, &mut parsed)? {
+        httparse::Status::Complete((len, headers)) => {
+            let mut trailers = HeaderMap::new();
+            for h in headers {
+                let name = HeaderName::from_bytes(h.name.as_bytes())
+                    .map_err(|_| ParseError::Header)?;
+                let value =
+                    HeaderValue::from_bytes(h.value).map_err(|_| ParseError::Header)?;
+                trailers.append(name, value);
+            }
+            (len, trailers)
         }
-    }
-    fn read_end_lf(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, ParseError>> {
-        match byte!(rdr) {
-            b'\n' => Poll::Ready(Ok(ChunkedState::End)),
-            _ => Poll::Ready(Err(ParseError::InvalidInput("Invalid chunk end LF"))),
+        httparse::Status::Partial => {
+            return if src.len() >= MAX_BUFFER_SIZE {
+                error!("MAX_BUFFER_SIZE unprocessed data reached, closing");
+                Err(ParseError::TooLarge)
+            } else {
+                Ok(None)
+            };
         }
-    }
+    };
+    src.advance(len);
+    Ok(Some(trailers))
 }
 
 #[cfg(test)]
@@ -1178,6 +1264,36 @@ mod tests {
         assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
     }
 
+    #[test]
+    fn test_parse_chunked_payload_trailers() {
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+        );
+        let mut reader = MessageDecoder::<Request>::default();
+        let (_, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        let mut pl = pl.unwrap();
+
+        buf.extend(b"4\r\ndata\r\n0\r\ngrpc-status: 0\r\n");
+        let msg = pl.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(msg.chunk().as_ref(), b"data");
+        assert!(pl.decode(&mut buf).unwrap().is_none());
+        assert!(pl.take_trailers().is_none());
+
+        buf.extend(b"grpc-message: ok\r\n\r\nGET");
+        assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
+        assert_eq!(&buf[..], b"GET");
+        let trailers = pl.take_trailers().unwrap();
+        assert_eq!(trailers.len(), 2);
+        assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+        assert_eq!(trailers.get("grpc-message").unwrap(), "ok");
+
+        // invalid trailer
+        let mut pl = PayloadDecoder::chunked();
+        let mut buf = BytesMut::from("0\r\ngrpc status\r\n\r\n");
+        assert!(matches!(pl.decode(&mut buf), Err(ParseError::Header)));
+    }
+
     #[test]
     fn test_parse_chunked_payload_chunk_extension() {
         let mut buf = BytesMut::from(
@@ -1210,4 +1326,30 @@ mod tests {
         let chunk = pl.decode(&mut buf).unwrap().unwrap();
         assert_eq!(chunk, PayloadItem::Chunk(Bytes::from_static(b"test data")));
     }
+
+    #[test]
+    fn test_response_length_and_chunked() {
+        let data = "HTTP/1.1 200 Ok\r\n\
+                    content-length: 100\r\n\
+                    transfer-encoding: chunked\r\n\r\n\
+                    4\r\ndata\r\n0\r\n\r\n";
+        let mut buf = BytesMut::from(data);
+
+        let mut reader = MessageDecoder::<ResponseHead>::default();
+        let (msg, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        assert!(!msg.headers.contains_key(header::CONTENT_LENGTH));
+        let mut pl = pl.unwrap();
+        let chunk = pl.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(chunk, PayloadItem::Chunk(Bytes::from_static(b"data")));
+        assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
+        assert!(buf.is_empty());
+
+        let mut buf = BytesMut::from(data);
+        let mut reader = MessageDecoder::<ResponseHead>::default();
+        reader.strict();
+        assert!(matches!(
+            reader.decode(&mut buf),
+            Err(ParseError::InvalidInput(_))
+        ));
+    }
 }
@@ -1,7 +1,7 @@
 use std::io::Write;
 use std::marker::PhantomData;
 use std::ptr::copy_nonoverlapping;
-use std::{cmp, io, mem, ptr, slice};
+use std::{cmp, fmt, io, mem, ptr, slice};
 
 use bytes::{BufMut, BytesMut};
 
@@ -11,7 +11,7 @@ use crate::http::header::{map, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODI
 use crate::http::helpers;
 use crate::http::message::{ConnectionType, RequestHeadType};
 use crate::http::response::Response;
-use crate::http::{HeaderMap, StatusCode, Version};
+use crate::http::{HeaderMap, StatusCode, Uri, Version};
 
 const AVERAGE_HEADER_SIZE: usize = 30;
 
@@ -19,6 +19,9 @@ const AVERAGE_HEADER_SIZE: usize = 30;
 pub(super) struct MessageEncoder<T: MessageType> {
     pub(super) length: BodySize,
     pub(super) te: TransferEncoding,
+    /// Request target is written in absolute-form,
+    /// it is used for requests sent to http proxy
+    pub(super) absolute_form: bool,
     _t: PhantomData<T>,
 }
 
@@ -27,6 +30,7 @@ impl<T: MessageType> Default for MessageEncoder<T> {
         MessageEncoder {
             length: BodySize::None,
             te: TransferEncoding::empty(),
+            absolute_form: false,
             _t: PhantomData,
         }
     }
@@ -41,7 +45,7 @@ pub(super) trait MessageType: Sized {
 
     fn chunked(&self) -> bool;
 
-    fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()>;
+    fn encode_status(&mut self, dst: &mut BytesMut, absolute: bool) -> io::Result<()>;
 
     fn encode_headers(
         &mut self,
@@ -208,7 +212,7 @@ impl MessageType for Response<()> {
         None
     }
 
-    fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
+    fn encode_status(&mut self, dst: &mut BytesMut, _: bool) -> io::Result<()> {
         let head = self.head();
         let reason = head.reason().as_bytes();
         dst.reserve(256 + head.headers.len() * AVERAGE_HEADER_SIZE + reason.len());
@@ -237,14 +241,14 @@ impl MessageType for RequestHeadType {
         self.extra_headers()
     }
 
-    fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
+    fn encode_status(&mut self, dst: &mut BytesMut, absolute: bool) -> io::Result<()> {
         let head = self.as_ref();
         dst.reserve(256 + head.headers.len() * AVERAGE_HEADER_SIZE);
         write!(
             helpers::Writer(dst),
             "{} {} {}",
             head.method,
-            head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/"),
+            RequestTarget(&head.uri, absolute),
             // only HTTP-0.9/1.1
             match head.version {
                 Version::HTTP_09 => "HTTP/0.9",
@@ -261,6 +265,20 @@ impl MessageType for RequestHeadType {
     }
 }
 
+/// Request target of the request line
+struct RequestTarget<'a>(&'a Uri, bool);
+
+impl<'a> fmt::Display for RequestTarget<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.1 && self.0.authority().is_some() {
+            write!(f, "{}", self.0)
+        } else {
+            let path = self.0.path_and_query().map(|u| u.as_str()).unwrap_or("/");
+            f.write_str(path)
+        }
+    }
+}
+
 impl<T: MessageType> MessageEncoder<T> {
     /// Encode message
     pub(super) fn encode_chunk(
@@ -276,6 +294,15 @@ impl<T: MessageType> MessageEncoder<T> {
         self.te.encode_eof(buf)
     }
 
+    /// Encode eof with trailer fields
+    pub(super) fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        self.te.encode_trailers(trailers, buf)
+    }
+
     pub(super) fn encode(
         &mut self,
         dst: &mut BytesMut,
@@ -305,7 +332,7 @@ impl<T: MessageType> MessageEncoder<T> {
             self.te = TransferEncoding::empty();
         }
 
-        message.encode_status(dst)?;
+        message.encode_status(dst, self.absolute_form)?;
         message.encode_headers(dst, version, length, ctype, timer)
     }
 }
@@ -425,6 +452,33 @@ impl TransferEncoding {
             }
         }
     }
+
+    /// Encode eof with trailer fields, trailers are sent for chunked
+    /// transfer encoding only
+    pub(super) fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        match self.kind {
+            TransferEncodingKind::Chunked(ref mut eof) => {
+                if !*eof {
+                    *eof = true;
+                    buf.extend_from_slice(b"0\r\n");
+                    for (name, value) in trailers {
+                        buf.reserve(name.as_str().len() + value.len() + 4);
+                        buf.extend_from_slice(name.as_str().as_bytes());
+                        buf.extend_from_slice(b": ");
+                        buf.extend_from_slice(value.as_bytes());
+                        buf.extend_from_slice(b"\r\n");
+                    }
+                    buf.extend_from_slice(b"\r\n");
+                }
+                Ok(())
+            }
+            _ => self.encode_eof(buf),
+        }
+    }
 }
 
 const DEC_DIGITS_LUT: &[u8] = b"0001020304050607080910111213141516171819\
@@ -580,7 +634,7 @@ mod tests {
     use bytes::Bytes;
 
     use super::*;
-    use crate::http::header::{HeaderValue, AUTHORIZATION};
+    use crate::http::header::{HeaderName, HeaderValue, AUTHORIZATION};
     use crate::http::RequestHead;
 
     #[test]
@@ -597,6 +651,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_chunked_trailers() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            HeaderName::from_static("x-checksum"),
+            HeaderValue::from_static("abc"),
+        );
+
+        let mut bytes = BytesMut::new();
+        let mut enc = TransferEncoding::chunked();
+        assert!(!enc.encode(b"test", &mut bytes).ok().unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert_eq!(
+            bytes.split().freeze(),
+            Bytes::from_static(b"4\r\ntest\r\n0\r\nx-checksum: abc\r\n\r\n")
+        );
+
+        // trailers are not supported without chunked encoding
+        let mut enc = TransferEncoding::length(4);
+        assert!(enc.encode(b"test", &mut bytes).ok().unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert_eq!(bytes.split().freeze(), Bytes::from_static(b"test"));
+    }
+
     #[test]
     fn test_extra_headers() {
         let mut bytes = BytesMut::with_capacity(2048);
@@ -11,6 +11,7 @@ mod payload;
 mod service;
 mod upgrade;
 
+pub(crate) use self::client::RawHeaders;
 pub use self::client::{ClientCodec, ClientPayloadCodec};
 pub use self::codec::Codec;
 pub use self::expect::ExpectHandler;
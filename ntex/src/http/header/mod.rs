@@ -21,6 +21,8 @@ pub enum ContentEncoding {
     Gzip,
     /// Indicates the identity function (i.e. no compression, nor modification)
     Identity,
+    /// A format using the Zstandard algorithm, requires `zstd` feature
+    Zstd,
 }
 
 impl ContentEncoding {
@@ -40,6 +42,7 @@ impl ContentEncoding {
             ContentEncoding::Br => "br",
             ContentEncoding::Gzip => "gzip",
             ContentEncoding::Deflate => "deflate",
+            ContentEncoding::Zstd => "zstd",
             ContentEncoding::Identity | ContentEncoding::Auto => "identity",
         }
     }
@@ -51,6 +54,7 @@ impl ContentEncoding {
             ContentEncoding::Br => 1.1,
             ContentEncoding::Gzip => 1.0,
             ContentEncoding::Deflate => 0.9,
+            ContentEncoding::Zstd => 1.05,
             ContentEncoding::Identity | ContentEncoding::Auto => 0.1,
         }
     }
@@ -66,6 +70,8 @@ impl<'a> From<&'a str> for ContentEncoding {
             ContentEncoding::Gzip
         } else if s.eq_ignore_ascii_case("deflate") {
             ContentEncoding::Deflate
+        } else if s.eq_ignore_ascii_case("zstd") {
+            ContentEncoding::Zstd
         } else {
             ContentEncoding::Identity
         }
@@ -111,6 +117,9 @@ mod tests {
     #[test]
     fn encoding() {
         assert!(ContentEncoding::Br.is_compressed());
+        assert!(ContentEncoding::Zstd.is_compressed());
+        assert_eq!(ContentEncoding::from(" ZSTD"), ContentEncoding::Zstd);
+        assert_eq!(ContentEncoding::Zstd.as_str(), "zstd");
         assert!(!ContentEncoding::Identity.is_compressed());
         assert!(!ContentEncoding::Auto.is_compressed());
         assert_eq!(format!("{:?}", ContentEncoding::Identity), "Identity");
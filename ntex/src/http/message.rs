@@ -1,10 +1,12 @@
 use std::cell::{Ref, RefCell, RefMut};
+use std::convert::TryFrom;
 use std::net;
 use std::rc::Rc;
 
 use bitflags::bitflags;
 
-use crate::http::header::HeaderMap;
+use crate::http::error::{HttpError, MethodError};
+use crate::http::header::{HeaderMap, HeaderName, HeaderValue};
 use crate::http::{header, Method, StatusCode, Uri, Version};
 use crate::util::Extensions;
 
@@ -175,6 +177,43 @@ impl RequestHeadType {
             RequestHeadType::Rc(_, headers) => headers.as_ref(),
         }
     }
+
+    /// Insert header, replaces existing header with the same name.
+    ///
+    /// Shared request head is not modified, header is stored in extra headers.
+    pub fn insert_header(&mut self, key: HeaderName, value: HeaderValue) {
+        match self {
+            RequestHeadType::Owned(head) => {
+                head.headers.insert(key, value);
+            }
+            RequestHeadType::Rc(_, headers) => {
+                headers
+                    .get_or_insert_with(HeaderMap::new)
+                    .insert(key, value);
+            }
+        }
+    }
+
+    /// Set request method.
+    ///
+    /// Method must be a valid http token, otherwise `MethodError::Invalid`
+    /// is returned. Shared request head could not be modified, method of
+    /// `Rc` variant is not changed and `MethodError::Shared` is returned.
+    pub fn set_method<M>(&mut self, method: M) -> Result<(), MethodError>
+    where
+        Method: TryFrom<M>,
+        <Method as TryFrom<M>>::Error: Into<HttpError>,
+    {
+        let method =
+            Method::try_from(method).map_err(|e| MethodError::Invalid(e.into()))?;
+        match self {
+            RequestHeadType::Owned(head) => {
+                head.method = method;
+                Ok(())
+            }
+            RequestHeadType::Rc(..) => Err(MethodError::Shared),
+        }
+    }
 }
 
 impl AsRef<RequestHead> for RequestHeadType {
@@ -461,3 +500,26 @@ impl BoxedResponsePool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_method() {
+        let mut head = RequestHeadType::from(RequestHead::default());
+        head.set_method("PURGE").unwrap();
+        assert_eq!(head.as_ref().method.as_str(), "PURGE");
+        head.set_method(Method::GET).unwrap();
+        assert_eq!(head.as_ref().method, Method::GET);
+
+        let err = head.set_method("BAD METHOD").err().unwrap();
+        assert!(matches!(err, MethodError::Invalid(_)));
+        assert_eq!(head.as_ref().method, Method::GET);
+
+        let mut head = RequestHeadType::Rc(Rc::new(RequestHead::default()), None);
+        let err = head.set_method(Method::POST).err().unwrap();
+        assert!(matches!(err, MethodError::Shared));
+        assert_eq!(head.as_ref().method, Method::GET);
+    }
+}
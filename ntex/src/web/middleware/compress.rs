@@ -217,6 +217,12 @@ impl AcceptEncoding {
         for enc in encodings {
             if let Some(enc) = enc {
                 if encoding == ContentEncoding::Auto {
+                    // skip encodings without enabled codec
+                    if enc.encoding.is_compressed()
+                        && !Encoder::is_supported(enc.encoding)
+                    {
+                        continue;
+                    }
                     return enc.encoding;
                 } else if encoding == enc.encoding {
                     return encoding;
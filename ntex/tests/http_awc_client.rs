@@ -1,21 +1,36 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
+use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use brotli2::write::BrotliEncoder;
 use bytes::Bytes;
 use coo_kie::Cookie;
 use flate2::{read::GzDecoder, write::GzEncoder, write::ZlibEncoder, Compression};
-use futures::future::ok;
-use futures::stream::once;
+use futures::future::{ok, LocalBoxFuture};
+use futures::stream::{once, StreamExt};
 use rand::Rng;
 
-use ntex::http::client::error::{JsonPayloadError, SendRequestError};
-use ntex::http::client::{Client, Connector};
+use ntex::http::body::{Body, BodyStream, ChunkedStream};
+use ntex::http::client::error::{
+    FreezeRequestError, InvalidUrl, JsonPayloadError, SendRequestError,
+    TextPayloadError, TimeoutPhase,
+};
+use ntex::http::client::{
+    CircuitBreaker, Client, ClientResponse, Connector, CookieJar, Multipart,
+    ProtocolMode, Proxy, RateLimit, RequestInterceptor, RequestMetrics, RequestSigner,
+    RetryPolicy, SignPayload, StreamOptions, StreamingBody, TraceContext, UserAgents,
+    VirtualClock,
+};
+use ntex::http::error::{ParseError, PayloadError};
+use ntex::http::header::{HeaderName, HeaderValue};
 use ntex::http::test::server as test_server;
-use ntex::http::{header, HttpMessage, HttpService};
+use ntex::http::{
+    header, HttpMessage, HttpService, RequestHeadType, StatusCode, Version,
+};
 use ntex::service::{map_config, pipeline_factory, Service};
 use ntex::web::dev::AppConfig;
 use ntex::web::middleware::Compress;
@@ -137,6 +152,97 @@ async fn test_json() {
     assert!(response.status().is_success());
 }
 
+#[ntex::test]
+async fn test_json_error_source() {
+    use std::error::Error as StdError;
+
+    // maps with non-string keys could not be serialized to json
+    let mut value = HashMap::new();
+    value.insert((1, 2), 3);
+
+    let err = Client::new()
+        .post("http://localhost/")
+        .send_json(&value)
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, SendRequestError::Error(_)));
+    let source = err.source().unwrap();
+    assert!(source.downcast_ref::<serde_json::Error>().is_some());
+    assert_eq!(source.to_string(), err.to_string());
+}
+
+#[ntex::test]
+async fn test_json_with() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| async move {
+                assert_eq!(
+                    req.headers().get(header::CONTENT_TYPE).unwrap(),
+                    "application/json"
+                );
+                HttpResponse::Ok().body(body)
+            },
+        )))
+    });
+
+    let mut response = srv
+        .post("/")
+        .send_json_with(&"TEST", |_| Ok(b"\"CUSTOM\"".to_vec()))
+        .await
+        .unwrap();
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"\"CUSTOM\""));
+
+    // same with frozen request
+    let request = srv.post("/").freeze().unwrap();
+    let mut response = request
+        .send_json_with(&vec![1, 2], |v| serde_json::to_vec_pretty(v))
+        .await
+        .unwrap();
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"[\n  1,\n  2\n]"));
+
+    let response = request
+        .extra_header("x-test2", "112")
+        .send_json_with(&"TEST", serde_json::to_vec)
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[cfg(feature = "msgpack")]
+#[ntex::test]
+async fn test_msgpack() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| async move {
+                assert_eq!(
+                    req.headers().get(header::CONTENT_TYPE).unwrap(),
+                    "application/msgpack"
+                );
+                assert_eq!(body, Bytes::from_static(b"\xa4TEST"));
+                HttpResponse::Ok()
+            },
+        )))
+    });
+
+    let response = srv.post("/").send_msgpack(&"TEST").await.unwrap();
+    assert!(response.status().is_success());
+
+    // same with frozen request
+    let request = srv.post("/").freeze().unwrap();
+    let response = request.send_msgpack(&"TEST").await.unwrap();
+    assert!(response.status().is_success());
+
+    let response = request
+        .extra_header("x-test2", "112")
+        .send_msgpack(&"TEST")
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
 #[ntex::test]
 async fn test_form() {
     let srv = test::server(|| {
@@ -189,11 +295,143 @@ async fn test_timeout() {
 
     let request = client.get(srv.url("/")).send();
     match request.await {
-        Err(SendRequestError::Timeout) => (),
+        Err(SendRequestError::Timeout { phase }) => {
+            assert_eq!(phase, TimeoutPhase::Response)
+        }
+        _ => panic!(),
+    }
+}
+
+#[ntex::test]
+async fn test_custom_connector_with_tcp_options() {
+    let srv = test::server(|| {
+        App::new().service(
+            web::resource("/").route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+        )
+    });
+
+    // custom connector is kept when tcp options are set after it
+    let opened = Arc::new(AtomicUsize::new(0));
+    let opened2 = opened.clone();
+    let connector = Connector::default()
+        .connector(
+            ntex::connect::Connector::new(ntex::connect::default_resolver()).map(
+                move |sock| {
+                    opened2.fetch_add(1, Ordering::Relaxed);
+                    (sock, ntex::http::Protocol::Http1)
+                },
+            ),
+        )
+        .tcp_nodelay(false)
+        .tcp_keepalive(Duration::from_secs(30))
+        .finish();
+
+    let client = Client::build().connector(connector).finish();
+    let res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(opened.load(Ordering::Relaxed), 1);
+
+    // and when happy eyeballs is enabled after it
+    let opened2 = opened.clone();
+    let connector = Connector::default()
+        .connector(
+            ntex::connect::Connector::new(ntex::connect::default_resolver()).map(
+                move |sock| {
+                    opened2.fetch_add(1, Ordering::Relaxed);
+                    (sock, ntex::http::Protocol::Http1)
+                },
+            ),
+        )
+        .happy_eyeballs(Duration::from_millis(250))
+        .finish();
+
+    let client = Client::build().connector(connector).finish();
+    let res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(opened.load(Ordering::Relaxed), 2);
+}
+
+#[ntex::test]
+async fn test_frozen_timeout() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            ntex::rt::time::delay_for(Duration::from_millis(200)).await;
+            HttpResponse::Ok().body(STR)
+        })))
+    });
+
+    let request = srv.get("/").freeze().unwrap();
+
+    // per-send timeout
+    match request.timeout(Duration::from_millis(50)).send().await {
+        Err(SendRequestError::Timeout { phase }) => {
+            assert_eq!(phase, TimeoutPhase::Response)
+        }
+        _ => panic!(),
+    }
+
+    // frozen request keeps its own timeout
+    let mut response = request.send().await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    // per-send address
+    let mut response = request.address(srv.addr()).send().await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    // validation errors are reported on freeze
+    match Client::new().get("/no-scheme").freeze() {
+        Err(FreezeRequestError::Url(InvalidUrl::MissingScheme)) => (),
         _ => panic!(),
     }
 }
 
+#[ntex::test]
+async fn test_frozen_concurrent_timeout_phase() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+            HttpResponse::Ok().body(STR)
+        })))
+    });
+
+    // each send tracks its own phase, frozen head is shared
+    let request = srv.get("/").freeze().unwrap();
+    let (res1, res2) = futures::join!(
+        request.timeout(Duration::from_millis(100)).send(),
+        request.timeout(Duration::from_millis(100)).send()
+    );
+    for res in vec![res1, res2] {
+        match res {
+            Err(SendRequestError::Timeout { phase }) => {
+                assert_eq!(phase, TimeoutPhase::Response)
+            }
+            _ => panic!(),
+        }
+    }
+}
+
+#[ntex::test]
+async fn test_timings() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            ntex::rt::time::delay_for(Duration::from_millis(100)).await;
+            HttpResponse::Ok().body(STR)
+        })))
+    });
+
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+
+    let timings = response.timings();
+    assert!(timings.time_to_first_byte() >= Duration::from_millis(100));
+    assert!(timings.time_to_first_byte() <= timings.total());
+    assert!(timings.connect() <= timings.time_to_first_byte());
+}
+
 #[ntex::test]
 async fn test_timeout_override() {
     let srv = test::server(|| {
@@ -211,9 +449,159 @@ async fn test_timeout_override() {
         .timeout(Duration::from_millis(50))
         .send();
     match request.await {
-        Err(SendRequestError::Timeout) => (),
+        Err(SendRequestError::Timeout { .. }) => (),
+        _ => panic!(),
+    }
+}
+
+#[ntex::test]
+async fn test_timeout_with_timeout() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            ntex::rt::time::delay_for(Duration::from_millis(200)).await;
+            HttpResponse::Ok().body(STR)
+        })))
+    });
+
+    let client = Client::build()
+        .timeout(Duration::from_millis(50000))
+        .finish();
+    let request = client
+        .get(srv.url("/"))
+        .send()
+        .with_timeout(Duration::from_millis(50));
+    match request.await {
+        Err(SendRequestError::Timeout { .. }) => (),
         _ => panic!(),
     }
+
+    // timeout could not be changed after first poll
+    let mut request = client.get(srv.url("/")).send();
+    assert!(futures::poll!(&mut request).is_pending());
+    let request = request.with_timeout(Duration::from_millis(1));
+    assert!(request.await.unwrap().status().is_success());
+}
+
+#[ntex::test]
+async fn test_remaining_timeout() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            ntex::rt::time::delay_for(Duration::from_millis(200)).await;
+            HttpResponse::Ok().body(STR)
+        })))
+    });
+
+    let client = Client::build().timeout(Duration::from_secs(5)).finish();
+    let mut request = client.get(srv.url("/")).send();
+    let remaining = request.remaining_timeout().unwrap();
+    assert!(remaining <= Duration::from_secs(5));
+    assert!(remaining > Duration::from_secs(4));
+
+    assert!(futures::poll!(&mut request).is_pending());
+    ntex::rt::time::delay_for(Duration::from_millis(100)).await;
+    assert!(request.remaining_timeout().unwrap() < remaining);
+
+    let response = (&mut request).await;
+    assert!(response.unwrap().status().is_success());
+    assert!(request.remaining_timeout().is_none());
+
+    // timeout is not set
+    let client = Client::build().disable_timeout().finish();
+    let request = client.get(srv.url("/")).send();
+    assert!(request.remaining_timeout().is_none());
+}
+
+#[ntex::test]
+async fn test_virtual_clock_timeout() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            futures::future::pending::<()>().await;
+            HttpResponse::Ok().finish()
+        })))
+    });
+
+    let clock = VirtualClock::new();
+    let client = Client::build()
+        .timer(clock.clone())
+        .timeout(Duration::from_secs(30))
+        .finish();
+
+    let mut request = client.get(srv.url("/")).send();
+    assert!(futures::poll!(&mut request).is_pending());
+    assert_eq!(request.remaining_timeout(), Some(Duration::from_secs(30)));
+
+    clock.advance(Duration::from_secs(29));
+    assert!(futures::poll!(&mut request).is_pending());
+    assert_eq!(request.remaining_timeout(), Some(Duration::from_secs(1)));
+
+    clock.advance(Duration::from_secs(1));
+    assert!(matches!(
+        request.await,
+        Err(SendRequestError::Timeout { .. })
+    ));
+
+    // replaced timeout uses the same timer
+    let request = client
+        .get(srv.url("/"))
+        .send()
+        .with_timeout(Duration::from_secs(60));
+    clock.advance(Duration::from_secs(60));
+    assert!(matches!(
+        request.await,
+        Err(SendRequestError::Timeout { .. })
+    ));
+    // timeout set on request without default timeout uses the same timer
+    let client = Client::build()
+        .timer(clock.clone())
+        .disable_timeout()
+        .finish();
+    let request = client
+        .get(srv.url("/"))
+        .send()
+        .with_timeout(Duration::from_secs(10));
+    clock.advance(Duration::from_secs(10));
+    assert!(matches!(
+        request.await,
+        Err(SendRequestError::Timeout { .. })
+    ));
+}
+
+#[ntex::test]
+async fn test_timeout_deadline() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            ntex::rt::time::delay_for(Duration::from_millis(100)).await;
+            HttpResponse::Ok().body(STR)
+        })))
+    });
+
+    let client = Client::build()
+        .timeout(Duration::from_millis(50000))
+        .finish();
+
+    // deadline is shared by sequential requests
+    let deadline = Instant::now() + Duration::from_millis(180);
+    let response = client.get(srv.url("/")).deadline(deadline).send().await;
+    assert!(response.unwrap().status().is_success());
+    let response = client.get(srv.url("/")).deadline(deadline).send().await;
+    assert!(matches!(response, Err(SendRequestError::Timeout { .. })));
+
+    // deadline in the past
+    let response = client
+        .get(srv.url("/"))
+        .send()
+        .with_deadline(Instant::now() - Duration::from_millis(1))
+        .await;
+    assert!(matches!(response, Err(SendRequestError::Timeout { .. })));
+
+    // earliest of timeout and deadline applies
+    let response = client
+        .get(srv.url("/"))
+        .timeout(Duration::from_millis(10))
+        .deadline(Instant::now() + Duration::from_secs(10))
+        .send()
+        .await;
+    assert!(matches!(response, Err(SendRequestError::Timeout { .. })));
 }
 
 #[ntex::test]
@@ -443,6 +831,110 @@ async fn test_with_query_parameter() {
     assert!(res.status().is_success());
 }
 
+#[ntex::test]
+async fn test_multipart() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| async move {
+                let ct = req.headers().get(header::CONTENT_TYPE).unwrap();
+                HttpResponse::Ok()
+                    .header("x-content-type", ct.clone())
+                    .body(body)
+            },
+        )))
+    });
+
+    let stream = BodyStream::new(once(ok::<_, io::Error>(Bytes::from_static(b"data"))));
+    let form = Multipart::new().text("name", "value").file(
+        "file",
+        "a.txt",
+        mime::TEXT_PLAIN,
+        Body::from_message(stream),
+    );
+    let boundary = form.boundary().to_string();
+
+    let mut response = srv.post("/").send_multipart(form).await.unwrap();
+    assert_eq!(
+        response.headers().get("x-content-type").unwrap(),
+        &format!("multipart/form-data; boundary={}", boundary)
+    );
+    let bytes = response.body().await.unwrap();
+    let expected = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nvalue\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+         Content-Type: text/plain\r\n\r\ndata\r\n--{b}--\r\n",
+        b = boundary
+    );
+    assert_eq!(bytes, Bytes::from(expected));
+}
+
+#[ntex::test]
+async fn test_request_compress() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| async move {
+                let encoding = req
+                    .headers()
+                    .get(header::CONTENT_ENCODING)
+                    .map(|v| v.to_str().unwrap().to_string())
+                    .unwrap_or_default();
+                HttpResponse::Ok().header("x-encoding", encoding).body(body)
+            },
+        )))
+    });
+
+    for encoding in &[
+        header::ContentEncoding::Gzip,
+        header::ContentEncoding::Deflate,
+        header::ContentEncoding::Br,
+    ] {
+        let mut response = srv
+            .post("/")
+            .compress(*encoding)
+            .send_body(STR)
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(
+            response.headers().get("x-encoding").unwrap(),
+            encoding.as_str()
+        );
+        let bytes = response.body().await.unwrap();
+        assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+        // streaming body
+        let mut response = srv
+            .post("/")
+            .compress(*encoding)
+            .send_stream(once(ok::<_, Error>(Bytes::from_static(STR.as_ref()))))
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(
+            response.headers().get("x-encoding").unwrap(),
+            encoding.as_str()
+        );
+        let bytes = response.body().await.unwrap();
+        assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+    }
+
+    // already encoded body is sent as is
+    let mut e = GzEncoder::new(Vec::new(), Compression::default());
+    e.write_all(STR.as_ref()).unwrap();
+    let data = e.finish().unwrap();
+
+    let mut response = srv
+        .post("/")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .compress(header::ContentEncoding::Br)
+        .send_body(data)
+        .await
+        .unwrap();
+    assert_eq!(response.headers().get("x-encoding").unwrap(), "gzip");
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
 #[ntex::test]
 async fn test_no_decompress() {
     let srv = test::server(|| {
@@ -489,6 +981,41 @@ async fn test_no_decompress() {
     assert_eq!(Bytes::from(dec), Bytes::from_static(STR.as_ref()));
 }
 
+#[ntex::test]
+async fn test_decompress_misleading_encoding() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            // payload is not compressed
+            HttpResponse::Ok()
+                .header(header::CONTENT_ENCODING, "gzip")
+                .body(STR)
+        })))
+    });
+
+    let client = Client::new();
+
+    // decompress is disabled, payload is returned as is
+    let mut res = client
+        .get(srv.url("/"))
+        .decompress(false)
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    // decompress is enabled
+    let mut res = client
+        .get(srv.url("/"))
+        .decompress(true)
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert!(res.body().await.is_err());
+}
+
 #[ntex::test]
 async fn test_client_gzip_encoding() {
     let srv = test::server(|| {
@@ -513,10 +1040,111 @@ async fn test_client_gzip_encoding() {
 }
 
 #[ntex::test]
-async fn test_client_gzip_encoding_large() {
-    let srv = test::server(|| {
-        App::new().service(web::resource("/").route(web::to(|| async {
-            let mut e = GzEncoder::new(Vec::new(), Compression::default());
+async fn test_client_raw_payload() {
+    let mut e = GzEncoder::new(Vec::new(), Compression::default());
+    e.write_all(STR.as_ref()).unwrap();
+    let data = Bytes::from(e.finish().unwrap());
+    let data2 = data.clone();
+
+    let srv = test::server(move || {
+        let data = data2.clone();
+        App::new().service(web::resource("/").route(web::to(move || {
+            let data = data.clone();
+            async move {
+                HttpResponse::Ok()
+                    .header("content-encoding", "gzip")
+                    .body(data)
+            }
+        })))
+    });
+
+    let res = srv.get("/").send().raw().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+
+    // payload is wire bytes
+    let mut payload = res.into_body();
+    let mut body = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        body.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(Bytes::from(body), data);
+}
+
+#[ntex::test]
+async fn test_client_max_response_size() {
+    let srv = test::server(|| {
+        App::new()
+            .service(
+                web::resource("/")
+                    .route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+            )
+            .service(web::resource("/gzip").route(web::to(|| async {
+                let mut e = GzEncoder::new(Vec::new(), Compression::default());
+                e.write_all(STR.as_ref()).unwrap();
+                let data = e.finish().unwrap();
+
+                HttpResponse::Ok()
+                    .header("content-encoding", "gzip")
+                    .body(data)
+            })))
+            .service(web::resource("/chunked").route(web::to(|| async {
+                HttpResponse::Ok().streaming(futures::stream::iter(vec![
+                    Ok::<_, io::Error>(Bytes::from_static(&STR.as_bytes()[..64])),
+                    Ok(Bytes::from_static(&STR.as_bytes()[64..])),
+                ]))
+            })))
+    });
+
+    let client = Client::build().max_response_size(100).finish();
+
+    // content-length exceeds limit
+    let res = client.get(srv.url("/")).send().await;
+    assert!(matches!(
+        res,
+        Err(SendRequestError::ResponsePayloadTooLarge { limit: 100 })
+    ));
+
+    // compressed payload fits, decoded payload does not
+    let mut response = client.get(srv.url("/gzip")).send().await.unwrap();
+    assert!(response.status().is_success());
+    let res = response.body().await;
+    assert!(matches!(res, Err(PayloadError::TooLarge(100))));
+
+    // text body is limited as well
+    let mut response = client.get(srv.url("/gzip")).send().await.unwrap();
+    let res = response.text().await;
+    assert!(matches!(
+        res,
+        Err(TextPayloadError::Payload(PayloadError::TooLarge(100)))
+    ));
+
+    // chunked payload without content-length, overflow converts to
+    // the same error as content-length check
+    let mut response = client.get(srv.url("/chunked")).send().await.unwrap();
+    assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+    let err = response.body().await.err().unwrap();
+    assert!(matches!(
+        SendRequestError::from(err),
+        SendRequestError::ResponsePayloadTooLarge { limit: 100 }
+    ));
+
+    // per-request override
+    let mut response = client
+        .get(srv.url("/gzip"))
+        .max_response_size(STR.len())
+        .send()
+        .await
+        .unwrap();
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
+#[ntex::test]
+async fn test_client_gzip_encoding_large() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            let mut e = GzEncoder::new(Vec::new(), Compression::default());
             e.write_all(STR.repeat(10).as_ref()).unwrap();
             let data = e.finish().unwrap();
 
@@ -535,6 +1163,78 @@ async fn test_client_gzip_encoding_large() {
     assert_eq!(bytes, Bytes::from(STR.repeat(10)));
 }
 
+#[ntex::test]
+async fn test_client_max_decompression_ratio() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            let mut e = GzEncoder::new(Vec::new(), Compression::best());
+            e.write_all(&[0; 1_000_000]).unwrap();
+            let data = e.finish().unwrap();
+
+            HttpResponse::Ok()
+                .header("content-encoding", "gzip")
+                .body(data)
+        })))
+    });
+
+    let client = Client::build().max_decompression_ratio(100).finish();
+
+    // ratio exceeds client limit
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    match res.body().limit(2_000_000).await {
+        Err(PayloadError::DecompressionRatioExceeded(ratio)) => assert_eq!(ratio, 100),
+        res => panic!("unexpected result: {:?}", res),
+    }
+
+    // per-request override
+    let mut res = client
+        .get(srv.url("/"))
+        .max_decompression_ratio(0)
+        .send()
+        .await
+        .unwrap();
+    let bytes = res.body().limit(2_000_000).await.unwrap();
+    assert_eq!(bytes.len(), 1_000_000);
+}
+
+#[ntex::test]
+async fn test_client_conditional() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|req: HttpRequest| {
+            let etag = req.headers().get(header::IF_NONE_MATCH).cloned();
+            async move {
+                if etag == Some(HeaderValue::from_static("\"v1\"")) {
+                    HttpResponse::NotModified().finish()
+                } else {
+                    HttpResponse::Ok()
+                        .header(header::ETAG, "\"v1\"")
+                        .header(header::LAST_MODIFIED, "Sun, 06 Nov 1994 08:49:37 GMT")
+                        .body(STR)
+                }
+            }
+        })))
+    });
+
+    let mut res = srv.get("/").send().await.unwrap();
+    assert!(!res.is_not_modified());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    let req = srv.get("/").conditional(
+        res.headers().get(header::ETAG),
+        res.headers().get(header::LAST_MODIFIED),
+    );
+    assert_eq!(
+        req.headers().get(header::IF_MODIFIED_SINCE).unwrap(),
+        "Sun, 06 Nov 1994 08:49:37 GMT"
+    );
+    let mut res = req.send().await.unwrap();
+    assert!(res.is_not_modified());
+    let bytes = res.body().await.unwrap();
+    assert!(bytes.is_empty());
+}
+
 #[ntex::test]
 async fn test_client_gzip_encoding_large_random() {
     let data = rand::thread_rng()
@@ -683,6 +1383,107 @@ async fn test_client_brotli_encoding_large_random() {
     assert_eq!(bytes, Bytes::from(data.clone()));
 }
 
+#[cfg(feature = "zstd")]
+#[ntex::test]
+async fn test_client_zstd_encoding() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|data: Bytes| async move {
+            let data = z_std::encode_all(data.as_ref(), 3).unwrap();
+            HttpResponse::Ok()
+                .header("content-encoding", "zstd")
+                .body(data)
+        })))
+    });
+
+    // client request
+    let mut response = srv.post("/").send_body(STR).await.unwrap();
+    assert!(response.status().is_success());
+
+    // read response
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
+#[cfg(feature = "zstd")]
+#[ntex::test]
+async fn test_client_zstd_encoding_large_random() {
+    let data = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(70_000)
+        .collect::<String>();
+
+    let srv = test::server(|| {
+        App::new()
+            .wrap(Compress::new(header::ContentEncoding::Zstd))
+            .service(web::resource("/").route(web::to(|data: Bytes| async move {
+                HttpResponse::Ok().body(data)
+            })))
+    });
+
+    // client request
+    let mut response = srv.post("/").send_body(data.clone()).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get(header::CONTENT_ENCODING).unwrap(),
+        "zstd"
+    );
+
+    // read response
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes.len(), data.len());
+    assert_eq!(bytes, Bytes::from(data));
+}
+
+#[ntex::test]
+async fn test_client_chained_encoding() {
+    let data = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(70_000)
+        .collect::<String>();
+
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/").route(web::to(|data: Bytes| async move {
+                let mut e = GzEncoder::new(Vec::new(), Compression::default());
+                e.write_all(&data).unwrap();
+                let data = e.finish().unwrap();
+                let mut e = BrotliEncoder::new(Vec::new(), 5);
+                e.write_all(&data).unwrap();
+                let data = e.finish().unwrap();
+
+                HttpResponse::Ok()
+                    .header("content-encoding", "gzip, br")
+                    .body(data)
+            })))
+            .service(web::resource("/unknown").route(web::to(|| async {
+                let mut e = GzEncoder::new(Vec::new(), Compression::default());
+                e.write_all(STR.as_ref()).unwrap();
+                let data = e.finish().unwrap();
+
+                HttpResponse::Ok()
+                    .header("content-encoding", "x-custom, gzip")
+                    .body(data)
+            })))
+    });
+
+    // client request
+    let mut response = srv.post("/").send_body(data.clone()).await.unwrap();
+    assert!(response.status().is_success());
+
+    // read response
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes.len(), data.len());
+    assert_eq!(bytes, Bytes::from(data));
+
+    // unknown encoding in chain
+    let mut response = srv.get("/unknown").send().await.unwrap();
+    assert!(response.status().is_success());
+    match response.body().await {
+        Err(PayloadError::UnknownEncoding(enc)) => assert_eq!(enc, "x-custom"),
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
 #[ntex::test]
 async fn test_client_deflate_encoding() {
     let srv = test::server(|| {
@@ -908,3 +1709,3029 @@ async fn client_bearer_auth() {
     let response = request.send().await.unwrap();
     assert!(response.status().is_success());
 }
+
+#[ntex::test]
+async fn client_retry_dropped_connection() {
+    let addr = ntex::server::TestServer::unused_addr();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for (idx, stream) in lst.incoming().enumerate() {
+            let mut stream = stream.unwrap();
+            let mut b = [0; 1000];
+            let _ = stream.read(&mut b).unwrap();
+            // drop first connection without response
+            if idx > 0 {
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+                );
+            }
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::build().timeout(Duration::from_secs(30)).finish();
+
+    // without retries request fails
+    let res = client
+        .get(format!("http://{}/", addr).as_str())
+        .send()
+        .await;
+    assert!(res.is_err());
+
+    let res = client
+        .get(format!("http://{}/", addr).as_str())
+        .retry(RetryPolicy::new(2).fixed_backoff(Duration::from_millis(10)))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+}
+
+#[ntex::test]
+async fn client_retry_idempotency_key() {
+    let keys = Arc::new(Mutex::new(Vec::new()));
+    let keys2 = keys.clone();
+
+    let srv = test::server(move || {
+        let keys = keys2.clone();
+        App::new().service(web::resource("/").route(web::to(
+            move |req: HttpRequest, _: Bytes| {
+                let mut keys = keys.lock().unwrap();
+                keys.push(req.headers().get("idempotency-key").cloned());
+                let res = if keys.len() < 2 {
+                    HttpResponse::ServiceUnavailable().finish()
+                } else {
+                    HttpResponse::Ok().finish()
+                };
+                async move { res }
+            },
+        )))
+    });
+
+    let client = Client::build()
+        .retry(RetryPolicy::new(3).fixed_backoff(Duration::from_millis(10)))
+        .finish();
+
+    let res = client
+        .post(srv.url("/"))
+        .idempotency_key()
+        .send_body("data")
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    {
+        let keys = keys.lock().unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].as_ref().unwrap().len(), 36);
+        assert_eq!(keys[0], keys[1]);
+    }
+
+    // user supplied key
+    keys.lock().unwrap().clear();
+    let res = client
+        .post(srv.url("/"))
+        .header("Idempotency-Key", "my-key")
+        .idempotency_key()
+        .send_body("data")
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let keys = keys.lock().unwrap();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0].as_ref().unwrap(), "my-key");
+    assert_eq!(keys[1].as_ref().unwrap(), "my-key");
+}
+
+#[ntex::test]
+async fn client_retry_server_errors() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let srv = test::server(move || {
+        let num = num2.clone();
+        App::new().service(web::resource("/").route(web::to(move |_: Bytes| {
+            num.fetch_add(1, Ordering::Relaxed);
+            async { HttpResponse::ServiceUnavailable() }
+        })))
+    });
+
+    let client = Client::build()
+        .retry(RetryPolicy::new(3).fixed_backoff(Duration::from_millis(10)))
+        .finish();
+
+    let res = client.post(srv.url("/")).send_body("data").await.unwrap();
+    assert_eq!(res.status(), 503);
+    assert_eq!(num.load(Ordering::Relaxed), 3);
+
+    // streaming body is not replayable
+    let res = client
+        .post(srv.url("/"))
+        .send_stream(once(ok::<_, std::io::Error>(Bytes::from_static(b"data"))))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 503);
+    assert_eq!(num.load(Ordering::Relaxed), 4);
+
+    // per-request policy overrides client policy
+    let res = client
+        .post(srv.url("/"))
+        .retry(RetryPolicy::new(1))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 503);
+    assert_eq!(num.load(Ordering::Relaxed), 5);
+}
+
+#[ntex::test]
+async fn client_connect_timeout() {
+    let srv = test::server(|| {
+        App::new().service(
+            web::resource("/").route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+        )
+    });
+
+    let client = Client::build()
+        .timeout(Duration::from_secs(30))
+        .connect_timeout(Duration::from_millis(100))
+        .connector(Connector::default().limit(1).finish())
+        .finish();
+
+    // occupy the only connection
+    let mut response = client.get(srv.url("/")).send().await.unwrap();
+    assert!(response.status().is_success());
+
+    // waiting for connection in the pool is part of connect phase
+    let res = client.get(srv.url("/")).send().await;
+    assert!(matches!(res, Err(SendRequestError::ConnectTimeout)));
+
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    let res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[ntex::test]
+async fn client_connect_io_error_kind() {
+    // reserve free port and close listener
+    let addr = {
+        let lst = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        lst.local_addr().unwrap()
+    };
+
+    let res = Client::new()
+        .get(format!("http://localhost:{}/", addr.port()))
+        .send()
+        .await;
+    match res {
+        Err(err @ SendRequestError::Connect(_)) => {
+            assert_eq!(err.io_error_kind(), Some(io::ErrorKind::ConnectionRefused))
+        }
+        res => panic!("unexpected result: {:?}", res.map(|_| ())),
+    }
+}
+
+#[ntex::test]
+async fn client_pool_key() {
+    let srv = test::server(|| {
+        App::new().service(
+            web::resource("/").route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+        )
+    });
+
+    let client = Client::build()
+        .connect_timeout(Duration::from_millis(100))
+        .connector(Connector::default().limit(1).finish())
+        .finish();
+
+    // occupy the only connection of the default pool
+    let mut response = client.get(srv.url("/")).send().await.unwrap();
+    assert!(response.status().is_success());
+
+    let res = client.get(srv.url("/")).send().await;
+    assert!(matches!(res, Err(SendRequestError::ConnectTimeout)));
+
+    // separate pool is not affected
+    let mut res = client
+        .get(srv.url("/"))
+        .pool_key("background")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
+#[ntex::test]
+async fn client_max_connections_per_host() {
+    let srv = test::server(|| {
+        App::new().service(
+            web::resource("/").route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+        )
+    });
+
+    let client = Client::build()
+        .connect_timeout(Duration::from_millis(100))
+        .max_connections_per_host(1)
+        .keep_alive_timeout(Duration::from_secs(30))
+        .finish();
+
+    // occupy the only connection of the host
+    let mut response = client.get(srv.url("/")).send().await.unwrap();
+    assert!(response.status().is_success());
+
+    let res = client.get(srv.url("/")).send().await;
+    assert!(matches!(res, Err(SendRequestError::ConnectTimeout)));
+
+    // other host is not affected
+    let url = format!("http://127.0.0.1:{}/", srv.addr().port());
+    let mut res = client.get(url).send().await.unwrap();
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    // waiting request gets released connection
+    let (res, bytes) =
+        futures::future::join(client.get(srv.url("/")).send(), async move {
+            response.body().await
+        })
+        .await;
+    assert_eq!(bytes.unwrap(), Bytes::from_static(STR.as_ref()));
+    assert!(res.unwrap().status().is_success());
+}
+
+#[ntex::test]
+async fn client_protocol_h2_prior_knowledge() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let srv = test_server(move || {
+        let num2 = num2.clone();
+        pipeline_factory(move |io| {
+            num2.fetch_add(1, Ordering::Relaxed);
+            ok(io)
+        })
+        .and_then(
+            HttpService::build()
+                .h2(map_config(
+                    App::new().service(
+                        web::resource("/")
+                            .route(web::to(|| async { HttpResponse::Ok() })),
+                    ),
+                    |_| AppConfig::default(),
+                ))
+                .tcp(),
+        )
+    });
+
+    let client = Client::build().protocol(ProtocolMode::Http2Only).finish();
+
+    let responses =
+        futures::future::join_all((0..10).map(|_| client.get(srv.url("/")).send()))
+            .await;
+    for response in responses {
+        let response = response.unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(response.version(), Version::HTTP_2);
+    }
+    assert_eq!(num.load(Ordering::Relaxed), 1);
+}
+
+#[ntex::test]
+async fn client_peer_addr() {
+    let srv = test::server(|| {
+        App::new().service(
+            web::resource("/").route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+        )
+    });
+
+    let mut res = srv.get("/").send().await.unwrap();
+    assert_eq!(res.peer_addr(), Some(srv.addr()));
+    assert!(res.tls_info().is_none());
+    let _ = res.body().await.unwrap();
+
+    // pooled connection
+    let res = srv.get("/").send().await.unwrap();
+    assert_eq!(res.peer_addr(), Some(srv.addr()));
+}
+
+#[ntex::test]
+async fn client_custom_resolver() {
+    let srv = test::server(|| {
+        App::new().service(
+            web::resource("/").route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+        )
+    });
+
+    let addr = srv.addr();
+    let hosts = Arc::new(Mutex::new(Vec::new()));
+    let hosts2 = hosts.clone();
+    let client = Client::build()
+        .resolver(move |host: &str, port: u16| {
+            hosts2.lock().unwrap().push((host.to_string(), port));
+            let addrs = if host == "example.test" {
+                vec![addr]
+            } else {
+                Vec::new()
+            };
+            ok(addrs)
+        })
+        .finish();
+
+    let url = format!("http://example.test:{}/", addr.port());
+    let mut res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.peer_addr(), Some(addr));
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+    assert_eq!(
+        *hosts.lock().unwrap(),
+        vec![("example.test".to_string(), addr.port())]
+    );
+
+    let url = format!("http://unknown.test:{}/", addr.port());
+    let err = client.get(&url).send().await.err().unwrap();
+    match err {
+        SendRequestError::Connect(e) => assert_eq!(e.dns_host(), Some("unknown.test")),
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[ntex::test]
+async fn client_expect_continue() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|body: Bytes| async move {
+            HttpResponse::Ok().body(body)
+        })))
+    });
+
+    let mut res = srv
+        .post("/")
+        .expect_continue()
+        .send_stream(once(ok::<_, io::Error>(Bytes::from_static(STR.as_ref()))))
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
+#[ntex::test]
+async fn client_expect_continue_rejected() {
+    let addr = ntex::server::TestServer::unused_addr();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let mut b = [0; 1000];
+            let _ = stream.read(&mut b).unwrap();
+            let _ = stream.write_all(
+                b"HTTP/1.1 417 Expectation Failed\r\ncontent-length: 0\r\n\r\n",
+            );
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let polled = Arc::new(AtomicUsize::new(0));
+    let polled2 = polled.clone();
+    let stream = futures::stream::poll_fn(move |_| {
+        polled2.fetch_add(1, Ordering::Relaxed);
+        std::task::Poll::Ready(None::<Result<Bytes, io::Error>>)
+    });
+
+    let res = Client::new()
+        .post(format!("http://{}/", addr))
+        .expect_continue()
+        .send_stream(stream)
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 417);
+    assert_eq!(polled.load(Ordering::Relaxed), 0);
+}
+
+#[ntex::test]
+async fn client_max_response_header_size() {
+    let addr = ntex::server::TestServer::unused_addr();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            std::thread::spawn(move || {
+                let mut b = [0; 1000];
+                while let Ok(n) = stream.read(&mut b) {
+                    if n == 0 {
+                        break;
+                    }
+                    if b[..n].starts_with(b"GET /redirect ") {
+                        let _ = stream.write_all(
+                            b"HTTP/1.1 302 Found\r\nlocation: /\r\ncontent-length: 0\r\n\r\n",
+                        );
+                        continue;
+                    }
+                    if b[..n].starts_with(b"GET /large ") {
+                        // response head never completes
+                        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nx-data: ");
+                        let _ = stream.write_all(&[b'a'; 4096]);
+                        continue;
+                    }
+                    // head bytes: 17 + 19 + 20 + 2
+                    let _ = stream.write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nx-data: 0123456789\r\n\r\n",
+                    );
+                }
+            });
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::build().max_response_header_size(58).finish();
+    let res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let err = client
+        .get(format!("http://{}/", addr))
+        .max_response_header_size(57)
+        .send()
+        .await
+        .err()
+        .unwrap();
+    match err {
+        SendRequestError::ResponseHeadersTooLarge { limit } => assert_eq!(limit, 57),
+        e => panic!("unexpected error: {:?}", e),
+    }
+
+    // limit is checked before response head is complete
+    let err = client
+        .get(format!("http://{}/large", addr))
+        .max_response_header_size(1024)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .err()
+        .unwrap();
+    match err {
+        SendRequestError::ResponseHeadersTooLarge { limit } => assert_eq!(limit, 1024),
+        e => panic!("unexpected error: {:?}", e),
+    }
+
+    // request limit applies to redirect hops
+    let client = Client::build().max_redirects(3).finish();
+    let err = client
+        .get(format!("http://{}/redirect", addr))
+        .max_response_header_size(57)
+        .send()
+        .await
+        .err()
+        .unwrap();
+    match err {
+        SendRequestError::ResponseHeadersTooLarge { limit } => assert_eq!(limit, 57),
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[ntex::test]
+async fn client_request_headers() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|body: Bytes| async move {
+            HttpResponse::Ok().body(body)
+        })))
+    });
+
+    let res = srv
+        .post("/")
+        .bearer_auth("secret")
+        .send_json(&serde_json::json!({"a": 1}))
+        .await
+        .unwrap();
+    let headers = res.request_headers().unwrap();
+    assert_eq!(
+        headers.get(header::HOST).unwrap(),
+        &format!("localhost:{}", srv.addr().port())
+    );
+    assert_eq!(
+        headers.get(header::CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+    assert_eq!(headers.get(header::CONTENT_LENGTH).unwrap(), "7");
+    assert_eq!(headers.get(header::AUTHORIZATION).unwrap(), "Bearer secret");
+
+    let res = srv
+        .post("/")
+        .send_stream(once(ok::<_, io::Error>(Bytes::from_static(b"data"))))
+        .await
+        .unwrap();
+    let headers = res.request_headers().unwrap();
+    assert_eq!(headers.get(header::TRANSFER_ENCODING).unwrap(), "chunked");
+    assert!(!headers.contains_key(header::CONTENT_LENGTH));
+}
+
+#[ntex::test]
+async fn client_trace_context() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|req: HttpRequest| {
+            let header = |name| {
+                req.headers()
+                    .get(name)
+                    .map(|v| v.to_str().unwrap().to_string())
+                    .unwrap_or_default()
+            };
+            let body = format!("{};{}", header("traceparent"), header("tracestate"));
+            async move { HttpResponse::Ok().body(body) }
+        })))
+    });
+
+    let current = Rc::new(RefCell::new(None));
+    let current2 = current.clone();
+    let client = Client::build()
+        .trace_context(move || current2.borrow().clone())
+        .finish();
+
+    // no current context
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b";"));
+
+    *current.borrow_mut() = Some(
+        TraceContext::new(0x4bf92f3577b34da6a3ce929d0e0e4736, 0x00f067aa0ba902b7)
+            .state("congo=t61rcWkgMzE"),
+    );
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert_eq!(
+        res.body().await.unwrap(),
+        Bytes::from_static(
+            b"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01;congo=t61rcWkgMzE"
+        )
+    );
+
+    // traceparent set by caller is preserved
+    let mut res = client
+        .get(srv.url("/"))
+        .header(
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        res.body().await.unwrap(),
+        Bytes::from_static(b"00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01;")
+    );
+}
+
+#[ntex::test]
+async fn client_request_interceptors() {
+    struct RequireHeader(HeaderName);
+
+    impl RequestInterceptor for RequireHeader {
+        fn on_request<'a>(
+            &'a self,
+            head: &'a mut RequestHeadType,
+        ) -> LocalBoxFuture<'a, Result<(), SendRequestError>> {
+            Box::pin(async move {
+                if head.as_ref().headers.contains_key(&self.0) {
+                    Ok(())
+                } else {
+                    Err(SendRequestError::Error(Box::new(io::Error::new(
+                        io::ErrorKind::Other,
+                        "header is required",
+                    ))))
+                }
+            })
+        }
+    }
+
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+    let srv = test::server(move || {
+        let num = num2.clone();
+        App::new().service(web::resource("/").route(web::to(move |req: HttpRequest| {
+            num.fetch_add(1, Ordering::Relaxed);
+            let body = req
+                .headers()
+                .get("x-token")
+                .map(|v| v.to_str().unwrap().to_string())
+                .unwrap_or_default();
+            async move { HttpResponse::Ok().body(body) }
+        })))
+    });
+
+    // interceptors are chained in order of registration
+    let client = Client::build()
+        .request_interceptor(|head: &mut RequestHeadType| {
+            head.insert_header(
+                HeaderName::from_static("x-token"),
+                HeaderValue::from_static("secret"),
+            );
+            Ok(())
+        })
+        .request_interceptor(RequireHeader(HeaderName::from_static("x-token")))
+        .finish();
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"secret"));
+    assert_eq!(num.load(Ordering::Relaxed), 1);
+
+    // failed interceptor aborts request without connecting
+    let client = Client::build()
+        .request_interceptor(RequireHeader(HeaderName::from_static("x-token")))
+        .finish();
+    let res = client.get(srv.url("/")).send().await;
+    assert!(matches!(res, Err(SendRequestError::Error(_))));
+    assert_eq!(num.load(Ordering::Relaxed), 1);
+}
+
+#[ntex::test]
+async fn client_response_interceptors() {
+    #[derive(Debug)]
+    struct AuthError;
+
+    impl std::fmt::Display for AuthError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Authentication required")
+        }
+    }
+
+    impl std::error::Error for AuthError {}
+
+    let srv = test::server(|| {
+        App::new()
+            .service(
+                web::resource("/")
+                    .route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+            )
+            .service(
+                web::resource("/private")
+                    .route(web::to(|| async { HttpResponse::Unauthorized().finish() })),
+            )
+    });
+
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let seen2 = seen.clone();
+    let client = Client::build()
+        .response_interceptor(move |res: Result<ClientResponse, SendRequestError>| {
+            if let Ok(ref res) = res {
+                seen2.borrow_mut().push(res.status().as_u16());
+            }
+            res
+        })
+        .response_interceptor(
+            |res: Result<ClientResponse, SendRequestError>| match res {
+                Ok(res) if res.status() == StatusCode::UNAUTHORIZED => {
+                    Err(SendRequestError::Error(Box::new(AuthError)))
+                }
+                res => res,
+            },
+        )
+        .finish();
+
+    // response body is not consumed by interceptors
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(STR.as_ref()));
+
+    match client.get(srv.url("/private")).send().await {
+        Err(SendRequestError::Error(e)) => assert!(e.is::<AuthError>()),
+        res => panic!("unexpected result: {:?}", res.map(|_| ())),
+    }
+    assert_eq!(*seen.borrow(), vec![200, 401]);
+}
+
+#[ntex::test]
+async fn client_lenient_decompression() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            let mut e = GzEncoder::new(Vec::new(), Compression::default());
+            e.write_all(STR.as_ref()).unwrap();
+            let mut data = e.finish().unwrap();
+            data.extend_from_slice(b"trailing garbage");
+
+            HttpResponse::Ok()
+                .header(header::CONTENT_ENCODING, "gzip")
+                .body(data)
+        })))
+    });
+
+    // strict by default
+    let mut res = srv.get("/").send().await.unwrap();
+    assert!(res.body().await.is_err());
+
+    let client = Client::build().lenient_decompression().finish();
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
+#[ntex::test]
+async fn client_send_stream_with_options() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let mut data = Vec::new();
+            let mut b = [0; 1000];
+            while let Ok(n) = stream.read(&mut b) {
+                if n == 0 {
+                    break;
+                }
+                data.extend_from_slice(&b[..n]);
+                if data.ends_with(b"0\r\n\r\n") {
+                    let pos = data.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+                    let _ = tx.send(data.split_off(pos + 4));
+                    data.clear();
+                    let _ = stream
+                        .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n");
+                }
+            }
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let chunks = || {
+        futures::stream::iter(
+            (0..10).map(|_| Ok::<_, io::Error>(Bytes::from_static(b"a"))),
+        )
+    };
+    let client = Client::new();
+
+    // small chunks are coalesced
+    let res = client
+        .post(format!("http://{}/", addr))
+        .send_stream_with(
+            chunks(),
+            StreamOptions::new().min_chunk_size(4).max_buffer_size(1),
+        )
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(
+        rx.recv().unwrap(),
+        b"4\r\naaaa\r\n4\r\naaaa\r\n2\r\naa\r\n0\r\n\r\n".to_vec()
+    );
+
+    // chunks are sent as is by default
+    let res = client
+        .post(format!("http://{}/", addr))
+        .send_stream(chunks())
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(
+        rx.recv().unwrap(),
+        [b"1\r\na\r\n".repeat(10), b"0\r\n\r\n".to_vec()].concat()
+    );
+}
+
+#[ntex::test]
+async fn client_drop_request_closes_connection() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let idx = num2.fetch_add(1, Ordering::Relaxed);
+            std::thread::spawn(move || {
+                let mut data = Vec::new();
+                let mut b = [0; 1000];
+                while let Ok(n) = stream.read(&mut b) {
+                    if n == 0 {
+                        break;
+                    }
+                    data.extend_from_slice(&b[..n]);
+                    if data.windows(4).any(|w| w == b"\r\n\r\n") {
+                        data.clear();
+                        // first connection responds before request body is sent
+                        let body: &[u8] = if idx == 0 { b"stale" } else { b"fresh" };
+                        let _ = stream
+                            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\n");
+                        let _ = stream.write_all(body);
+                    }
+                }
+            });
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::new();
+
+    // request body never completes, request future is dropped mid-body
+    let body = once(ok::<_, io::Error>(Bytes::from_static(b"data")))
+        .chain(futures::stream::pending());
+    let req = client.post(format!("http://{}/", addr)).send_stream(body);
+    let delay = ntex::rt::time::delay_for(Duration::from_millis(200));
+    match futures::future::select(req, delay).await {
+        futures::future::Either::Left(_) => panic!("request must not complete"),
+        futures::future::Either::Right(_) => (),
+    }
+
+    // next request uses fresh connection
+    let mut res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"fresh"));
+    assert_eq!(num.load(Ordering::Relaxed), 2);
+}
+
+#[ntex::test]
+async fn client_response_trailers() {
+    let addr = ntex::server::TestServer::unused_addr();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let mut b = [0; 1000];
+            while let Ok(n) = stream.read(&mut b) {
+                if n == 0 {
+                    break;
+                }
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n\
+                      4\r\ndata\r\n0\r\ngrpc-status: 0\r\n\r\n",
+                );
+            }
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::new();
+    let mut res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.trailers().is_none());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"data"));
+    let trailers = res.trailers().unwrap();
+    assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+
+    // connection is reused after trailers
+    let mut res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"data"));
+    assert_eq!(res.trailers().unwrap().len(), 1);
+}
+
+#[ntex::test]
+async fn client_response_length_and_chunked() {
+    let addr = ntex::server::TestServer::unused_addr();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            std::thread::spawn(move || {
+                let mut b = [0; 1000];
+                while let Ok(n) = stream.read(&mut b) {
+                    if n == 0 {
+                        break;
+                    }
+                    if b[..n].starts_with(b"GET /redirect ") {
+                        let _ = stream.write_all(
+                            b"HTTP/1.1 302 Found\r\nlocation: /\r\n\
+                              content-length: 0\r\n\r\n",
+                        );
+                        continue;
+                    }
+                    let _ = stream.write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\
+                          transfer-encoding: chunked\r\n\r\n\
+                          4\r\ndata\r\n0\r\n\r\n",
+                    );
+                }
+            });
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    // body length is taken from chunked framing
+    let client = Client::new();
+    let mut res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(!res.headers().contains_key(header::CONTENT_LENGTH));
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"data"));
+
+    // connection is reused, framing is not broken
+    let mut res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"data"));
+
+    // strict mode rejects conflicting framing
+    let client = Client::build().strict_framing().finish();
+    let res = client.get(format!("http://{}/", addr)).send().await;
+    assert!(matches!(
+        res,
+        Err(SendRequestError::Response(ParseError::InvalidInput(_)))
+    ));
+
+    // strict mode applies to redirect hops
+    let client = Client::build().strict_framing().max_redirects(3).finish();
+    let res = client.get(format!("http://{}/redirect", addr)).send().await;
+    assert!(matches!(
+        res,
+        Err(SendRequestError::Response(ParseError::InvalidInput(_)))
+    ));
+}
+
+#[ntex::test]
+async fn client_cookie_jar() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/login").route(web::to(|| async {
+                HttpResponse::Ok()
+                    .header(header::SET_COOKIE, "session=1; Path=/")
+                    .finish()
+            })))
+            .service(web::resource("/redirect").route(web::to(|| async {
+                HttpResponse::Found()
+                    .header(header::SET_COOKIE, "hop=2; Path=/")
+                    .header(header::LOCATION, "/check")
+                    .finish()
+            })))
+            .service(web::resource("/check").route(web::to(|req: HttpRequest| {
+                let cookies = req
+                    .headers()
+                    .get(header::COOKIE)
+                    .map(|v| v.to_str().unwrap().to_string())
+                    .unwrap_or_default();
+                async move { HttpResponse::Ok().body(cookies) }
+            })))
+    });
+
+    let jar = Arc::new(Mutex::new(CookieJar::new()));
+    let client = Client::build()
+        .cookie_jar(jar.clone())
+        .max_redirects(2)
+        .finish();
+
+    let res = client.get(srv.url("/login")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(jar.lock().unwrap().get("session").unwrap().value(), "1");
+
+    let mut res = client.get(srv.url("/check")).send().await.unwrap();
+    let body = res.body().await.unwrap();
+    assert_eq!(body, Bytes::from_static(b"session=1"));
+
+    // jar is consulted on each redirect hop
+    let mut res = client.get(srv.url("/redirect")).send().await.unwrap();
+    let body = res.body().await.unwrap();
+    assert_eq!(body, Bytes::from_static(b"session=1; hop=2"));
+    assert_eq!(client.cookie_jar().unwrap().lock().unwrap().len(), 2);
+}
+
+#[ntex::test]
+async fn client_origin_referer() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/target").route(web::to(|req: HttpRequest| {
+                let header = |name| {
+                    req.headers()
+                        .get(name)
+                        .map(|v| v.to_str().unwrap().to_string())
+                        .unwrap_or_default()
+                };
+                let body =
+                    format!("{} {}", header(header::ORIGIN), header(header::REFERER));
+                async move { HttpResponse::Ok().body(body) }
+            })))
+            .service(web::resource("/found").route(web::to(|| async {
+                HttpResponse::Found()
+                    .header(header::LOCATION, "/target")
+                    .finish()
+            })))
+    });
+
+    let client = Client::build().max_redirects(3).finish();
+    let mut res = client
+        .get(srv.url("/target"))
+        .origin("http://localhost:8080/login")
+        .referer("http://localhost:8080/login?next=1")
+        .send()
+        .await
+        .unwrap();
+    let body = res.body().await.unwrap();
+    assert_eq!(
+        body,
+        Bytes::from_static(b"http://localhost:8080 http://localhost:8080/login?next=1")
+    );
+
+    // referer is updated to the redirected url
+    let mut res = client
+        .get(srv.url("/found"))
+        .origin("http://localhost:8080/")
+        .referer("http://localhost:8080/login")
+        .send()
+        .await
+        .unwrap();
+    let body = res.body().await.unwrap();
+    assert_eq!(
+        body,
+        Bytes::from(format!("http://localhost:8080 {}", srv.url("/found")))
+    );
+
+    let res = client
+        .get(srv.url("/target"))
+        .referer("/login")
+        .send()
+        .await;
+    assert!(matches!(
+        res,
+        Err(SendRequestError::Url(InvalidUrl::MissingScheme))
+    ));
+}
+
+#[ntex::test]
+async fn client_redirects() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/target").route(web::to(
+                |req: HttpRequest, body: Bytes| async move {
+                    let auth = req.headers().contains_key(header::AUTHORIZATION);
+                    HttpResponse::Ok().body(format!(
+                        "{} {} {}",
+                        req.method(),
+                        String::from_utf8_lossy(&body),
+                        auth
+                    ))
+                },
+            )))
+            .service(web::resource("/found").route(web::to(|_: Bytes| async {
+                HttpResponse::Found()
+                    .header(header::LOCATION, "/target")
+                    .finish()
+            })))
+            .service(web::resource("/see-other").route(web::to(|_: Bytes| async {
+                HttpResponse::SeeOther()
+                    .header(header::LOCATION, "target")
+                    .finish()
+            })))
+            .service(web::resource("/temporary").route(web::to(|_: Bytes| async {
+                HttpResponse::TemporaryRedirect()
+                    .header(header::LOCATION, "/target")
+                    .finish()
+            })))
+            .service(web::resource("/loop").route(web::to(|req: HttpRequest| {
+                // endless redirects, every hop has new url
+                let hop = req.query_string().parse::<usize>().unwrap_or(0) + 1;
+                async move {
+                    HttpResponse::Found()
+                        .header(header::LOCATION, format!("/loop?{}", hop))
+                        .finish()
+                }
+            })))
+    });
+
+    // redirects are not followed by default
+    let res = Client::new().get(srv.url("/found")).send().await.unwrap();
+    assert_eq!(res.status(), 302);
+
+    let client = Client::build().max_redirects(3).finish();
+
+    let mut res = client
+        .post(srv.url("/found"))
+        .bearer_auth("token")
+        .send_body("data")
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.url().path(), "/target");
+    let body = res.body().await.unwrap();
+    assert_eq!(body, Bytes::from_static(b"GET  true"));
+
+    let mut res = client
+        .put(srv.url("/see-other"))
+        .send_body("data")
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let body = res.body().await.unwrap();
+    assert_eq!(body, Bytes::from_static(b"GET  false"));
+
+    let mut res = client
+        .post(srv.url("/temporary"))
+        .send_body("data")
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let body = res.body().await.unwrap();
+    assert_eq!(body, Bytes::from_static(b"POST data false"));
+
+    // streaming body could not be sent again
+    let res = client
+        .post(srv.url("/temporary"))
+        .send_stream(once(ok::<_, std::io::Error>(Bytes::from_static(b"data"))))
+        .await;
+    assert!(matches!(res, Err(SendRequestError::BodyNotReplayable)));
+
+    let res = client.get(srv.url("/loop")).send().await;
+    assert!(matches!(res, Err(SendRequestError::TooManyRedirects)));
+
+    let client = Client::build()
+        .max_redirects(3)
+        .disable_redirects()
+        .finish();
+    let res = client.get(srv.url("/found")).send().await.unwrap();
+    assert_eq!(res.status(), 302);
+}
+
+#[ntex::test]
+async fn client_redirect_loop() {
+    fn redirect_to(url: Arc<Mutex<String>>) -> test::TestServer {
+        test::server(move || {
+            let url = url.clone();
+            App::new().service(web::resource("/").route(web::to(move || {
+                let location = url.lock().unwrap().clone();
+                async move {
+                    HttpResponse::Found()
+                        .header(header::LOCATION, location)
+                        .finish()
+                }
+            })))
+        })
+    }
+
+    // two servers redirect to each other
+    let url1 = Arc::new(Mutex::new(String::new()));
+    let url2 = Arc::new(Mutex::new(String::new()));
+    let srv1 = redirect_to(url2.clone());
+    let srv2 = redirect_to(url1.clone());
+    *url1.lock().unwrap() = srv1.url("/");
+    *url2.lock().unwrap() = srv2.url("/");
+
+    let client = Client::build().max_redirects(10).finish();
+    let res = client.get(srv1.url("/")).send().await;
+    match res {
+        Err(SendRequestError::RedirectLoop { chain }) => {
+            let chain: Vec<_> = chain.iter().map(|u| u.to_string()).collect();
+            assert_eq!(chain, vec![srv1.url("/"), srv2.url("/"), srv1.url("/")]);
+        }
+        _ => panic!("redirect loop is expected"),
+    }
+
+    // hop limit is checked first
+    let client = Client::build().max_redirects(1).finish();
+    let res = client.get(srv1.url("/")).send().await;
+    assert!(matches!(res, Err(SendRequestError::TooManyRedirects)));
+}
+
+#[ntex::test]
+async fn client_deny_redirects() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/found").route(web::to(|| async {
+                HttpResponse::Found()
+                    .header(header::LOCATION, "/target")
+                    .finish()
+            })))
+            .service(
+                web::resource("/not-modified")
+                    .route(web::to(|| async { HttpResponse::NotModified().finish() })),
+            )
+            .service(
+                web::resource("/target")
+                    .route(web::to(|| async { HttpResponse::Ok().finish() })),
+            )
+    });
+
+    // redirect response is returned by default
+    let res = Client::new().get(srv.url("/found")).send().await.unwrap();
+    assert_eq!(res.status(), 302);
+
+    let client = Client::build().max_redirects(3).deny_redirects().finish();
+    match client.get(srv.url("/found")).send().await {
+        Err(SendRequestError::UnexpectedRedirect { status, location }) => {
+            assert_eq!(status, 302);
+            assert_eq!(location.as_deref(), Some("/target"));
+        }
+        _ => panic!(),
+    }
+
+    let res = client.get(srv.url("/not-modified")).send().await.unwrap();
+    assert_eq!(res.status(), 304);
+    let res = client.get(srv.url("/target")).send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[ntex::test]
+async fn client_send_reader() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| async move {
+                let length = req
+                    .headers()
+                    .get(header::CONTENT_LENGTH)
+                    .map(|v| v.to_str().unwrap().to_string())
+                    .unwrap_or_else(|| "chunked".to_string());
+                HttpResponse::Ok().header("x-length", length).body(body)
+            },
+        )))
+    });
+
+    // known size
+    let mut res = srv
+        .post("/")
+        .send_reader(STR.as_bytes(), Some(STR.len() as u64), 100)
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(
+        res.headers().get("x-length").unwrap(),
+        STR.len().to_string().as_str()
+    );
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    // unknown size
+    let mut res = srv
+        .post("/")
+        .send_reader(STR.as_bytes(), None, 100)
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get("x-length").unwrap(), "chunked");
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
+#[ntex::test]
+async fn client_content_length_mismatch() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|body: Bytes| async move {
+            HttpResponse::Ok().body(body)
+        })))
+    });
+
+    let res = srv.post("/").content_length(100).send_body("data").await;
+    match res {
+        Err(SendRequestError::Error(e)) => {
+            let e = e.downcast_ref::<io::Error>().unwrap();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        }
+        res => panic!("unexpected result: {:?}", res.map(|_| ())),
+    }
+
+    let mut res = srv
+        .post("/")
+        .content_length(4)
+        .send_body("data")
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"data"));
+}
+
+#[ntex::test]
+async fn client_rate_limit() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/").route(web::to(|| async { HttpResponse::Ok() })))
+    });
+
+    let client = Client::build()
+        .rate_limit(RateLimit::new(10).burst(2))
+        .finish();
+    let start = Instant::now();
+    for _ in 0..4 {
+        let res = client.get(srv.url("/")).send().await.unwrap();
+        assert!(res.status().is_success());
+    }
+    // burst is sent at once, two more requests wait for tokens
+    assert!(start.elapsed() >= Duration::from_millis(190));
+
+    // request waiting too long for a token times out
+    let client = Client::build()
+        .rate_limit(RateLimit::new(1))
+        .timeout(Duration::from_millis(200))
+        .finish();
+    let res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    let res = client.get(srv.url("/")).send().await;
+    assert!(matches!(
+        res,
+        Err(SendRequestError::Timeout {
+            phase: TimeoutPhase::Connect
+        })
+    ));
+}
+
+#[ntex::test]
+async fn client_http_proxy() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    // proxy records request heads and responds itself
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                let mut data = Vec::new();
+                let mut b = [0; 1000];
+                while let Ok(n) = stream.read(&mut b) {
+                    if n == 0 {
+                        break;
+                    }
+                    data.extend_from_slice(&b[..n]);
+                    if data.ends_with(b"\r\n\r\n") {
+                        let _ = tx.send(String::from_utf8_lossy(&data).to_string());
+                        data.clear();
+                        let _ = stream.write_all(
+                            b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok",
+                        );
+                    }
+                }
+            });
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let proxy = Proxy::http(format!("http://{}", addr).as_str())
+        .unwrap()
+        .basic_auth("user", "pass")
+        .no_proxy("localhost");
+    let client = Client::build().proxy(proxy).finish();
+
+    let mut res = client
+        .get("http://rust-lang.org:8080/test?q=1")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+
+    let head = rx.recv().unwrap();
+    assert!(head.starts_with("GET http://rust-lang.org:8080/test?q=1 HTTP/1.1\r\n"));
+    assert!(head.contains("host: rust-lang.org:8080\r\n"));
+    assert!(head.contains("proxy-authorization: Basic dXNlcjpwYXNz\r\n"));
+
+    // excluded host is connected directly
+    let res = client
+        .get(format!("http://localhost:{}/", addr.port()))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let head = rx.recv().unwrap();
+    assert!(head.starts_with("GET / HTTP/1.1\r\n"));
+    assert!(!head.contains("proxy-authorization"));
+}
+
+#[ntex::test]
+async fn client_response_version() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/").route(web::to(|| async { HttpResponse::Ok() })))
+    });
+
+    let res = srv.get("/").send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.version(), Version::HTTP_11);
+
+    // http/1.0 server
+    let addr = ntex::server::TestServer::unused_addr();
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+        let (mut stream, _) = lst.accept().unwrap();
+        let mut b = [0; 1000];
+        let _ = stream.read(&mut b).unwrap();
+        let _ = stream.write_all(b"HTTP/1.0 200 OK\r\ncontent-length: 0\r\n\r\n");
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let res = Client::new()
+        .get(format!("http://{}/", addr).as_str())
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.version(), Version::HTTP_10);
+}
+
+#[ntex::test]
+async fn client_request_signer() {
+    // signature is a checksum of method, path and body
+    fn signature(head: &RequestHeadType, body: SignPayload<'_>) -> String {
+        let head = head.as_ref();
+        match body {
+            SignPayload::Empty => format!("{} {} empty", head.method, head.uri.path()),
+            SignPayload::Bytes(b) => format!(
+                "{} {} {}",
+                head.method,
+                head.uri.path(),
+                b.iter().map(|b| *b as usize).sum::<usize>()
+            ),
+            SignPayload::Unsigned => {
+                format!("{} {} unsigned", head.method, head.uri.path())
+            }
+        }
+    }
+
+    struct BufferingSigner;
+
+    impl RequestSigner for BufferingSigner {
+        fn sign(
+            &self,
+            head: &mut RequestHeadType,
+            body: SignPayload<'_>,
+        ) -> Result<(), SendRequestError> {
+            let sig = signature(head, body);
+            head.insert_header(
+                HeaderName::from_static("x-signature"),
+                HeaderValue::from_str(&sig).unwrap(),
+            );
+            Ok(())
+        }
+
+        fn streaming_body(&self) -> StreamingBody {
+            StreamingBody::Buffer
+        }
+    }
+
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/test").to(|req: HttpRequest, body: Bytes| {
+                let sig = req
+                    .headers()
+                    .get("x-signature")
+                    .map(|v| v.to_str().unwrap().to_string())
+                    .unwrap_or_default();
+                async move { HttpResponse::Ok().header("x-signature", sig).body(body) }
+            }))
+            .service(web::resource("/redirect").to(|_: Bytes| async {
+                HttpResponse::Found()
+                    .header(header::LOCATION, "/test")
+                    .finish()
+            }))
+    });
+    let stream = || once(ok::<_, Error>(Bytes::from_static(b"\x01\x02")));
+
+    let client = Client::build()
+        .signer(|head: &mut RequestHeadType, body: SignPayload<'_>| {
+            let sig = signature(head, body);
+            head.insert_header(
+                HeaderName::from_static("x-signature"),
+                HeaderValue::from_str(&sig).unwrap(),
+            );
+            Ok(())
+        })
+        .max_redirects(2)
+        .finish();
+
+    let res = client.get(srv.url("/test")).send().await.unwrap();
+    assert_eq!(res.headers().get("x-signature").unwrap(), "GET /test empty");
+    let res = client
+        .post(srv.url("/test"))
+        .send_body(Bytes::from_static(b"\x01\x02\x03"))
+        .await
+        .unwrap();
+    assert_eq!(res.headers().get("x-signature").unwrap(), "POST /test 6");
+    let mut res = client
+        .post(srv.url("/test"))
+        .send_stream(stream())
+        .await
+        .unwrap();
+    assert_eq!(
+        res.headers().get("x-signature").unwrap(),
+        "POST /test unsigned"
+    );
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"\x01\x02"));
+
+    // redirect hop is signed again
+    let res = client
+        .post(srv.url("/redirect"))
+        .send_body(Bytes::from_static(b"\x01\x02\x03"))
+        .await
+        .unwrap();
+    assert_eq!(res.headers().get("x-signature").unwrap(), "GET /test empty");
+
+    // streaming body is buffered and signed
+    let client = Client::build().signer(BufferingSigner).finish();
+    let mut res = client
+        .post(srv.url("/test"))
+        .send_stream(stream())
+        .await
+        .unwrap();
+    assert_eq!(res.headers().get("x-signature").unwrap(), "POST /test 3");
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"\x01\x02"));
+
+    // signer error fails request
+    let client = Client::build()
+        .signer(|_: &mut RequestHeadType, _: SignPayload<'_>| {
+            Err(SendRequestError::Error("no credentials".into()))
+        })
+        .finish();
+    assert!(client.get(srv.url("/test")).send().await.is_err());
+}
+
+#[ntex::test]
+async fn client_happy_eyeballs() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/").route(web::to(|| async { HttpResponse::Ok() })))
+    });
+
+    let client = Client::build()
+        .happy_eyeballs(Duration::from_millis(50))
+        .finish();
+    let url = format!("http://localhost:{}/", srv.addr().port());
+    let res = client.get(url).send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[ntex::test]
+async fn client_preconnect() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let srv = test_server(move || {
+        let num2 = num2.clone();
+        pipeline_factory(move |io| {
+            num2.fetch_add(1, Ordering::Relaxed);
+            ok(io)
+        })
+        .and_then(
+            HttpService::new(map_config(
+                App::new().service(
+                    web::resource("/").route(web::to(|| async { HttpResponse::Ok() })),
+                ),
+                |_| AppConfig::default(),
+            ))
+            .tcp(),
+        )
+    });
+
+    let client = Client::new();
+    client.preconnect(srv.url("/")).await.unwrap();
+    ntex::rt::time::delay_for(Duration::from_millis(100)).await;
+    assert_eq!(num.load(Ordering::Relaxed), 1);
+
+    // request reuses warm connection
+    let response = client.get(srv.url("/")).send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(num.load(Ordering::Relaxed), 1);
+
+    assert!(matches!(
+        client.preconnect("/test").await,
+        Err(SendRequestError::Url(_))
+    ));
+
+    // idle connection is reused, connection limit is respected
+    let client = Client::build().max_connections_per_host(1).finish();
+    client.preconnect(srv.url("/")).await.unwrap();
+    client.preconnect(srv.url("/")).await.unwrap();
+    ntex::rt::time::delay_for(Duration::from_millis(100)).await;
+    assert_eq!(num.load(Ordering::Relaxed), 2);
+
+    // connect errors are returned
+    let addr = ntex::server::TestServer::unused_addr();
+    let res = client.preconnect(format!("http://{}/", addr)).await;
+    assert!(matches!(res, Err(SendRequestError::Connect(_))));
+}
+
+#[ntex::test]
+async fn client_chunked_stream() {
+    let data = Arc::new(Mutex::new(Vec::new()));
+    let data2 = data.clone();
+
+    let addr = ntex::server::TestServer::unused_addr();
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+        let (mut stream, _) = lst.accept().unwrap();
+        let mut buf = Vec::new();
+        let mut b = [0; 1000];
+        while !buf.ends_with(b"\r\n0\r\nx-checksum: 12\r\n\r\n") {
+            let n = stream.read(&mut b).unwrap();
+            if n == 0 {
+                break;
+            }
+            buf.extend_from_slice(&b[..n]);
+        }
+        *data2.lock().unwrap() = buf;
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n");
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let mut trailers = header::HeaderMap::new();
+    trailers.insert(
+        HeaderName::from_static("x-checksum"),
+        HeaderValue::from_static("12"),
+    );
+    let chunks = vec![
+        Ok::<_, io::Error>(Bytes::from_static(b"event: 1\n")),
+        Ok(Bytes::new()),
+        Ok(Bytes::from_static(b"data")),
+    ];
+    let body = ChunkedStream::new(futures::stream::iter(chunks)).set_trailers(trailers);
+
+    let res = Client::new()
+        .post(format!("http://{}/", addr))
+        .send_body(Body::from_message(body))
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let data = data.lock().unwrap();
+    let data = String::from_utf8_lossy(&data);
+    assert!(data.contains("transfer-encoding: chunked\r\n"));
+    assert!(data.ends_with(
+        "\r\n\r\n9\r\nevent: 1\n\r\n4\r\ndata\r\n0\r\nx-checksum: 12\r\n\r\n"
+    ));
+}
+
+#[ntex::test]
+async fn client_send_stream_buffer_body() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| async move {
+                let length = req
+                    .headers()
+                    .get(header::CONTENT_LENGTH)
+                    .map(|v| v.to_str().unwrap().to_string())
+                    .unwrap_or_else(|| "chunked".to_string());
+                HttpResponse::Ok().header("x-length", length).body(body)
+            },
+        )))
+    });
+
+    let chunks = |n| {
+        futures::stream::iter(
+            (0..n).map(|_| Ok::<_, io::Error>(Bytes::from_static(b"aa"))),
+        )
+    };
+
+    // stream fits into buffer
+    let mut res = srv
+        .post("/")
+        .send_stream_with(chunks(5), StreamOptions::new().buffer_body(10))
+        .await
+        .unwrap();
+    assert_eq!(res.headers().get("x-length").unwrap(), "10");
+    assert_eq!(res.body().await.unwrap(), Bytes::from("a".repeat(10)));
+
+    // stream is larger than buffer
+    let mut res = srv
+        .post("/")
+        .send_stream_with(chunks(6), StreamOptions::new().buffer_body(10))
+        .await
+        .unwrap();
+    assert_eq!(res.headers().get("x-length").unwrap(), "chunked");
+    assert_eq!(res.body().await.unwrap(), Bytes::from("a".repeat(12)));
+
+    // stream is not buffered by default
+    let res = srv.post("/").send_stream(chunks(1)).await.unwrap();
+    assert_eq!(res.headers().get("x-length").unwrap(), "chunked");
+}
+
+#[ntex::test]
+async fn client_interrupted_payload() {
+    let addr = ntex::server::TestServer::unused_addr();
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for (idx, stream) in lst.incoming().enumerate() {
+            let mut stream = stream.unwrap();
+            let mut b = [0; 1000];
+            let _ = stream.read(&mut b).unwrap();
+            if idx == 2 {
+                let _ = stream.write_all(b"HTTP/1.0 200 OK\r\n\r\nfull body");
+                continue;
+            }
+            let ranges = if idx == 0 {
+                "accept-ranges: bytes\r\n"
+            } else {
+                ""
+            };
+            let _ = stream.write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: 100\r\n{}\r\n{}",
+                    ranges,
+                    "a".repeat(40)
+                )
+                .as_bytes(),
+            );
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::new();
+    let mut res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    let err = res.body().await.err().unwrap();
+    assert_eq!(err.resume_offset(), Some(40));
+    assert!(matches!(
+        err,
+        PayloadError::Interrupted {
+            received: 40,
+            accept_ranges: true,
+            ..
+        }
+    ));
+
+    // server does not support ranges
+    let mut res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    let err = res.body().await.err().unwrap();
+    assert!(err.resume_offset().is_none());
+    assert!(matches!(
+        err,
+        PayloadError::Interrupted {
+            received: 40,
+            accept_ranges: false,
+            ..
+        }
+    ));
+
+    // payload is delimited by connection close
+    let mut res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"full body"));
+}
+
+#[ntex::test]
+async fn client_byte_ranges() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").to(|req: HttpRequest| async move {
+            let ranges = req.headers().get(header::RANGE).unwrap().to_str().unwrap();
+            let ranges: Vec<(usize, usize)> = ranges[6..]
+                .split(',')
+                .map(|range| {
+                    let mut range = range.split('-');
+                    let start = range.next().unwrap().parse().unwrap();
+                    (start, range.next().unwrap().parse().unwrap())
+                })
+                .collect();
+
+            if ranges.len() == 1 {
+                let (start, end) = ranges[0];
+                return HttpResponse::PartialContent()
+                    .header(
+                        header::CONTENT_RANGE,
+                        format!("bytes {}-{}/{}", start, end, STR.len()),
+                    )
+                    .body(&STR[start..=end]);
+            }
+            let mut body = String::new();
+            for (start, end) in ranges {
+                body.push_str(&format!(
+                    "--sep\r\ncontent-type: text/plain\r\n\
+                     content-range: bytes {}-{}/{}\r\n\r\n{}\r\n",
+                    start,
+                    end,
+                    STR.len(),
+                    &STR[start..=end]
+                ));
+            }
+            body.push_str("--sep--\r\n");
+            HttpResponse::PartialContent()
+                .content_type("multipart/byteranges; boundary=sep")
+                .body(body)
+        }))
+    });
+
+    let mut res = srv.get("/").range(10, 19).send().await.unwrap();
+    assert!(res.is_partial());
+    assert_eq!(res.content_range().unwrap().total, Some(STR.len() as u64));
+    let parts: Vec<_> = res.byte_ranges().collect().await;
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0].as_ref().unwrap().body, &STR.as_bytes()[10..20]);
+
+    let mut res = srv
+        .get("/")
+        .range(0, 4)
+        .range(100, 199)
+        .send()
+        .await
+        .unwrap();
+    assert!(res.is_partial());
+    assert!(res.content_range().is_none());
+    let parts: Vec<_> = res.byte_ranges().collect().await;
+    assert_eq!(parts.len(), 2);
+    let part = parts[0].as_ref().unwrap();
+    assert_eq!((part.range.start, part.range.end), (0, 4));
+    assert_eq!(
+        part.headers.get(header::CONTENT_TYPE).unwrap(),
+        "text/plain"
+    );
+    assert_eq!(part.body, &STR.as_bytes()[..5]);
+    let part = parts[1].as_ref().unwrap();
+    assert_eq!((part.range.start, part.range.end), (100, 199));
+    assert_eq!(part.body, &STR.as_bytes()[100..200]);
+}
+
+#[ntex::test]
+async fn client_fallback_addresses() {
+    let srv = test::server(|| {
+        App::new().service(
+            web::resource("/").route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+        )
+    });
+
+    // reserve free port and close listener
+    let closed = {
+        let lst = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        lst.local_addr().unwrap()
+    };
+
+    let client = Client::new();
+    let mut res = client
+        .get("http://service.local/")
+        .addresses(vec![closed, srv.addr()])
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.peer_addr(), Some(srv.addr()));
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    let res = client
+        .get("http://other.local/")
+        .addresses(vec![closed])
+        .send()
+        .await;
+    assert!(matches!(res, Err(SendRequestError::Connect(_))));
+}
+
+#[cfg(feature = "client-digest")]
+#[ntex::test]
+async fn client_save_to() {
+    use sha1::{Digest, Sha1};
+
+    let srv = test::server(|| {
+        App::new()
+            .service(
+                web::resource("/")
+                    .route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+            )
+            .service(web::resource("/gzip").route(web::to(|| async {
+                let mut e = GzEncoder::new(Vec::new(), Compression::default());
+                e.write_all(STR.as_ref()).unwrap();
+                HttpResponse::Ok()
+                    .header("content-encoding", "gzip")
+                    .body(e.finish().unwrap())
+            })))
+    });
+    let dir = std::env::temp_dir();
+    let dest = dir.join(format!("ntex-save-{}", rand::random::<u32>()));
+
+    let mut res = srv.get("/").send().await.unwrap();
+    let size = res
+        .save_to(&dest)
+        .digest::<Sha1, _>(Sha1::digest(STR.as_bytes()).to_vec())
+        .await
+        .unwrap();
+    assert_eq!(size, STR.len() as u64);
+    assert_eq!(std::fs::read(&dest).unwrap(), STR.as_bytes());
+    std::fs::remove_file(&dest).unwrap();
+
+    // decoded payload exceeds limit, partial file is removed
+    let client = Client::build().max_response_size(100).finish();
+    let mut res = client.get(srv.url("/gzip")).send().await.unwrap();
+    let err = res.save_to(&dest).await.err().unwrap();
+    assert!(matches!(
+        err,
+        ntex::http::client::error::SaveError::Payload(PayloadError::TooLarge(100))
+    ));
+    assert!(!dest.exists());
+}
+
+#[ntex::test]
+async fn client_host_header() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").to(|req: HttpRequest| async move {
+            let host = req.headers().get(header::HOST).unwrap().clone();
+            HttpResponse::Ok().body(host.as_bytes().to_vec())
+        }))
+    });
+
+    // request is sent to server address with virtual host
+    let mut res = Client::new()
+        .get("http://canary.local/")
+        .address(srv.addr())
+        .host_header("www.example.com")
+        .send()
+        .await
+        .unwrap();
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"www.example.com"));
+
+    // explicit header is not overridden
+    let mut res = srv
+        .get("/")
+        .header(header::HOST, "explicit.local")
+        .host_header("www.example.com")
+        .send()
+        .await
+        .unwrap();
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"explicit.local"));
+}
+
+#[ntex::test]
+async fn client_send_sized_stream() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| async move {
+                assert!(!req.headers().contains_key(header::TRANSFER_ENCODING));
+                let length = req.headers().get(header::CONTENT_LENGTH).unwrap().clone();
+                HttpResponse::Ok().header("x-length", length).body(body)
+            },
+        )))
+    });
+
+    let chunks = || {
+        futures::stream::iter(vec![
+            Ok::<_, io::Error>(Bytes::from_static(b"hello ")),
+            Ok(Bytes::new()),
+            Ok(Bytes::from_static(b"world")),
+        ])
+    };
+
+    let mut res = srv.post("/").send_sized_stream(chunks(), 11).await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get("x-length").unwrap(), "11");
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"hello world"));
+
+    // stream is longer or shorter than declared size
+    for size in &[5, 20] {
+        let res = srv.post("/").send_sized_stream(chunks(), *size).await;
+        match res {
+            Err(SendRequestError::Error(e)) => {
+                let e = e.downcast_ref::<io::Error>().unwrap();
+                assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            }
+            res => panic!("unexpected result: {:?}", res.map(|_| ())),
+        }
+    }
+}
+
+#[ntex::test]
+async fn client_default_headers() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").to(|req: HttpRequest| async move {
+            let agents: Vec<_> = req
+                .headers()
+                .get_all(header::USER_AGENT)
+                .map(|v| v.to_str().unwrap().to_string())
+                .collect();
+            HttpResponse::Ok()
+                .header(
+                    "x-api-version",
+                    req.headers().get("x-api-version").unwrap().clone(),
+                )
+                .body(agents.join(","))
+        }))
+    });
+
+    let client = Client::build()
+        .header(header::USER_AGENT, "test-agent/1.0")
+        .header("x-api-version", "2")
+        .header(header::CONTENT_LENGTH, "100")
+        .finish();
+
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get("x-api-version").unwrap(), "2");
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"test-agent/1.0"));
+
+    // per-request header wins
+    let mut res = client
+        .get(srv.url("/"))
+        .header(header::USER_AGENT, "custom")
+        .send()
+        .await
+        .unwrap();
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"custom"));
+}
+
+#[ntex::test]
+async fn client_normalized_url() {
+    let srv = test::server(|| {
+        App::new().default_service(web::to(|req: HttpRequest| async move {
+            HttpResponse::Ok().body(req.path().to_string())
+        }))
+    });
+
+    let mut res = srv.get("/a/b/../c/.").send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.url().to_string(), srv.url("/a/c/"));
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"/a/c/"));
+}
+
+#[ntex::test]
+async fn client_user_agent_rotation() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").to(|req: HttpRequest| async move {
+            let agent = req.headers().get(header::USER_AGENT).unwrap().clone();
+            HttpResponse::Ok().body(agent.as_bytes().to_vec())
+        }))
+    });
+
+    let pool = vec!["agent-a", "agent-b", "agent-c"];
+    let client = Client::build()
+        .header(header::USER_AGENT, "default")
+        .user_agents(UserAgents::new(pool.clone()))
+        .finish();
+
+    let mut seen = Vec::new();
+    for _ in 0..6 {
+        let mut res = client.get(srv.url("/")).send().await.unwrap();
+        let bytes = res.body().await.unwrap();
+        seen.push(String::from_utf8(bytes.to_vec()).unwrap());
+    }
+    assert_eq!(&seen[..3], &pool[..]);
+    assert_eq!(&seen[3..], &pool[..]);
+
+    // caller set header overrides rotation
+    let mut res = client
+        .get(srv.url("/"))
+        .header(header::USER_AGENT, "custom")
+        .send()
+        .await
+        .unwrap();
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"custom"));
+
+    // frozen request gets new value for each send
+    let client = Client::build()
+        .user_agents(UserAgents::new(pool.clone()).random())
+        .finish();
+    let req = client.get(srv.url("/")).freeze().unwrap();
+    let mut seen = std::collections::HashSet::new();
+    for _ in 0..100 {
+        let mut res = req.send().await.unwrap();
+        let bytes = res.body().await.unwrap();
+        seen.insert(String::from_utf8(bytes.to_vec()).unwrap());
+        if seen.len() == pool.len() {
+            break;
+        }
+    }
+    assert_eq!(seen.len(), pool.len());
+}
+
+#[ntex::test]
+async fn client_dns_timeout() {
+    let srv = test::server(|| {
+        App::new().service(
+            web::resource("/").route(web::to(|| async { HttpResponse::Ok().body(STR) })),
+        )
+    });
+
+    let addr = srv.addr();
+    let client = Client::build()
+        .resolver(move |host: &str, _: u16| {
+            let delay = if host == "slow.test" { 500 } else { 0 };
+            async move {
+                ntex::rt::time::delay_for(Duration::from_millis(delay)).await;
+                Ok(vec![addr])
+            }
+        })
+        .dns_timeout(Duration::from_millis(100))
+        .connect_timeout(Duration::from_secs(5))
+        .finish();
+
+    let start = Instant::now();
+    let url = format!("http://slow.test:{}/", addr.port());
+    let err = client.get(&url).send().await.err().unwrap();
+    match err {
+        SendRequestError::DnsTimeout { ref host } => assert_eq!(host, "slow.test"),
+        e => panic!("unexpected error: {:?}", e),
+    }
+    assert!(start.elapsed() < Duration::from_millis(500));
+
+    // resolution within timeout
+    let url = format!("http://fast.test:{}/", addr.port());
+    let res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+
+    // timeout does not apply if address is set
+    let url = format!("http://slow.test:{}/", addr.port());
+    let res = client.get(&url).address(addr).send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[ntex::test]
+async fn client_raw_headers() {
+    let addr = ntex::server::TestServer::unused_addr();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let mut b = [0; 1000];
+            let n = stream.read(&mut b).unwrap();
+            if b[..n].starts_with(b"GET /redirect ") {
+                let _ = stream.write_all(
+                    b"HTTP/1.1 302 Found\r\nlocation: /\r\n\
+                      content-length: 0\r\nconnection: close\r\n\r\n",
+                );
+                continue;
+            }
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nX-Signature: abc\r\ncontent-length: 2\r\n\
+                  X-Multi: 1\r\nx-multi: 2\r\n\r\nok",
+            );
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let url = format!("http://{}/", addr);
+    let client = Client::build().preserve_raw_headers().finish();
+    let mut res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+    let raw: Vec<_> = res
+        .raw_headers()
+        .unwrap()
+        .iter()
+        .map(|(name, value)| (&name[..], &value[..]))
+        .collect();
+    assert_eq!(
+        raw,
+        vec![
+            (&b"X-Signature"[..], &b"abc"[..]),
+            (b"content-length", b"2"),
+            (b"X-Multi", b"1"),
+            (b"x-multi", b"2"),
+        ]
+    );
+    assert_eq!(res.headers().get_all("x-multi").count(), 2);
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"ok"));
+
+    // raw headers are captured on redirect hops
+    let client = Client::build()
+        .preserve_raw_headers()
+        .max_redirects(3)
+        .finish();
+    let res = client.get(format!("{}redirect", url)).send().await.unwrap();
+    assert!(res.status().is_success());
+    let raw = res.raw_headers().unwrap();
+    assert_eq!(&raw.iter().next().unwrap().0[..], b"X-Signature");
+
+    // raw headers are not captured by default
+    let res = Client::new().get(&url).send().await.unwrap();
+    assert!(res.raw_headers().is_none());
+    assert_eq!(res.headers().get("x-signature").unwrap(), "abc");
+}
+
+#[ntex::test]
+async fn client_write_timeout() {
+    let addr = ntex::server::TestServer::unused_addr();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let mut b = [0; 1000];
+            let n = stream.read(&mut b).unwrap();
+            if b[..n].starts_with(b"POST /redirect ") {
+                // read whole body and redirect to slow endpoint
+                let mut total = n;
+                while total < 64 * 1024 * 1024 {
+                    let mut buf = [0; 65536];
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => total += n,
+                    }
+                }
+                let _ = stream.write_all(
+                    b"HTTP/1.1 307 Temporary Redirect\r\nlocation: /\r\n\
+                      content-length: 0\r\nconnection: close\r\n\r\n",
+                );
+                continue;
+            }
+            // read request head and stop reading body
+            std::thread::sleep(Duration::from_secs(5));
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let url = format!("http://{}/", addr);
+    let client = Client::build()
+        .write_timeout(Duration::from_millis(300))
+        .timeout(Duration::from_secs(30))
+        .finish();
+    let start = Instant::now();
+    let res = client
+        .post(&url)
+        .send_body(Bytes::from(vec![b'x'; 64 * 1024 * 1024]))
+        .await;
+    match res {
+        Err(SendRequestError::Timeout { phase }) => {
+            assert_eq!(phase, TimeoutPhase::Write)
+        }
+        _ => panic!("write timeout is expected"),
+    }
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // write timeout applies to redirect hops
+    let client = Client::build()
+        .write_timeout(Duration::from_millis(300))
+        .timeout(Duration::from_secs(30))
+        .max_redirects(3)
+        .finish();
+    let start = Instant::now();
+    let res = client
+        .post(format!("{}redirect", url))
+        .send_body(Bytes::from(vec![b'x'; 64 * 1024 * 1024]))
+        .await;
+    match res {
+        Err(SendRequestError::Timeout { phase }) => {
+            assert_eq!(phase, TimeoutPhase::Write)
+        }
+        _ => panic!("write timeout is expected"),
+    }
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[ntex::test]
+async fn client_send_bytes() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| async move {
+                assert!(!req.headers().contains_key(header::TRANSFER_ENCODING));
+                let length = req.headers().get(header::CONTENT_LENGTH).unwrap().clone();
+                let ctype = req.headers().get(header::CONTENT_TYPE).unwrap().clone();
+                HttpResponse::Ok()
+                    .header("x-length", length)
+                    .header("x-type", ctype)
+                    .body(body)
+            },
+        )))
+    });
+
+    let mut res = srv
+        .post("/")
+        .send_bytes("application/octet-stream", Bytes::from_static(b"raw bytes"))
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get("x-length").unwrap(), "9");
+    assert_eq!(
+        res.headers().get("x-type").unwrap(),
+        "application/octet-stream"
+    );
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"raw bytes"));
+
+    // explicit content type is kept
+    let res = srv
+        .post("/")
+        .content_type("image/png")
+        .freeze()
+        .unwrap()
+        .send_bytes("application/octet-stream", Bytes::from_static(b"png"))
+        .await
+        .unwrap();
+    assert_eq!(res.headers().get("x-length").unwrap(), "3");
+    assert_eq!(res.headers().get("x-type").unwrap(), "image/png");
+}
+
+#[ntex::test]
+async fn client_connection_close() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let conns = connections.clone();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            conns.fetch_add(1, Ordering::Relaxed);
+            // server keeps socket open, client must not reuse it
+            std::thread::spawn(move || {
+                let mut stream = stream.unwrap();
+                let mut b = [0; 1000];
+                while let Ok(n) = stream.read(&mut b) {
+                    if n == 0 {
+                        break;
+                    }
+                    let res: &[u8] = if b[..n].starts_with(b"GET /http10") {
+                        b"HTTP/1.0 200 OK\r\ncontent-length: 2\r\n\r\nok"
+                    } else if b[..n].starts_with(b"GET /close") {
+                        b"HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 2\r\n\r\nok"
+                    } else {
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok"
+                    };
+                    let _ = stream.write_all(res);
+                }
+            });
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::new();
+    let request = |path: &'static str| {
+        let client = client.clone();
+        async move {
+            let url = format!("http://{}{}", addr, path);
+            let mut res = client.get(&url).send().await.unwrap();
+            assert!(res.status().is_success());
+            assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+        }
+    };
+
+    // keep-alive connection is reused
+    request("/").await;
+    request("/").await;
+    assert_eq!(connections.load(Ordering::Relaxed), 1);
+
+    // `connection: close` response, next request opens new connection
+    request("/close").await;
+    assert_eq!(connections.load(Ordering::Relaxed), 1);
+    request("/").await;
+    assert_eq!(connections.load(Ordering::Relaxed), 2);
+
+    // http/1.0 response closes connection by default
+    request("/http10").await;
+    request("/").await;
+    assert_eq!(connections.load(Ordering::Relaxed), 3);
+}
+
+#[ntex::test]
+async fn client_byte_counters() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let received = Arc::new(AtomicUsize::new(0));
+    let recv = received.clone();
+    const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n\
+                              5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            // read chunked request till the last chunk
+            let mut stream = stream.unwrap();
+            let mut data = Vec::new();
+            let mut b = [0; 1000];
+            while !data.ends_with(b"0\r\n\r\n") {
+                let n = stream.read(&mut b).unwrap();
+                data.extend_from_slice(&b[..n]);
+            }
+            recv.store(data.len(), Ordering::Relaxed);
+            let _ = stream.write_all(RESPONSE);
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let url = format!("http://{}/", addr);
+    let body = futures::stream::iter(vec![
+        Ok::<_, io::Error>(Bytes::from_static(b"hello ")),
+        Ok(Bytes::from_static(b"world")),
+    ]);
+    let mut res = Client::new().post(&url).send_stream(body).await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(
+        res.request_bytes_sent(),
+        received.load(Ordering::Relaxed) as u64
+    );
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"hello world"));
+    assert_eq!(res.response_bytes_received(), RESPONSE.len() as u64);
+}
+
+#[ntex::test]
+async fn client_expect_continue_timeout() {
+    let addr = ntex::server::TestServer::unused_addr();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            // server ignores expectation and reads whole request
+            let mut stream = stream.unwrap();
+            let mut data = Vec::new();
+            let mut b = [0; 1000];
+            while !data.ends_with(b"request body") {
+                let n = stream.read(&mut b).unwrap();
+                data.extend_from_slice(&b[..n]);
+            }
+            if data.starts_with(b"POST /redirect ") {
+                let _ = stream.write_all(
+                    b"HTTP/1.1 307 Temporary Redirect\r\nlocation: /\r\n\
+                      content-length: 0\r\nconnection: close\r\n\r\n",
+                );
+                continue;
+            }
+            let _ = stream.write_all(
+                b"HTTP/1.1 100 Continue\r\n\r\n\
+                  HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok",
+            );
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::build()
+        .expect_continue_timeout(Duration::from_millis(100))
+        .finish();
+    let start = Instant::now();
+    let mut res = client
+        .post(format!("http://{}/", addr))
+        .expect_continue()
+        .send_body("request body")
+        .await
+        .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"ok"));
+
+    // timeout applies to redirect hops
+    let client = Client::build()
+        .expect_continue_timeout(Duration::from_millis(100))
+        .max_redirects(3)
+        .finish();
+    let start = Instant::now();
+    let res = client
+        .post(format!("http://{}/redirect", addr))
+        .expect_continue()
+        .send_body("request body")
+        .await
+        .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(res.status().is_success());
+}
+
+#[cfg(feature = "body-serializer")]
+#[ntex::test]
+async fn client_send_typed() {
+    use ntex::http::client::ErasedSerialize;
+
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| async move {
+                let ctype = req.headers().get(header::CONTENT_TYPE).unwrap().clone();
+                HttpResponse::Ok().header("x-type", ctype).body(body)
+            },
+        )))
+    });
+
+    let client = Client::build()
+        .body_serializer("text/plain", |value: &dyn ErasedSerialize| {
+            let value = serde_json::to_value(value)?;
+            Ok(value["name"].as_str().unwrap_or("").as_bytes().to_vec())
+        })
+        .finish();
+    let value = serde_json::json!({"name": "ntex"});
+
+    for (ctype, body) in &[
+        ("application/json", &b"{\"name\":\"ntex\"}"[..]),
+        ("application/x-www-form-urlencoded", b"name=ntex"),
+        ("text/plain; charset=utf-8", b"ntex"),
+    ] {
+        let mut res = client
+            .post(srv.url("/"))
+            .send_typed(&value, ctype)
+            .await
+            .unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.headers().get("x-type").unwrap(), ctype);
+        let bytes = res.body().await.unwrap();
+        assert_eq!(bytes, Bytes::copy_from_slice(body));
+    }
+
+    let res = client
+        .post(srv.url("/"))
+        .send_typed(&value, "application/cbor")
+        .await;
+    match res {
+        Err(SendRequestError::NoSerializer(ctype)) => {
+            assert_eq!(ctype, "application/cbor")
+        }
+        res => panic!("unexpected result: {:?}", res.map(|_| ())),
+    }
+}
+
+#[ntex::test]
+async fn client_close() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let closed = Arc::new(AtomicUsize::new(0));
+    let closed2 = closed.clone();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let closed = closed2.clone();
+            std::thread::spawn(move || {
+                let mut stream = stream.unwrap();
+                let mut b = [0; 1000];
+                loop {
+                    match stream.read(&mut b) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => {
+                            if b[..n].starts_with(b"GET /slow") {
+                                std::thread::sleep(Duration::from_millis(300));
+                            }
+                            let _ = stream.write_all(
+                                b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok",
+                            );
+                        }
+                    }
+                }
+                closed.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::new();
+    let request = |path: &'static str| {
+        let client = client.clone();
+        async move {
+            let url = format!("http://{}{}", addr, path);
+            let mut res = client.get(&url).send().await?;
+            assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+            Ok::<_, SendRequestError>(())
+        }
+    };
+
+    // close client while slow request is in flight and
+    // connection of the fast one is idle
+    let fast = async {
+        ntex::rt::time::delay_for(Duration::from_millis(50)).await;
+        request("/").await.unwrap();
+        client.close();
+    };
+    let (slow, _) = futures::future::join(request("/slow"), fast).await;
+    assert!(slow.is_ok());
+    assert!(client.is_closed());
+
+    // both connections are closed
+    ntex::rt::time::delay_for(Duration::from_millis(200)).await;
+    assert_eq!(closed.load(Ordering::Relaxed), 2);
+
+    // new requests fail
+    assert!(matches!(request("/").await, Err(SendRequestError::Closed)));
+    let res = client.preconnect(format!("http://{}/", addr)).await;
+    assert!(matches!(res, Err(SendRequestError::Closed)));
+}
+
+#[ntex::test]
+async fn client_empty_body_length() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let mut b = [0; 1000];
+            let n = stream.read(&mut b).unwrap();
+            let _ = tx.send(String::from_utf8_lossy(&b[..n]).to_lowercase());
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+            );
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::new();
+    let url = format!("http://{}/", addr);
+
+    // empty body of post request
+    let res = client.post(&url).send().await.unwrap();
+    assert!(rx.recv().unwrap().contains("\r\ncontent-length: 0\r\n"));
+    let headers = res.request_headers().unwrap();
+    assert_eq!(headers.get(header::CONTENT_LENGTH).unwrap(), "0");
+    client.put(&url).send_body(Body::Empty).await.unwrap();
+    assert!(rx.recv().unwrap().contains("\r\ncontent-length: 0\r\n"));
+
+    // methods without request payload semantics
+    let res = client.get(&url).send().await.unwrap();
+    assert!(!rx.recv().unwrap().contains("content-length"));
+    let headers = res.request_headers().unwrap();
+    assert!(!headers.contains_key(header::CONTENT_LENGTH));
+    client.delete(&url).send().await.unwrap();
+    assert!(!rx.recv().unwrap().contains("content-length"));
+
+    // bodyless post request
+    client.post(&url).send_body(Body::None).await.unwrap();
+    assert!(!rx.recv().unwrap().contains("content-length"));
+}
+
+#[ntex::test]
+async fn client_on_complete() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/").route(web::to(|| async { HttpResponse::Ok() })))
+            .service(web::resource("/slow").route(web::to(|| async {
+                ntex::rt::time::delay_for(Duration::from_millis(500)).await;
+                HttpResponse::Ok()
+            })))
+    });
+
+    let metrics = Rc::new(RefCell::new(Vec::new()));
+    let metrics2 = metrics.clone();
+    let client = Client::build()
+        .on_complete(move |m: &RequestMetrics<'_>| {
+            metrics2.borrow_mut().push((
+                m.method.clone(),
+                m.host.to_string(),
+                m.status,
+                m.error.map(|e| e.to_string()),
+                m.bytes_sent,
+                m.bytes_received,
+            ))
+        })
+        .finish();
+
+    // success
+    let res = client.post(srv.url("/")).send_body("data").await.unwrap();
+    assert!(res.status().is_success());
+    {
+        let metrics = metrics.borrow();
+        assert_eq!(metrics.len(), 1);
+        let (method, host, status, error, sent, received) = &metrics[0];
+        assert_eq!(method, &http::Method::POST);
+        assert_eq!(host, "localhost");
+        assert_eq!(*status, Some(StatusCode::OK));
+        assert!(error.is_none());
+        assert!(*sent > 4);
+        assert!(*received > 0);
+    }
+
+    // timeout
+    let res = client
+        .get(srv.url("/slow"))
+        .timeout(Duration::from_millis(100))
+        .send()
+        .await;
+    assert!(res.is_err());
+    assert_eq!(metrics.borrow().len(), 2);
+    assert_eq!(metrics.borrow()[1].2, None);
+    assert!(metrics.borrow()[1].3.is_some());
+
+    // connect error
+    let addr = ntex::server::TestServer::unused_addr();
+    let res = client.get(format!("http://{}/", addr)).send().await;
+    assert!(res.is_err());
+    assert_eq!(metrics.borrow().len(), 3);
+    assert!(metrics.borrow()[2].3.is_some());
+
+    // request is rejected before sending
+    client.close();
+    let res = client.get(srv.url("/")).send().await;
+    assert!(matches!(res, Err(SendRequestError::Closed)));
+    assert_eq!(metrics.borrow().len(), 4);
+    assert_eq!(metrics.borrow()[3].4, 0);
+}
+
+#[ntex::test]
+async fn client_send_to() {
+    let backend = |name: &'static str| {
+        test::server(move || {
+            App::new().service(web::resource("/health").to(move |req: HttpRequest| {
+                let id = req.headers().get("x-check").unwrap().clone();
+                async move {
+                    HttpResponse::Ok().body(format!("{}:{}", name, id.to_str().unwrap()))
+                }
+            }))
+        })
+    };
+    let srv1 = backend("first");
+    let srv2 = backend("second");
+
+    let request = Client::new()
+        .get("http://backend.local/health")
+        .header("x-check", "0")
+        .freeze()
+        .unwrap();
+
+    for (idx, (srv, name)) in [(&srv1, "first"), (&srv2, "second")].iter().enumerate() {
+        for _ in 0..2 {
+            let mut headers = ntex::http::HeaderMap::new();
+            headers.insert(
+                HeaderName::from_static("x-check"),
+                HeaderValue::from_str(&idx.to_string()).unwrap(),
+            );
+            let mut res = request.send_to(srv.addr(), headers).await.unwrap();
+            assert!(res.status().is_success());
+            let body = res.body().await.unwrap();
+            assert_eq!(body, Bytes::from(format!("{}:{}", name, idx)));
+        }
+    }
+
+    // shared head is not modified
+    assert_eq!(request.headers().get("x-check").unwrap(), "0");
+}
+
+#[ntex::test]
+async fn client_circuit_breaker() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let client = Client::build()
+        .circuit_breaker(CircuitBreaker::new(2).cooldown(Duration::from_millis(300)))
+        .finish();
+    let url = format!("http://{}/", addr);
+
+    // connection is refused
+    for _ in 0..2 {
+        let res = client.get(&url).send().await;
+        assert!(matches!(res, Err(SendRequestError::Connect(_))));
+    }
+    let res = client.get(&url).send().await;
+    assert!(matches!(res, Err(SendRequestError::CircuitOpen)));
+
+    // breaker is shared by client clones
+    let res = client.clone().get(&url).send().await;
+    assert!(matches!(res, Err(SendRequestError::CircuitOpen)));
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let mut b = [0; 1000];
+            let _ = stream.read(&mut b).unwrap();
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n");
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(400)).await;
+
+    // trial request closes breaker
+    let res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+    let res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[ntex::test]
+async fn client_http10_request() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let mut b = [0; 1000];
+            let n = stream.read(&mut b).unwrap();
+            tx.send(String::from_utf8_lossy(&b[..n]).to_string())
+                .unwrap();
+            let _ = stream.write_all(b"HTTP/1.0 200 OK\r\ncontent-length: 2\r\n\r\nok");
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::new();
+    let url = format!("http://{}/", addr);
+    let mut res = client
+        .post(&url)
+        .version(Version::HTTP_10)
+        .send_body("data")
+        .await
+        .unwrap();
+    assert_eq!(res.version(), Version::HTTP_10);
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+
+    let req = rx.recv().unwrap();
+    assert!(req.starts_with("POST / HTTP/1.0\r\n"));
+    assert!(req.contains("content-length: 4\r\n"));
+    assert!(!req.contains("transfer-encoding"));
+
+    // streaming body of unknown size is refused
+    let res = client
+        .post(&url)
+        .version(Version::HTTP_10)
+        .send_stream(once(ok::<_, JsonPayloadError>(Bytes::from_static(b"data"))))
+        .await;
+    assert!(matches!(res, Err(SendRequestError::ChunkedNotSupported)));
+    assert!(rx.try_recv().is_err());
+}
+
+#[ntex::test]
+async fn client_upload_progress() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/").route(web::to(|body: Bytes| async move {
+                HttpResponse::Ok().body(format!("{}", body.len()))
+            })))
+            .service(web::resource("/redirect").route(web::to(|_: Bytes| async {
+                HttpResponse::TemporaryRedirect()
+                    .header(header::LOCATION, "/")
+                    .finish()
+            })))
+    });
+
+    let progress = Rc::new(RefCell::new(Vec::new()));
+    let progress2 = progress.clone();
+    let data = vec![b'x'; 200 * 1024];
+    let mut res = srv
+        .post("/")
+        .upload_progress(move |sent, total| progress2.borrow_mut().push((sent, total)))
+        .send_body(data)
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"204800"));
+    {
+        let progress = progress.borrow();
+        assert_eq!(progress.last(), Some(&(200 * 1024, Some(200 * 1024))));
+        assert!(progress.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    // body of unknown size
+    progress.borrow_mut().clear();
+    let progress2 = progress.clone();
+    let chunks = futures::stream::iter(vec![
+        Ok::<_, io::Error>(Bytes::from_static(b"hello ")),
+        Ok(Bytes::from_static(b"world")),
+    ]);
+    let mut res = srv
+        .post("/")
+        .upload_progress(move |sent, total| progress2.borrow_mut().push((sent, total)))
+        .send_stream(chunks)
+        .await
+        .unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"11"));
+    assert_eq!(progress.borrow().last(), Some(&(11, None)));
+
+    // no body
+    progress.borrow_mut().clear();
+    let progress2 = progress.clone();
+    let res = srv
+        .get("/")
+        .upload_progress(move |sent, total| progress2.borrow_mut().push((sent, total)))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert!(progress.borrow().is_empty());
+
+    // redirected request reports progress of each hop from zero
+    progress.borrow_mut().clear();
+    let progress2 = progress.clone();
+    let client = Client::build().max_redirects(3).finish();
+    let mut res = client
+        .post(srv.url("/redirect"))
+        .upload_progress(move |sent, total| progress2.borrow_mut().push((sent, total)))
+        .send_body(vec![b'x'; 200 * 1024])
+        .await
+        .unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"204800"));
+    let progress = progress.borrow();
+    assert_eq!(progress.last(), Some(&(200 * 1024, Some(200 * 1024))));
+    assert_eq!(progress.iter().filter(|p| p.0 == 200 * 1024).count(), 2);
+}
+
+#[ntex::test]
+async fn client_try_clone() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|body: Bytes| async move {
+            HttpResponse::Ok().body(body)
+        })))
+    });
+
+    let req = srv.post("/").replayable().send_body("hello");
+    let req2 = req.try_clone().unwrap();
+    let (res, res2) = futures::join!(req, req2);
+    assert_eq!(
+        res.unwrap().body().await.unwrap(),
+        Bytes::from_static(b"hello")
+    );
+    assert_eq!(
+        res2.unwrap().body().await.unwrap(),
+        Bytes::from_static(b"hello")
+    );
+
+    // clone of frozen request
+    let req = srv
+        .post("/")
+        .replayable()
+        .freeze()
+        .unwrap()
+        .send_body("frozen");
+    let mut res = req.try_clone().unwrap().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"frozen"));
+    let mut res = req.await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"frozen"));
+
+    // streaming body could not be replayed
+    let chunks =
+        futures::stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(b"stream"))]);
+    assert!(srv
+        .post("/")
+        .replayable()
+        .send_stream(chunks)
+        .try_clone()
+        .is_none());
+
+    // request is not marked as replayable
+    assert!(srv.post("/").send_body("hello").try_clone().is_none());
+
+    // already polled
+    let mut req = srv.post("/").replayable().send_body("hello");
+    assert!(futures::poll!(&mut req).is_pending());
+    assert!(req.try_clone().is_none());
+    let mut res = req.await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"hello"));
+}
+
+#[ntex::test]
+async fn client_head_request() {
+    let addr = ntex::server::TestServer::unused_addr();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let conns = connections.clone();
+
+    std::thread::spawn(move || {
+        let lst = std::net::TcpListener::bind(addr).unwrap();
+
+        for stream in lst.incoming() {
+            conns.fetch_add(1, Ordering::Relaxed);
+            std::thread::spawn(move || {
+                let mut stream = stream.unwrap();
+                let mut b = [0; 1000];
+                while let Ok(n) = stream.read(&mut b) {
+                    if n == 0 {
+                        break;
+                    }
+                    // response to `HEAD` declares length of the body it omits
+                    let res: &[u8] = if b[..n].starts_with(b"HEAD ") {
+                        b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 1000000\r\n\r\n"
+                    } else {
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok"
+                    };
+                    let _ = stream.write_all(res);
+                }
+            });
+        }
+    });
+    ntex::rt::time::delay_for(Duration::from_millis(300)).await;
+
+    let client = Client::build().max_response_size(100).finish();
+    let url = format!("http://{}/", addr);
+
+    let fut = async {
+        let mut res = client.head(&url).send().await.unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.headers().get("content-length").unwrap(), "1000000");
+        assert_eq!(res.body().await.unwrap(), Bytes::new());
+
+        let mut res = client.head(&url).send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "");
+
+        // connection is reused
+        let mut res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+    };
+    ntex::rt::time::timeout(Duration::from_secs(5), fut)
+        .await
+        .unwrap();
+    assert_eq!(connections.load(Ordering::Relaxed), 1);
+}
@@ -1,16 +1,27 @@
 #![cfg(feature = "openssl")]
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use futures::future::ok;
-use open_ssl::ssl::{SslAcceptor, SslConnector, SslFiletype, SslMethod, SslVerifyMode};
+use bytes::Bytes;
+use futures::future::{join_all, ok};
+use futures::{SinkExt, StreamExt};
+use open_ssl::ssl::{
+    NameType, SslAcceptor, SslConnector, SslFiletype, SslMethod, SslVerifyMode,
+};
 
-use ntex::http::client::{Client, Connector};
+use ntex::codec::Framed;
+use ntex::http::client::error::{ConnectError, SendRequestError};
+use ntex::http::client::{
+    Client, ClientIdentity, Connector, ProtocolMode, RootCertificates,
+};
 use ntex::http::test::server as test_server;
-use ntex::http::{HttpService, Version};
+use ntex::http::ws::handshake_response;
+use ntex::http::{body::BodySize, h1, header, HttpService, Request, Response, Version};
 use ntex::service::{map_config, pipeline_factory, ServiceFactory};
-use ntex::web::{self, dev::AppConfig, App, HttpResponse};
+use ntex::util::framed::Dispatcher;
+use ntex::web::{self, dev::AppConfig, App, HttpRequest, HttpResponse};
+use ntex::ws;
 
 fn ssl_acceptor() -> SslAcceptor {
     // load ssl keys
@@ -83,6 +94,347 @@ async fn test_connection_reuse_h2() {
     assert!(response.status().is_success());
     assert_eq!(response.version(), Version::HTTP_2);
 
+    // req 3, upload progress of http/2 stream
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let progress2 = progress.clone();
+    let response = client
+        .post(srv.surl("/"))
+        .upload_progress(move |sent, total| {
+            progress2.lock().unwrap().push((sent, total))
+        })
+        .send_body("data")
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(progress.lock().unwrap().last(), Some(&(4, Some(4))));
+
     // one connection
     assert_eq!(num.load(Ordering::Relaxed), 1);
 }
+
+#[ntex::test]
+async fn test_h2_multiplexing() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let srv = test_server(move || {
+        let num2 = num2.clone();
+        pipeline_factory(move |io| {
+            num2.fetch_add(1, Ordering::Relaxed);
+            ok(io)
+        })
+        .and_then(
+            HttpService::build()
+                .h2(map_config(
+                    App::new().service(web::resource("/").route(web::to(|| async {
+                        ntex::rt::time::delay_for(Duration::from_millis(50)).await;
+                        HttpResponse::Ok()
+                    }))),
+                    |_| AppConfig::default(),
+                ))
+                .openssl(ssl_acceptor())
+                .map_err(|_| ()),
+        )
+    });
+
+    // disable ssl verification
+    let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+    builder.set_verify(SslVerifyMode::NONE);
+    let _ = builder
+        .set_alpn_protos(b"\x02h2")
+        .map_err(|e| log::error!("Can not set alpn protocol: {:?}", e));
+
+    let client = Client::build()
+        .connector(
+            Connector::default()
+                .protocol(ProtocolMode::Http2Only)
+                .openssl(builder.build())
+                .finish(),
+        )
+        .finish();
+
+    // concurrent requests share one connection
+    let responses = join_all((0..10).map(|_| client.get(srv.surl("/")).send())).await;
+    let mut peer_addrs = Vec::new();
+    for response in responses {
+        let response = response.unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(response.version(), Version::HTTP_2);
+        peer_addrs.push(response.peer_addr().unwrap());
+    }
+    assert!(peer_addrs.iter().all(|addr| *addr == peer_addrs[0]));
+    assert_eq!(num.load(Ordering::Relaxed), 1);
+
+    // connection is reused by following requests
+    let response = client.get(srv.surl("/")).send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(num.load(Ordering::Relaxed), 1);
+}
+
+#[ntex::test]
+async fn test_root_certificates() {
+    let srv = test_server(move || {
+        let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        builder
+            .set_private_key_file("./tests/localhost-key.pem", SslFiletype::PEM)
+            .unwrap();
+        builder
+            .set_certificate_chain_file("./tests/localhost-cert.pem")
+            .unwrap();
+
+        HttpService::build()
+            .finish(map_config(
+                App::new().service(
+                    web::resource("/").route(web::to(|| async { HttpResponse::Ok() })),
+                ),
+                |_| AppConfig::default(),
+            ))
+            .openssl(builder.build())
+            .map_err(|_| ())
+    });
+    let url = format!("https://localhost:{}/", srv.addr().port());
+    let ca = std::fs::read("./tests/ca.pem").unwrap();
+
+    // server certificate is signed by unknown ca
+    let res = Client::new().get(&url).send().await;
+    assert!(res.is_err());
+
+    let client = Client::build()
+        .root_certificates(RootCertificates::new().pem(ca.clone()))
+        .finish();
+    let res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+
+    let client = Client::build()
+        .root_certificates(RootCertificates::new().pem(ca).strict())
+        .finish();
+    let res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+
+    let client = Client::build()
+        .root_certificates(RootCertificates::new().strict())
+        .finish();
+    assert!(client.get(&url).send().await.is_err());
+}
+
+#[ntex::test]
+async fn test_tls_info() {
+    let srv = test_server(move || {
+        let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        builder
+            .set_private_key_file("./tests/localhost-key.pem", SslFiletype::PEM)
+            .unwrap();
+        builder
+            .set_certificate_chain_file("./tests/localhost-cert.pem")
+            .unwrap();
+
+        HttpService::build()
+            .finish(map_config(
+                App::new().service(
+                    web::resource("/").route(web::to(|| async { HttpResponse::Ok() })),
+                ),
+                |_| AppConfig::default(),
+            ))
+            .openssl(builder.build())
+            .map_err(|_| ())
+    });
+    let url = format!("https://localhost:{}/", srv.addr().port());
+    let ca = std::fs::read("./tests/ca.pem").unwrap();
+    let client = Client::build()
+        .root_certificates(RootCertificates::new().pem(ca))
+        .finish();
+
+    let mut res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+    let _ = res.body().await.unwrap();
+    let tls = res.tls_info().unwrap();
+    assert!(tls.version().starts_with("TLSv1"));
+    assert!(!tls.cipher().is_empty());
+    assert_eq!(tls.peer_subject(), Some("CN=localhost"));
+
+    // pooled connection keeps session details
+    let res = client.get(&url).send().await.unwrap();
+    let pooled = res.tls_info().unwrap();
+    assert_eq!(pooled.version(), tls.version());
+    assert_eq!(pooled.cipher(), tls.cipher());
+}
+
+#[ntex::test]
+async fn test_client_identity() {
+    let srv = test_server(move || {
+        let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        builder
+            .set_private_key_file("./tests/localhost-key.pem", SslFiletype::PEM)
+            .unwrap();
+        builder
+            .set_certificate_chain_file("./tests/localhost-cert.pem")
+            .unwrap();
+        builder.set_ca_file("./tests/ca.pem").unwrap();
+        builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+
+        HttpService::build()
+            .finish(map_config(
+                App::new().service(
+                    web::resource("/").route(web::to(|| async { HttpResponse::Ok() })),
+                ),
+                |_| AppConfig::default(),
+            ))
+            .openssl(builder.build())
+            .map_err(|_| ())
+    });
+    let url = format!("https://localhost:{}/", srv.addr().port());
+    let ca = RootCertificates::new().pem(std::fs::read("./tests/ca.pem").unwrap());
+    let identity = ClientIdentity::pem(
+        std::fs::read("./tests/localhost-cert.pem").unwrap(),
+        std::fs::read("./tests/localhost-key.pem").unwrap(),
+    );
+    let unknown = ClientIdentity::pem(
+        std::fs::read("./tests/cert.pem").unwrap(),
+        std::fs::read("./tests/key.pem").unwrap(),
+    );
+
+    // server requires client certificate
+    let client = Client::build().root_certificates(ca.clone()).finish();
+    assert!(client.get(&url).send().await.is_err());
+
+    // certificate is signed by unknown ca
+    let client = Client::build()
+        .root_certificates(ca.clone())
+        .client_identity(unknown.clone())
+        .finish();
+    let res = client.get(&url).send().await;
+    assert!(matches!(
+        res,
+        Err(SendRequestError::Connect(ConnectError::ClientCertificate(
+            _
+        )))
+    ));
+
+    let client = Client::build()
+        .root_certificates(ca.clone())
+        .client_identity(identity.clone())
+        .finish();
+    let res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+
+    // host specific identity
+    let client = Client::build()
+        .root_certificates(ca.clone())
+        .client_identity(unknown.clone())
+        .host_identity("LOCALHOST", identity.clone())
+        .finish();
+    let res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+
+    let client = Client::build()
+        .root_certificates(ca)
+        .client_identity(unknown)
+        .host_identity("example.com", identity)
+        .finish();
+    let res = client.get(&url).send().await;
+    assert!(matches!(
+        res,
+        Err(SendRequestError::Connect(ConnectError::ClientCertificate(
+            _
+        )))
+    ));
+}
+
+#[ntex::test]
+async fn test_host_header_sni() {
+    let sni = Arc::new(Mutex::new(None));
+    let sni2 = sni.clone();
+    let srv = test_server(move || {
+        let sni = sni2.clone();
+        let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        builder
+            .set_private_key_file("./tests/localhost-key.pem", SslFiletype::PEM)
+            .unwrap();
+        builder
+            .set_certificate_chain_file("./tests/localhost-cert.pem")
+            .unwrap();
+        builder.set_servername_callback(move |ssl, _| {
+            *sni.lock().unwrap() = ssl.servername(NameType::HOST_NAME).map(String::from);
+            Ok(())
+        });
+
+        HttpService::build()
+            .finish(map_config(
+                App::new().service(web::resource("/").to(
+                    |req: HttpRequest| async move {
+                        let host = req.headers().get(header::HOST).unwrap().clone();
+                        HttpResponse::Ok().body(host.as_bytes().to_vec())
+                    },
+                )),
+                |_| AppConfig::default(),
+            ))
+            .openssl(builder.build())
+            .map_err(|_| ())
+    });
+    let ca = std::fs::read("./tests/ca.pem").unwrap();
+    let client = Client::build()
+        .root_certificates(RootCertificates::new().pem(ca))
+        .finish();
+
+    // server name and certificate verification use url host
+    let mut res = client
+        .get(format!("https://localhost:{}/", srv.addr().port()))
+        .address(srv.addr())
+        .host_header("canary.example.com")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let body = res.body().await.unwrap();
+    assert_eq!(&body[..], b"canary.example.com");
+    assert_eq!(sni.lock().unwrap().as_deref(), Some("localhost"));
+}
+
+#[ntex::test]
+async fn test_wss() {
+    let srv = test_server(move || {
+        let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        builder
+            .set_private_key_file("./tests/localhost-key.pem", SslFiletype::PEM)
+            .unwrap();
+        builder
+            .set_certificate_chain_file("./tests/localhost-cert.pem")
+            .unwrap();
+
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| async move {
+                let res = handshake_response(req.head()).finish();
+                framed
+                    .send(h1::Message::Item((res.drop_body(), BodySize::None)))
+                    .await?;
+                let framed = framed.into_framed(ws::Codec::default());
+                Dispatcher::new(framed, |frame| async move {
+                    Ok::<_, std::io::Error>(match frame {
+                        ws::Frame::Text(text) => Some(ws::Message::Binary(text)),
+                        _ => None,
+                    })
+                })
+                .await
+            })
+            .finish(|_| ok::<_, std::io::Error>(Response::NotFound()))
+            .openssl(builder.build())
+            .map_err(|_| ())
+    });
+    let ca = std::fs::read("./tests/ca.pem").unwrap();
+    let client = Client::build()
+        .root_certificates(RootCertificates::new().pem(ca))
+        .finish();
+
+    let (res, mut framed) = client
+        .ws(format!("wss://localhost:{}/", srv.addr().port()))
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 101);
+    framed
+        .send(ws::Message::Text("text".to_string()))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Binary(Bytes::from_static(b"text")));
+}
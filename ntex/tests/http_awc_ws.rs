@@ -5,9 +5,10 @@ use futures::future::ok;
 use futures::{SinkExt, StreamExt};
 
 use ntex::codec::Framed;
+use ntex::http::client::{error::WsClientError, Client};
 use ntex::http::test::server as test_server;
 use ntex::http::ws::handshake_response;
-use ntex::http::{body::BodySize, h1, HttpService, Request, Response};
+use ntex::http::{body::BodySize, h1, header, HttpService, Request, Response};
 use ntex::util::framed::Dispatcher;
 use ntex::ws;
 
@@ -73,3 +74,89 @@ async fn test_simple() {
     let item = framed.next().await.unwrap().unwrap();
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 }
+
+#[ntex::test]
+async fn test_handshake_validation() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| async move {
+                let mut res = handshake_response(req.head());
+                let host = req.headers().get(header::HOST).unwrap().clone();
+                res.header("x-host", host);
+                match req.path() {
+                    "/proto" => {
+                        let protos = req.headers().get(header::SEC_WEBSOCKET_PROTOCOL);
+                        let proto =
+                            protos.unwrap().to_str().unwrap().split(',').nth(1).unwrap();
+                        res.header(header::SEC_WEBSOCKET_PROTOCOL, proto.trim());
+                    }
+                    "/bad-proto" => {
+                        res.header(header::SEC_WEBSOCKET_PROTOCOL, "unknown");
+                    }
+                    "/bad-key" => {
+                        res.set_header(header::SEC_WEBSOCKET_ACCEPT, "bad");
+                    }
+                    _ => (),
+                }
+                framed
+                    .send(h1::Message::Item((
+                        res.finish().drop_body(),
+                        BodySize::None,
+                    )))
+                    .await?;
+                let framed = framed.into_framed(ws::Codec::default());
+                Dispatcher::new(framed, ws_service).await
+            })
+            .finish(|_| ok::<_, io::Error>(Response::NotFound()))
+            .tcp()
+    });
+
+    // server selects one of requested protocols
+    let client = Client::new();
+    let (res, mut framed) = client
+        .ws(srv.url("/proto"))
+        .protocols(&["feed.v1", "feed.v2"])
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(
+        res.headers().get(header::SEC_WEBSOCKET_PROTOCOL).unwrap(),
+        "feed.v2"
+    );
+    assert_eq!(
+        res.headers().get("x-host").unwrap().to_str().unwrap(),
+        format!("localhost:{}", srv.addr().port())
+    );
+    framed
+        .send(ws::Message::Text("text".to_string()))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"text")));
+
+    // server selects protocol that is not requested
+    let err = client
+        .ws(srv.url("/bad-proto"))
+        .protocols(&["feed.v1"])
+        .connect()
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, WsClientError::InvalidProtocol(_)));
+    let err = client
+        .ws(srv.url("/bad-proto"))
+        .connect()
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, WsClientError::InvalidProtocol(_)));
+
+    // invalid accept key
+    let err = client
+        .ws(srv.url("/bad-key"))
+        .connect()
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, WsClientError::InvalidChallengeResponse(..)));
+}
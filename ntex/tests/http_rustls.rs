@@ -456,3 +456,47 @@ async fn test_ssl_handshake_timeout() {
     let _ = stream.read_to_string(&mut data);
     assert!(data.is_empty());
 }
+
+#[cfg(not(feature = "openssl"))]
+#[ntex::test]
+async fn test_client_root_certificates() {
+    use ntex::http::client::{Client, RootCertificates};
+
+    let srv = test_server(move || {
+        let mut config = RustlsServerConfig::new(NoClientAuth::new());
+        let cert_file =
+            &mut BufReader::new(File::open("./tests/localhost-cert.pem").unwrap());
+        let key_file =
+            &mut BufReader::new(File::open("./tests/localhost-key.pem").unwrap());
+        let cert_chain = certs(cert_file).unwrap();
+        let mut keys = pkcs8_private_keys(key_file).unwrap();
+        config.set_single_cert(cert_chain, keys.remove(0)).unwrap();
+
+        HttpService::build()
+            .h1(|_| ok::<_, io::Error>(Response::Ok().finish()))
+            .rustls(config)
+    });
+    let url = format!("https://localhost:{}/", srv.addr().port());
+    let ca = std::fs::read("./tests/ca.pem").unwrap();
+
+    // server certificate is signed by unknown ca
+    let res = Client::new().get(&url).send().await;
+    assert!(res.is_err());
+
+    let client = Client::build()
+        .root_certificates(RootCertificates::new().pem(ca.clone()))
+        .finish();
+    let res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+
+    let client = Client::build()
+        .root_certificates(RootCertificates::new().pem(ca).strict())
+        .finish();
+    let res = client.get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+
+    let client = Client::build()
+        .root_certificates(RootCertificates::new().strict())
+        .finish();
+    assert!(client.get(&url).send().await.is_err());
+}